@@ -119,6 +119,17 @@ impl WalletClient {
         Ok(resp.json().await?)
     }
 
+    pub async fn send_data_transaction(
+        &self,
+        req: &SendDataTransactionRequest,
+    ) -> Result<ApiResponse<SendTransactionResponse>, ApiError> {
+        let url = self.base.url("/api/v1/transactions/data")?;
+        let rb = self.base.http.post(url).json(req);
+        let rb = self.base.with_auth(rb);
+        let resp = rb.send().await?.error_for_status()?;
+        Ok(resp.json().await?)
+    }
+
     pub async fn get_address_transactions(
         &self,
         address: &str,
@@ -130,6 +141,29 @@ impl WalletClient {
         let resp = rb.send().await?.error_for_status()?;
         Ok(resp.json().await?)
     }
+
+    pub async fn get_tx_out_proof(&self, txid: &str) -> Result<ApiResponse<Value>, ApiError> {
+        let url = self
+            .base
+            .url(&format!("/api/v1/transactions/{}/proof", txid))?;
+        let rb = self.base.with_auth(self.base.http.get(url));
+        let resp = rb.send().await?.error_for_status()?;
+        Ok(resp.json().await?)
+    }
+
+    pub async fn verify_tx_out_proof(
+        &self,
+        txid: &str,
+        proof: &str,
+    ) -> Result<ApiResponse<Value>, ApiError> {
+        let url = self
+            .base
+            .url(&format!("/api/v1/transactions/{}/proof/verify", txid))?;
+        let body = json!({ "proof": proof });
+        let rb = self.base.with_auth(self.base.http.post(url).json(&body));
+        let resp = rb.send().await?.error_for_status()?;
+        Ok(resp.json().await?)
+    }
 }
 
 #[cfg(feature = "admin")]
@@ -153,6 +187,13 @@ impl AdminClient {
         Ok(resp.json().await?)
     }
 
+    pub async fn get_tx_out_set_info(&self) -> Result<ApiResponse<TxOutSetInfo>, ApiError> {
+        let url = self.base.url("/api/admin/blockchain/txoutsetinfo")?;
+        let rb = self.base.with_auth(self.base.http.get(url));
+        let resp = rb.send().await?.error_for_status()?;
+        Ok(resp.json().await?)
+    }
+
     pub async fn get_latest_blocks(&self) -> Result<ApiResponse<Vec<BlockSummary>>, ApiError> {
         let url = self.base.url("/api/admin/blockchain/blocks/latest")?;
         let rb = self.base.with_auth(self.base.http.get(url));
@@ -183,6 +224,13 @@ impl AdminClient {
         Ok(resp.json().await?)
     }
 
+    pub async fn get_block_template(&self) -> Result<ApiResponse<Value>, ApiError> {
+        let url = self.base.url("/api/admin/mining/blocktemplate")?;
+        let rb = self.base.with_auth(self.base.http.get(url));
+        let resp = rb.send().await?.error_for_status()?;
+        Ok(resp.json().await?)
+    }
+
     pub async fn generate_to_address(
         &self,
         address: &str,
@@ -196,6 +244,24 @@ impl AdminClient {
         Ok(resp.json().await?)
     }
 
+    /// Pin the clock of a regtest node to `timestamp` milliseconds; 0 returns to the
+    /// system clock.
+    pub async fn set_mock_time(&self, timestamp: i64) -> Result<ApiResponse<Value>, ApiError> {
+        let url = self.base.url("/api/admin/mining/setmocktime")?;
+        let body = json!({ "timestamp": timestamp });
+        let rb = self.base.with_auth(self.base.http.post(url).json(&body));
+        let resp = rb.send().await?.error_for_status()?;
+        Ok(resp.json().await?)
+    }
+
+    /// Write the node's mempool to disk, where it is reloaded from on the next start.
+    pub async fn save_mempool(&self) -> Result<ApiResponse<Value>, ApiError> {
+        let url = self.base.url("/api/admin/mempool/savemempool")?;
+        let rb = self.base.with_auth(self.base.http.post(url));
+        let resp = rb.send().await?.error_for_status()?;
+        Ok(resp.json().await?)
+    }
+
     pub async fn health(&self) -> Result<ApiResponse<Value>, ApiError> {
         let url = self.base.url("/api/admin/health")?;
         let rb = self.base.with_auth(self.base.http.get(url));
@@ -308,4 +374,14 @@ impl AdminClient {
         let resp = rb.send().await?.error_for_status()?;
         Ok(resp.json().await?)
     }
+
+    pub async fn send_data_transaction_admin(
+        &self,
+        req: &SendDataTransactionRequest,
+    ) -> Result<ApiResponse<SendTransactionResponse>, ApiError> {
+        let url = self.base.url("/api/admin/transactions/data")?;
+        let rb = self.base.with_auth(self.base.http.post(url).json(req));
+        let resp = rb.send().await?.error_for_status()?;
+        Ok(resp.json().await?)
+    }
 }
@@ -22,23 +22,87 @@ pub struct CreateWalletResponse {
     pub address: String,
 }
 
+// Amount of satoshis; serialized as a plain integer like the server's `Amount`
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Amount(u64);
+
+impl Amount {
+    pub const ZERO: Amount = Amount(0);
+
+    pub const fn from_sat(satoshis: u64) -> Amount {
+        Amount(satoshis)
+    }
+
+    pub const fn to_sat(self) -> u64 {
+        self.0
+    }
+
+    pub fn checked_add(self, rhs: Amount) -> Option<Amount> {
+        self.0.checked_add(rhs.0).map(Amount)
+    }
+
+    pub fn checked_sub(self, rhs: Amount) -> Option<Amount> {
+        self.0.checked_sub(rhs.0).map(Amount)
+    }
+}
+
+impl std::fmt::Display for Amount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}.{:08} BTC",
+            self.0 / 100_000_000,
+            self.0 % 100_000_000
+        )
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SendTransactionRequest {
     pub from_address: String,
     pub to_address: String,
-    pub amount: u64,
+    pub amount: Amount,
+    // Absolute fee; the server defaults to 0 when neither fee nor fee_rate is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee: Option<Amount>,
+    // Fee in satoshis per byte of serialized transaction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_rate: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SendDataTransactionRequest {
+    // Pays the fee and receives the change
+    pub from_address: String,
+    // Hex payload of the data carrier (OP_RETURN) output, at most 80 bytes
+    pub data: String,
+    // Absolute fee; a data transaction must pay one, either absolute or per byte
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee: Option<Amount>,
+    // Fee in satoshis per byte of serialized transaction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_rate: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SendTransactionResponse {
     pub txid: String,
+    #[serde(default)]
+    pub fee: Amount,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BalanceResponse {
     pub address: String,
-    pub confirmed: u64,
-    pub unconfirmed: u64,
+    pub confirmed: Amount,
+    pub unconfirmed: Amount,
+    #[serde(default)]
+    pub spendable_balance: Amount,
+    #[serde(default)]
+    pub immature_balance: Amount,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +121,8 @@ pub struct BlockSummary {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockchainInfo {
+    #[serde(default)]
+    pub chain: String,
     pub height: usize,
     pub difficulty: u32,
     pub total_blocks: usize,
@@ -67,6 +133,19 @@ pub struct BlockchainInfo {
     pub last_block_timestamp: String, // DateTime<Utc> serializes to ISO 8601 string
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxOutSetInfo {
+    pub network: String,
+    pub height: usize,
+    pub best_block_hash: String,
+    pub transactions: usize,
+    pub txouts: usize,
+    pub total_amount: Amount,
+    pub total_subsidy: Amount,
+    pub block_subsidy: Amount,
+    pub halving_interval: usize,
+}
+
 fn deserialize_datetime_string<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
     D: serde::Deserializer<'de>,
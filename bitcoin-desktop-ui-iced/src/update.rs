@@ -2,7 +2,7 @@ use crate::api::*;
 use crate::app::AdminApp;
 use crate::runtime::spawn_on_tokio;
 use crate::types::{DataSection, Menu, Message};
-use bitcoin_api::{ApiConfig, CreateWalletRequest, SendTransactionRequest};
+use bitcoin_api::{Amount, ApiConfig, CreateWalletRequest, SendTransactionRequest};
 use iced::Task;
 use serde_json::Value;
 
@@ -304,7 +304,9 @@ pub fn update(app: &mut AdminApp, message: Message) -> Task<Message> {
             let req = SendTransactionRequest {
                 from_address: app.send_from_address.clone(),
                 to_address: app.send_to_address.clone(),
-                amount: amount_sat,
+                amount: Amount::from_sat(amount_sat),
+                fee: None,
+                fee_rate: None,
             };
             Task::perform(spawn_on_tokio(send_transaction(cfg, req)), Message::TxSent)
         }
@@ -1256,6 +1256,7 @@ fn view_transactions<'a>(app: &'a AdminApp) -> Element<'a, Message> {
                 Message::TransactionsEditorAction,
                 400.0
             ),
+            data_outputs_display(&app.transactions_data),
         ]
         .spacing(8)
         .into(),
@@ -1453,3 +1454,73 @@ where
         text("").into()
     }
 }
+
+// Helper function listing the data carrier (OP_RETURN) outputs of a page of transactions,
+// with each payload decoded as text when it is printable UTF-8
+fn data_outputs_display<'a>(data: &Option<serde_json::Value>) -> Element<'a, Message> {
+    let items = data
+        .as_ref()
+        .and_then(|data| data.get("items"))
+        .and_then(|items| items.as_array());
+    let mut rows = column![].spacing(4);
+    let mut count = 0;
+    for item in items.into_iter().flatten() {
+        let txid = item
+            .get("transaction_id")
+            .and_then(|txid| txid.as_str())
+            .unwrap_or_default();
+        let outputs = item.get("outputs").and_then(|outputs| outputs.as_array());
+        for (vout, output) in outputs.into_iter().flatten().enumerate() {
+            let Some(hex) = output.get("data_hex").and_then(|hex| hex.as_str()) else {
+                continue;
+            };
+            count += 1;
+            rows = rows.push(
+                column![
+                    text(format!("{}:{}", txid, vout))
+                        .size(12)
+                        .font(Font::MONOSPACE),
+                    text(format!("hex: {}", hex)).size(12).font(Font::MONOSPACE),
+                    text(decode_data_payload(hex)).size(13),
+                ]
+                .spacing(2),
+            );
+        }
+    }
+    if count == 0 {
+        return text("").into();
+    }
+    column![
+        text(format!("Data Outputs ({})", count))
+            .size(14)
+            .font(Font {
+                weight: iced::font::Weight::Bold,
+                ..Font::DEFAULT
+            }),
+        rows,
+    ]
+    .spacing(6)
+    .into()
+}
+
+// Decode a hex data carrier payload for display: the text if it is printable UTF-8,
+// otherwise its size
+fn decode_data_payload(hex: &str) -> String {
+    let bytes: Option<Vec<u8>> = (0..hex.len())
+        .step_by(2)
+        .map(|idx| {
+            hex.get(idx..idx + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+        })
+        .collect();
+    let Some(bytes) = bytes else {
+        return "(invalid hex)".to_string();
+    };
+    match String::from_utf8(bytes) {
+        Ok(text) if text.chars().all(|c| !c.is_control() || c == '\n') => {
+            format!("text: \"{}\"", text)
+        }
+        Ok(text) => format!("(binary, {} bytes)", text.len()),
+        Err(err) => format!("(binary, {} bytes)", err.as_bytes().len()),
+    }
+}
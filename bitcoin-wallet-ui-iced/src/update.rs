@@ -153,7 +153,9 @@ pub fn update(app: &mut WalletApp, message: Message) -> Task<Message> {
             let req = bitcoin_api::SendTransactionRequest {
                 from_address,
                 to_address: app.to.clone(),
-                amount: amount_sat,
+                amount: bitcoin_api::Amount::from_sat(amount_sat),
+                fee: None,
+                fee_rate: None,
             };
             Task::perform(spawn_on_tokio(api::send_tx(cfg, req)), Message::TxSent)
         }
@@ -1,8 +1,11 @@
 use crate::WalletAddress;
+use crate::chain::coins::Coins;
 use crate::chain::utxo_set::UTXOSet;
+use crate::config::GLOBAL_CONFIG;
 use crate::error::{BtcError, Result};
+use crate::pow::MiningOptions;
 use crate::primitives::block::Block;
-use crate::primitives::transaction::{TXOutput, Transaction, TxSummary, WalletTransaction};
+use crate::primitives::transaction::{Transaction, TxSummary, WalletTransaction};
 
 use sled::Db;
 use std::collections::HashMap;
@@ -10,6 +13,7 @@ use std::collections::HashMap;
 use std::fs;
 use std::future::Future;
 use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 use tokio::sync::RwLock as TokioRwLock;
 
 pub use crate::store::file_system_db_chain::*;
@@ -129,8 +133,8 @@ impl BlockchainService {
         .await
     }
 
-    /// Get a block by its hash
-    pub async fn get_block_by_hash(&self, block_hash: &[u8]) -> Result<Option<Block>> {
+    /// Get a block by the hex form of its hash
+    pub async fn get_block_by_hash(&self, block_hash: &str) -> Result<Option<Block>> {
         self.read(|blockchain: BlockchainFileSystem| async move {
             blockchain.get_block_by_hash(block_hash).await
         })
@@ -154,6 +158,18 @@ impl BlockchainService {
     /// Bitcoin Core's `TestBlockValidity` which validates the block template
     /// before committing to mining.
     pub async fn mine_block(&self, transactions: &[Transaction]) -> Result<Block> {
+        let never_cancelled = AtomicBool::new(false);
+        let options = MiningOptions::new(GLOBAL_CONFIG.get_mining_threads(), &never_cancelled);
+        self.mine_block_with_options(transactions, &options).await
+    }
+
+    /// Mine a block as `mine_block` does, with the nonce search run as `options` describe
+    /// (worker count and cancellation token).
+    pub async fn mine_block_with_options(
+        &self,
+        transactions: &[Transaction],
+        options: &MiningOptions<'_>,
+    ) -> Result<Block> {
         for trasaction in transactions {
             let is_valid = trasaction.verify(self).await?;
             if !is_valid {
@@ -179,14 +195,8 @@ impl BlockchainService {
             for input in tx.get_vin() {
                 match utxo_tree.get(input.get_txid()) {
                     Ok(Some(outs_bytes)) => {
-                        let outputs: Vec<crate::primitives::TXOutput> =
-                            bincode::serde::decode_from_slice(
-                                outs_bytes.as_ref(),
-                                bincode::config::standard(),
-                            )
-                            .map(|(v, _)| v)
-                            .unwrap_or_default();
-                        if input.get_vout() >= outputs.len() {
+                        let coins = Coins::deserialize(outs_bytes.as_ref()).unwrap_or_default();
+                        if !coins.is_available(input.get_vout()) {
                             return Err(BtcError::InvalidValueForMiner(
                                 "Transaction input already spent (stale mining)".to_string(),
                             ));
@@ -201,7 +211,9 @@ impl BlockchainService {
             }
         }
 
-        blockchain_guard.mine_block(transactions).await
+        blockchain_guard
+            .mine_block_with_options(transactions, options)
+            .await
     }
 
     pub async fn find_user_transaction(
@@ -222,6 +234,53 @@ impl BlockchainService {
         .await
     }
 
+    /// Find the block on the active chain that contains a transaction
+    pub async fn find_transaction_block(&self, txid: &[u8]) -> Result<Option<Block>> {
+        self.read(|blockchain: BlockchainFileSystem| async move {
+            blockchain.find_transaction_block(txid).await
+        })
+        .await
+    }
+
+    /// Get the compact target a child of `pre_block_hash` must use, or `None` if its
+    /// retarget window is not stored yet
+    pub async fn get_next_work_required(&self, pre_block_hash: &str) -> Result<Option<u32>> {
+        self.read(|blockchain: BlockchainFileSystem| async move {
+            blockchain.get_next_work_required(pre_block_hash).await
+        })
+        .await
+    }
+
+    /// Get the median timestamp of a block and its recent ancestors
+    pub async fn get_median_time_past(&self, block_hash: &str) -> Result<Option<i64>> {
+        self.read(|blockchain: BlockchainFileSystem| async move {
+            blockchain.get_median_time_past(block_hash).await
+        })
+        .await
+    }
+
+    /// Get the median time past of the block at `height` on the active chain
+    pub async fn get_median_time_past_at_height(&self, height: usize) -> Result<Option<i64>> {
+        let iterator = self.iterator().await?;
+        for block in iterator {
+            if block.get_height() < height {
+                break;
+            }
+            if block.get_height() == height {
+                return self.get_median_time_past(&block.get_hash()).await;
+            }
+        }
+        Ok(None)
+    }
+
+    /// Check whether a block is part of the active chain
+    pub async fn is_in_best_chain(&self, block_hash: &str) -> Result<bool> {
+        self.read(|blockchain: BlockchainFileSystem| async move {
+            blockchain.is_in_best_chain(block_hash).await
+        })
+        .await
+    }
+
     pub async fn find_all_transactions(&self) -> Result<HashMap<String, TxSummary>> {
         self.read(|blockchain: BlockchainFileSystem| async move {
             blockchain.find_all_transactions().await
@@ -229,7 +288,7 @@ impl BlockchainService {
         .await
     }
 
-    pub async fn find_utxo(&self) -> Result<HashMap<String, Vec<TXOutput>>> {
+    pub async fn find_utxo(&self) -> Result<HashMap<String, Coins>> {
         self.read(|blockchain: BlockchainFileSystem| async move { blockchain.find_utxo().await })
             .await
     }
@@ -451,7 +510,10 @@ mod tests {
             .expect("Failed to create iterator");
         let genesis_block = iterator.next().expect("Genesis block should exist");
         assert_eq!(genesis_block.get_height(), 1);
-        assert_eq!(genesis_block.get_pre_block_hash(), "None");
+        assert_eq!(
+            genesis_block.get_pre_block_hash(),
+            crate::GENESIS_BLOCK_PRE_BLOCK_HASH
+        );
     }
 
     #[tokio::test]
@@ -460,8 +522,8 @@ mod tests {
         let genesis_address = generate_test_genesis_address();
 
         // Create a new block
-        let coinbase_tx =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions = vec![coinbase_tx];
         let new_block = test_blockchain
             .blockchain()
@@ -492,8 +554,8 @@ mod tests {
         let genesis_address = generate_test_genesis_address();
 
         // Create and add a block
-        let coinbase_tx =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions = vec![coinbase_tx];
         let new_block = test_blockchain
             .blockchain()
@@ -524,8 +586,8 @@ mod tests {
         let genesis_address = generate_test_genesis_address();
 
         // Add a few blocks
-        for _i in 0..3 {
-            let coinbase_tx = Transaction::new_coinbase_tx(&genesis_address)
+        for height in 2..5 {
+            let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, height)
                 .expect("Failed to create coinbase tx");
             let transactions = vec![coinbase_tx];
             let new_block = test_blockchain
@@ -556,8 +618,8 @@ mod tests {
 
         // Add a block
         let genesis_address = generate_test_genesis_address();
-        let coinbase_tx =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions = vec![coinbase_tx];
         let new_block = test_blockchain
             .blockchain()
@@ -674,7 +736,7 @@ mod tests {
                 .expect("Failed to create blockchain");
 
             // Add a block
-            let coinbase_tx = Transaction::new_coinbase_tx(&genesis_address)
+            let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
                 .expect("Failed to create coinbase tx");
             let transactions = vec![coinbase_tx];
             let new_block = blockchain
@@ -710,8 +772,8 @@ mod tests {
         let test_blockchain = TestBlockchain::new().await;
 
         let genesis_address = generate_test_genesis_address();
-        let coinbase_tx =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions = vec![coinbase_tx];
 
         let new_block = test_blockchain
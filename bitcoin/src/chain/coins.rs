@@ -0,0 +1,290 @@
+//! Unspent outputs as stored in the `chainstate` tree (Bitcoin Core: coins.h)
+//!
+//! The UTXO set keeps, for every transaction with unspent outputs, the outputs that are
+//! still unspent together with the height of the block that created them and whether the
+//! transaction was a coinbase. The creation height is what coinbase maturity is measured
+//! against: a coinbase output may only be spent once its block is buried deep enough that
+//! a reorganization is unlikely to remove it.
+//!
+//! Outputs keep their position in the transaction, so an input's `vout` finds the output
+//! it spends directly: spent outputs are replaced by null outputs and trailing ones are
+//! dropped, like Bitcoin Core's pre-0.15 `CCoins`. Outputs that can never be spent, such as
+//! data carriers, are stored as null outputs from the start.
+
+use crate::error::{BtcError, Result};
+use crate::primitives::transaction::{TXOutput, Transaction};
+use serde::{Deserialize, Serialize};
+
+/// Unspent outputs of one transaction (Bitcoin Core's per-transaction `CCoins`), indexed
+/// by their position in the transaction.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Coins {
+    height: usize,
+    is_coinbase: bool,
+    outputs: Vec<TXOutput>,
+}
+
+/// A single unspent output with the metadata of the transaction that created it
+/// (Bitcoin Core's `Coin`).
+#[derive(Clone)]
+pub struct Coin {
+    output: TXOutput,
+    height: usize,
+    is_coinbase: bool,
+}
+
+impl Coins {
+    pub fn new(height: usize, is_coinbase: bool, outputs: Vec<TXOutput>) -> Coins {
+        Coins {
+            height,
+            is_coinbase,
+            outputs,
+        }
+    }
+
+    /// The spendable outputs of `tx`, created by the block at `height`. Unspendable outputs
+    /// are never added (Bitcoin Core's `AddCoins`), so a transaction that only carries data
+    /// yields a pruned entry.
+    pub fn from_transaction(tx: &Transaction, height: usize) -> Coins {
+        let mut outputs: Vec<TXOutput> = tx
+            .get_vout()
+            .iter()
+            .map(|output| {
+                if output.is_unspendable() {
+                    TXOutput::null()
+                } else {
+                    output.clone()
+                }
+            })
+            .collect();
+        while outputs.last().is_some_and(TXOutput::is_null) {
+            outputs.pop();
+        }
+        Coins::new(height, tx.is_coinbase(), outputs)
+    }
+
+    /// Height of the block that created the outputs
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    pub fn is_coinbase(&self) -> bool {
+        self.is_coinbase
+    }
+
+    /// The outputs by position in the transaction; spent outputs are null.
+    pub fn get_outputs(&self) -> &[TXOutput] {
+        &self.outputs
+    }
+
+    /// The unspent outputs with their position in the transaction.
+    pub fn unspent_outputs(&self) -> impl Iterator<Item = (usize, &TXOutput)> {
+        self.outputs
+            .iter()
+            .enumerate()
+            .filter(|(_, output)| !output.is_null())
+    }
+
+    /// The unspent output at position `vout`, for updating its flags.
+    pub fn get_output_mut(&mut self, vout: usize) -> Option<&mut TXOutput> {
+        self.outputs
+            .get_mut(vout)
+            .filter(|output| !output.is_null())
+    }
+
+    /// Whether the output at position `vout` is unspent (Bitcoin Core's `IsAvailable`).
+    pub fn is_available(&self, vout: usize) -> bool {
+        self.outputs
+            .get(vout)
+            .is_some_and(|output| !output.is_null())
+    }
+
+    /// Whether every output has been spent (Bitcoin Core's `IsPruned`); the entry can then
+    /// be removed from the UTXO set.
+    pub fn is_pruned(&self) -> bool {
+        self.outputs.iter().all(TXOutput::is_null)
+    }
+
+    /// Mark the output at position `vout` as spent (Bitcoin Core's `CCoins::Spend`).
+    ///
+    /// # Returns
+    ///
+    /// The spent coin, or `None` if it was not unspent.
+    pub fn spend(&mut self, vout: usize) -> Option<Coin> {
+        let coin = self.get_coin(vout)?;
+        self.outputs[vout] = TXOutput::null();
+        while self.outputs.last().is_some_and(TXOutput::is_null) {
+            self.outputs.pop();
+        }
+        Some(coin)
+    }
+
+    /// Put back the output at position `vout` when a block spending it is disconnected.
+    pub fn restore(&mut self, vout: usize, output: TXOutput) {
+        if self.outputs.len() <= vout {
+            self.outputs.resize(vout + 1, TXOutput::null());
+        }
+        self.outputs[vout] = output;
+    }
+
+    /// Whether the outputs may be spent by a transaction in the block at `spend_height`.
+    ///
+    /// Regular outputs are always mature. Coinbase outputs need `maturity` blocks between
+    /// their block and the spending block (Bitcoin Core: `nSpendHeight - coin.nHeight <
+    /// COINBASE_MATURITY` is rejected as `bad-txns-premature-spend-of-coinbase`).
+    ///
+    /// # Arguments
+    ///
+    /// * `spend_height` - Height of the block that would include the spending transaction.
+    /// * `maturity` - Required coinbase depth in blocks.
+    pub fn is_mature(&self, spend_height: usize, maturity: usize) -> bool {
+        !self.is_coinbase || spend_height.saturating_sub(self.height) >= maturity
+    }
+
+    /// The unspent output at position `idx` together with this entry's metadata.
+    pub fn get_coin(&self, idx: usize) -> Option<Coin> {
+        self.outputs
+            .get(idx)
+            .filter(|output| !output.is_null())
+            .map(|output| Coin {
+                output: output.clone(),
+                height: self.height,
+                is_coinbase: self.is_coinbase,
+            })
+    }
+
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        bincode::serde::encode_to_vec(self, bincode::config::standard())
+            .map_err(|e| BtcError::TransactionSerializationError(e.to_string()))
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Result<Coins> {
+        bincode::serde::decode_from_slice(bytes, bincode::config::standard())
+            .map(|(coins, _)| coins)
+            .map_err(|e| BtcError::TransactionDeserializationError(e.to_string()))
+    }
+}
+
+impl Coin {
+    pub fn new(output: TXOutput, height: usize, is_coinbase: bool) -> Coin {
+        Coin {
+            output,
+            height,
+            is_coinbase,
+        }
+    }
+
+    pub fn get_output(&self) -> &TXOutput {
+        &self.output
+    }
+
+    pub fn into_output(self) -> TXOutput {
+        self.output
+    }
+
+    /// Height of the block that created the output
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    pub fn is_coinbase(&self) -> bool {
+        self.is_coinbase
+    }
+
+    /// See [`Coins::is_mature`].
+    pub fn is_mature(&self, spend_height: usize, maturity: usize) -> bool {
+        !self.is_coinbase || spend_height.saturating_sub(self.height) >= maturity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::Amount;
+    use crate::primitives::transaction::SEQUENCE_FINAL;
+
+    fn test_output() -> TXOutput {
+        let wallet = crate::wallet::Wallet::new().expect("Failed to create test wallet");
+        let address = wallet.get_address().expect("Failed to get wallet address");
+        TXOutput::new(Amount::from_sat(10), &address).expect("Failed to create output")
+    }
+
+    #[test]
+    fn test_coinbase_maturity() {
+        let coinbase = Coins::new(5, true, vec![test_output()]);
+        assert!(!coinbase.is_mature(6, 100));
+        assert!(!coinbase.is_mature(104, 100));
+        assert!(coinbase.is_mature(105, 100));
+        assert!(coinbase.is_mature(5, 0));
+
+        let regular = Coins::new(5, false, vec![test_output()]);
+        assert!(regular.is_mature(6, 100));
+
+        let coin = coinbase.get_coin(0).unwrap();
+        assert_eq!(coin.get_height(), 5);
+        assert!(!coin.is_mature(104, 100));
+        assert!(coinbase.get_coin(1).is_none());
+    }
+
+    #[test]
+    fn test_coins_serialization_roundtrip() {
+        let coins = Coins::new(7, true, vec![test_output(), test_output()]);
+        let decoded = Coins::deserialize(&coins.serialize().unwrap()).unwrap();
+        assert_eq!(decoded.get_height(), 7);
+        assert!(decoded.is_coinbase());
+        assert_eq!(decoded.get_outputs().len(), 2);
+    }
+
+    #[test]
+    fn test_spent_outputs_keep_positions() {
+        let mut coins = Coins::new(7, false, vec![test_output(), test_output(), test_output()]);
+        assert!(coins.spend(0).is_some());
+        assert!(coins.spend(0).is_none());
+        assert!(!coins.is_available(0));
+        assert!(coins.get_coin(2).is_some());
+        assert_eq!(
+            coins
+                .unspent_outputs()
+                .map(|(vout, _)| vout)
+                .collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+
+        // Trailing spent outputs are dropped
+        assert!(coins.spend(2).is_some());
+        assert_eq!(coins.get_outputs().len(), 2);
+        assert!(coins.spend(1).is_some());
+        assert!(coins.is_pruned());
+
+        coins.restore(2, test_output());
+        assert!(coins.is_available(2));
+        assert!(!coins.is_available(1));
+        assert!(!coins.is_pruned());
+    }
+
+    /// A transaction with the given outputs and one input.
+    fn transaction_with_outputs(outputs: &[TXOutput]) -> Transaction {
+        outputs
+            .iter()
+            .fold(
+                Transaction::test_builder().with_input(1, 0, SEQUENCE_FINAL),
+                |builder, output| builder.with_output(output.clone()),
+            )
+            .build()
+    }
+
+    #[test]
+    fn test_unspendable_outputs_are_not_added() {
+        let data = TXOutput::new_data_carrier(b"anchor").unwrap();
+        let coins =
+            Coins::from_transaction(&transaction_with_outputs(&[data.clone(), test_output()]), 3);
+        assert!(!coins.is_available(0));
+        assert!(coins.is_available(1));
+
+        let coins = Coins::from_transaction(&transaction_with_outputs(&[test_output(), data]), 3);
+        assert_eq!(coins.get_outputs().len(), 1);
+
+        let data_only = TXOutput::new_data_carrier(b"anchor").unwrap();
+        assert!(Coins::from_transaction(&transaction_with_outputs(&[data_only]), 3).is_pruned());
+    }
+}
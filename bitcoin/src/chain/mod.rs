@@ -4,11 +4,14 @@
 //! Bitcoin Core's chain/ directory which contains:
 //! - CChainState: Active blockchain state
 //! - CCoinsView: UTXO set management
+//! - CCoins: Unspent outputs stored per transaction
 //! - CBlockIndex: Block indexing
 
 pub mod chainstate;
+pub mod coins;
 pub mod utxo_set;
 
 // Re-export main types for convenience
 pub use chainstate::BlockchainService;
-pub use utxo_set::UTXOSet;
+pub use coins::{Coin, Coins};
+pub use utxo_set::{TxOutSetInfo, UTXOSet, WalletBalance};
@@ -1,15 +1,90 @@
 use crate::chain::chainstate::BlockchainService;
+use crate::chain::coins::{Coin, Coins};
+use crate::config::GLOBAL_CONFIG;
+use crate::consensus::{
+    Amount, RelativeLockTime, calculate_sequence_locks, evaluate_sequence_locks, get_total_subsidy,
+    is_final_tx,
+};
 use crate::error::{BtcError, Result};
+use crate::node::GLOBAL_MEMORY_POOL;
 use crate::primitives::block::Block;
-use crate::primitives::transaction::{TXOutput, Transaction};
+use crate::primitives::transaction::{TXInput, TXOutput, Transaction};
 use crate::wallet::WalletAddress;
 use crate::wallet::get_pub_key_hash;
 use data_encoding::HEXLOWER;
-use std::collections::HashMap;
-use tracing::{debug, info, trace};
+use std::collections::{HashMap, HashSet};
+use tracing::{debug, info};
 
 const UTXO_TREE: &str = "chainstate";
 
+/// Statistics about the unspent transaction output set (Bitcoin Core's `CCoinsStats`,
+/// reported by the `gettxoutsetinfo` RPC).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxOutSetInfo {
+    height: usize,
+    best_block_hash: String,
+    transactions: usize,
+    txouts: usize,
+    total_amount: Amount,
+    total_subsidy: Amount,
+}
+
+/// Balance of an address split by whether its outputs can be spent in the next block.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WalletBalance {
+    spendable: Amount,
+    immature: Amount,
+}
+
+impl WalletBalance {
+    /// Value of the outputs that can be spent in the next block
+    pub fn get_spendable(&self) -> Amount {
+        self.spendable
+    }
+
+    /// Value of coinbase outputs that have not reached coinbase maturity
+    pub fn get_immature(&self) -> Amount {
+        self.immature
+    }
+
+    pub fn get_total(&self) -> Result<Amount> {
+        self.spendable
+            .checked_add(self.immature)
+            .ok_or(BtcError::AmountOverflow)
+    }
+}
+
+impl TxOutSetInfo {
+    /// Height of the tip the statistics were taken at
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get_best_block_hash(&self) -> &str {
+        &self.best_block_hash
+    }
+
+    /// Number of transactions with at least one unspent output
+    pub fn get_transactions(&self) -> usize {
+        self.transactions
+    }
+
+    /// Number of unspent outputs
+    pub fn get_txouts(&self) -> usize {
+        self.txouts
+    }
+
+    /// Value held by all unspent outputs
+    pub fn get_total_amount(&self) -> Amount {
+        self.total_amount
+    }
+
+    /// Subsidy the schedule allowed to be minted from genesis up to the tip
+    pub fn get_total_subsidy(&self) -> Amount {
+        self.total_subsidy
+    }
+}
+
 pub struct UTXOSet {
     blockchain: BlockchainService,
 }
@@ -27,6 +102,10 @@ impl UTXOSet {
     /// The `find_spendable_outputs` function finds the spendable outputs for a given public key hash and amount.
     /// It iterates through UTXOs, checks ownership, accumulates values,
     /// and forms a HashMap of transaction IDs to output indices for spendable outputs.
+    /// Coinbase outputs that are not yet mature for the next block are skipped, and so are
+    /// outputs a memory pool transaction already spends. When confirmed outputs do not cover
+    /// `amount`, unspent outputs of memory pool transactions are selected too, so a payment
+    /// can spend change or funds that are not confirmed yet.
     ///
     /// # Arguments
     ///
@@ -39,40 +118,43 @@ impl UTXOSet {
     pub async fn find_spendable_outputs(
         &self,
         from_pub_key_hash: &[u8],
-        amount: i32,
-    ) -> Result<(i32, HashMap<String, Vec<usize>>)> {
+        amount: Amount,
+    ) -> Result<(Amount, HashMap<String, Vec<usize>>)> {
         debug!("Finding spendable outputs for amount: {}", amount);
         let mut unspent_outputs_indexes: HashMap<String, Vec<usize>> = HashMap::new();
-        let mut accmulated = 0;
+        let mut accmulated = Amount::ZERO;
         let db = self.blockchain.get_db().await?;
         let utxo_tree = db
             .open_tree(UTXO_TREE)
             .map_err(|e| BtcError::UTXODBconnection(e.to_string()))?;
+        let spend_height = self.blockchain.get_best_height().await? + 1;
+        let maturity = GLOBAL_CONFIG.get_coinbase_maturity();
         let mut total_checked = 0;
         for item in utxo_tree.iter() {
             let (k, v) = item.map_err(|e| BtcError::GettingUTXOError(e.to_string()))?;
             let txid_hex = HEXLOWER.encode(k.to_vec().as_slice());
-            let (tx_out, _): (Vec<TXOutput>, usize) = bincode::serde::decode_from_slice(
-                v.to_vec().as_slice(),
-                bincode::config::standard(),
-            )
-            .map_err(|e| BtcError::TransactionDeserializationError(e.to_string()))?;
-            for (current_out_index, out) in tx_out.iter().enumerate() {
+            let coins = Coins::deserialize(v.as_ref())?;
+            if !coins.is_mature(spend_height, maturity) {
+                debug!("Skipping immature coinbase outputs of tx {}", txid_hex);
+                continue;
+            }
+            for (current_out_index, out) in coins.unspent_outputs() {
                 total_checked += 1;
                 debug!(
-                    "Checking output {} in tx {}: value={}, in_mempool={}, locked_with_key={}",
+                    "Checking output {} in tx {}: value={}, locked_with_key={}",
                     current_out_index,
                     txid_hex,
                     out.get_value(),
-                    out.is_in_global_mem_pool(),
                     out.is_locked_with_key(from_pub_key_hash)
                 );
-                if out.not_in_global_mem_pool()
-                    && out.get_value() > 0
+                if out.get_value() > Amount::ZERO
                     && out.is_locked_with_key(from_pub_key_hash)
                     && accmulated < amount
+                    && !GLOBAL_MEMORY_POOL.is_spent(&k, current_out_index)?
                 {
-                    accmulated += out.get_value();
+                    accmulated = accmulated
+                        .checked_add(out.get_value())
+                        .ok_or(BtcError::AmountOverflow)?;
                     debug!(
                         "Adding spendable output: tx={}, idx={}, value={}, accumulated={}",
                         txid_hex,
@@ -94,6 +176,38 @@ impl UTXOSet {
                 }
             }
         }
+        // Unconfirmed outputs are only used when confirmed ones do not cover the amount
+        let mut pool = GLOBAL_MEMORY_POOL.get_all()?;
+        pool.sort_by_key(|tx| tx.get_tx_id_hex());
+        for tx in pool.iter() {
+            if accmulated >= amount {
+                break;
+            }
+            let txid_hex = tx.get_tx_id_hex();
+            for (current_out_index, out) in tx.get_vout().iter().enumerate() {
+                total_checked += 1;
+                if out.get_value() > Amount::ZERO
+                    && out.is_locked_with_key(from_pub_key_hash)
+                    && accmulated < amount
+                    && !GLOBAL_MEMORY_POOL.is_spent(tx.get_id(), current_out_index)?
+                {
+                    accmulated = accmulated
+                        .checked_add(out.get_value())
+                        .ok_or(BtcError::AmountOverflow)?;
+                    debug!(
+                        "Adding unconfirmed output: tx={}, idx={}, value={}, accumulated={}",
+                        txid_hex,
+                        current_out_index,
+                        out.get_value(),
+                        accmulated
+                    );
+                    unspent_outputs_indexes
+                        .entry(txid_hex.clone())
+                        .or_default()
+                        .push(current_out_index);
+                }
+            }
+        }
         debug!(
             "find_spendable_outputs completed: checked {} outputs, accumulated={}, found {} spendable transactions",
             total_checked,
@@ -103,6 +217,379 @@ impl UTXOSet {
         Ok((accmulated, unspent_outputs_indexes))
     }
 
+    ///
+    /// The `get_spendable_output` function returns the output an input spends from the UTXO set
+    /// or, if it is not there, from the memory pool transaction that creates it (Bitcoin Core's
+    /// `CCoinsViewMemPool`). Wallets and memory pool acceptance use this view so transactions
+    /// can spend unconfirmed outputs; block validation only trusts the UTXO set.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input whose previous output is looked up.
+    ///
+    /// # Returns
+    ///
+    /// The spent output, or `None` if neither the UTXO set nor the memory pool has it.
+    pub async fn get_spendable_output(&self, input: &TXInput) -> Result<Option<TXOutput>> {
+        if let Some(output) = self.get_unspent_output(input).await? {
+            return Ok(Some(output));
+        }
+        Ok(GLOBAL_MEMORY_POOL
+            .get(&input.get_input_tx_id_hex())?
+            .and_then(|parent| parent.get_vout().get(input.get_vout()).cloned())
+            .filter(|output| !output.is_unspendable()))
+    }
+
+    ///
+    /// The `get_unspent_output` function returns the output an input spends, if it is still unspent.
+    /// See `get_unspent_coin`.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input whose previous output is looked up.
+    ///
+    /// # Returns
+    ///
+    /// The spent output, or `None` if it does not exist or has already been spent.
+    pub async fn get_unspent_output(&self, input: &TXInput) -> Result<Option<TXOutput>> {
+        Ok(self.get_unspent_coin(input).await?.map(Coin::into_output))
+    }
+
+    ///
+    /// The `get_unspent_coin` function returns the output an input spends, if it is still unspent,
+    /// together with the height and coinbase flag of the transaction that created it.
+    /// Outputs keep their position in the UTXO tree, so this is a single `chainstate` lookup
+    /// and never searches the chain.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input whose previous output is looked up.
+    ///
+    /// # Returns
+    ///
+    /// The spent coin, or `None` if it does not exist or has already been spent.
+    pub async fn get_unspent_coin(&self, input: &TXInput) -> Result<Option<Coin>> {
+        let db = self.blockchain.get_db().await?;
+        let utxo_tree = db
+            .open_tree(UTXO_TREE)
+            .map_err(|e| BtcError::UTXODBconnection(e.to_string()))?;
+        let Some(outs_bytes) = utxo_tree
+            .get(input.get_txid())
+            .map_err(|e| BtcError::GettingUTXOError(e.to_string()))?
+        else {
+            return Ok(None);
+        };
+        Ok(Coins::deserialize(outs_bytes.as_ref())?.get_coin(input.get_vout()))
+    }
+
+    ///
+    /// The `get_spent_outputs` function returns the unspent outputs a transaction spends, in
+    /// input order: what signing and verifying its inputs commit to. Outputs of memory pool
+    /// transactions count as unspent (see `get_spendable_output`).
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The transaction whose inputs are looked up.
+    ///
+    /// # Returns
+    ///
+    /// The spent outputs (none for a coinbase), or `MissingOrSpentInput` if an input does
+    /// not spend an unspent output.
+    pub async fn get_spent_outputs(&self, tx: &Transaction) -> Result<Vec<TXOutput>> {
+        if tx.is_coinbase() {
+            return Ok(vec![]);
+        }
+        let mut spent_outputs = Vec::with_capacity(tx.get_vin().len());
+        for input in tx.get_vin() {
+            let output = self.get_spendable_output(input).await?.ok_or_else(|| {
+                BtcError::MissingOrSpentInput(
+                    tx.get_tx_id_hex(),
+                    format!("{}:{}", input.get_input_tx_id_hex(), input.get_vout()),
+                )
+            })?;
+            spent_outputs.push(output);
+        }
+        Ok(spent_outputs)
+    }
+
+    ///
+    /// The `check_coinbase_maturity` function rejects a transaction that spends a coinbase output
+    /// before it has `maturity` blocks on top of its own block. Outputs of memory pool
+    /// transactions are never coinbase outputs.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The transaction to check.
+    /// * `spend_height` - Height of the block that would include the transaction.
+    /// * `maturity` - Required coinbase depth in blocks.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if every input is mature, `PrematureCoinbaseSpend` for the first immature
+    /// input, or `MissingOrSpentInput` if an input does not spend an unspent output.
+    pub async fn check_coinbase_maturity(
+        &self,
+        tx: &Transaction,
+        spend_height: usize,
+        maturity: usize,
+    ) -> Result<()> {
+        if tx.is_coinbase() {
+            return Ok(());
+        }
+        for input in tx.get_vin() {
+            let outpoint = format!("{}:{}", input.get_input_tx_id_hex(), input.get_vout());
+            let Some(coin) = self.get_unspent_coin(input).await? else {
+                // Memory pool transactions are never coinbases
+                if self.get_spendable_output(input).await?.is_none() {
+                    return Err(BtcError::MissingOrSpentInput(tx.get_tx_id_hex(), outpoint));
+                }
+                continue;
+            };
+            if !coin.is_mature(spend_height, maturity) {
+                return Err(BtcError::PrematureCoinbaseSpend(
+                    tx.get_tx_id_hex(),
+                    outpoint,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// The `check_sequence_locks` function rejects a transaction whose inputs' relative lock
+    /// times (BIP68) do not allow it into the block at `spend_height` (Bitcoin Core's
+    /// `SequenceLocks`).
+    ///
+    /// Time-based locks are measured from the median time past of the block before the one
+    /// that created the spent output; outputs created at `spend_height` itself (earlier in the
+    /// same block) use `spend_time`.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The transaction to check.
+    /// * `coin_heights` - Height of the block that created each spent output, in input order.
+    /// * `spend_height` - Height of the block that would include the transaction.
+    /// * `spend_time` - Median time past of that block's parent, in milliseconds.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if every lock has passed, or `SequenceLockNotSatisfied` naming the first
+    /// locked input.
+    pub async fn check_sequence_locks(
+        &self,
+        tx: &Transaction,
+        coin_heights: &[usize],
+        spend_height: usize,
+        spend_time: i64,
+    ) -> Result<()> {
+        let mut coin_times = vec![0; coin_heights.len()];
+        for (idx, input) in tx.get_vin().iter().enumerate() {
+            let Some(coin_height) = coin_heights.get(idx).copied() else {
+                break;
+            };
+            if !RelativeLockTime::from_input(input).is_some_and(|lock| lock.is_time_based()) {
+                continue;
+            }
+            coin_times[idx] = if coin_height >= spend_height {
+                spend_time
+            } else {
+                self.blockchain
+                    .get_median_time_past_at_height(coin_height.saturating_sub(1))
+                    .await?
+                    .unwrap_or_default()
+            };
+        }
+        let locks = calculate_sequence_locks(tx, coin_heights, &coin_times);
+        if evaluate_sequence_locks(spend_height, spend_time, locks) {
+            return Ok(());
+        }
+        let locked_input = tx
+            .get_vin()
+            .iter()
+            .find(|input| RelativeLockTime::from_input(input).is_some())
+            .map(|input| format!("{}:{}", input.get_input_tx_id_hex(), input.get_vout()))
+            .unwrap_or_default();
+        Err(BtcError::SequenceLockNotSatisfied(
+            tx.get_tx_id_hex(),
+            locked_input,
+        ))
+    }
+
+    ///
+    /// The `check_block_transactions` function checks a block's transactions against the UTXO
+    /// set (Bitcoin Core's `ConnectBlock`). It resolves the output spent by every input, checks
+    /// lock times, verifies signatures and value balance, and checks the coinbase does not
+    /// claim more than the subsidy of its height plus fees. Outputs created earlier in the
+    /// same block may be spent by later transactions.
+    ///
+    /// # Arguments
+    ///
+    /// * `block` - A block extending the tip the UTXO set reflects.
+    /// * `block_time` - Median time past of the block's parent, which lock times are
+    ///   compared against (BIP113).
+    /// * `maturity` - Blocks a spent coinbase output needs on top of it.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if all transactions are valid, or the first violated rule.
+    pub async fn check_block_transactions(
+        &self,
+        block: &Block,
+        block_time: i64,
+        maturity: usize,
+    ) -> Result<()> {
+        let transactions = block.get_transactions().await?;
+        let mut block_txs: HashMap<Vec<u8>, &Transaction> = HashMap::new();
+        let mut spent_outpoints: HashSet<(Vec<u8>, usize)> = HashSet::new();
+        let mut fees = Amount::ZERO;
+
+        for tx in transactions {
+            if tx.is_coinbase() {
+                block_txs.insert(tx.get_id_bytes(), tx);
+                continue;
+            }
+            let txid_hex = tx.get_tx_id_hex();
+            if !is_final_tx(tx, block.get_height(), block_time) {
+                return Err(BtcError::NonFinalTransaction(txid_hex));
+            }
+
+            let mut spent_outputs = Vec::with_capacity(tx.get_vin().len());
+            let mut coin_heights = Vec::with_capacity(tx.get_vin().len());
+            for input in tx.get_vin() {
+                let outpoint = format!("{}:{}", input.get_input_tx_id_hex(), input.get_vout());
+                if !spent_outpoints.insert((input.get_txid().to_vec(), input.get_vout())) {
+                    return Err(BtcError::DoubleSpendInBlock(txid_hex, outpoint));
+                }
+                let spent_coin = match block_txs.get(input.get_txid()) {
+                    Some(prev_tx) => prev_tx.get_vout().get(input.get_vout()).map(|output| {
+                        Coin::new(output.clone(), block.get_height(), prev_tx.is_coinbase())
+                    }),
+                    None => self.get_unspent_coin(input).await?,
+                };
+                match spent_coin {
+                    Some(coin) if !coin.is_mature(block.get_height(), maturity) => {
+                        return Err(BtcError::PrematureCoinbaseSpend(txid_hex, outpoint));
+                    }
+                    Some(coin) => {
+                        coin_heights.push(coin.get_height());
+                        spent_outputs.push(coin.into_output());
+                    }
+                    None => return Err(BtcError::MissingOrSpentInput(txid_hex, outpoint)),
+                }
+            }
+            self.check_sequence_locks(tx, &coin_heights, block.get_height(), block_time)
+                .await?;
+
+            if !tx.verify_signatures(&spent_outputs)? {
+                return Err(BtcError::InvalidTransactionSignature(txid_hex));
+            }
+
+            // Bitcoin Core's CheckTxInputs: input total in money range, outputs covered
+            let value_in = Amount::checked_sum(spent_outputs.iter().map(|o| o.get_value()))
+                .filter(|value| value.is_money_range())
+                .ok_or_else(|| BtcError::InputValueOutOfRange(txid_hex.clone()))?;
+            let value_out = tx.get_output_value()?;
+            let fee = value_in
+                .checked_sub(value_out)
+                .ok_or_else(|| BtcError::TransactionOutputsExceedInputs(txid_hex.clone()))?;
+            fees = fees
+                .checked_add(fee)
+                .filter(|fees| fees.is_money_range())
+                .ok_or(BtcError::InputValueOutOfRange(txid_hex))?;
+            block_txs.insert(tx.get_id_bytes(), tx);
+        }
+
+        let coinbase_value = transactions[0].get_output_value()?;
+        let subsidy = GLOBAL_CONFIG
+            .get_network()
+            .get_block_subsidy(block.get_height());
+        let max_coinbase_value = subsidy.checked_add(fees).ok_or(BtcError::AmountOverflow)?;
+        if coinbase_value > max_coinbase_value {
+            return Err(BtcError::InvalidCoinbaseValue(
+                coinbase_value,
+                max_coinbase_value,
+            ));
+        }
+        Ok(())
+    }
+
+    ///
+    /// The `check_input_scripts` function runs the unlocking script of every input against the
+    /// locking script of the unspent output it spends (Bitcoin Core's `CheckInputScripts`).
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The transaction to check.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if every input may spend its output, `InvalidTransactionSignature` if a script
+    /// fails, or `MissingOrSpentInput` if an input does not spend an unspent output.
+    pub async fn check_input_scripts(&self, tx: &Transaction) -> Result<()> {
+        if tx.is_coinbase() {
+            return Ok(());
+        }
+        let spent_outputs = self.get_spent_outputs(tx).await?;
+        if !tx.verify_signatures(&spent_outputs)? {
+            return Err(BtcError::InvalidTransactionSignature(tx.get_tx_id_hex()));
+        }
+        Ok(())
+    }
+
+    ///
+    /// The `get_input_value` function sums the values of the unspent outputs a transaction spends,
+    /// including outputs of memory pool transactions (see `get_spendable_output`).
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The transaction whose inputs are valued.
+    ///
+    /// # Returns
+    ///
+    /// The total input value (0 for a coinbase), or `MissingOrSpentInput` if an input
+    /// does not spend an unspent output.
+    pub async fn get_input_value(&self, tx: &Transaction) -> Result<Amount> {
+        if tx.is_coinbase() {
+            return Ok(Amount::ZERO);
+        }
+        let mut input_value = Amount::ZERO;
+        for input in tx.get_vin() {
+            let output = self.get_spendable_output(input).await?.ok_or_else(|| {
+                BtcError::MissingOrSpentInput(
+                    tx.get_tx_id_hex(),
+                    format!("{}:{}", input.get_input_tx_id_hex(), input.get_vout()),
+                )
+            })?;
+            input_value = input_value
+                .checked_add(output.get_value())
+                .filter(|value| value.is_money_range())
+                .ok_or_else(|| BtcError::InputValueOutOfRange(tx.get_tx_id_hex()))?;
+        }
+        Ok(input_value)
+    }
+
+    ///
+    /// The `get_transaction_fee` function computes the fee a transaction pays: inputs − outputs.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The transaction whose fee is computed.
+    ///
+    /// # Returns
+    ///
+    /// The fee (0 for a coinbase), or an error if an input is missing or the outputs
+    /// exceed the inputs.
+    pub async fn get_transaction_fee(&self, tx: &Transaction) -> Result<Amount> {
+        if tx.is_coinbase() {
+            return Ok(Amount::ZERO);
+        }
+        let input_value = self.get_input_value(tx).await?;
+        let output_value = tx.get_output_value()?;
+        input_value
+            .checked_sub(output_value)
+            .ok_or_else(|| BtcError::TransactionOutputsExceedInputs(tx.get_tx_id_hex()))
+    }
+
     pub async fn find_utxo(&self, pub_key_hash: &[u8]) -> Result<Vec<TXOutput>> {
         let db = self.blockchain.get_db().await?;
         let utxo_tree = db
@@ -117,15 +604,14 @@ impl UTXOSet {
             let txid_hex = HEXLOWER.encode(&k);
             debug!("Checking UTXO tree item: {}", txid_hex);
 
-            let outs: Vec<TXOutput> = bincode::serde::decode_from_slice(
-                v.to_vec().as_slice(),
-                bincode::config::standard(),
-            )
-            .map_err(|e| BtcError::TransactionDeserializationError(e.to_string()))?
-            .0;
+            let coins = Coins::deserialize(v.as_ref())?;
 
-            debug!("Transaction {} has {} outputs", txid_hex, outs.len());
-            for (idx, out) in outs.iter().enumerate() {
+            debug!(
+                "Transaction {} has {} outputs",
+                txid_hex,
+                coins.get_outputs().len()
+            );
+            for (idx, out) in coins.unspent_outputs() {
                 debug!(
                     "Output {}: value = {}, checking if locked with key",
                     idx,
@@ -157,6 +643,50 @@ impl UTXOSet {
         Ok(counter)
     }
 
+    ///
+    /// The `get_tx_out_set_info` function scans the UTXO set and reports its size and
+    /// total value next to the subsidy issued by the chain up to the tip.
+    ///
+    /// Fees only move value from spent outputs into the coinbase, so `total_amount` equals
+    /// `total_subsidy` unless a miner claimed less than it was allowed to.
+    ///
+    /// # Returns
+    ///
+    /// The statistics of the UTXO set at the current tip.
+    pub async fn get_tx_out_set_info(&self) -> Result<TxOutSetInfo> {
+        let height = self.blockchain.get_best_height().await?;
+        let best_block_hash = self.blockchain.get_tip_hash().await?;
+        let db = self.blockchain.get_db().await?;
+        let utxo_tree = db
+            .open_tree(UTXO_TREE)
+            .map_err(|e| BtcError::UTXODBconnection(e.to_string()))?;
+
+        let mut transactions = 0;
+        let mut txouts = 0;
+        let mut total_amount = Amount::ZERO;
+        for item in utxo_tree.iter() {
+            let (_, outs_bytes) = item.map_err(|e| BtcError::GettingUTXOError(e.to_string()))?;
+            let coins = Coins::deserialize(outs_bytes.as_ref())?;
+            transactions += 1;
+            for (_, out) in coins.unspent_outputs() {
+                txouts += 1;
+                total_amount = total_amount
+                    .checked_add(out.get_value())
+                    .ok_or(BtcError::AmountOverflow)?;
+            }
+        }
+
+        let halving_interval = GLOBAL_CONFIG.get_network().get_subsidy_halving_interval();
+        Ok(TxOutSetInfo {
+            height,
+            best_block_hash,
+            transactions,
+            txouts,
+            total_amount,
+            total_subsidy: get_total_subsidy(height, halving_interval),
+        })
+    }
+
     /// The `reindex` function reindexes the UTXO set by clearing the existing UTXO tree and rebuilding it from the blockchain.
     /// It iterates through the blockchain, finds all UTXOs, and inserts them into the UTXO tree.
     ///
@@ -177,17 +707,16 @@ impl UTXOSet {
         let utxo_map = self.blockchain.find_utxo().await?;
         debug!("Found {} transactions with UTXOs", utxo_map.len());
 
-        for (txid_hex, outs) in &utxo_map {
+        for (txid_hex, coins) in &utxo_map {
             debug!(
                 "Processing transaction {} with {} outputs",
                 txid_hex,
-                outs.len()
+                coins.get_outputs().len()
             );
             let txid = HEXLOWER
                 .decode(txid_hex.as_bytes())
                 .map_err(|e| BtcError::TransactionIdHexDecodingError(e.to_string()))?;
-            let value = bincode::serde::encode_to_vec(outs, bincode::config::standard())
-                .map_err(|e| BtcError::TransactionSerializationError(e.to_string()))?;
+            let value = coins.serialize()?;
             let _ = utxo_tree
                 .insert(txid.as_slice(), value)
                 .map_err(|e| BtcError::SavingUTXOError(e.to_string()))?;
@@ -205,7 +734,6 @@ impl UTXOSet {
             // Coinbase transactions dont have inputs
             if !curr_block_tx.is_coinbase() {
                 for curr_blc_tx_inpt in curr_block_tx.get_vin() {
-                    let mut updated_outs = vec![];
                     let curr_blc_tx_inpt_utxo_ivec = utxo_tree
                         .get(curr_blc_tx_inpt.get_txid())
                         .map_err(|e| BtcError::GettingUTXOError(e.to_string()))?
@@ -213,46 +741,25 @@ impl UTXOSet {
                             "(update) UTXO {} not found",
                             curr_blc_tx_inpt.get_input_tx_id_hex()
                         )))?;
-                    let curr_blc_tx_inpt_utxo_list: Vec<TXOutput> =
-                        bincode::serde::decode_from_slice(
-                            curr_blc_tx_inpt_utxo_ivec.as_ref(),
-                            bincode::config::standard(),
-                        )
-                        .map_err(|e| BtcError::TransactionDeserializationError(e.to_string()))?
-                        .0;
-                    for (utxo_curr_utxo_idx, db_curr_utxo) in
-                        curr_blc_tx_inpt_utxo_list.iter().enumerate()
-                    {
-                        if utxo_curr_utxo_idx != curr_blc_tx_inpt.get_vout() {
-                            updated_outs.push(db_curr_utxo.clone())
-                        }
-                    }
-                    if updated_outs.is_empty() {
+                    let mut coins = Coins::deserialize(curr_blc_tx_inpt_utxo_ivec.as_ref())?;
+                    coins.spend(curr_blc_tx_inpt.get_vout());
+                    if coins.is_pruned() {
                         utxo_tree
                             .remove(curr_blc_tx_inpt.get_txid())
                             .map_err(|e| BtcError::RemovingUTXOError(e.to_string()))?;
                     } else {
-                        let outs_bytes = bincode::serde::encode_to_vec(
-                            &updated_outs,
-                            bincode::config::standard(),
-                        )
-                        .map_err(|e| BtcError::TransactionSerializationError(e.to_string()))?;
                         utxo_tree
-                            .insert(curr_blc_tx_inpt.get_txid(), outs_bytes)
+                            .insert(curr_blc_tx_inpt.get_txid(), coins.serialize()?)
                             .map_err(|e| BtcError::SavingUTXOError(e.to_string()))?;
                     }
                 }
             }
-            let mut new_outputs = vec![];
-            for curr_tx_out in curr_block_tx.get_vout() {
-                new_outputs.push(curr_tx_out.clone())
+            let new_coins = Coins::from_transaction(curr_block_tx, block.get_height());
+            if !new_coins.is_pruned() {
+                let _ = utxo_tree
+                    .insert(curr_block_tx.get_id(), new_coins.serialize()?)
+                    .map_err(|e| BtcError::SavingUTXOError(e.to_string()))?;
             }
-            let outs_bytes =
-                bincode::serde::encode_to_vec(&new_outputs, bincode::config::standard())
-                    .map_err(|e| BtcError::TransactionSerializationError(e.to_string()))?;
-            let _ = utxo_tree
-                .insert(curr_block_tx.get_id(), outs_bytes)
-                .map_err(|e| BtcError::SavingUTXOError(e.to_string()))?;
         }
         Ok(())
     }
@@ -295,50 +802,35 @@ impl UTXOSet {
                             // Previously, if the txid was fully removed from the UTXO tree
                             // (all outputs spent), outs_to_restore stayed empty and the
                             // output was lost. Now we always restore the output.
-                            let mut outs_to_restore = if let Some(existing_outs_bytes) = utxo_tree
+                            let mut coins_to_restore = if let Some(existing_outs_bytes) = utxo_tree
                                 .get(curr_blc_tx_inpt.get_txid())
                                 .map_err(|e| BtcError::GettingUTXOError(e.to_string()))?
                             {
                                 // Deserialize existing outputs for this transaction
-                                bincode::serde::decode_from_slice(
-                                    existing_outs_bytes.as_ref(),
-                                    bincode::config::standard(),
-                                )
-                                .map_err(|e| {
-                                    BtcError::TransactionDeserializationError(e.to_string())
-                                })?
-                                .0
+                                Coins::deserialize(existing_outs_bytes.as_ref())?
                             } else {
                                 // Transaction was fully spent — no entry in UTXO tree
-                                // Start with empty vec; we'll insert the restored output below
+                                // Recreate the entry with the height of the block that
+                                // created the transaction; we'll insert the restored output below
                                 info!(
                                     "Restoring fully-spent UTXO for txid: {}",
                                     HEXLOWER.encode(curr_blc_tx_inpt.get_txid())
                                 );
-                                vec![]
+                                let height = self
+                                    .blockchain
+                                    .find_transaction_block(curr_blc_tx_inpt.get_txid())
+                                    .await?
+                                    .map(|input_block| input_block.get_height())
+                                    .unwrap_or_default();
+                                Coins::new(height, input_tx.is_coinbase(), vec![])
                             };
 
-                            // Insert the restored output at the correct vout position
-                            let vout_idx = curr_blc_tx_inpt.get_vout();
-                            if vout_idx <= outs_to_restore.len() {
-                                outs_to_restore.insert(vout_idx, output.clone());
-                            } else {
-                                // Pad with clones of the output up to the required position
-                                while outs_to_restore.len() < vout_idx {
-                                    outs_to_restore.push(output.clone());
-                                }
-                                outs_to_restore.push(output.clone());
-                            }
+                            // Put the restored output back at its vout position
+                            coins_to_restore.restore(curr_blc_tx_inpt.get_vout(), output.clone());
 
                             // Save the restored UTXOs back to the database
-                            let outs_bytes = bincode::serde::encode_to_vec(
-                                &outs_to_restore,
-                                bincode::config::standard(),
-                            )
-                            .map_err(|e| BtcError::TransactionSerializationError(e.to_string()))?;
-
                             utxo_tree
-                                .insert(curr_blc_tx_inpt.get_txid(), outs_bytes)
+                                .insert(curr_blc_tx_inpt.get_txid(), coins_to_restore.serialize()?)
                                 .map_err(|e| BtcError::SavingUTXOError(e.to_string()))?;
                         }
                     }
@@ -349,61 +841,7 @@ impl UTXOSet {
         Ok(())
     }
 
-    pub async fn set_global_mem_pool_flag(&self, tx: &Transaction, flag: bool) -> Result<()> {
-        let db = self.blockchain.get_db().await?;
-        let utxo_tree = db
-            .open_tree(UTXO_TREE)
-            .map_err(|e| BtcError::UTXODBconnection(e.to_string()))?;
-
-        if !tx.is_coinbase() {
-            // Coinbase transactions dont have inputs
-            for curr_tx_inpt in tx.get_vin() {
-                if let Some(curr_tx_inpt_utxo_ivec) = utxo_tree
-                    .get(curr_tx_inpt.get_txid())
-                    .map_err(|e| BtcError::GettingUTXOError(e.to_string()))?
-                {
-                    let mut curr_tx_inpt_utxo_list: Vec<TXOutput> =
-                        bincode::serde::decode_from_slice(
-                            curr_tx_inpt_utxo_ivec.as_ref(),
-                            bincode::config::standard(),
-                        )
-                        .map_err(|e| BtcError::TransactionDeserializationError(e.to_string()))?
-                        .0;
-                    for (utxo_curr_utxo_idx, db_curr_utxo) in
-                        curr_tx_inpt_utxo_list.iter_mut().enumerate()
-                    {
-                        if utxo_curr_utxo_idx == curr_tx_inpt.get_vout() {
-                            // Flag the TXOutput as in global mem pool
-                            db_curr_utxo.set_in_global_mem_pool(flag);
-                            trace!("\n");
-                            trace!("------------------------------------------------------");
-                            debug!("Set TXOUT to Intransit");
-                            trace!("utxo_curr_utxo_idx: {:?}", utxo_curr_utxo_idx);
-                            trace!("db_curr_utxo.get_value(): {:?}", db_curr_utxo.get_value());
-                            for tx_out in tx.get_vout() {
-                                trace!("tx_out.get_value(): {:?}", tx_out.get_value());
-                            }
-                            trace!("------------------------------------------------------");
-                        }
-                    }
-                    trace!("Update UTXO in DB");
-                    let outs_bytes = bincode::serde::encode_to_vec(
-                        &curr_tx_inpt_utxo_list,
-                        bincode::config::standard(),
-                    )
-                    .map_err(|e| BtcError::TransactionSerializationError(e.to_string()))?;
-                    utxo_tree
-                        .insert(curr_tx_inpt.get_txid(), outs_bytes)
-                        .map_err(|e| BtcError::SavingUTXOError(e.to_string()))?;
-                } else {
-                    debug!("TXOUT not found in DB");
-                }
-            }
-        }
-        Ok(())
-    }
-
-    pub async fn get_balance(&self, wlt_address: &WalletAddress) -> Result<i32> {
+    pub async fn get_balance(&self, wlt_address: &WalletAddress) -> Result<Amount> {
         let pub_key_hash = get_pub_key_hash(wlt_address)?;
         debug!("Getting balance for address: {}", wlt_address.as_str());
         debug!("Public key hash: {:?}", pub_key_hash);
@@ -415,15 +853,69 @@ impl UTXOSet {
             wlt_address.as_str()
         );
 
-        let mut balance = 0;
+        let mut balance = Amount::ZERO;
         for (idx, utxo) in utxos.iter().enumerate() {
             debug!("UTXO {}: value = {}", idx, utxo.get_value());
-            balance += utxo.get_value();
+            balance = balance
+                .checked_add(utxo.get_value())
+                .ok_or(BtcError::AmountOverflow)?;
         }
         debug!("Total balance for {}: {}", wlt_address.as_str(), balance);
         Ok(balance)
     }
 
+    ///
+    /// The `get_wallet_balance` function splits the balance of an address into outputs that can be
+    /// spent in the next block and coinbase outputs that have not reached `maturity` yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `wlt_address` - The address whose outputs are summed.
+    /// * `maturity` - Required coinbase depth in blocks.
+    ///
+    /// # Returns
+    ///
+    /// The spendable and immature balances of the address.
+    pub async fn get_wallet_balance(
+        &self,
+        wlt_address: &WalletAddress,
+        maturity: usize,
+    ) -> Result<WalletBalance> {
+        let pub_key_hash = get_pub_key_hash(wlt_address)?;
+        let spend_height = self.blockchain.get_best_height().await? + 1;
+        let db = self.blockchain.get_db().await?;
+        let utxo_tree = db
+            .open_tree(UTXO_TREE)
+            .map_err(|e| BtcError::UTXODBconnection(e.to_string()))?;
+
+        let mut balance = WalletBalance::default();
+        for item in utxo_tree.iter() {
+            let (_, v) = item.map_err(|e| BtcError::GettingUTXOError(e.to_string()))?;
+            let coins = Coins::deserialize(v.as_ref())?;
+            let is_mature = coins.is_mature(spend_height, maturity);
+            for (_, out) in coins.unspent_outputs() {
+                if !out.is_locked_with_key(pub_key_hash.as_slice()) {
+                    continue;
+                }
+                let total = if is_mature {
+                    &mut balance.spendable
+                } else {
+                    &mut balance.immature
+                };
+                *total = total
+                    .checked_add(out.get_value())
+                    .ok_or(BtcError::AmountOverflow)?;
+            }
+        }
+        debug!(
+            "Balance for {}: spendable {}, immature {}",
+            wlt_address.as_str(),
+            balance.spendable,
+            balance.immature
+        );
+        Ok(balance)
+    }
+
     pub async fn utxo_count(&self, wlt_address: &WalletAddress) -> Result<usize> {
         let pub_key_hash = get_pub_key_hash(wlt_address)?;
         debug!("Getting balance for address: {}", wlt_address.as_str());
@@ -1,32 +1,49 @@
+use crate::consensus::{ChainParams, Network};
+use crate::policy::{DEFAULT_MAX_MEMPOOL_SIZE, DEFAULT_MEMPOOL_EXPIRY_HOURS};
 use crate::wallet::WalletAddress;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use std::env;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::sync::RwLock;
+use std::thread;
 
 pub static GLOBAL_CONFIG: Lazy<Config> = Lazy::new(Config::new);
 
-static DEFAULT_NODE_ADDR: SocketAddr =
-    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2001);
+const DEFAULT_NODE_IP: IpAddr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
 
 const NODE_ADDRESS_KEY: &str = "NODE_ADDRESS";
 const MINING_ADDRESS_KEY: &str = "MINING_ADDRESS";
+const NODE_ADDR_ENV: &str = "NODE_ADDR";
+const NETWORK_ENV: &str = "NETWORK";
+const COINBASE_MATURITY_ENV: &str = "COINBASE_MATURITY";
+const MINING_THREADS_ENV: &str = "MINING_THREADS";
+const MAX_MEMPOOL_SIZE_ENV: &str = "MAX_MEMPOOL_SIZE";
+const MEMPOOL_EXPIRY_ENV: &str = "MEMPOOL_EXPIRY";
 
 ///
 /// The `Config` struct manages configuration settings for the blockchain system.
 /// It uses a read-write lock to ensure thread-safe access to the configuration data.
-/// Stores NODE_ADDRESS and MINING_ADDRESS
+/// Stores NODE_ADDRESS, MINING_ADDRESS, the network the node runs on, the coinbase maturity,
+/// the number of proof-of-work threads and the memory pool size and expiry limits. Defaults
+/// that differ between networks (the node port and the coinbase maturity) come from the
+/// network's `ChainParams`
 ///
 pub struct Config {
     node_addresses: RwLock<HashMap<String, SocketAddr>>,
     minner_addresses: RwLock<HashMap<String, WalletAddress>>,
     web_server_enabled: RwLock<bool>,
+    network: RwLock<Network>,
+    coinbase_maturity: RwLock<usize>,
+    mining_threads: RwLock<usize>,
+    max_mempool_size: RwLock<usize>,
+    mempool_expiry_hours: RwLock<u64>,
 }
 
 impl Config {
     pub fn new() -> Config {
-        let node_addr = Config::get_server_addr_port();
+        let network = Config::get_env_network();
+        let node_addr = Config::get_server_addr_port(network);
         let mut map = HashMap::new();
         map.insert(String::from(NODE_ADDRESS_KEY), node_addr);
 
@@ -34,6 +51,11 @@ impl Config {
             node_addresses: RwLock::new(map),
             minner_addresses: RwLock::new(HashMap::new()),
             web_server_enabled: RwLock::new(false),
+            network: RwLock::new(network),
+            coinbase_maturity: RwLock::new(Config::get_env_coinbase_maturity(network)),
+            mining_threads: RwLock::new(Config::get_env_mining_threads()),
+            max_mempool_size: RwLock::new(Config::get_env_max_mempool_size()),
+            mempool_expiry_hours: RwLock::new(Config::get_env_mempool_expiry_hours()),
         }
     }
 
@@ -73,11 +95,162 @@ impl Config {
         miners.contains_key(MINING_ADDRESS_KEY)
     }
 
-    pub fn get_server_addr_port() -> SocketAddr {
-        env::var("NODE_ADDR")
+    ///
+    /// The `set_network` function selects the network whose chain parameters (such as the
+    /// genesis block, ports and subsidy halving interval) the node follows. The node address
+    /// and the coinbase maturity move to the new network's defaults unless their
+    /// environment variables set them.
+    ///
+    /// # Arguments
+    ///
+    /// * `network` - The network to run on.
+    pub fn set_network(&self, network: Network) {
+        let mut current = self.network.write().unwrap();
+        *current = network;
+        if env::var(NODE_ADDR_ENV).is_err() {
+            let mut node_addresses = self.node_addresses.write().unwrap();
+            node_addresses.insert(
+                String::from(NODE_ADDRESS_KEY),
+                Config::get_server_addr_port(network),
+            );
+        }
+        if env::var(COINBASE_MATURITY_ENV).is_err() {
+            self.set_coinbase_maturity(network.get_params().get_coinbase_maturity());
+        }
+    }
+
+    pub fn get_network(&self) -> Network {
+        let network = self.network.read().unwrap();
+        *network
+    }
+
+    /// Parameters of the network the node runs on.
+    pub fn get_chain_params(&self) -> &'static ChainParams {
+        self.get_network().get_params()
+    }
+
+    /// Network named by the `NETWORK` environment variable, mainnet when unset or invalid.
+    pub fn get_env_network() -> Network {
+        env::var(NETWORK_ENV)
+            .ok()
+            .and_then(|network| network.parse().ok())
+            .unwrap_or_default()
+    }
+
+    ///
+    /// The `set_coinbase_maturity` function sets how many blocks a coinbase output must
+    /// wait before it can be spent.
+    ///
+    /// # Arguments
+    ///
+    /// * `maturity` - The required depth in blocks.
+    pub fn set_coinbase_maturity(&self, maturity: usize) {
+        let mut current = self.coinbase_maturity.write().unwrap();
+        *current = maturity;
+    }
+
+    pub fn get_coinbase_maturity(&self) -> usize {
+        let coinbase_maturity = self.coinbase_maturity.read().unwrap();
+        *coinbase_maturity
+    }
+
+    /// Maturity named by the `COINBASE_MATURITY` environment variable, the maturity of
+    /// `network` when unset or invalid.
+    pub fn get_env_coinbase_maturity(network: Network) -> usize {
+        env::var(COINBASE_MATURITY_ENV)
+            .ok()
+            .and_then(|maturity| maturity.parse().ok())
+            .unwrap_or_else(|| network.get_params().get_coinbase_maturity())
+    }
+
+    ///
+    /// The `set_mining_threads` function sets how many threads search for proof-of-work
+    /// nonces in parallel.
+    ///
+    /// # Arguments
+    ///
+    /// * `threads` - The number of worker threads; zero is treated as one.
+    pub fn set_mining_threads(&self, threads: usize) {
+        let mut current = self.mining_threads.write().unwrap();
+        *current = threads.max(1);
+    }
+
+    pub fn get_mining_threads(&self) -> usize {
+        let mining_threads = self.mining_threads.read().unwrap();
+        *mining_threads
+    }
+
+    /// Thread count named by the `MINING_THREADS` environment variable, one per available
+    /// CPU when unset or invalid.
+    pub fn get_env_mining_threads() -> usize {
+        env::var(MINING_THREADS_ENV)
+            .ok()
+            .and_then(|threads| threads.parse().ok())
+            .filter(|threads| *threads > 0)
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()))
+    }
+
+    ///
+    /// The `set_max_mempool_size` function bounds the serialized size of all memory pool
+    /// transactions; past it, the transactions paying the lowest fee rate are evicted.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The largest total size in bytes.
+    pub fn set_max_mempool_size(&self, bytes: usize) {
+        let mut current = self.max_mempool_size.write().unwrap();
+        *current = bytes;
+    }
+
+    pub fn get_max_mempool_size(&self) -> usize {
+        let max_mempool_size = self.max_mempool_size.read().unwrap();
+        *max_mempool_size
+    }
+
+    /// Size named by the `MAX_MEMPOOL_SIZE` environment variable, in bytes,
+    /// `DEFAULT_MAX_MEMPOOL_SIZE` when unset or invalid.
+    pub fn get_env_max_mempool_size() -> usize {
+        env::var(MAX_MEMPOOL_SIZE_ENV)
+            .ok()
+            .and_then(|bytes| bytes.parse().ok())
+            .unwrap_or(DEFAULT_MAX_MEMPOOL_SIZE)
+    }
+
+    ///
+    /// The `set_mempool_expiry_hours` function sets how long a transaction may wait in the
+    /// memory pool without being mined before it is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `hours` - The largest age in hours.
+    pub fn set_mempool_expiry_hours(&self, hours: u64) {
+        let mut current = self.mempool_expiry_hours.write().unwrap();
+        *current = hours;
+    }
+
+    pub fn get_mempool_expiry_hours(&self) -> u64 {
+        let mempool_expiry_hours = self.mempool_expiry_hours.read().unwrap();
+        *mempool_expiry_hours
+    }
+
+    /// Expiry named by the `MEMPOOL_EXPIRY` environment variable, in hours,
+    /// `DEFAULT_MEMPOOL_EXPIRY_HOURS` when unset or invalid.
+    pub fn get_env_mempool_expiry_hours() -> u64 {
+        env::var(MEMPOOL_EXPIRY_ENV)
+            .ok()
+            .and_then(|hours| hours.parse().ok())
+            .unwrap_or(DEFAULT_MEMPOOL_EXPIRY_HOURS)
+    }
+
+    /// Address named by the `NODE_ADDR` environment variable, the loopback address on the
+    /// default port of `network` when unset or invalid.
+    pub fn get_server_addr_port(network: Network) -> SocketAddr {
+        env::var(NODE_ADDR_ENV)
             .ok()
             .and_then(|node| node.parse().ok())
-            .unwrap_or(DEFAULT_NODE_ADDR)
+            .unwrap_or_else(|| {
+                SocketAddr::new(DEFAULT_NODE_IP, network.get_params().get_default_port())
+            })
     }
 }
 
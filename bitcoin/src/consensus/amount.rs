@@ -0,0 +1,161 @@
+//! Monetary amounts (Bitcoin Core: consensus/amount.h)
+//!
+//! Every value on the chain - output values, the block subsidy, fees and balances - is an
+//! `Amount`: a whole number of satoshis held in a `u64`. Negative values cannot be
+//! represented, and arithmetic is checked so that an overflow is reported instead of
+//! wrapping. `MAX_MONEY` bounds any single value or sum that consensus accepts.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use utoipa::ToSchema;
+
+/// Number of satoshis in one bitcoin.
+pub const COIN: u64 = 100_000_000;
+
+/// Largest amount consensus accepts for an output, a transaction total or a block reward
+/// (Bitcoin Core's `MAX_MONEY`: the 21 million coins that will ever exist).
+pub const MAX_MONEY: Amount = Amount(21_000_000 * COIN);
+
+/// Unit an amount is displayed in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Denomination {
+    /// 1 BTC = 100,000,000 satoshis, shown with 8 decimal places
+    Bitcoin,
+    /// The base unit
+    Satoshi,
+}
+
+/// An amount of satoshis (Bitcoin Core's `CAmount`, without the negative range).
+///
+/// Serialized as a plain integer, so JSON and the wire format carry the satoshi count.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    ToSchema,
+)]
+#[serde(transparent)]
+#[schema(value_type = u64)]
+pub struct Amount(u64);
+
+impl Amount {
+    pub const ZERO: Amount = Amount(0);
+
+    pub const fn from_sat(satoshis: u64) -> Amount {
+        Amount(satoshis)
+    }
+
+    pub const fn to_sat(self) -> u64 {
+        self.0
+    }
+
+    pub fn checked_add(self, rhs: Amount) -> Option<Amount> {
+        self.0.checked_add(rhs.0).map(Amount)
+    }
+
+    pub fn checked_sub(self, rhs: Amount) -> Option<Amount> {
+        self.0.checked_sub(rhs.0).map(Amount)
+    }
+
+    pub fn checked_mul(self, rhs: u64) -> Option<Amount> {
+        self.0.checked_mul(rhs).map(Amount)
+    }
+
+    /// Sum amounts, returning `None` on overflow.
+    ///
+    /// # Arguments
+    ///
+    /// * `amounts` - The amounts to add up.
+    pub fn checked_sum<I: IntoIterator<Item = Amount>>(amounts: I) -> Option<Amount> {
+        amounts
+            .into_iter()
+            .try_fold(Amount::ZERO, |total, amount| total.checked_add(amount))
+    }
+
+    /// Whether the amount lies within `0..=MAX_MONEY` (Bitcoin Core's `MoneyRange`).
+    pub fn is_money_range(self) -> bool {
+        self <= MAX_MONEY
+    }
+
+    /// Format the amount followed by its unit, e.g. `0.00000010 BTC` or `10 sat`.
+    ///
+    /// # Arguments
+    ///
+    /// * `denomination` - The unit to display the amount in.
+    pub fn to_string_in(self, denomination: Denomination) -> String {
+        match denomination {
+            Denomination::Bitcoin => {
+                format!("{}.{:08} BTC", self.0 / COIN, self.0 % COIN)
+            }
+            Denomination::Satoshi => format!("{} sat", self.0),
+        }
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_string_in(Denomination::Bitcoin))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let five = Amount::from_sat(5);
+        assert_eq!(five.checked_add(five), Some(Amount::from_sat(10)));
+        assert_eq!(five.checked_sub(Amount::from_sat(6)), None);
+        assert_eq!(Amount::from_sat(u64::MAX).checked_add(five), None);
+        assert_eq!(five.checked_mul(3), Some(Amount::from_sat(15)));
+        assert_eq!(Amount::from_sat(u64::MAX).checked_mul(2), None);
+        assert_eq!(
+            Amount::checked_sum([five, five, five]),
+            Some(Amount::from_sat(15))
+        );
+        assert_eq!(
+            Amount::checked_sum([Amount::from_sat(u64::MAX), five]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_money_range() {
+        assert!(Amount::ZERO.is_money_range());
+        assert!(MAX_MONEY.is_money_range());
+        assert!(
+            !MAX_MONEY
+                .checked_add(Amount::from_sat(1))
+                .unwrap()
+                .is_money_range()
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Amount::from_sat(10).to_string(), "0.00000010 BTC");
+        assert_eq!(Amount::from_sat(2 * COIN + 5).to_string(), "2.00000005 BTC");
+        assert_eq!(
+            Amount::from_sat(10).to_string_in(Denomination::Satoshi),
+            "10 sat"
+        );
+    }
+
+    #[test]
+    fn test_serializes_as_integer() {
+        let json = serde_json::to_string(&Amount::from_sat(42)).unwrap();
+        assert_eq!(json, "42");
+        let amount: Amount = serde_json::from_str("42").unwrap();
+        assert_eq!(amount, Amount::from_sat(42));
+        assert!(serde_json::from_str::<Amount>("-1").is_err());
+    }
+}
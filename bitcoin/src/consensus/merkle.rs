@@ -0,0 +1,312 @@
+//! # Merkle Tree
+//!
+//! Bitcoin-style Merkle tree over the transaction ids of a block (Bitcoin Core: consensus/merkle.cpp).
+//!
+//! Leaves are the txids in block order. Each level is built by hashing adjacent pairs with
+//! double SHA-256; when a level has an odd number of nodes the last node is paired with itself
+//! (the "duplicate last leaf" rule). The single remaining hash is the Merkle root committed
+//! to in the block header.
+//!
+//! A [`MerkleProof`] carries the sibling hashes on the path from one leaf to the root, which lets
+//! a light client check that a transaction is in a block knowing only the block header.
+
+use crate::crypto::sha256d;
+use crate::error::{BtcError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Merkle root of a block without transactions.
+const EMPTY_MERKLE_ROOT: [u8; 32] = [0u8; 32];
+
+/// Hash two child nodes into their parent: `SHA256(SHA256(left || right))`.
+fn hash_pair(left: &[u8], right: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(left.len() + right.len());
+    data.extend_from_slice(left);
+    data.extend_from_slice(right);
+    sha256d(data.as_slice())
+}
+
+/// Build the next level of the tree, duplicating the last node if the level is odd.
+fn next_level(level: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_pair(left, right),
+            [single] => hash_pair(single, single),
+            _ => unreachable!("chunks(2) yields one or two elements"),
+        })
+        .collect()
+}
+
+/// Compute the Merkle root of a list of leaves (transaction ids).
+///
+/// # Arguments
+///
+/// * `leaves` - The transaction ids in block order.
+///
+/// # Returns
+///
+/// The 32-byte Merkle root. A single leaf is its own root and an empty list yields all zeros.
+///
+/// # Note
+///
+/// As in Bitcoin Core, duplicating the last leaf means a transaction list ending in a repeated
+/// transaction has the same root as the list without it (CVE-2012-2459). Block validation must
+/// therefore reject blocks containing duplicate transactions.
+pub fn compute_merkle_root(leaves: &[Vec<u8>]) -> Vec<u8> {
+    if leaves.is_empty() {
+        return EMPTY_MERKLE_ROOT.to_vec();
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level.remove(0)
+}
+
+/// Compute the Merkle branch (sibling hashes from leaf to root) for the leaf at `index`.
+///
+/// # Arguments
+///
+/// * `leaves` - The transaction ids in block order.
+/// * `index` - Position of the leaf to prove.
+///
+/// # Returns
+///
+/// `Some(branch)` with one sibling hash per tree level, or `None` if `index` is out of range.
+pub fn compute_merkle_branch(leaves: &[Vec<u8>], index: usize) -> Option<Vec<Vec<u8>>> {
+    if index >= leaves.len() {
+        return None;
+    }
+    let mut branch = vec![];
+    let mut level = leaves.to_vec();
+    let mut position = index;
+    while level.len() > 1 {
+        // The last node of an odd level is its own sibling
+        let sibling = (position ^ 1).min(level.len() - 1);
+        branch.push(level[sibling].clone());
+        level = next_level(&level);
+        position /= 2;
+    }
+    Some(branch)
+}
+
+/// Number of levels below the root of a tree over `leaf_count` leaves, which is the length
+/// of every Merkle branch in it: `ceil(log2(leaf_count))`, zero for a single leaf.
+///
+/// A shorter branch that still hashes to the root starts from an interior node rather than a
+/// transaction id, so proofs must have exactly this length.
+pub fn merkle_branch_length(leaf_count: usize) -> usize {
+    leaf_count.max(1).next_power_of_two().trailing_zeros() as usize
+}
+
+/// Recompute the Merkle root from a leaf, its position and its Merkle branch.
+///
+/// # Arguments
+///
+/// * `leaf` - The transaction id being proven.
+/// * `index` - Position of the leaf in the block.
+/// * `branch` - Sibling hashes from the leaf level up to just below the root.
+///
+/// # Returns
+///
+/// The Merkle root implied by the branch.
+pub fn root_from_branch(leaf: &[u8], index: usize, branch: &[Vec<u8>]) -> Vec<u8> {
+    let mut hash = leaf.to_vec();
+    let mut position = index;
+    for sibling in branch {
+        hash = if position & 1 == 1 {
+            hash_pair(sibling, &hash)
+        } else {
+            hash_pair(&hash, sibling)
+        };
+        position >>= 1;
+    }
+    hash
+}
+
+/// Proof that a transaction is included in a block (Bitcoin Core: `gettxoutproof` output).
+///
+/// `block_hash`: Hash of the block containing the transaction.
+/// `merkle_root`: The Merkle root committed to in that block's header.
+/// `txid`: The transaction id being proven.
+/// `index`: Position of the transaction in the block.
+/// `branch`: Sibling hashes from the transaction up to the root.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    block_hash: String,
+    merkle_root: Vec<u8>,
+    txid: Vec<u8>,
+    index: usize,
+    branch: Vec<Vec<u8>>,
+}
+
+impl MerkleProof {
+    pub fn new(
+        block_hash: String,
+        merkle_root: Vec<u8>,
+        txid: Vec<u8>,
+        index: usize,
+        branch: Vec<Vec<u8>>,
+    ) -> MerkleProof {
+        MerkleProof {
+            block_hash,
+            merkle_root,
+            txid,
+            index,
+            branch,
+        }
+    }
+
+    pub fn get_block_hash(&self) -> &str {
+        self.block_hash.as_str()
+    }
+
+    pub fn get_merkle_root(&self) -> &[u8] {
+        self.merkle_root.as_slice()
+    }
+
+    pub fn get_txid(&self) -> &[u8] {
+        self.txid.as_slice()
+    }
+
+    pub fn get_index(&self) -> usize {
+        self.index
+    }
+
+    pub fn get_branch(&self) -> &[Vec<u8>] {
+        self.branch.as_slice()
+    }
+
+    /// Check that the branch links the transaction id to the Merkle root in this proof.
+    ///
+    /// This only proves internal consistency. Callers must also check that `merkle_root`
+    /// matches the header of a block they trust (see `NodeContext::verify_tx_out_proof`).
+    pub fn verify(&self) -> bool {
+        root_from_branch(&self.txid, self.index, &self.branch) == self.merkle_root
+    }
+
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        bincode::serde::encode_to_vec(self, bincode::config::standard())
+            .map_err(|e| BtcError::MerkleProofSerializationError(e.to_string()))
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Result<MerkleProof> {
+        bincode::serde::decode_from_slice(bytes, bincode::config::standard())
+            .map_err(|e| BtcError::MerkleProofDeserializationError(e.to_string()))
+            .map(|(proof, _)| proof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::sha256_digest;
+
+    fn leaves(count: u8) -> Vec<Vec<u8>> {
+        (0..count).map(|i| sha256_digest(&[i])).collect()
+    }
+
+    #[test]
+    fn test_empty_merkle_root() {
+        assert_eq!(compute_merkle_root(&[]), EMPTY_MERKLE_ROOT.to_vec());
+    }
+
+    #[test]
+    fn test_single_leaf_is_root() {
+        let leaves = leaves(1);
+        assert_eq!(compute_merkle_root(&leaves), leaves[0]);
+        assert_eq!(compute_merkle_branch(&leaves, 0), Some(vec![]));
+    }
+
+    #[test]
+    fn test_two_leaves() {
+        let leaves = leaves(2);
+        assert_eq!(
+            compute_merkle_root(&leaves),
+            hash_pair(&leaves[0], &leaves[1])
+        );
+    }
+
+    #[test]
+    fn test_odd_level_duplicates_last_leaf() {
+        let leaves = leaves(3);
+        let left = hash_pair(&leaves[0], &leaves[1]);
+        let right = hash_pair(&leaves[2], &leaves[2]);
+        assert_eq!(compute_merkle_root(&leaves), hash_pair(&left, &right));
+
+        // Appending a copy of the last leaf does not change the root (CVE-2012-2459)
+        let mut mutated = leaves.clone();
+        mutated.push(leaves[2].clone());
+        assert_eq!(compute_merkle_root(&mutated), compute_merkle_root(&leaves));
+    }
+
+    #[test]
+    fn test_branch_round_trip_for_every_leaf() {
+        for count in 1..=9 {
+            let leaves = leaves(count);
+            let root = compute_merkle_root(&leaves);
+            for (index, leaf) in leaves.iter().enumerate() {
+                let branch = compute_merkle_branch(&leaves, index).expect("index in range");
+                assert_eq!(root_from_branch(leaf, index, &branch), root);
+            }
+        }
+    }
+
+    #[test]
+    fn test_branch_length_matches_tree_depth() {
+        for count in 1..=9u8 {
+            let leaves = leaves(count);
+            let branch = compute_merkle_branch(&leaves, 0).unwrap();
+            assert_eq!(merkle_branch_length(leaves.len()), branch.len());
+        }
+        assert_eq!(merkle_branch_length(1), 0);
+        assert_eq!(merkle_branch_length(5), 3);
+    }
+
+    #[test]
+    fn test_branch_out_of_range() {
+        assert_eq!(compute_merkle_branch(&leaves(3), 3), None);
+    }
+
+    #[test]
+    fn test_proof_verify_and_tamper() {
+        let leaves = leaves(5);
+        let root = compute_merkle_root(&leaves);
+        let branch = compute_merkle_branch(&leaves, 3).unwrap();
+        let proof = MerkleProof::new(
+            "block".to_string(),
+            root.clone(),
+            leaves[3].clone(),
+            3,
+            branch.clone(),
+        );
+        assert!(proof.verify());
+
+        let wrong_index = MerkleProof::new(
+            "block".to_string(),
+            root.clone(),
+            leaves[3].clone(),
+            2,
+            branch.clone(),
+        );
+        assert!(!wrong_index.verify());
+
+        let wrong_txid = MerkleProof::new("block".to_string(), root, leaves[4].clone(), 3, branch);
+        assert!(!wrong_txid.verify());
+    }
+
+    #[test]
+    fn test_proof_serialization() {
+        let leaves = leaves(4);
+        let proof = MerkleProof::new(
+            "block".to_string(),
+            compute_merkle_root(&leaves),
+            leaves[1].clone(),
+            1,
+            compute_merkle_branch(&leaves, 1).unwrap(),
+        );
+        let bytes = proof.serialize().unwrap();
+        assert_eq!(MerkleProof::deserialize(&bytes).unwrap(), proof);
+        assert!(MerkleProof::deserialize(&[0xff]).is_err());
+    }
+}
@@ -0,0 +1,28 @@
+//! Consensus module (Bitcoin Core alignment)
+//!
+//! Pure consensus rules that do not depend on chain state, following Bitcoin Core's
+//! `consensus/` directory:
+//! - consensus/amount.h - Monetary amounts and `MAX_MONEY`
+//! - consensus/merkle.h - Merkle root computation and inclusion proofs
+//! - consensus/params.h - Per-network consensus parameters
+//! - validation.cpp `GetBlockSubsidy` - Block subsidy halving schedule
+//! - consensus/validation.h - Context-free block checks (`CheckBlock`)
+//! - consensus/tx_verify.h - Transaction finality and relative lock times (BIP68/BIP113)
+
+pub mod amount;
+pub mod merkle;
+pub mod params;
+pub mod subsidy;
+pub mod tx_verify;
+pub mod validation;
+
+pub use amount::{Amount, COIN, Denomination, MAX_MONEY};
+pub use merkle::{MerkleProof, compute_merkle_branch, compute_merkle_root, root_from_branch};
+pub use params::{
+    COINBASE_MATURITY, ChainParams, MAINNET_PARAMS, Network, REGTEST_PARAMS, TESTNET_PARAMS,
+};
+pub use subsidy::{INITIAL_SUBSIDY, get_block_subsidy, get_total_subsidy};
+pub use tx_verify::{
+    RelativeLockTime, SequenceLocks, calculate_sequence_locks, evaluate_sequence_locks, is_final_tx,
+};
+pub use validation::{MAX_FUTURE_BLOCK_TIME_MS, MEDIAN_TIME_SPAN, check_block, median_time_past};
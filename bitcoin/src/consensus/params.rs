@@ -0,0 +1,303 @@
+//! Consensus parameters (Bitcoin Core: consensus/params.h, kernel/chainparams.cpp)
+//!
+//! Parameters that differ between networks. Each network keeps its own halving
+//! interval so a test network can walk through the emission schedule in a few hundred
+//! blocks while mainnet follows Bitcoin's 210,000 block eras.
+//!
+//! `ChainParams` gathers everything else a network fixes: its genesis block, the bytes
+//! that start every message, default ports, address version bytes and proof-of-work
+//! rules. Nodes on different networks therefore never accept each other's messages,
+//! blocks or addresses.
+
+use crate::consensus::Amount;
+use crate::consensus::subsidy::get_block_subsidy;
+use crate::error::{BtcError, Result};
+use crate::pow::{DIFFICULTY_ADJUSTMENT_INTERVAL, POW_LIMIT_BITS, TARGET_BLOCK_SPACING_MS};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+/// Blocks a coinbase output must wait before it can be spent (Bitcoin Core's
+/// `COINBASE_MATURITY`, consensus/consensus.h).
+pub const COINBASE_MATURITY: usize = 100;
+
+/// Network the node runs on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Testnet,
+    Regtest,
+}
+
+/// Parameters of one network (Bitcoin Core's `CChainParams`).
+///
+/// `network`: The network the parameters belong to.
+/// `chain_name`: Short name reported by `getmininginfo` and `getblockchaininfo`
+/// ("main", "test" or "regtest").
+/// `message_start`: Bytes every peer-to-peer connection starts with (Bitcoin Core's
+/// `pchMessageStart`); connections carrying another network's bytes are dropped.
+/// `default_port`: Peer-to-peer port used when `NODE_ADDR` does not name one.
+/// `default_web_port`: Port of the REST API.
+/// `pub_key_address_version`: Version byte of pay-to-public-key-hash (wallet) addresses.
+/// `script_address_version`: Version byte of pay-to-script-hash addresses.
+/// `genesis_message`: Data the genesis coinbase pushes after its height and extra nonce,
+/// which makes the genesis block of every network different.
+/// `pow_limit_bits`: Easiest target a block may use, in compact form; the genesis block
+/// uses it.
+/// `pow_no_retargeting`: Whether the target stays at its genesis value (regtest).
+/// `difficulty_adjustment_interval`: Blocks between difficulty adjustments.
+/// `target_block_spacing_ms`: Desired time between blocks in milliseconds.
+/// `subsidy_halving_interval`: Blocks between subsidy halvings.
+/// `coinbase_maturity`: Blocks a coinbase output must wait before it can be spent.
+/// `mining_transaction_threshold`: Memory pool size at which a miner starts a block, or
+/// `None` when blocks are only produced on demand by `generatetoaddress` (regtest).
+#[derive(Debug, PartialEq, Eq)]
+pub struct ChainParams {
+    network: Network,
+    chain_name: &'static str,
+    message_start: [u8; 4],
+    default_port: u16,
+    default_web_port: u16,
+    pub_key_address_version: u8,
+    script_address_version: u8,
+    genesis_message: &'static [u8],
+    pow_limit_bits: u32,
+    pow_no_retargeting: bool,
+    difficulty_adjustment_interval: usize,
+    target_block_spacing_ms: i64,
+    subsidy_halving_interval: usize,
+    coinbase_maturity: usize,
+    mining_transaction_threshold: Option<usize>,
+}
+
+/// Parameters of the main network.
+pub static MAINNET_PARAMS: ChainParams = ChainParams {
+    network: Network::Mainnet,
+    chain_name: "main",
+    message_start: [0xf9, 0xbe, 0xb4, 0xd9],
+    default_port: 2001,
+    default_web_port: 8080,
+    pub_key_address_version: 0x01,
+    script_address_version: 0x05,
+    genesis_message: b"blockchain mainnet genesis",
+    pow_limit_bits: POW_LIMIT_BITS,
+    pow_no_retargeting: false,
+    difficulty_adjustment_interval: DIFFICULTY_ADJUSTMENT_INTERVAL,
+    target_block_spacing_ms: TARGET_BLOCK_SPACING_MS,
+    subsidy_halving_interval: 210_000,
+    coinbase_maturity: COINBASE_MATURITY,
+    mining_transaction_threshold: Some(3),
+};
+
+/// Parameters of the public test network: mainnet's rules under its own genesis block,
+/// message start, ports and address versions.
+pub static TESTNET_PARAMS: ChainParams = ChainParams {
+    network: Network::Testnet,
+    chain_name: "test",
+    message_start: [0x0b, 0x11, 0x09, 0x07],
+    default_port: 12001,
+    default_web_port: 18080,
+    pub_key_address_version: 0x6f,
+    script_address_version: 0xc4,
+    genesis_message: b"blockchain testnet genesis",
+    pow_limit_bits: POW_LIMIT_BITS,
+    pow_no_retargeting: false,
+    difficulty_adjustment_interval: DIFFICULTY_ADJUSTMENT_INTERVAL,
+    target_block_spacing_ms: TARGET_BLOCK_SPACING_MS,
+    subsidy_halving_interval: 210_000,
+    coinbase_maturity: COINBASE_MATURITY,
+    mining_transaction_threshold: Some(3),
+};
+
+/// Parameters of the local regression test network: a trivial fixed target, halvings
+/// every 150 blocks and no automatic mining, so tests decide when blocks are made.
+pub static REGTEST_PARAMS: ChainParams = ChainParams {
+    network: Network::Regtest,
+    chain_name: "regtest",
+    message_start: [0xfa, 0xbf, 0xb5, 0xda],
+    default_port: 22001,
+    default_web_port: 28080,
+    pub_key_address_version: 0x6f,
+    script_address_version: 0xc4,
+    genesis_message: b"blockchain regtest genesis",
+    pow_limit_bits: 0x207f_ffff,
+    pow_no_retargeting: true,
+    difficulty_adjustment_interval: DIFFICULTY_ADJUSTMENT_INTERVAL,
+    target_block_spacing_ms: TARGET_BLOCK_SPACING_MS,
+    subsidy_halving_interval: 150,
+    coinbase_maturity: COINBASE_MATURITY,
+    mining_transaction_threshold: None,
+};
+
+impl ChainParams {
+    pub fn get_network(&self) -> Network {
+        self.network
+    }
+
+    pub fn get_chain_name(&self) -> &'static str {
+        self.chain_name
+    }
+
+    pub fn get_message_start(&self) -> [u8; 4] {
+        self.message_start
+    }
+
+    pub fn get_default_port(&self) -> u16 {
+        self.default_port
+    }
+
+    pub fn get_default_web_port(&self) -> u16 {
+        self.default_web_port
+    }
+
+    pub fn get_pub_key_address_version(&self) -> u8 {
+        self.pub_key_address_version
+    }
+
+    pub fn get_script_address_version(&self) -> u8 {
+        self.script_address_version
+    }
+
+    pub fn get_genesis_message(&self) -> &'static [u8] {
+        self.genesis_message
+    }
+
+    pub fn get_pow_limit_bits(&self) -> u32 {
+        self.pow_limit_bits
+    }
+
+    pub fn is_pow_no_retargeting(&self) -> bool {
+        self.pow_no_retargeting
+    }
+
+    pub fn get_difficulty_adjustment_interval(&self) -> usize {
+        self.difficulty_adjustment_interval
+    }
+
+    pub fn get_target_block_spacing_ms(&self) -> i64 {
+        self.target_block_spacing_ms
+    }
+
+    /// Expected duration of one retarget window (Bitcoin Core's `nPowTargetTimespan`).
+    pub fn get_target_timespan_ms(&self) -> i64 {
+        self.target_block_spacing_ms * self.difficulty_adjustment_interval as i64
+    }
+
+    pub fn get_subsidy_halving_interval(&self) -> usize {
+        self.subsidy_halving_interval
+    }
+
+    pub fn get_coinbase_maturity(&self) -> usize {
+        self.coinbase_maturity
+    }
+
+    pub fn get_mining_transaction_threshold(&self) -> Option<usize> {
+        self.mining_transaction_threshold
+    }
+}
+
+impl Network {
+    /// Parameters of this network (Bitcoin Core's `Params()` after `SelectParams`).
+    pub fn get_params(&self) -> &'static ChainParams {
+        match self {
+            Network::Mainnet => &MAINNET_PARAMS,
+            Network::Testnet => &TESTNET_PARAMS,
+            Network::Regtest => &REGTEST_PARAMS,
+        }
+    }
+
+    /// Number of blocks between subsidy halvings (Bitcoin Core's `nSubsidyHalvingInterval`).
+    pub fn get_subsidy_halving_interval(&self) -> usize {
+        self.get_params().get_subsidy_halving_interval()
+    }
+
+    /// Block subsidy paid at `height` on this network.
+    ///
+    /// # Arguments
+    ///
+    /// * `height` - Height of the block the coinbase belongs to (genesis is height 1).
+    pub fn get_block_subsidy(&self, height: usize) -> Amount {
+        get_block_subsidy(height, self.get_subsidy_halving_interval())
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Network::Mainnet => "mainnet",
+            Network::Testnet => "testnet",
+            Network::Regtest => "regtest",
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Network {
+    type Err = BtcError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "mainnet" | "main" => Ok(Network::Mainnet),
+            "testnet" | "test" => Ok(Network::Testnet),
+            "regtest" => Ok(Network::Regtest),
+            _ => Err(BtcError::InvalidNetwork(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::subsidy::INITIAL_SUBSIDY;
+
+    #[test]
+    fn test_parse_network() {
+        assert_eq!(Network::from_str("mainnet").unwrap(), Network::Mainnet);
+        assert_eq!(Network::from_str("Regtest").unwrap(), Network::Regtest);
+        assert!(Network::from_str("signet").is_err());
+        assert_eq!(Network::Testnet.to_string(), "testnet");
+    }
+
+    #[test]
+    fn test_halving_interval_per_network() {
+        assert_eq!(Network::Mainnet.get_subsidy_halving_interval(), 210_000);
+        assert_eq!(Network::Regtest.get_subsidy_halving_interval(), 150);
+        assert_eq!(Network::Regtest.get_block_subsidy(150), INITIAL_SUBSIDY);
+        assert_eq!(
+            Network::Regtest.get_block_subsidy(151),
+            Amount::from_sat(INITIAL_SUBSIDY.to_sat() / 2)
+        );
+        assert_eq!(Network::Mainnet.get_block_subsidy(151), INITIAL_SUBSIDY);
+    }
+
+    #[test]
+    fn test_networks_do_not_share_identifying_params() {
+        let all = [&MAINNET_PARAMS, &TESTNET_PARAMS, &REGTEST_PARAMS];
+        for (idx, params) in all.iter().enumerate() {
+            assert_eq!(params.get_network().get_params(), *params);
+            for other in &all[idx + 1..] {
+                assert_ne!(params.get_message_start(), other.get_message_start());
+                assert_ne!(params.get_default_port(), other.get_default_port());
+                assert_ne!(params.get_genesis_message(), other.get_genesis_message());
+                assert_ne!(params.get_chain_name(), other.get_chain_name());
+            }
+        }
+        assert_ne!(
+            MAINNET_PARAMS.get_pub_key_address_version(),
+            TESTNET_PARAMS.get_pub_key_address_version()
+        );
+        assert_ne!(
+            MAINNET_PARAMS.get_script_address_version(),
+            TESTNET_PARAMS.get_script_address_version()
+        );
+        assert!(REGTEST_PARAMS.is_pow_no_retargeting());
+        assert!(!MAINNET_PARAMS.is_pow_no_retargeting());
+        assert_eq!(REGTEST_PARAMS.get_mining_transaction_threshold(), None);
+    }
+}
@@ -0,0 +1,98 @@
+//! Block subsidy schedule (Bitcoin Core: validation.cpp `GetBlockSubsidy`)
+//!
+//! The coinbase of every block may mint a subsidy on top of the fees it collects. The
+//! subsidy starts at `INITIAL_SUBSIDY` and is halved (shifted right) every
+//! `halving_interval` blocks until it reaches zero, which caps the total supply.
+//!
+//! Heights in this chain start at 1 for the genesis block, so the first era covers
+//! heights `1..=halving_interval`, the second the next `halving_interval` blocks, and so on.
+
+use crate::consensus::Amount;
+
+/// Subsidy paid by blocks of the first era.
+pub const INITIAL_SUBSIDY: Amount = Amount::from_sat(10);
+
+/// Number of completed halvings at `height`.
+fn get_halvings(height: usize, halving_interval: usize) -> usize {
+    height
+        .saturating_sub(1)
+        .checked_div(halving_interval)
+        .unwrap_or(0)
+}
+
+/// Subsidy a block at `height` may mint.
+///
+/// # Arguments
+///
+/// * `height` - Height of the block (genesis is height 1).
+/// * `halving_interval` - Blocks per era; `0` disables halving.
+///
+/// # Returns
+///
+/// `INITIAL_SUBSIDY` shifted right once per completed era, or zero once every bit has
+/// been shifted out.
+pub fn get_block_subsidy(height: usize, halving_interval: usize) -> Amount {
+    let halvings = get_halvings(height, halving_interval);
+    // Shifting a u64 by 64 or more is undefined, so the subsidy is zero from then on
+    if halvings >= 64 {
+        return Amount::ZERO;
+    }
+    Amount::from_sat(INITIAL_SUBSIDY.to_sat() >> halvings)
+}
+
+/// Total subsidy minted by the blocks at heights `1..=height`.
+///
+/// Summed era by era, so it is cheap even for a large height. Comparing it against the
+/// value held in the UTXO set shows whether any coinbase claimed less than allowed or
+/// whether coins were destroyed.
+///
+/// # Arguments
+///
+/// * `height` - Height of the chain tip.
+/// * `halving_interval` - Blocks per era; `0` disables halving.
+pub fn get_total_subsidy(height: usize, halving_interval: usize) -> Amount {
+    if halving_interval == 0 {
+        return Amount::from_sat(INITIAL_SUBSIDY.to_sat().saturating_mul(height as u64));
+    }
+    let mut total: u64 = 0;
+    let mut era_start = 1;
+    while era_start <= height {
+        let subsidy = get_block_subsidy(era_start, halving_interval);
+        if subsidy == Amount::ZERO {
+            break;
+        }
+        let era_end = height.min(era_start.saturating_add(halving_interval - 1));
+        let blocks = (era_end - era_start + 1) as u64;
+        total = total.saturating_add(subsidy.to_sat().saturating_mul(blocks));
+        era_start = era_end.saturating_add(1);
+    }
+    Amount::from_sat(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_subsidy_halves_every_interval() {
+        assert_eq!(get_block_subsidy(1, 4), Amount::from_sat(10));
+        assert_eq!(get_block_subsidy(4, 4), Amount::from_sat(10));
+        assert_eq!(get_block_subsidy(5, 4), Amount::from_sat(5));
+        assert_eq!(get_block_subsidy(9, 4), Amount::from_sat(2));
+        assert_eq!(get_block_subsidy(13, 4), Amount::from_sat(1));
+        assert_eq!(get_block_subsidy(17, 4), Amount::ZERO);
+        assert_eq!(get_block_subsidy(usize::MAX, 1), Amount::ZERO);
+        assert_eq!(get_block_subsidy(1_000, 0), INITIAL_SUBSIDY);
+    }
+
+    #[test]
+    fn test_total_subsidy_matches_block_by_block_sum() {
+        for height in 0..40 {
+            let expected = Amount::checked_sum((1..=height).map(|h| get_block_subsidy(h, 4)));
+            assert_eq!(Some(get_total_subsidy(height, 4)), expected);
+        }
+        // 4 * (10 + 5 + 2 + 1): nothing is minted after the fourth era
+        assert_eq!(get_total_subsidy(1_000_000, 4), Amount::from_sat(72));
+        assert_eq!(get_total_subsidy(3, 0), Amount::from_sat(30));
+    }
+}
@@ -0,0 +1,229 @@
+//! Transaction finality and relative lock times (Bitcoin Core's `consensus/tx_verify.cpp`)
+//!
+//! A transaction's absolute lock time (nLockTime) and the relative lock time of each of its
+//! inputs (BIP68) keep it out of blocks until a height or a time. Times are compared against
+//! the median time past of the chain (BIP113) rather than block timestamps, which miners
+//! choose. Like every chain time here they are in milliseconds; lock times themselves
+//! count seconds, as in Bitcoin.
+//!
+//! These functions are pure: callers supply the heights of the blocks that created the spent
+//! outputs and the median times past they need, from the UTXO set or the block being
+//! validated.
+
+use crate::primitives::transaction::{
+    LOCKTIME_THRESHOLD, SEQUENCE_FINAL, SEQUENCE_LOCKTIME_DISABLE_FLAG,
+    SEQUENCE_LOCKTIME_GRANULARITY, SEQUENCE_LOCKTIME_MASK, SEQUENCE_LOCKTIME_TYPE_FLAG,
+};
+use crate::primitives::{TXInput, Transaction};
+
+/// Relative lock time of an input, decoded from its sequence (BIP68).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RelativeLockTime {
+    /// The spent output needs this many confirmations.
+    Blocks(u32),
+    /// The spent output must have been confirmed for this many milliseconds, measured
+    /// between median times past.
+    Time(i64),
+}
+
+impl RelativeLockTime {
+    /// Decode the relative lock time of `input`, or `None` if its sequence disables it.
+    pub fn from_input(input: &TXInput) -> Option<RelativeLockTime> {
+        let sequence = input.get_sequence();
+        if sequence & SEQUENCE_LOCKTIME_DISABLE_FLAG != 0 {
+            return None;
+        }
+        let value = sequence & SEQUENCE_LOCKTIME_MASK;
+        if sequence & SEQUENCE_LOCKTIME_TYPE_FLAG != 0 {
+            let seconds = i64::from(value) << SEQUENCE_LOCKTIME_GRANULARITY;
+            Some(RelativeLockTime::Time(seconds * 1000))
+        } else {
+            Some(RelativeLockTime::Blocks(value))
+        }
+    }
+
+    /// Whether checking the lock needs the median time past before the spent output's block.
+    pub fn is_time_based(&self) -> bool {
+        matches!(self, RelativeLockTime::Time(_))
+    }
+}
+
+/// Earliest block a transaction's relative lock times allow it into (Bitcoin Core's
+/// `CalculateSequenceLocks` result): the block must be above `min_height` and its parent's
+/// median time past above `min_time`. `-1` means no constraint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SequenceLocks {
+    min_height: i64,
+    min_time: i64,
+}
+
+impl SequenceLocks {
+    pub fn get_min_height(&self) -> i64 {
+        self.min_height
+    }
+
+    pub fn get_min_time(&self) -> i64 {
+        self.min_time
+    }
+}
+
+/// Whether `tx` may be included in the block at `block_height` (Bitcoin Core's `IsFinalTx`).
+///
+/// # Arguments
+///
+/// * `tx` - The transaction to check.
+/// * `block_height` - Height of the block that would include the transaction.
+/// * `block_time` - Median time past of that block's parent, in milliseconds.
+///
+/// # Returns
+///
+/// `true` if the lock time is unset or already passed, or every input opts out of it
+/// with `SEQUENCE_FINAL`.
+pub fn is_final_tx(tx: &Transaction, block_height: usize, block_time: i64) -> bool {
+    let lock_time = tx.get_lock_time();
+    if lock_time == 0 {
+        return true;
+    }
+    let reached = if lock_time < LOCKTIME_THRESHOLD {
+        (lock_time as usize) < block_height
+    } else {
+        i64::from(lock_time) * 1000 < block_time
+    };
+    reached
+        || tx
+            .get_vin()
+            .iter()
+            .all(|input| input.get_sequence() == SEQUENCE_FINAL)
+}
+
+/// Compute the relative lock times of `tx` (Bitcoin Core's `CalculateSequenceLocks`).
+///
+/// A height lock of `n` blocks on an output created at height `h` is satisfied from height
+/// `h + n`; a time lock of `t` is satisfied once the median time past is `t` after the one
+/// before the output's block.
+///
+/// # Arguments
+///
+/// * `tx` - The transaction whose inputs are checked.
+/// * `coin_heights` - Height of the block that created each spent output, in input order.
+/// * `coin_times` - Median time past of the block before each of those blocks, in
+///   milliseconds; only read for inputs with a time-based lock.
+///
+/// # Returns
+///
+/// The combined locks of every input; a coinbase has none.
+pub fn calculate_sequence_locks(
+    tx: &Transaction,
+    coin_heights: &[usize],
+    coin_times: &[i64],
+) -> SequenceLocks {
+    let mut locks = SequenceLocks {
+        min_height: -1,
+        min_time: -1,
+    };
+    if tx.is_coinbase() {
+        return locks;
+    }
+    for (idx, input) in tx.get_vin().iter().enumerate() {
+        match RelativeLockTime::from_input(input) {
+            None => {}
+            Some(RelativeLockTime::Blocks(blocks)) => {
+                let coin_height = coin_heights.get(idx).copied().unwrap_or_default() as i64;
+                locks.min_height = locks.min_height.max(coin_height + i64::from(blocks) - 1);
+            }
+            Some(RelativeLockTime::Time(time)) => {
+                let coin_time = coin_times.get(idx).copied().unwrap_or_default();
+                locks.min_time = locks.min_time.max(coin_time + time - 1);
+            }
+        }
+    }
+    locks
+}
+
+/// Whether the block at `block_height` satisfies `locks` (Bitcoin Core's
+/// `EvaluateSequenceLocks`).
+///
+/// # Arguments
+///
+/// * `block_height` - Height of the block that would include the transaction.
+/// * `block_time` - Median time past of that block's parent, in milliseconds.
+/// * `locks` - The transaction's locks from `calculate_sequence_locks`.
+pub fn evaluate_sequence_locks(block_height: usize, block_time: i64, locks: SequenceLocks) -> bool {
+    locks.min_height < block_height as i64 && locks.min_time < block_time
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::transaction::{MAX_SEQUENCE_NONFINAL, TxLockTime};
+
+    /// A transaction spending one output with the given lock time and input sequence.
+    fn locked_transaction(lock_time: u32, sequence: u32) -> Transaction {
+        Transaction::test_builder()
+            .with_input(1, 0, sequence)
+            .with_lock_time(lock_time)
+            .build()
+    }
+
+    fn with_lock(lock: TxLockTime) -> Transaction {
+        locked_transaction(lock.get_lock_time(), lock.get_sequence())
+    }
+
+    #[test]
+    fn test_height_lock_time_is_final_above_lock_height() {
+        let tx = with_lock(TxLockTime::at_height(10).unwrap());
+        assert!(!is_final_tx(&tx, 10, 0));
+        assert!(is_final_tx(&tx, 11, 0));
+        assert!(is_final_tx(&with_lock(TxLockTime::none()), 1, 0));
+    }
+
+    #[test]
+    fn test_time_lock_time_compares_median_time_past() {
+        let tx = with_lock(TxLockTime::at_time(LOCKTIME_THRESHOLD + 60).unwrap());
+        let lock_ms = i64::from(LOCKTIME_THRESHOLD + 60) * 1000;
+        assert!(!is_final_tx(&tx, 1_000_000, lock_ms));
+        assert!(is_final_tx(&tx, 1, lock_ms + 1));
+    }
+
+    #[test]
+    fn test_final_sequences_disable_lock_time() {
+        assert!(!is_final_tx(
+            &locked_transaction(10, MAX_SEQUENCE_NONFINAL),
+            5,
+            0
+        ));
+        assert!(is_final_tx(&locked_transaction(10, SEQUENCE_FINAL), 5, 0));
+    }
+
+    #[test]
+    fn test_relative_height_lock() {
+        let tx = with_lock(TxLockTime::after_blocks(3));
+        // Output confirmed at height 20: spendable from height 23
+        let locks = calculate_sequence_locks(&tx, &[20], &[0]);
+        assert_eq!(locks.get_min_height(), 22);
+        assert_eq!(locks.get_min_time(), -1);
+        assert!(!evaluate_sequence_locks(22, 0, locks));
+        assert!(evaluate_sequence_locks(23, 0, locks));
+    }
+
+    #[test]
+    fn test_relative_time_lock() {
+        let tx = with_lock(TxLockTime::after_seconds(1024).unwrap());
+        assert_eq!(
+            RelativeLockTime::from_input(&tx.get_vin()[0]),
+            Some(RelativeLockTime::Time(1_024_000))
+        );
+        let coin_time = 5_000_000;
+        let locks = calculate_sequence_locks(&tx, &[20], &[coin_time]);
+        assert!(!evaluate_sequence_locks(100, coin_time + 1_023_999, locks));
+        assert!(evaluate_sequence_locks(100, coin_time + 1_024_000, locks));
+    }
+
+    #[test]
+    fn test_disabled_relative_lock() {
+        let tx = with_lock(TxLockTime::none());
+        assert_eq!(RelativeLockTime::from_input(&tx.get_vin()[0]), None);
+        let locks = calculate_sequence_locks(&tx, &[20], &[0]);
+        assert!(evaluate_sequence_locks(1, 0, locks));
+    }
+}
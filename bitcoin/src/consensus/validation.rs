@@ -0,0 +1,207 @@
+//! Context-free block checks (Bitcoin Core's `CheckBlock` / `CheckTransaction`)
+//!
+//! These rules only look at the block itself: its proof of work, its Merkle commitment and
+//! the shape of its transactions. Rules that need the parent block or the UTXO set
+//! (timestamps, heights, inputs and coinbase value) are applied by
+//! `NodeContext::validate_block`, which runs these checks first.
+
+use crate::config::GLOBAL_CONFIG;
+use crate::consensus::merkle::compute_merkle_root;
+use crate::error::{BtcError, Result};
+use crate::pow::ProofOfWork;
+use crate::primitives::block::Block;
+use std::collections::HashSet;
+
+/// Number of previous blocks whose median timestamp a new block must exceed.
+pub const MEDIAN_TIME_SPAN: usize = 11;
+
+/// Size limits of a coinbase's unlocking script (Bitcoin Core's `bad-cb-length` rule).
+pub const MIN_COINBASE_SCRIPT_SIG_SIZE: usize = 2;
+pub const MAX_COINBASE_SCRIPT_SIG_SIZE: usize = 100;
+
+/// How far ahead of the local clock a block timestamp may be, in milliseconds (2 hours).
+pub const MAX_FUTURE_BLOCK_TIME_MS: i64 = 2 * 60 * 60 * 1000;
+
+/// Run the consensus checks that do not depend on chain state.
+///
+/// # Arguments
+///
+/// * `block` - The block to check.
+///
+/// # Returns
+///
+/// * `Ok(())` - The block passed every check
+/// * `Err(_)` - The first rule the block violates
+///
+/// # Rules
+///
+/// 1. **Proof of work** - The header hash is recomputed and must be below the target,
+///    which must be within the proof-of-work limit of the configured network
+/// 2. **Merkle root** - Every transaction id matches its contents and the header commits
+///    to them
+/// 3. **No duplicates** - No transaction appears twice. A duplicated last transaction
+///    produces the same Merkle root as the original block (CVE-2012-2459), so the
+///    root alone cannot catch it
+/// 4. **Coinbase** - The first transaction, and only the first, is a coinbase, and its
+///    unlocking script is 2 to 100 bytes long
+/// 5. **Output values** - No output, and no transaction's output total, exceeds
+///    `MAX_MONEY` (values are unsigned, so they cannot be negative)
+pub async fn check_block(block: &Block) -> Result<()> {
+    let block_hash = block.get_hash().to_string();
+
+    if !ProofOfWork::new_proof_of_work(block.clone()).validate(GLOBAL_CONFIG.get_chain_params()) {
+        return Err(BtcError::InvalidBlockProofOfWork(block_hash));
+    }
+
+    let transactions = block.get_transactions().await?;
+    for tx in transactions {
+        if !tx.has_valid_id()? {
+            return Err(BtcError::InvalidBlockMerkleRoot(block_hash));
+        }
+    }
+    if compute_merkle_root(&block.get_tx_ids()) != block.get_merkle_root() {
+        return Err(BtcError::InvalidBlockMerkleRoot(block_hash));
+    }
+
+    let mut seen = HashSet::new();
+    for tx in transactions {
+        if !seen.insert(tx.get_id()) {
+            return Err(BtcError::DuplicateBlockTransaction(
+                block_hash,
+                tx.get_tx_id_hex(),
+            ));
+        }
+    }
+
+    match transactions.first() {
+        None => {
+            return Err(BtcError::InvalidBlockCoinbase(
+                block_hash,
+                "block has no transactions".to_string(),
+            ));
+        }
+        Some(tx) if tx.not_coinbase() => {
+            return Err(BtcError::InvalidBlockCoinbase(
+                block_hash,
+                "first transaction is not a coinbase".to_string(),
+            ));
+        }
+        Some(tx) => {
+            let script_sig_size = tx.get_vin()[0].get_script_sig().len();
+            if !(MIN_COINBASE_SCRIPT_SIG_SIZE..=MAX_COINBASE_SCRIPT_SIG_SIZE)
+                .contains(&script_sig_size)
+            {
+                return Err(BtcError::InvalidBlockCoinbase(
+                    block_hash,
+                    format!("script_sig of {} bytes", script_sig_size),
+                ));
+            }
+        }
+    }
+    if transactions[1..].iter().any(|tx| tx.is_coinbase()) {
+        return Err(BtcError::InvalidBlockCoinbase(
+            block_hash,
+            "more than one coinbase".to_string(),
+        ));
+    }
+
+    for tx in transactions {
+        let in_range = tx
+            .get_vout()
+            .iter()
+            .all(|out| out.get_value().is_money_range())
+            && tx
+                .get_output_value()
+                .is_ok_and(|total| total.is_money_range());
+        if !in_range {
+            return Err(BtcError::OutputValueOutOfRange(tx.get_tx_id_hex()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Median of the given block timestamps (Bitcoin Core's `GetMedianTimePast`).
+///
+/// # Arguments
+///
+/// * `timestamps` - Timestamps of up to `MEDIAN_TIME_SPAN` most recent blocks.
+///
+/// # Returns
+///
+/// The median timestamp, or `None` if no timestamps were given.
+pub fn median_time_past(timestamps: &[i64]) -> Option<i64> {
+    if timestamps.is_empty() {
+        return None;
+    }
+    let mut sorted = timestamps.to_vec();
+    sorted.sort_unstable();
+    Some(sorted[sorted.len() / 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::block::BLOCK_HASH_SIZE;
+    use crate::primitives::transaction::Transaction;
+
+    const PARENT_HASH: [u8; BLOCK_HASH_SIZE] = [0x22; BLOCK_HASH_SIZE];
+
+    fn coinbase() -> Transaction {
+        let wallet = crate::wallet::Wallet::new().expect("Failed to create test wallet");
+        let address = wallet.get_address().expect("Failed to get wallet address");
+        Transaction::new_coinbase_tx(&address).expect("Failed to create coinbase")
+    }
+
+    #[test]
+    fn test_median_time_past() {
+        assert_eq!(median_time_past(&[]), None);
+        assert_eq!(median_time_past(&[5]), Some(5));
+        assert_eq!(median_time_past(&[9, 1, 5]), Some(5));
+        assert_eq!(median_time_past(&[4, 1, 3, 2]), Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_check_block_accepts_mined_block() {
+        let block = Block::new_block(&PARENT_HASH, &[coinbase()], 2);
+        assert!(check_block(&block).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_check_block_rejects_missing_coinbase() {
+        let block = Block::new_block(&PARENT_HASH, &[], 2);
+        assert!(matches!(
+            check_block(&block).await,
+            Err(BtcError::InvalidBlockCoinbase(..))
+        ));
+
+        let block = Block::new_block(&PARENT_HASH, &[coinbase(), coinbase()], 2);
+        assert!(matches!(
+            check_block(&block).await,
+            Err(BtcError::InvalidBlockCoinbase(..))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_check_block_rejects_output_above_max_money() {
+        let wallet = crate::wallet::Wallet::new().expect("Failed to create test wallet");
+        let address = wallet.get_address().expect("Failed to get wallet address");
+        let coinbase = Transaction::new_coinbase_tx_for_height(&address, 2, crate::MAX_MONEY)
+            .expect("Failed to create coinbase");
+        let block = Block::new_block(&PARENT_HASH, &[coinbase], 2);
+        assert!(matches!(
+            check_block(&block).await,
+            Err(BtcError::OutputValueOutOfRange(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_check_block_rejects_duplicate_transactions() {
+        let tx = coinbase();
+        let block = Block::new_block(&PARENT_HASH, &[tx.clone(), tx], 2);
+        assert!(matches!(
+            check_block(&block).await,
+            Err(BtcError::DuplicateBlockTransaction(..))
+        ));
+    }
+}
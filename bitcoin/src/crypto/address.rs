@@ -1,5 +1,10 @@
+use crate::config::GLOBAL_CONFIG;
+use crate::crypto::hash::sha256_digest;
 use crate::error::{BtcError, Result};
 
+/// Length of the double SHA-256 checksum that ends every address payload.
+const ADDRESS_CHECKSUM_LEN: usize = 4;
+
 ///
 /// The `base58_encode` function encodes the given byte slice using the Base58 encoding scheme
 /// and returns the encoded string representation.
@@ -72,6 +77,127 @@ pub fn base58_decode(data: &str) -> Result<Vec<u8>> {
         .map_err(|e| BtcError::AddressDecodingError(e.to_string()))
 }
 
+///
+/// The `script_hash_address` function derives the pay-to-script-hash address of a redeem
+/// script, for example a multisig script. The payload is the version byte, the SHA-256
+/// hash of the script and a 4-byte double SHA-256 checksum, like the wallet's own
+/// addresses, so anyone can pay to it without knowing the script. The version byte is the
+/// configured network's `script_address_version` (`0x05` on mainnet, as in Bitcoin).
+///
+/// # Usage Examples
+///
+/// - **Multisig addresses**: Funds sent to the address of an M-of-N multisig script can only be
+///   spent by revealing the script and M signatures
+///
+/// # Arguments
+///
+/// * `redeem_script` - The bytes of the script the address pays to.
+///
+/// # Returns
+///
+/// The Base58-encoded address.
+pub fn script_hash_address(redeem_script: &[u8]) -> Result<String> {
+    let version = GLOBAL_CONFIG
+        .get_chain_params()
+        .get_script_address_version();
+    encode_address(version, &sha256_digest(redeem_script))
+}
+
+///
+/// The `pub_key_hash_address` function derives the pay-to-public-key-hash (wallet) address
+/// of a public key hash. The version byte is the configured network's
+/// `pub_key_address_version` (`0x01` on mainnet, `0x6f` on testnet and regtest), so an
+/// address made on one network is rejected by `check_address_network` on another.
+///
+/// # Arguments
+///
+/// * `pub_key_hash` - The hash of the public key the address pays to.
+///
+/// # Returns
+///
+/// The Base58-encoded address.
+pub fn pub_key_hash_address(pub_key_hash: &[u8]) -> Result<String> {
+    let version = GLOBAL_CONFIG
+        .get_chain_params()
+        .get_pub_key_address_version();
+    encode_address(version, pub_key_hash)
+}
+
+///
+/// The `check_address_network` function checks that an address is well formed and belongs
+/// to the configured network: its checksum must match and its version byte must be the
+/// network's public-key-hash or script-hash address version.
+///
+/// # Arguments
+///
+/// * `address` - A Base58-encoded address.
+///
+/// # Returns
+///
+/// `Ok(())` if the address can be used on the configured network.
+///
+/// # Error Handling
+///
+/// Returns `BtcError::InvalidAddress` if the address is malformed or was made for another
+/// network.
+pub fn check_address_network(address: &str) -> Result<()> {
+    let chain_params = GLOBAL_CONFIG.get_chain_params();
+    match decode_address(address) {
+        Some((version, _))
+            if version == chain_params.get_pub_key_address_version()
+                || version == chain_params.get_script_address_version() =>
+        {
+            Ok(())
+        }
+        _ => Err(BtcError::InvalidAddress(address.to_string())),
+    }
+}
+
+///
+/// The `get_script_hash` function extracts the script hash from a pay-to-script-hash
+/// address.
+///
+/// # Arguments
+///
+/// * `address` - A Base58-encoded address.
+///
+/// # Returns
+///
+/// `Some(script_hash)` if `address` is a well-formed pay-to-script-hash address of the
+/// configured network, `None` for any other address.
+pub fn get_script_hash(address: &str) -> Option<Vec<u8>> {
+    let version = GLOBAL_CONFIG
+        .get_chain_params()
+        .get_script_address_version();
+    match decode_address(address)? {
+        (address_version, hash) if address_version == version => Some(hash),
+        _ => None,
+    }
+}
+
+/// Base58 address of `hash` under `version`, followed by the 4-byte checksum.
+fn encode_address(version: u8, hash: &[u8]) -> Result<String> {
+    let mut payload = vec![version];
+    payload.extend(hash);
+    let checksum = sha256_digest(&sha256_digest(&payload));
+    payload.extend(&checksum[..ADDRESS_CHECKSUM_LEN]);
+    base58_encode(&payload)
+}
+
+/// Version byte and hash of a Base58 address, or `None` if it is malformed or its checksum
+/// does not match.
+fn decode_address(address: &str) -> Option<(u8, Vec<u8>)> {
+    let payload = base58_decode(address).ok()?;
+    if payload.len() <= ADDRESS_CHECKSUM_LEN + 1 {
+        return None;
+    }
+    let (body, checksum) = payload.split_at(payload.len() - ADDRESS_CHECKSUM_LEN);
+    if &sha256_digest(&sha256_digest(body))[..ADDRESS_CHECKSUM_LEN] != checksum {
+        return None;
+    }
+    Some((body[0], body[1..].to_vec()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -328,4 +454,43 @@ mod tests {
             panic!("Expected AddressDecodingError");
         }
     }
+
+    #[test]
+    fn test_script_hash_address_roundtrip() {
+        let script = b"redeem script";
+        let address = script_hash_address(script).unwrap();
+        assert_eq!(get_script_hash(&address), Some(sha256_digest(script)));
+
+        // A different version byte or a corrupted checksum is not a script hash address
+        let mut payload = base58_decode(&address).unwrap();
+        payload[0] = 0x00;
+        assert_eq!(get_script_hash(&base58_encode(&payload).unwrap()), None);
+        let mut payload = base58_decode(&address).unwrap();
+        let last = payload.len() - 1;
+        payload[last] ^= 1;
+        assert_eq!(get_script_hash(&base58_encode(&payload).unwrap()), None);
+    }
+
+    #[test]
+    fn test_check_address_network() {
+        use crate::consensus::params::TESTNET_PARAMS;
+
+        let pub_key_hash = sha256_digest(b"public key");
+        let address = pub_key_hash_address(&pub_key_hash).unwrap();
+        assert!(check_address_network(&address).is_ok());
+        assert!(check_address_network(&script_hash_address(b"redeem script").unwrap()).is_ok());
+
+        // The same hash under another network's version bytes is rejected
+        for version in [
+            TESTNET_PARAMS.get_pub_key_address_version(),
+            TESTNET_PARAMS.get_script_address_version(),
+        ] {
+            let foreign = encode_address(version, &pub_key_hash).unwrap();
+            assert!(matches!(
+                check_address_network(&foreign),
+                Err(BtcError::InvalidAddress(_))
+            ));
+        }
+        assert!(check_address_network("not an address").is_err());
+    }
 }
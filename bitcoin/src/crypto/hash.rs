@@ -74,8 +74,7 @@ use sha2::{Digest as Sha2Digest, Sha256 as Sha2Hash};
 /// # Usage Examples
 ///
 /// - **Transaction ID generation**: Used in `Transaction::hash()` to create unique transaction identifiers
-/// - **Block hashing**: Used in `Block::get_hash()` for Merkle tree root calculation
-/// - **Mining**: Used in `ProofOfWork::run()` for block hash calculation during mining
+/// - **Block hashing**: Used twice by `sha256d` for block hashes and Merkle tree nodes
 /// - **Signature verification**: Used in Schnorr signature verification for message hashing
 ///
 /// # Arguments
@@ -92,6 +91,21 @@ pub fn sha256_digest(data: &[u8]) -> Vec<u8> {
     digest.as_ref().to_vec()
 }
 
+/// Double SHA-256 (Bitcoin Core's `Hash256`): `SHA256(SHA256(data))`.
+///
+/// Block hashes and the nodes of the transaction Merkle tree are double SHA-256 digests.
+///
+/// # Arguments
+///
+/// * `data` - A reference to the input data to be hashed.
+///
+/// # Returns
+///
+/// A 32-byte hash as a vector of bytes.
+pub fn sha256d(data: &[u8]) -> Vec<u8> {
+    sha256_digest(sha256_digest(data).as_slice())
+}
+
 ///
 /// The `taproot_hash` function calculates the Taproot-compatible hash of the input data.
 ///
@@ -205,6 +219,15 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sha256d_known_value() {
+        let expected =
+            hex::decode("5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456")
+                .expect("Failed to decode expected hash");
+        assert_eq!(sha256d(b""), expected);
+        assert_eq!(sha256d(b"abc"), sha256_digest(&sha256_digest(b"abc")));
+    }
+
     #[test]
     fn test_taproot_hash_basic() {
         let data = b"Hello, Taproot!";
@@ -308,8 +308,6 @@ pub fn schnorr_sign_digest(private_key: &[u8], message: &[u8]) -> Result<Vec<u8>
 /// A boolean indicating whether the signature is valid (`true`) or invalid (`false`).
 ///
 pub fn schnorr_sign_verify(public_key: &[u8], signature: &[u8], message: &[u8]) -> bool {
-    let secp = Secp256k1::new();
-
     // Parse the public key
     let public_key_array: [u8; 33] = match public_key.try_into() {
         Ok(arr) => arr,
@@ -321,7 +319,32 @@ pub fn schnorr_sign_verify(public_key: &[u8], signature: &[u8], message: &[u8])
     };
 
     // Convert to XOnlyPublicKey for Schnorr verification
-    let xonly_array: [u8; 32] = match public_key_obj.serialize()[1..33].try_into() {
+    let (xonly_public_key, _parity) = public_key_obj.x_only_public_key();
+    schnorr_sign_verify_xonly(&xonly_public_key.serialize(), signature, message)
+}
+
+///
+/// The `schnorr_sign_verify_xonly` function verifies a Schnorr signature against a 32-byte
+/// x-only public key (BIP340), the form a pay-to-taproot output commits to.
+///
+/// # Arguments
+///
+/// * `xonly_public_key` - A reference to the 32-byte x-only public key.
+/// * `signature` - A reference to the signature bytes (64 bytes for Schnorr signature).
+/// * `message` - A reference to the original message that was signed (typically a signature hash).
+///
+/// # Returns
+///
+/// A boolean indicating whether the signature is valid (`true`) or invalid (`false`).
+///
+pub fn schnorr_sign_verify_xonly(
+    xonly_public_key: &[u8],
+    signature: &[u8],
+    message: &[u8],
+) -> bool {
+    let secp = Secp256k1::new();
+
+    let xonly_array: [u8; 32] = match xonly_public_key.try_into() {
         Ok(arr) => arr,
         Err(_) => return false,
     };
@@ -332,11 +355,6 @@ pub fn schnorr_sign_verify(public_key: &[u8], signature: &[u8], message: &[u8])
 
     // Hash the message
     let message_hash = sha256_digest(message);
-    let message_hash_array: [u8; 32] = match message_hash.clone().try_into() {
-        Ok(arr) => arr,
-        Err(_) => return false,
-    };
-    let _message_obj = Message::from_digest(message_hash_array);
 
     // Parse the signature
     let signature_array: [u8; 64] = match signature.try_into() {
@@ -350,6 +368,31 @@ pub fn schnorr_sign_verify(public_key: &[u8], signature: &[u8], message: &[u8])
         .is_ok()
 }
 
+///
+/// The `get_xonly_public_key` function converts a 33-byte compressed public key into the
+/// 32-byte x-only form used by Schnorr signatures and pay-to-taproot outputs.
+///
+/// # Arguments
+///
+/// * `public_key` - A reference to the 33-byte compressed public key.
+///
+/// # Returns
+///
+/// A `Result<Vec<u8>>` containing the 32-byte x-only public key.
+///
+/// # Error Handling
+///
+/// Returns `BtcError::WalletKeyPairError` if the public key is not a valid compressed key.
+///
+pub fn get_xonly_public_key(public_key: &[u8]) -> Result<Vec<u8>> {
+    let public_key_array: [u8; 33] = public_key
+        .try_into()
+        .map_err(|_| BtcError::WalletKeyPairError("Invalid public key length".to_string()))?;
+    let public_key_obj = PublicKey::from_byte_array_compressed(public_key_array)
+        .map_err(|e| BtcError::WalletKeyPairError(e.to_string()))?;
+    Ok(public_key_obj.x_only_public_key().0.serialize().to_vec())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -473,6 +516,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_schnorr_signature_xonly_roundtrip() {
+        let private_key = new_schnorr_key_pair().expect("Failed to generate Schnorr key pair");
+        let public_key = get_schnorr_public_key(&private_key).expect("Failed to get public key");
+        let xonly_public_key =
+            get_xonly_public_key(&public_key).expect("Failed to get x-only public key");
+        assert_eq!(xonly_public_key.as_slice(), &public_key[1..]);
+
+        let message = b"Hello, taproot output key!";
+        let signature = schnorr_sign_digest(&private_key, message).expect("Failed to sign message");
+        assert!(schnorr_sign_verify_xonly(
+            &xonly_public_key,
+            &signature,
+            message
+        ));
+        assert!(!schnorr_sign_verify_xonly(
+            &xonly_public_key,
+            &signature,
+            b"Wrong message"
+        ));
+        assert!(!schnorr_sign_verify_xonly(&public_key, &signature, message));
+    }
+
     #[test]
     fn test_schnorr_key_generation() {
         // Generate multiple key pairs to ensure randomness
@@ -1,3 +1,4 @@
+use crate::consensus::Amount;
 use thiserror::Error;
 
 #[derive(Clone, Error, Debug)]
@@ -22,6 +23,42 @@ pub enum BtcError {
     InvalidBlock,
     #[error("Invalid block header")]
     InvalidBlockHeader,
+    #[error("Invalid block bits: expected {0:#010x}, got {1:#010x}")]
+    InvalidBlockBits(u32, u32),
+    #[error("Block {0} does not satisfy its proof-of-work target")]
+    InvalidBlockProofOfWork(String),
+    #[error("Block {0} merkle root does not match its transactions")]
+    InvalidBlockMerkleRoot(String),
+    #[error("Block {0} contains duplicate transaction {1}")]
+    DuplicateBlockTransaction(String, String),
+    #[error("Block {0} has an invalid coinbase: {1}")]
+    InvalidBlockCoinbase(String, String),
+    #[error("Coinbase pays {0} but the block may claim at most {1}")]
+    InvalidCoinbaseValue(Amount, Amount),
+    #[error("Invalid block height: parent is at {0}, got {1}")]
+    InvalidBlockHeight(usize, usize),
+    #[error("Block timestamp {0} is not after median time past {1}")]
+    BlockTimeTooOld(i64, i64),
+    #[error("Block timestamp {0} is too far in the future (limit {1})")]
+    BlockTimeTooNew(i64, i64),
+    #[error("Transaction {0} has an invalid signature")]
+    InvalidTransactionSignature(String),
+    #[error("Transaction {0} spends missing or already spent output {1}")]
+    MissingOrSpentInput(String, String),
+    #[error("Transaction {0} double-spends output {1} within the block")]
+    DoubleSpendInBlock(String, String),
+    #[error("Transaction {0} spends immature coinbase output {1}")]
+    PrematureCoinbaseSpend(String, String),
+    #[error("Transaction {0} is not final: its lock time has not been reached")]
+    NonFinalTransaction(String),
+    #[error("Transaction {0} spends output {1} before its relative lock time has passed")]
+    SequenceLockNotSatisfied(String, String),
+    #[error("Transaction {0} spends more than its inputs")]
+    TransactionOutputsExceedInputs(String),
+    #[error("Transaction {0} has an output value or output total above MAX_MONEY")]
+    OutputValueOutOfRange(String),
+    #[error("Transaction {0} spends inputs whose total is above MAX_MONEY")]
+    InputValueOutOfRange(String),
     #[error("Invalid transaction input")]
     InvalidTransactionInput,
     #[error("Invalid transaction output")]
@@ -40,9 +77,47 @@ pub enum BtcError {
     BlockDeserializationError(String),
     #[error("Block serialization error: {0}")]
     BlockSerializationError(String),
+    #[error("Consensus decode error: {0}")]
+    ConsensusDecodeError(String),
+    #[error("Merkle proof deserialization error: {0}")]
+    MerkleProofDeserializationError(String),
+    #[error("Merkle proof serialization error: {0}")]
+    MerkleProofSerializationError(String),
+    #[error("Block not in best chain: {0}")]
+    BlockNotInBestChain(String),
 
     #[error("Not enough funds")]
     NotEnoughFunds,
+    #[error("Invalid fee: {0}")]
+    InvalidFee(String),
+    #[error("Amount overflow")]
+    AmountOverflow,
+    #[error("Invalid network: {0}")]
+    InvalidNetwork(String),
+    #[error("Message start {0} does not belong to the {1} network")]
+    InvalidMessageStart(String, String),
+    #[error("Invalid multisig: {0}")]
+    InvalidMultisig(String),
+    #[error("Invalid lock time: {0}")]
+    InvalidLockTime(String),
+    #[error("Invalid coinbase script: {0}")]
+    InvalidCoinbaseScript(String),
+    #[error("Data carrier payload of {0} bytes exceeds {1} bytes")]
+    DataCarrierTooLarge(usize, usize),
+    #[error("Transaction {0} is not standard: {1}")]
+    NonStandardTransaction(String, String),
+    #[error("Transaction {0} conflicts with memory pool transaction {1}, which is not replaceable")]
+    TransactionConflictsWithMemoryPool(String, String),
+    #[error("Transaction {0} cannot replace memory pool transactions: {1}")]
+    InvalidReplacement(String, String),
+    #[error("Transaction {0} would make a chain of more than {1} unconfirmed transactions")]
+    TooLongMempoolChain(String, usize),
+    #[error("Transaction {0} pays fee {1}, below the memory pool minimum of {2}")]
+    MempoolMinFeeNotMet(String, Amount, Amount),
+    #[error("Transaction {0} was evicted from the full memory pool")]
+    MempoolFull(String),
+    #[error("Mempool dump error: {0}")]
+    MempoolDumpError(String),
 
     #[error("Blockchain tip hash error: {0}")]
     BlockChainTipHashError(String),
@@ -124,4 +199,35 @@ pub enum BtcError {
     InvalidConfiguration(String),
 }
 
+impl BtcError {
+    /// Whether this error is a consensus rule violation by a block, as opposed to a local failure.
+    ///
+    /// Blocks rejected for these reasons are dropped and the reason is reported to the peer
+    /// that sent them (Bitcoin Core's `BlockValidationState::Invalid`).
+    pub fn is_block_validation_error(&self) -> bool {
+        matches!(
+            self,
+            BtcError::InvalidBlock
+                | BtcError::InvalidBlockBits(..)
+                | BtcError::InvalidBlockProofOfWork(_)
+                | BtcError::InvalidBlockMerkleRoot(_)
+                | BtcError::DuplicateBlockTransaction(..)
+                | BtcError::InvalidBlockCoinbase(..)
+                | BtcError::InvalidCoinbaseValue(..)
+                | BtcError::InvalidBlockHeight(..)
+                | BtcError::BlockTimeTooOld(..)
+                | BtcError::BlockTimeTooNew(..)
+                | BtcError::InvalidTransactionSignature(_)
+                | BtcError::MissingOrSpentInput(..)
+                | BtcError::DoubleSpendInBlock(..)
+                | BtcError::PrematureCoinbaseSpend(..)
+                | BtcError::NonFinalTransaction(_)
+                | BtcError::SequenceLockNotSatisfied(..)
+                | BtcError::TransactionOutputsExceedInputs(_)
+                | BtcError::OutputValueOutOfRange(_)
+                | BtcError::InputValueOutOfRange(_)
+        )
+    }
+}
+
 pub type Result<T> = std::result::Result<T, BtcError>;
@@ -35,11 +35,20 @@ pub mod web;
 
 // Moved from primitives/ to root level (Bitcoin Core alignment)
 pub mod txmempool;
-pub use txmempool::{BlockInTransit, MemoryPool};
+pub use txmempool::{AncestorPackage, BlockInTransit, MemoryPool, TxMemPoolEntry};
 
 pub mod pow;
+
+pub mod policy;
+
+pub mod serialize;
+
+pub mod consensus;
+pub use consensus::{Amount, COIN, Denomination, MAX_MONEY};
 pub use pow::ProofOfWork;
 
+pub mod script;
+
 #[cfg(test)]
 mod test_utils {
     use std::sync::Once;
@@ -1,4 +1,5 @@
-use blockchain::node::NodeContext;
+use blockchain::consensus::Network;
+use blockchain::node::{MEMPOOL_DUMP_INTERVAL, NodeContext};
 use blockchain::web::server::create_web_server;
 use blockchain::{
     BlockchainService, BtcError, ConnectNode, GLOBAL_CONFIG, Result, Server, UTXOSet,
@@ -54,6 +55,12 @@ impl FromStr for IsWebServer {
 #[derive(Debug, Parser)]
 #[command(name = "blockchain")]
 struct Opt {
+    #[arg(
+        long,
+        global = true,
+        help = "Network to run on: mainnet, testnet or regtest (default: NETWORK or mainnet)"
+    )]
+    network: Option<Network>,
     #[command(subcommand)]
     command: Command,
 }
@@ -261,9 +268,18 @@ async fn start_node(
     let blockchain = open_or_create_blockchain(&wlt_mining_addr, &connect_nodes).await?;
     let node_context = NodeContext::new(blockchain);
 
+    // Reload the mempool saved by the previous run, revalidated against the current tip
+    if let Err(e) = node_context.load_mempool().await {
+        error!("Failed to load mempool: {}", e);
+    }
+
     // Get node configuration
     let socket_addr = GLOBAL_CONFIG.get_node_addr();
-    info!("Starting node at address: {}", socket_addr);
+    info!(
+        "Starting {} node at address: {}",
+        GLOBAL_CONFIG.get_network(),
+        socket_addr
+    );
     info!("Will try connect to nodes: {:?}", connect_nodes);
 
     // Convert connect nodes to HashSet
@@ -272,6 +288,24 @@ async fn start_node(
     // Centralized shutdown handling
     let (shutdown_tx, _) = tokio::sync::broadcast::channel::<()>(1);
 
+    // Save the mempool periodically, so a crash loses at most one interval of transactions
+    let dump_context = node_context.clone();
+    let mut dump_shutdown_rx = shutdown_tx.subscribe();
+    tokio::spawn(async move {
+        let start = tokio::time::Instant::now() + MEMPOOL_DUMP_INTERVAL;
+        let mut interval = tokio::time::interval_at(start, MEMPOOL_DUMP_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if let Err(e) = dump_context.save_mempool().await {
+                        error!("Failed to save mempool: {}", e);
+                    }
+                }
+                _ = dump_shutdown_rx.recv() => break,
+            }
+        }
+    });
+
     // Start network server concurrently using tokio::spawn
     let network_server = Server::new(node_context.clone());
     info!("Starting network server...");
@@ -291,7 +325,7 @@ async fn start_node(
     let result: Result<()> = match (is_web_server, is_miner) {
         (IsWebServer::Yes, IsMiner::No) => {
             // Start both servers concurrently using tokio::spawn
-            let web_server = create_web_server(node_context);
+            let web_server = create_web_server(node_context.clone());
             info!("Starting web server...");
             let web_handle = tokio::spawn(async move {
                 match web_server.start_with_shutdown().await {
@@ -349,6 +383,11 @@ async fn start_node(
         }
     };
 
+    // Save the mempool for the next start
+    if let Err(e) = node_context.save_mempool().await {
+        error!("Failed to save mempool: {}", e);
+    }
+
     result
 }
 
@@ -379,6 +418,11 @@ async fn main() {
     // Parse command line arguments
     let opt = Opt::parse();
 
+    // Select the chain parameters before anything reads the configuration
+    if let Some(network) = opt.network {
+        GLOBAL_CONFIG.set_network(network);
+    }
+
     // Process command with error handling
     if let Err(e) = process_command(opt.command).await {
         eprintln!("Error: {}", e);
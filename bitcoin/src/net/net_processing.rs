@@ -4,11 +4,11 @@
 //! similar to Bitcoin Core's net_processing.cpp
 
 use crate::node::{GLOBAL_NODES, MessageType, NODE_VERSION, OpType, Package, TCP_WRITE_TIMEOUT};
-use crate::{Block, GLOBAL_CONFIG, Transaction, UTXOSet, WalletAddress};
+use crate::{Amount, Block, GLOBAL_CONFIG, Transaction, TxFee, UTXOSet, WalletAddress};
 
 use crate::node::NodeContext;
 use std::collections::HashSet;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::net::{Shutdown, SocketAddr, TcpStream};
 use std::time::Duration;
 
@@ -28,7 +28,19 @@ pub async fn process_stream(
 ) -> Result<(), Box<dyn Error>> {
     // peer_addr is the address of the peer that is sending the request.
     let peer_addr = stream.peer_addr()?;
-    let reader = BufReader::new(&stream);
+    let mut reader = BufReader::new(&stream);
+
+    // Every connection starts with the message start bytes of the sender's network
+    // (Bitcoin Core's `pchMessageStart`); peers of another network are dropped.
+    let params = GLOBAL_CONFIG.get_chain_params();
+    let mut message_start = [0u8; 4];
+    reader.read_exact(&mut message_start)?;
+    if message_start != params.get_message_start() {
+        return Err(Box::new(BtcError::InvalidMessageStart(
+            HEXLOWER.encode(&message_start),
+            params.get_network().to_string(),
+        )));
+    }
     let pkg_reader = Deserializer::from_reader(reader).into_iter::<Package>();
 
     // The `serve` function processes incoming network requests from a TCP stream.
@@ -63,18 +75,42 @@ pub async fn process_stream(
                     .unwrap_or(None)
                     .is_none();
 
+                // Consensus validation runs before the block can touch the chain state.
                 // If the block is not the best block, do nothing
                 // `add_block` will not add the block if its height is less than current tip height in the block chain.
-                node_context
-                    .add_block(&block)
-                    .await
-                    .expect("Blockchain write error");
+                let accepted = match node_context.validate_block(&block).await {
+                    Ok(()) => node_context.add_block(&block).await,
+                    Err(e) => Err(e),
+                };
+                match accepted {
+                    Ok(()) => (),
+                    Err(e) if e.is_block_validation_error() => {
+                        // Block breaks a consensus rule: drop it and tell the sender why
+                        warn!(
+                            "Rejected block {} from {}: {}",
+                            block.get_hash(),
+                            addr_from,
+                            e
+                        );
+                        GLOBAL_BLOCKS_IN_TRANSIT
+                            .remove(block.get_hash_bytes().as_slice())
+                            .expect("Block removal error");
+                        send_message(
+                            &addr_from,
+                            MessageType::Error,
+                            format!("Block {} rejected: {}", block.get_hash(), e),
+                        )
+                        .await;
+                        continue;
+                    }
+                    Err(e) => Err(e)?,
+                }
                 let added_block_hash = block.get_hash_bytes();
                 info!("Added block {:?}", added_block_hash.as_slice());
 
                 // Remove transactions in block from memory pool functionally, since they have already been mined by other nodes
                 for tx in block.get_transactions().await? {
-                    node_context.remove_from_memory_pool(tx.clone()).await;
+                    node_context.remove_from_memory_pool(tx.clone());
                 }
 
                 // BLOCK RELAY: Forward NEW blocks to all peers except the sender.
@@ -228,6 +264,22 @@ pub async fn process_stream(
                         )
                         .await;
                     }
+                    Err(
+                        e @ (BtcError::MissingOrSpentInput(..)
+                        | BtcError::PrematureCoinbaseSpend(..)
+                        | BtcError::NonFinalTransaction(_)
+                        | BtcError::SequenceLockNotSatisfied(..)
+                        | BtcError::InvalidTransactionSignature(_)
+                        | BtcError::TransactionOutputsExceedInputs(_)
+                        | BtcError::TransactionConflictsWithMemoryPool(..)
+                        | BtcError::InvalidReplacement(..)
+                        | BtcError::TooLongMempoolChain(..)
+                        | BtcError::MempoolMinFeeNotMet(..)
+                        | BtcError::MempoolFull(_)),
+                    ) => {
+                        warn!("Rejected transaction from {}: {}", addr_from, e);
+                        send_message(&addr_from, MessageType::Error, e.to_string()).await;
+                    }
                     Err(e) => Err(e)?,
                 }
             }
@@ -279,7 +331,10 @@ pub async fn process_stream(
                     (Ok(from), Ok(to)) => {
                         let utxo_set = UTXOSet::new(node_context.get_blockchain().clone());
 
-                        match node_context.btc_transaction(&from, &to, amount).await {
+                        match node_context
+                            .btc_transaction(&from, &to, amount, TxFee::Absolute(Amount::ZERO))
+                            .await
+                        {
                             Ok(_) => (),
                             Err(BtcError::TransactionAlreadyExistsInMemoryPool(txid)) => {
                                 send_message(
@@ -292,13 +347,13 @@ pub async fn process_stream(
                             Err(BtcError::NotEnoughFunds) => {
                                 // Get current balance for detailed error message
                                 let current_balance =
-                                    utxo_set.get_balance(&from).await.unwrap_or(0);
+                                    utxo_set.get_balance(&from).await.unwrap_or_default();
 
                                 send_message(
                                     &addr_from,
                                     MessageType::Error,
                                     format!(
-                                        "Insufficient funds: cannot send {} bitcoin. Current balance: {} bitcoin",
+                                        "Insufficient funds: cannot send {}. Current balance: {}",
                                         amount, current_balance
                                     ),
                                 )
@@ -446,13 +501,15 @@ pub async fn process_stream(
                         info!("│  ┌─ Outputs ({}):", tx_summary_outputs.len());
                         for (output_idx, output_summary) in tx_summary_outputs.iter().enumerate() {
                             info!(
-                                "│  │  {} └─ To: {} (value: {} BTC)",
+                                "│  │  {} └─ To: {} (value: {})",
                                 if output_idx == tx_summary_outputs.len() - 1 {
                                     "└"
                                 } else {
                                     "├"
                                 },
-                                output_summary.get_wlt_addr().as_str(),
+                                output_summary
+                                    .get_wlt_addr()
+                                    .map_or("data carrier", |addr| addr.as_str()),
                                 output_summary.get_value()
                             );
                         }
@@ -674,6 +731,8 @@ async fn send_data(addr_to: &SocketAddr, pkg: Package) {
 
     let mut stream = stream.expect("Stream connect error");
     let _ = stream.set_write_timeout(Option::from(Duration::from_millis(TCP_WRITE_TIMEOUT)));
+    let message_start = GLOBAL_CONFIG.get_chain_params().get_message_start();
+    let _ = stream.write_all(&message_start);
     let _ = serde_json::to_writer(&stream, &pkg);
     let _ = stream.flush();
 }
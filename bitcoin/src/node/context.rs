@@ -55,19 +55,27 @@
 //! ```
 
 use crate::GLOBAL_CONFIG;
-use crate::chain::{BlockchainService, UTXOSet};
+use crate::chain::{BlockchainService, TxOutSetInfo, UTXOSet, WalletBalance};
+use crate::consensus::merkle::{MerkleProof, compute_merkle_branch, merkle_branch_length};
+use crate::consensus::validation::{MAX_FUTURE_BLOCK_TIME_MS, check_block};
+use crate::consensus::{Amount, is_final_tx};
 use crate::error::{BtcError, Result};
 use crate::net::net_processing::send_inv;
+use crate::node::mempool_persist::{dump_mempool, read_mempool};
 use crate::node::miner;
 use crate::node::miner::{
     cleanup_invalid_transactions, prepare_mining_utxo, process_mine_block, should_trigger_mining,
 };
 use crate::node::txmempool::{
-    add_to_memory_pool, remove_from_memory_pool, transaction_exists_in_pool,
+    add_to_memory_pool, check_ancestor_limit, check_conflicts, check_mempool_min_fee,
+    get_expiry_cutoff, limit_memory_pool, remove_from_memory_pool, transaction_exists_in_pool,
 };
-use crate::node::{CENTERAL_NODE, GLOBAL_NODES, Node, OpType};
-use crate::transaction::TxSummary;
-use crate::{Block, Transaction, WalletAddress, WalletTransaction};
+use crate::node::{BlockAssembler, BlockTemplate, CENTERAL_NODE, GLOBAL_NODES, Node, OpType};
+use crate::policy::check_standard_outputs;
+use crate::pow::MiningOptions;
+use crate::transaction::{TxFee, TxLockTime, TxSummary};
+use crate::{Block, GENESIS_BLOCK_PRE_BLOCK_HASH, Transaction, WalletAddress, WalletTransaction};
+use data_encoding::HEXLOWER;
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use tracing::{error, info, warn};
@@ -255,7 +263,9 @@ impl NodeContext {
     ///
     /// * `Ok(Some(block))` - Block found
     /// * `Ok(None)` - Block not found
-    /// * `Err(_)` - Database error or invalid hex
+    /// * `Err(_)` - Database error
+    ///
+    /// An invalid hex string is reported as not found.
     ///
     /// # Example
     ///
@@ -270,7 +280,7 @@ impl NodeContext {
     /// # }
     /// ```
     pub async fn get_block_by_hash(&self, hash: &str) -> Result<Option<Block>> {
-        self.blockchain.get_block_by_hash(hash.as_bytes()).await
+        self.blockchain.get_block_by_hash(hash).await
     }
 
     /// Get the latest N blocks from the blockchain
@@ -341,6 +351,33 @@ impl NodeContext {
         self.blockchain.mine_block(transactions).await
     }
 
+    /// Mine a block with the given transactions, searching nonces as `options` describe
+    /// (worker count and cancellation token). See `mine_block`.
+    pub async fn mine_block_with_options(
+        &self,
+        transactions: &[Transaction],
+        options: &MiningOptions<'_>,
+    ) -> Result<Block> {
+        self.blockchain
+            .mine_block_with_options(transactions, options)
+            .await
+    }
+
+    /// Build a template for the next block from the mempool
+    ///
+    /// Similar to Bitcoin Core's `getblocktemplate`: chooses mempool transactions by
+    /// ancestor fee rate within the default block size limit, without mining anything.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(template)` - The template's transactions, fees and header fields
+    /// * `Err(_)` - The chain state could not be read
+    pub async fn get_block_template(&self) -> Result<BlockTemplate> {
+        BlockAssembler::new()
+            .create_block_template(&self.blockchain)
+            .await
+    }
+
     /// Mine an empty block (only coinbase transaction)
     ///
     /// Creates and mines a block with no user transactions, only the
@@ -395,6 +432,128 @@ impl NodeContext {
         self.blockchain.find_user_transaction(address).await
     }
 
+    /// Build a Merkle inclusion proof for a confirmed transaction
+    ///
+    /// Equivalent to Bitcoin Core's `gettxoutproof` RPC. The proof contains the block hash,
+    /// the block's Merkle root, the transaction's position and its Merkle branch, so an
+    /// SPV client holding only the block header can check inclusion.
+    ///
+    /// # Arguments
+    ///
+    /// * `txid` - Transaction ID (hex)
+    /// * `block_hash` - Optional hash of a block on the active chain to look in; when
+    ///   omitted the active chain is scanned for the transaction
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(proof)` - Merkle proof for the transaction
+    /// * `Err(BtcError::TransactionNotFoundError)` - Transaction is not in the block or chain
+    /// * `Err(BtcError::BlockNotInBestChain)` - `block_hash` is unknown or not on the best
+    ///   chain
+    /// * `Err(_)` - Invalid txid hex or database error
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use blockchain::node::NodeContext;
+    /// # async fn example(node: &NodeContext) -> Result<(), Box<dyn std::error::Error>> {
+    /// let proof = node.get_tx_out_proof("a1b2c3...", None).await?;
+    /// println!("Included in block {}", proof.get_block_hash());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get_tx_out_proof(
+        &self,
+        txid: &str,
+        block_hash: Option<&str>,
+    ) -> Result<MerkleProof> {
+        let txid_bytes = HEXLOWER
+            .decode(txid.as_bytes())
+            .map_err(|e| BtcError::TransactionIdHexDecodingError(e.to_string()))?;
+
+        let block = match block_hash {
+            Some(hash) => {
+                // A proof is only useful against a header on the active chain
+                if !self.blockchain.is_in_best_chain(hash).await? {
+                    return Err(BtcError::BlockNotInBestChain(hash.to_string()));
+                }
+                self.get_block_by_hash(hash).await?
+            }
+            None => self.blockchain.find_transaction_block(&txid_bytes).await?,
+        }
+        .ok_or_else(|| BtcError::TransactionNotFoundError(txid.to_string()))?;
+
+        let tx_ids = block.get_tx_ids();
+        let index = tx_ids
+            .iter()
+            .position(|id| id.eq(&txid_bytes))
+            .ok_or_else(|| BtcError::TransactionNotFoundError(txid.to_string()))?;
+        let branch = compute_merkle_branch(&tx_ids, index)
+            .ok_or_else(|| BtcError::TransactionNotFoundError(txid.to_string()))?;
+
+        Ok(MerkleProof::new(
+            block.get_hash().to_string(),
+            block.get_merkle_root().to_vec(),
+            txid_bytes,
+            index,
+            branch,
+        ))
+    }
+
+    /// Verify a Merkle inclusion proof against our active chain
+    ///
+    /// Equivalent to Bitcoin Core's `verifytxoutproof` RPC. The proof's branch must link
+    /// the transaction to the Merkle root, and that root must match the header of the
+    /// referenced block on our best chain. The branch must span the full depth of the
+    /// block's tree and the transaction must be the block's transaction at the proof's
+    /// position, so an interior node of the tree cannot pass as a transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The proof returned by `get_tx_out_proof`
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(txids)` - The transaction IDs (hex) the proof commits to; empty if the proof is invalid
+    /// * `Err(BtcError::BlockNotInBestChain)` - The referenced block is unknown or not on the best chain
+    pub async fn verify_tx_out_proof(&self, proof: &MerkleProof) -> Result<Vec<String>> {
+        if !self
+            .blockchain
+            .is_in_best_chain(proof.get_block_hash())
+            .await?
+        {
+            return Err(BtcError::BlockNotInBestChain(
+                proof.get_block_hash().to_string(),
+            ));
+        }
+        let block = self
+            .get_block_by_hash(proof.get_block_hash())
+            .await?
+            .ok_or_else(|| BtcError::BlockNotInBestChain(proof.get_block_hash().to_string()))?;
+
+        if !proof.verify() || block.get_merkle_root() != proof.get_merkle_root() {
+            warn!(
+                "Merkle proof for block {} does not match its header",
+                proof.get_block_hash()
+            );
+            return Ok(vec![]);
+        }
+
+        // A branch that hashes to the root from an interior node, or from a transaction at
+        // another position, proves nothing about the claimed transaction
+        let tx_ids = block.get_tx_ids();
+        if proof.get_branch().len() != merkle_branch_length(tx_ids.len())
+            || tx_ids.get(proof.get_index()).map(Vec::as_slice) != Some(proof.get_txid())
+        {
+            warn!(
+                "Merkle proof for block {} does not prove a transaction of the block",
+                proof.get_block_hash()
+            );
+            return Ok(vec![]);
+        }
+        Ok(vec![HEXLOWER.encode(proof.get_txid())])
+    }
+
     //=============================================================================
     // Transaction Mempool Methods
     //=============================================================================
@@ -409,6 +568,7 @@ impl NodeContext {
     /// * `wlt_frm_addr` - Source wallet address
     /// * `wlt_to_addr` - Destination wallet address
     /// * `amount` - Amount to transfer (in satoshis)
+    /// * `fee` - Fee paid to the miner, absolute or per byte
     ///
     /// # Returns
     ///
@@ -418,7 +578,7 @@ impl NodeContext {
     /// # Process Flow
     ///
     /// 1. Create UTXO set
-    /// 2. Build and sign transaction
+    /// 2. Build and sign transaction, funding `amount + fee`
     /// 3. Validate transaction
     /// 4. Add to mempool
     /// 5. Broadcast to network
@@ -427,12 +587,14 @@ impl NodeContext {
     /// # Example
     ///
     /// ```rust,no_run
-    /// # use blockchain::{WalletAddress, node::NodeContext};
+    /// # use blockchain::{Amount, TxFee, WalletAddress, node::NodeContext};
     /// # async fn example(node: &NodeContext) -> Result<(), Box<dyn std::error::Error>> {
     /// let from = WalletAddress::validate("1A1zP1eP...".to_string())?;
     /// let to = WalletAddress::validate("1BvBMSEY...".to_string())?;
     ///
-    /// let txid = node.btc_transaction(&from, &to, 50).await?;
+    /// let txid = node
+    ///     .btc_transaction(&from, &to, Amount::from_sat(50), TxFee::Absolute(Amount::from_sat(1)))
+    ///     .await?;
     /// println!("Transaction submitted: {}", txid);
     /// # Ok(())
     /// # }
@@ -441,20 +603,143 @@ impl NodeContext {
         &self,
         wlt_frm_addr: &WalletAddress,
         wlt_to_addr: &WalletAddress,
-        amount: i32,
+        amount: Amount,
+        fee: TxFee,
     ) -> Result<String> {
-        // Create UTXO set for transaction building
-        let utxo_set = UTXOSet::new(self.blockchain.clone());
-
         // Create and sign the transaction
-        let utxo =
-            Transaction::new_utxo_transaction(wlt_frm_addr, wlt_to_addr, amount, &utxo_set).await?;
+        let utxo = self
+            .create_transaction(wlt_frm_addr, wlt_to_addr, amount, fee)
+            .await?;
 
         // Process through mempool and network
         let addr_from = crate::GLOBAL_CONFIG.get_node_addr();
         self.process_transaction(&addr_from, utxo).await
     }
 
+    /// Build and sign a transaction without submitting it
+    ///
+    /// Selects unspent outputs of `wlt_frm_addr` covering `amount` plus the fee and
+    /// returns change to the sender. Callers that need the fee before submitting
+    /// (for example to report it) use this together with `get_transaction_fee` and
+    /// `submit_transaction`.
+    ///
+    /// # Arguments
+    ///
+    /// * `wlt_frm_addr` - Source wallet address
+    /// * `wlt_to_addr` - Destination wallet address
+    /// * `amount` - Amount to transfer (in satoshis)
+    /// * `fee` - Fee paid to the miner, absolute or per byte
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(tx)` - The signed transaction
+    /// * `Err(NotEnoughFunds)` - Balance does not cover amount plus fee
+    /// * `Err(InvalidFee)` - Negative amount or fee
+    pub async fn create_transaction(
+        &self,
+        wlt_frm_addr: &WalletAddress,
+        wlt_to_addr: &WalletAddress,
+        amount: Amount,
+        fee: TxFee,
+    ) -> Result<Transaction> {
+        self.create_transaction_with_lock_time(
+            wlt_frm_addr,
+            wlt_to_addr,
+            amount,
+            fee,
+            TxLockTime::none(),
+        )
+        .await
+    }
+
+    /// Build and sign a transaction that cannot be mined before a height or time
+    ///
+    /// Like `create_transaction`, but the transaction carries an absolute lock time
+    /// (nLockTime) or a relative lock time on every input (BIP68), for escrow and vesting
+    /// payouts. It is rejected by the mempool and by block validation until the lock
+    /// has passed.
+    ///
+    /// # Arguments
+    ///
+    /// * `wlt_frm_addr` - Source wallet address
+    /// * `wlt_to_addr` - Destination wallet address
+    /// * `amount` - Amount to transfer (in satoshis)
+    /// * `fee` - Fee paid to the miner, absolute or per byte
+    /// * `lock` - Height, time or confirmations before the transaction can be mined
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(tx)` - The signed transaction
+    /// * `Err(NotEnoughFunds)` - Balance does not cover amount plus fee
+    /// * `Err(InvalidFee)` - Negative amount or fee
+    pub async fn create_transaction_with_lock_time(
+        &self,
+        wlt_frm_addr: &WalletAddress,
+        wlt_to_addr: &WalletAddress,
+        amount: Amount,
+        fee: TxFee,
+        lock: TxLockTime,
+    ) -> Result<Transaction> {
+        let utxo_set = UTXOSet::new(self.blockchain.clone());
+        Transaction::new_utxo_transaction_with_lock_time(
+            wlt_frm_addr,
+            wlt_to_addr,
+            amount,
+            fee,
+            lock,
+            &utxo_set,
+        )
+        .await
+    }
+
+    /// Build and sign a transaction that anchors data on chain without submitting it
+    ///
+    /// The transaction has a data carrier output (`OP_RETURN <data>`) and is funded by
+    /// `wlt_frm_addr`, which pays the fee and receives the change. The data output is never
+    /// added to the UTXO set.
+    ///
+    /// # Arguments
+    ///
+    /// * `wlt_frm_addr` - Source wallet address, paying the fee
+    /// * `data` - The payload, at most `MAX_DATA_CARRIER_SIZE` bytes
+    /// * `fee` - Fee paid to the miner, absolute or per byte
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(tx)` - The signed transaction
+    /// * `Err(DataCarrierTooLarge)` - The payload is above `MAX_DATA_CARRIER_SIZE`
+    /// * `Err(NotEnoughFunds)` - Balance does not cover the fee
+    /// * `Err(InvalidFee)` - The fee is zero
+    pub async fn create_data_transaction(
+        &self,
+        wlt_frm_addr: &WalletAddress,
+        data: &[u8],
+        fee: TxFee,
+    ) -> Result<Transaction> {
+        let utxo_set = UTXOSet::new(self.blockchain.clone());
+        Transaction::new_data_carrier_transaction(wlt_frm_addr, data, fee, &utxo_set).await
+    }
+
+    /// Get the fee a transaction pays
+    ///
+    /// The fee is the value of the outputs it spends minus the value of the outputs
+    /// it creates, looked up in the UTXO set.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The transaction
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(fee)` - The fee (0 for a coinbase)
+    /// * `Err(MissingOrSpentInput)` - An input does not spend an unspent output
+    /// * `Err(TransactionOutputsExceedInputs)` - The outputs are worth more than the inputs
+    pub async fn get_transaction_fee(&self, tx: &Transaction) -> Result<Amount> {
+        UTXOSet::new(self.blockchain.clone())
+            .get_transaction_fee(tx)
+            .await
+    }
+
     /// Submit a pre-built transaction to the mempool
     ///
     /// Similar to Bitcoin Core's `BroadcastTransaction`. Accepts an already-created
@@ -510,19 +795,54 @@ impl NodeContext {
     ///
     /// * `Ok(txid)` - Transaction accepted, returns transaction ID (hex)
     /// * `Err(TransactionAlreadyExistsInMemoryPool)` - Duplicate transaction
+    /// * `Err(NonFinalTransaction)` - The lock time does not allow the next block yet
+    /// * `Err(NonStandardTransaction)` - More than one data carrier output, or one above
+    ///   `MAX_DATA_CARRIER_SIZE`
+    /// * `Err(MempoolMinFeeNotMet)` - Pays less than the minimum fee rate of a full mempool
+    /// * `Err(TransactionConflictsWithMemoryPool)` - Spends an output a mempool transaction
+    ///   already spends, and that transaction does not signal replace-by-fee
+    /// * `Err(InvalidReplacement)` - Does not pay enough to replace the mempool
+    ///   transactions it conflicts with
+    /// * `Err(TooLongMempoolChain)` - Depends on more than `DEFAULT_ANCESTOR_LIMIT` - 1
+    ///   unconfirmed transactions
+    /// * `Err(PrematureCoinbaseSpend)` - Spends a coinbase output that is not mature yet
+    /// * `Err(SequenceLockNotSatisfied)` - A relative lock time has not passed yet
+    /// * `Err(InvalidTransactionSignature)` - An input does not satisfy the locking script
+    ///   of the output it spends
+    /// * `Err(MempoolFull)` - Evicted again at once, paying the lowest fee rate in a full
+    ///   mempool; the transactions it would have replaced stay in the mempool
     /// * `Err(_)` - Other validation or processing error
     ///
     /// # Process Flow
     ///
     /// 1. **Check for duplicates** - Reject if already in mempool
-    /// 2. **Add to mempool** - Store transaction for mining consideration
-    /// 3. **Broadcast** - If central node, relay to other peers (background)
-    /// 4. **Trigger mining** - If threshold met, start mining (background)
-    /// 5. **Return txid** - Immediately return to caller
+    /// 2. **Check finality** - Reject if the lock time is above the next block's height or
+    ///    the tip's median time past
+    /// 3. **Check standardness** - Reject data carrier outputs the relay policy does not
+    ///    allow
+    /// 4. **Check the fee** - Reject if an input is missing or outputs exceed inputs;
+    ///    inputs may spend outputs of other mempool transactions. Reject a fee below the
+    ///    mempool minimum fee rate, which rises while the mempool is full
+    /// 5. **Check conflicts** - A transaction spending outputs that mempool transactions
+    ///    already spend replaces them (BIP125 replace-by-fee) only if they all signal it and
+    ///    it pays a higher fee and fee rate; otherwise reject. Reject too if it would make
+    ///    a chain of more than `DEFAULT_ANCESTOR_LIMIT` unconfirmed transactions
+    /// 6. **Check coinbase maturity** - Reject if a spent coinbase output has fewer than
+    ///    the configured coinbase maturity blocks on top of it
+    /// 7. **Check sequence locks** - Reject if a spent output has not been confirmed for
+    ///    the blocks or time its input's relative lock requires
+    /// 8. **Check input scripts** - Reject if a signature or multisig quorum is missing
+    /// 9. **Add to mempool** - Evict the replaced transactions and their descendants, then
+    ///    store the transaction for mining consideration. Drop expired transactions and
+    ///    evict the lowest fee rate ones while the mempool is above its size limit. If
+    ///    the transaction itself is evicted, put the replaced transactions back
+    /// 10. **Broadcast** - If central node, relay to other peers (background)
+    /// 11. **Trigger mining** - If threshold met, start mining (background)
+    /// 12. **Return txid** - Immediately return to caller
     ///
     /// # Background Operations
     ///
-    /// Steps 3-4 run asynchronously to prevent blocking the caller.
+    /// Steps 10-11 run asynchronously to prevent blocking the caller.
     /// This follows Bitcoin's pattern of immediate acceptance with async propagation.
     ///
     /// # Bitcoin Core Equivalent
@@ -537,16 +857,8 @@ impl NodeContext {
         addr_from: &std::net::SocketAddr,
         utxo: Transaction,
     ) -> Result<String> {
-        // Check if transaction already exists in mempool
-        if transaction_exists_in_pool(&utxo) {
-            info!("Transaction: {:?} already exists", utxo.get_id());
-            return Err(BtcError::TransactionAlreadyExistsInMemoryPool(
-                utxo.get_tx_id_hex(),
-            ));
-        }
-
-        // Add to memory pool
-        add_to_memory_pool(utxo.clone(), &self.blockchain).await?;
+        self.accept_to_memory_pool(&utxo, crate::current_timestamp())
+            .await?;
 
         // Submit transaction for mining and broadcast in background
         // This prevents blocking the API response
@@ -561,6 +873,114 @@ impl NodeContext {
         Ok(utxo.get_tx_id_hex())
     }
 
+    /// Validate a transaction and add it to the mempool, without relaying it
+    ///
+    /// Steps 1-9 of `process_transaction`, similar to Bitcoin Core's `AcceptToMemoryPool`.
+    /// Transactions reloaded from disk come through here with the time they first entered
+    /// the pool, so a restart does not reset their expiry.
+    ///
+    /// # Arguments
+    ///
+    /// * `utxo` - The transaction to accept
+    /// * `accept_time` - When the transaction entered the pool, in milliseconds since the
+    ///   Unix epoch
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The transaction is in the mempool
+    /// * `Err(_)` - The errors listed for `process_transaction`
+    pub async fn accept_to_memory_pool(&self, utxo: &Transaction, accept_time: i64) -> Result<()> {
+        // Check if transaction already exists in mempool
+        if transaction_exists_in_pool(utxo) {
+            info!("Transaction: {:?} already exists", utxo.get_id());
+            return Err(BtcError::TransactionAlreadyExistsInMemoryPool(
+                utxo.get_tx_id_hex(),
+            ));
+        }
+
+        // The lock time must allow the transaction into the next block
+        let spend_height = self.blockchain.get_best_height().await? + 1;
+        let tip_hash = self.blockchain.get_tip_hash().await?;
+        let spend_time = self
+            .blockchain
+            .get_median_time_past(&tip_hash)
+            .await?
+            .unwrap_or_default();
+        if !is_final_tx(utxo, spend_height, spend_time) {
+            return Err(BtcError::NonFinalTransaction(utxo.get_tx_id_hex()));
+        }
+
+        // Data carrier outputs must be within the relay policy
+        check_standard_outputs(utxo)?;
+
+        // Inputs must exist and cover the outputs; the difference is the miner's fee
+        let fee = self.get_transaction_fee(utxo).await?;
+        info!("Transaction {} pays fee {}", utxo.get_tx_id_hex(), fee);
+
+        // A full memory pool only takes transactions paying more than what it evicted
+        check_mempool_min_fee(utxo, fee)?;
+
+        // Outputs already spent in the mempool can only be spent again by a replacement
+        let replaced = check_conflicts(utxo, fee)?;
+
+        // Chains of unconfirmed transactions are bounded
+        check_ancestor_limit(utxo)?;
+
+        // Coinbase outputs must be mature in the block that would include the transaction
+        let utxo_set = UTXOSet::new(self.blockchain.clone());
+        utxo_set
+            .check_coinbase_maturity(utxo, spend_height, GLOBAL_CONFIG.get_coinbase_maturity())
+            .await?;
+
+        // Relative lock times must have passed since the spent outputs were confirmed
+        let mut coin_heights = Vec::with_capacity(utxo.get_vin().len());
+        for input in utxo.get_vin() {
+            // Outputs of mempool transactions would be created in the same block
+            let height = match utxo_set.get_unspent_coin(input).await? {
+                Some(coin) => coin.get_height(),
+                None => spend_height,
+            };
+            coin_heights.push(height);
+        }
+        utxo_set
+            .check_sequence_locks(utxo, &coin_heights, spend_height, spend_time)
+            .await?;
+
+        // Every input must satisfy the locking script of the output it spends
+        utxo_set.check_input_scripts(utxo).await?;
+
+        // Add to memory pool, in place of the transactions it replaces; their entries are
+        // kept in case the new transaction does not survive
+        use crate::node::GLOBAL_MEMORY_POOL;
+        let mut replaced_entries = Vec::with_capacity(replaced.len());
+        for tx in replaced {
+            if let Some(entry) = GLOBAL_MEMORY_POOL.get_entry(&tx.get_tx_id_hex())? {
+                replaced_entries.push(entry);
+            }
+            remove_from_memory_pool(tx);
+        }
+        add_to_memory_pool(utxo.clone(), fee, accept_time)?;
+
+        // Keep the pool within its size and age limits; the new transaction may not survive,
+        // in which case the transactions it replaced go back with their original entry time
+        let removed = limit_memory_pool()?;
+        if removed
+            .iter()
+            .any(|tx| tx.get_tx_id_hex() == utxo.get_tx_id_hex())
+        {
+            for entry in replaced_entries {
+                add_to_memory_pool(
+                    entry.get_transaction().clone(),
+                    entry.get_fee(),
+                    entry.get_time(),
+                )?;
+            }
+            return Err(BtcError::MempoolFull(utxo.get_tx_id_hex()));
+        }
+
+        Ok(())
+    }
+
     /// Get a transaction from the mempool by ID
     ///
     /// Looks up a transaction in the mempool using its transaction ID.
@@ -647,7 +1067,7 @@ impl NodeContext {
 
     /// Remove a transaction from the mempool
     ///
-    /// Removes a transaction from the mempool and updates UTXO flags.
+    /// Removes a transaction from the mempool.
     /// Typically called after a transaction is confirmed in a block.
     ///
     /// # Arguments
@@ -656,10 +1076,58 @@ impl NodeContext {
     ///
     /// # Note
     ///
-    /// This also clears the "in mempool" flag on the transaction's outputs
-    /// in the UTXO set.
-    pub async fn remove_from_memory_pool(&self, tx: Transaction) {
-        remove_from_memory_pool(tx, &self.blockchain).await;
+    /// The outputs the transaction spends no longer count as spent in the mempool, so
+    /// the wallet can select them again unless a block spent them.
+    pub fn remove_from_memory_pool(&self, tx: Transaction) {
+        remove_from_memory_pool(tx);
+    }
+
+    /// Save the mempool to disk
+    ///
+    /// Equivalent to Bitcoin Core's `savemempool` RPC. The node also saves its mempool
+    /// on shutdown and every `MEMPOOL_DUMP_INTERVAL`; `load_mempool` reads it back.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(count)` - How many transactions were saved
+    /// * `Err(MempoolDumpError)` - The database could not be written
+    pub async fn save_mempool(&self) -> Result<usize> {
+        dump_mempool(&self.blockchain).await
+    }
+
+    /// Reload the mempool saved by a previous run
+    ///
+    /// Similar to Bitcoin Core's `LoadMempool`. Every saved transaction that has not
+    /// expired goes through `accept_to_memory_pool` again, keeping the time it first
+    /// entered the pool, so transactions mined or double spent while the node was down,
+    /// or no longer valid on the current tip, are dropped. Nothing is relayed.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(count)` - How many saved transactions are back in the mempool
+    /// * `Err(MempoolDumpError)` - The saved mempool could not be read
+    pub async fn load_mempool(&self) -> Result<usize> {
+        let cutoff = get_expiry_cutoff();
+        let (mut succeeded, mut failed, mut expired, mut already_there) = (0, 0, 0, 0);
+        for (tx, time) in read_mempool(&self.blockchain).await? {
+            if time < cutoff {
+                expired += 1;
+                continue;
+            }
+            match self.accept_to_memory_pool(&tx, time).await {
+                Ok(()) => succeeded += 1,
+                Err(BtcError::TransactionAlreadyExistsInMemoryPool(_)) => already_there += 1,
+                Err(e) => {
+                    info!("Dropping saved transaction {}: {}", tx.get_tx_id_hex(), e);
+                    failed += 1;
+                }
+            }
+        }
+        info!(
+            "Imported mempool transactions from disk: {} succeeded, {} failed, {} expired, {} already there",
+            succeeded, failed, expired, already_there
+        );
+        Ok(succeeded)
     }
 
     //=============================================================================
@@ -690,11 +1158,59 @@ impl NodeContext {
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn get_balance(&self, address: &WalletAddress) -> Result<i32> {
+    pub async fn get_balance(&self, address: &WalletAddress) -> Result<Amount> {
         let utxo_set = UTXOSet::new(self.blockchain.clone());
         utxo_set.get_balance(address).await
     }
 
+    /// Get the balance of an address split into spendable and immature funds
+    ///
+    /// Coinbase outputs count as immature until they have the configured coinbase
+    /// maturity of blocks on top of them; they cannot be spent before then.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The wallet address to query
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(balance)` - Spendable and immature balances
+    /// * `Err(_)` - Invalid address or database error
+    ///
+    /// # Bitcoin Core Equivalent
+    ///
+    /// ```cpp
+    /// // Bitcoin Core: wallet/rpc/coins.cpp
+    /// RPCHelpMan getbalances()
+    /// ```
+    pub async fn get_wallet_balance(&self, address: &WalletAddress) -> Result<WalletBalance> {
+        let utxo_set = UTXOSet::new(self.blockchain.clone());
+        utxo_set
+            .get_wallet_balance(address, GLOBAL_CONFIG.get_coinbase_maturity())
+            .await
+    }
+
+    /// Get statistics about the UTXO set
+    ///
+    /// Reports the number of unspent outputs, their total value and the subsidy issued
+    /// up to the tip, so emission can be audited against the halving schedule.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(info)` - Statistics of the UTXO set at the current tip
+    /// * `Err(_)` - Database error
+    ///
+    /// # Bitcoin Core Equivalent
+    ///
+    /// ```cpp
+    /// // Bitcoin Core: rpc/blockchain.cpp
+    /// static RPCHelpMan gettxoutsetinfo()
+    /// ```
+    pub async fn get_tx_out_set_info(&self) -> Result<TxOutSetInfo> {
+        let utxo_set = UTXOSet::new(self.blockchain.clone());
+        utxo_set.get_tx_out_set_info().await
+    }
+
     /// Create a new wallet
     ///
     /// Generates a new wallet with a fresh key pair and address.
@@ -902,11 +1418,10 @@ impl NodeContext {
             return Ok(false);
         }
 
-        // Verify outputs are not already in mempool (prevent double-spend)
-        for output in tx.get_vout() {
-            if output.is_in_global_mem_pool() {
-                return Ok(false);
-            }
+        // Verify no other mempool transaction spends the same outputs (prevent double-spend)
+        use crate::node::GLOBAL_MEMORY_POOL;
+        if !GLOBAL_MEMORY_POOL.get_conflicts(tx)?.is_empty() {
+            return Ok(false);
         }
 
         Ok(true)
@@ -914,7 +1429,9 @@ impl NodeContext {
 
     /// Validate a block according to consensus rules
     ///
-    /// Performs structural validation on a block before accepting it to the chain.
+    /// Runs the full validation pipeline on a block received from a peer, before it is
+    /// handed to `add_block`. Checks that only need the block run first, then checks
+    /// against the parent block, then checks against the UTXO set.
     ///
     /// # Arguments
     ///
@@ -922,30 +1439,41 @@ impl NodeContext {
     ///
     /// # Returns
     ///
-    /// * `Ok(true)` - Block is valid
-    /// * `Ok(false)` - Block is invalid
-    /// * `Err(_)` - Validation error
+    /// * `Ok(())` - Block is valid
+    /// * `Err(e)` - Block is invalid (`e.is_block_validation_error()` is true and `e`
+    ///   names the violated rule) or a storage error occurred
     ///
     /// # Validation Rules
     ///
-    /// 1. **Has transactions** - Block must contain at least coinbase
-    /// 2. **First is coinbase** - First transaction must be coinbase
-    /// 3. **Only one coinbase** - No other transaction can be coinbase
-    ///
-    /// # Missing Validations (TODO)
-    ///
-    /// - Merkle root verification
-    /// - Proof-of-work verification
-    /// - Timestamp validation
-    /// - Block size/weight limits
-    /// - Transaction validation (signatures, scripts)
-    /// - Coinbase maturity
+    /// 1. **Block checks** - Proof of work, Merkle root, duplicate transactions, coinbase
+    ///    placement and negative outputs (see `consensus::check_block`)
+    /// 2. **Future drift** - Timestamp at most `MAX_FUTURE_BLOCK_TIME_MS` ahead of our clock
+    ///    and, for a genesis block, a coinbase carrying the configured network's genesis
+    ///    message
+    /// 3. **Parent checks** - Height is the parent's height + 1, the coinbase commits to
+    ///    that height (BIP34) and the timestamp is after the median time past of the last
+    ///    `MEDIAN_TIME_SPAN` blocks
+    /// 4. **Transaction checks** - Every input spends an unspent output (from the UTXO set
+    ///    or an earlier transaction in the block), no output is spent twice, spent coinbase
+    ///    outputs are mature, absolute and relative lock times have passed, Schnorr
+    ///    signatures are valid and outputs do not exceed inputs
+    /// 5. **Coinbase value** - The coinbase pays at most the subsidy scheduled for the
+    ///    block's height on the configured network plus the block's fees
+    ///
+    /// Parent checks are skipped for blocks whose parent is not stored yet (they are kept
+    /// by `add_block` for a later reorganization). Transaction checks only run here when
+    /// the block extends the current tip, because the UTXO set reflects the tip; blocks of
+    /// another branch have their transactions checked when a reorganization connects them,
+    /// and the reorganization is rolled back if one of them is invalid.
     ///
     /// # Bitcoin Core Equivalent
     ///
     /// ```cpp
     /// // Bitcoin Core: validation.cpp
-    /// bool CheckBlock(const CBlock& block, CValidationState& state, ...)
+    /// bool CheckBlock(const CBlock& block, BlockValidationState& state, ...)
+    /// bool ContextualCheckBlockHeader(const CBlockHeader& block, BlockValidationState& state, ...)
+    /// bool ContextualCheckBlock(const CBlock& block, BlockValidationState& state, ...)
+    /// bool Chainstate::ConnectBlock(const CBlock& block, BlockValidationState& state, ...)
     /// ```
     ///
     /// # Example
@@ -953,35 +1481,79 @@ impl NodeContext {
     /// ```rust,no_run
     /// # use blockchain::{Block, node::NodeContext};
     /// # async fn example(node: &NodeContext, block: &Block) -> Result<(), Box<dyn std::error::Error>> {
-    /// if node.validate_block(block).await? {
-    ///     println!("Block is valid");
-    /// } else {
-    ///     println!("Block is invalid");
+    /// match node.validate_block(block).await {
+    ///     Ok(()) => node.add_block(block).await?,
+    ///     Err(e) if e.is_block_validation_error() => println!("Block rejected: {}", e),
+    ///     Err(e) => return Err(e.into()),
     /// }
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn validate_block(&self, block: &Block) -> Result<bool> {
-        let transactions = block.get_transactions().await?;
-
-        // Check block has transactions
-        if transactions.is_empty() {
-            return Ok(false);
-        }
-
-        // Check first transaction is coinbase
-        if !transactions[0].is_coinbase() {
-            return Ok(false);
+    pub async fn validate_block(&self, block: &Block) -> Result<()> {
+        check_block(block).await?;
+
+        let max_timestamp = crate::current_timestamp() + MAX_FUTURE_BLOCK_TIME_MS;
+        if block.get_timestamp() > max_timestamp {
+            return Err(BtcError::BlockTimeTooNew(
+                block.get_timestamp(),
+                max_timestamp,
+            ));
         }
 
-        // Check only first transaction is coinbase
-        for tx in &transactions[1..] {
-            if tx.is_coinbase() {
-                return Ok(false);
+        let pre_block_hash = block.get_pre_block_hash();
+        // A genesis block of another network starts a different chain
+        if pre_block_hash == GENESIS_BLOCK_PRE_BLOCK_HASH {
+            let params = GLOBAL_CONFIG.get_chain_params();
+            if !block.get_transactions().await?[0].is_genesis_coinbase(params) {
+                return Err(BtcError::InvalidBlockCoinbase(
+                    block.get_hash().to_string(),
+                    format!("is not the {} genesis coinbase", params.get_network()),
+                ));
             }
         }
+        let Some(parent) = self
+            .blockchain
+            .get_block(block.get_pre_block_hash_bytes())
+            .await?
+        else {
+            return Ok(());
+        };
+        if block.get_height() != parent.get_height() + 1 {
+            return Err(BtcError::InvalidBlockHeight(
+                parent.get_height(),
+                block.get_height(),
+            ));
+        }
+        // BIP34: the coinbase commits to the height of its block
+        if !block.get_transactions().await?[0].commits_to_height(block.get_height()) {
+            return Err(BtcError::InvalidBlockCoinbase(
+                block.get_hash().to_string(),
+                format!("does not commit to height {}", block.get_height()),
+            ));
+        }
+        let median_time_past = self
+            .blockchain
+            .get_median_time_past(&pre_block_hash)
+            .await?;
+        if let Some(median_time_past) = median_time_past
+            && block.get_timestamp() <= median_time_past
+        {
+            return Err(BtcError::BlockTimeTooOld(
+                block.get_timestamp(),
+                median_time_past,
+            ));
+        }
 
-        Ok(true)
+        if pre_block_hash != self.blockchain.get_tip_hash().await? {
+            return Ok(());
+        }
+        UTXOSet::new(self.blockchain.clone())
+            .check_block_transactions(
+                block,
+                median_time_past.unwrap_or_default(),
+                GLOBAL_CONFIG.get_coinbase_maturity(),
+            )
+            .await
     }
 
     //=============================================================================
@@ -1113,6 +1685,10 @@ impl NodeContext {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::consensus::{INITIAL_SUBSIDY, MAX_MONEY, REGTEST_PARAMS};
+    use crate::node::GLOBAL_MEMORY_POOL;
+    use crate::primitives::block::block_hash_from_hex;
+    use std::sync::atomic::AtomicBool;
     use std::time::{SystemTime, UNIX_EPOCH};
 
     /// Generate a test wallet address for testing
@@ -1145,6 +1721,66 @@ mod tests {
         (blockchain, db_path)
     }
 
+    /// Setup test environment whose genesis coinbase pays a wallet the test controls
+    async fn setup_funded_blockchain() -> (BlockchainService, String, crate::WalletAddress) {
+        // The tests spend the genesis coinbase right away
+        GLOBAL_CONFIG.set_coinbase_maturity(0);
+        let db_path = create_unique_db_path();
+        unsafe {
+            std::env::set_var("TREE_DIR", &db_path);
+            std::env::set_var("BLOCKS_TREE", &db_path);
+        }
+
+        let genesis_address = generate_test_address();
+        let blockchain = BlockchainService::initialize(&genesis_address)
+            .await
+            .expect("Failed to create blockchain");
+        (blockchain, db_path, genesis_address)
+    }
+
+    /// Mine a block on `tip_hash` timestamped past the tip's median time past, as the
+    /// node's miner does, so blocks built back to back are valid without waiting for the
+    /// clock to move
+    async fn mine_on(
+        node: &NodeContext,
+        tip_hash: &str,
+        transactions: &[Transaction],
+        height: usize,
+    ) -> Block {
+        let min_time = node
+            .get_blockchain()
+            .get_median_time_past(tip_hash)
+            .await
+            .unwrap()
+            .unwrap_or_default()
+            + 1;
+        let never_cancelled = AtomicBool::new(false);
+        let options = MiningOptions::new(1, &never_cancelled).with_min_time(min_time);
+        Block::new_block_with_options(
+            &block_hash_from_hex(tip_hash).unwrap(),
+            transactions,
+            height,
+            GLOBAL_CONFIG.get_chain_params().get_pow_limit_bits(),
+            &options,
+        )
+        .unwrap()
+    }
+
+    /// Build a block on top of the current tip with a fresh coinbase
+    async fn block_on_tip(node: &NodeContext, transactions: &[Transaction]) -> Block {
+        let tip_hash = node.get_blockchain().get_tip_hash().await.unwrap();
+        let height = node.get_blockchain_height().await.unwrap();
+        let coinbase = Transaction::new_coinbase_tx_for_height(
+            &generate_test_address(),
+            height + 1,
+            Amount::ZERO,
+        )
+        .unwrap();
+        let mut block_txs = vec![coinbase];
+        block_txs.extend_from_slice(transactions);
+        mine_on(node, &tip_hash, &block_txs, height + 1).await
+    }
+
     /// Cleanup test blockchain directory
     fn cleanup_test_blockchain(db_path: &str) {
         use std::fs;
@@ -1204,4 +1840,1146 @@ mod tests {
 
         cleanup_test_blockchain(&db_path);
     }
+
+    #[tokio::test]
+    async fn test_tx_out_proof_round_trip() {
+        let (blockchain, db_path) = setup_test_blockchain().await;
+        let node = NodeContext::new(blockchain);
+
+        let coinbase_txs: Vec<Transaction> = (0..3)
+            .map(|_| Transaction::new_coinbase_tx(&generate_test_address()).unwrap())
+            .collect();
+        let block = node
+            .mine_block(&coinbase_txs)
+            .await
+            .expect("Failed to mine block");
+
+        let txid = coinbase_txs[2].get_tx_id_hex();
+        let proof = node
+            .get_tx_out_proof(&txid, None)
+            .await
+            .expect("Failed to build proof");
+        assert_eq!(proof.get_block_hash(), block.get_hash());
+        assert_eq!(proof.get_merkle_root(), block.get_merkle_root());
+        assert_eq!(proof.get_index(), 2);
+        assert!(proof.verify());
+
+        let txids = node
+            .verify_tx_out_proof(&proof)
+            .await
+            .expect("Failed to verify proof");
+        assert_eq!(txids, vec![txid.clone()]);
+
+        // Looking the transaction up in an explicit block gives the same proof
+        let proof_in_block = node
+            .get_tx_out_proof(&txid, Some(&block.get_hash()))
+            .await
+            .expect("Failed to build proof");
+        assert_eq!(proof_in_block, proof);
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_tx_out_proof_rejects_unknown_and_tampered() {
+        let (blockchain, db_path) = setup_test_blockchain().await;
+        let node = NodeContext::new(blockchain);
+
+        let genesis_hash = node.get_blockchain().get_tip_hash().await.unwrap();
+        let coinbase_txs: Vec<Transaction> = (0..2)
+            .map(|_| Transaction::new_coinbase_tx(&generate_test_address()).unwrap())
+            .collect();
+        let block = node.mine_block(&coinbase_txs).await.unwrap();
+
+        let unknown_txid = HEXLOWER.encode(&[0u8; 32]);
+        assert!(matches!(
+            node.get_tx_out_proof(&unknown_txid, None).await,
+            Err(BtcError::TransactionNotFoundError(_))
+        ));
+        assert!(matches!(
+            node.get_tx_out_proof("not-hex", None).await,
+            Err(BtcError::TransactionIdHexDecodingError(_))
+        ));
+
+        // Proof claiming the wrong position does not verify
+        let proof = node
+            .get_tx_out_proof(&coinbase_txs[0].get_tx_id_hex(), None)
+            .await
+            .unwrap();
+        let tampered = MerkleProof::new(
+            proof.get_block_hash().to_string(),
+            proof.get_merkle_root().to_vec(),
+            proof.get_txid().to_vec(),
+            1,
+            proof.get_branch().to_vec(),
+        );
+        assert!(
+            node.verify_tx_out_proof(&tampered)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        // Proofs are only built in blocks of the best chain
+        let unknown_block = HEXLOWER.encode(&[0u8; 32]);
+        assert!(matches!(
+            node.get_tx_out_proof(&coinbase_txs[0].get_tx_id_hex(), Some(&unknown_block))
+                .await,
+            Err(BtcError::BlockNotInBestChain(_))
+        ));
+        let tip = node.get_blockchain().get_tip_hash().await.unwrap();
+        let tip_coinbase = Transaction::new_test_coinbase(&generate_test_address(), 3).unwrap();
+        node.add_block(&mine_on(&node, &tip, &[tip_coinbase], 3).await)
+            .await
+            .unwrap();
+        let stale_coinbase = Transaction::new_test_coinbase(&generate_test_address(), 2).unwrap();
+        let stale = mine_on(
+            &node,
+            &genesis_hash,
+            std::slice::from_ref(&stale_coinbase),
+            2,
+        )
+        .await;
+        node.add_block(&stale).await.unwrap();
+        assert!(
+            node.get_block_by_hash(&stale.get_hash())
+                .await
+                .unwrap()
+                .is_some()
+        );
+        assert!(matches!(
+            node.get_tx_out_proof(&stale_coinbase.get_tx_id_hex(), Some(&stale.get_hash()))
+                .await,
+            Err(BtcError::BlockNotInBestChain(_))
+        ));
+
+        // Proof for a block we do not have is an error
+        let foreign = MerkleProof::new(
+            "unknown".to_string(),
+            block.get_merkle_root().to_vec(),
+            proof.get_txid().to_vec(),
+            0,
+            proof.get_branch().to_vec(),
+        );
+        assert!(matches!(
+            node.verify_tx_out_proof(&foreign).await,
+            Err(BtcError::BlockNotInBestChain(_))
+        ));
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_tx_out_proof_rejects_interior_node() {
+        let (blockchain, db_path) = setup_test_blockchain().await;
+        let node = NodeContext::new(blockchain);
+
+        let coinbase_txs: Vec<Transaction> = (0..4)
+            .map(|_| Transaction::new_coinbase_tx(&generate_test_address()).unwrap())
+            .collect();
+        let block = node.mine_block(&coinbase_txs).await.unwrap();
+        let tx_ids = block.get_tx_ids();
+
+        // The parent of the first two transactions with the sibling above it hashes to the
+        // block's root, but it is not a transaction of the block
+        let interior = crate::consensus::merkle::compute_merkle_root(&tx_ids[..2]);
+        let branch = compute_merkle_branch(&tx_ids, 0).unwrap();
+        let forged = MerkleProof::new(
+            block.get_hash().to_string(),
+            block.get_merkle_root().to_vec(),
+            interior,
+            0,
+            branch[1..].to_vec(),
+        );
+        assert!(forged.verify());
+        assert!(node.verify_tx_out_proof(&forged).await.unwrap().is_empty());
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_reorganization_to_fork_with_invalid_spend_is_rolled_back() {
+        let (blockchain, db_path, genesis_address) = setup_funded_blockchain().await;
+        let node = NodeContext::new(blockchain.clone());
+        let utxo_set = UTXOSet::new(blockchain);
+        let genesis_hash = node.get_blockchain().get_tip_hash().await.unwrap();
+
+        // Active chain: genesis -> A
+        let coinbase_a = Transaction::new_test_coinbase(&generate_test_address(), 2).unwrap();
+        let block_a = mine_on(&node, &genesis_hash, &[coinbase_a], 2).await;
+        node.validate_block(&block_a).await.unwrap();
+        node.add_block(&block_a).await.unwrap();
+
+        // Longer fork: genesis -> B2 -> B3, where B3 spends the genesis coinbase a second time.
+        // Neither block extends the tip, so their transactions are only checked when a
+        // reorganization connects them
+        let (spend, double_spend) =
+            conflicting_transactions(&node, &genesis_address, TxLockTime::none(), (1, 2)).await;
+        let coinbase_b2 = Transaction::new_test_coinbase(&generate_test_address(), 2).unwrap();
+        let block_b2 = mine_on(
+            &node,
+            &genesis_hash,
+            &[coinbase_b2.clone(), spend.clone()],
+            2,
+        )
+        .await;
+        node.validate_block(&block_b2).await.unwrap();
+        node.add_block(&block_b2).await.unwrap();
+        let coinbase_b3 = Transaction::new_test_coinbase(&generate_test_address(), 3).unwrap();
+        let block_b3 = mine_on(&node, &block_b2.get_hash(), &[coinbase_b3, double_spend], 3).await;
+        node.validate_block(&block_b3).await.unwrap();
+
+        // B3 gives the fork more work, but connecting it fails after B2 was connected
+        assert!(matches!(
+            node.add_block(&block_b3).await,
+            Err(BtcError::MissingOrSpentInput(..))
+        ));
+
+        // The reorganization was rolled back: A is the tip again and B2's effects are undone
+        assert_eq!(
+            node.get_blockchain().get_tip_hash().await.unwrap(),
+            block_a.get_hash()
+        );
+        assert!(
+            utxo_set
+                .get_unspent_coin(&spend.get_vin()[0])
+                .await
+                .unwrap()
+                .is_some(),
+            "Genesis coinbase should be unspent again"
+        );
+        let b2_coinbase_output = crate::TXInput::new(coinbase_b2.get_id(), 0);
+        assert!(
+            utxo_set
+                .get_unspent_coin(&b2_coinbase_output)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_coinbase_spend_requires_maturity() {
+        let (blockchain, db_path, genesis_address) = setup_funded_blockchain().await;
+        let node = NodeContext::new(blockchain.clone());
+        let utxo_set = UTXOSet::new(blockchain);
+
+        // The genesis coinbase (height 1) is spent by a transaction for block 2
+        let tx = Transaction::new_utxo_transaction(
+            &genesis_address,
+            &generate_test_address(),
+            Amount::from_sat(4),
+            &utxo_set,
+        )
+        .await
+        .expect("Failed to create transaction");
+        let block = block_on_tip(&node, std::slice::from_ref(&tx)).await;
+
+        let utxo_set = UTXOSet::new(node.blockchain.clone());
+        assert!(matches!(
+            utxo_set.check_block_transactions(&block, 0, 100).await,
+            Err(BtcError::PrematureCoinbaseSpend(..))
+        ));
+        utxo_set
+            .check_block_transactions(&block, 0, 1)
+            .await
+            .expect("Mature coinbase spend rejected");
+
+        // Mempool acceptance measures maturity against the next block
+        assert!(matches!(
+            utxo_set.check_coinbase_maturity(&tx, 2, 100).await,
+            Err(BtcError::PrematureCoinbaseSpend(..))
+        ));
+        utxo_set
+            .check_coinbase_maturity(&tx, 101, 100)
+            .await
+            .expect("Mature coinbase spend rejected");
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_multisig_spend_needs_quorum() {
+        use crate::crypto::address::script_hash_address;
+        use crate::script::multisig_script;
+
+        let signers: Vec<crate::Wallet> = (0..3).map(|_| crate::Wallet::new().unwrap()).collect();
+        let pub_keys: Vec<Vec<u8>> = signers
+            .iter()
+            .map(|wallet| wallet.get_public_key().to_vec())
+            .collect();
+        let redeem_script = multisig_script(2, &pub_keys).unwrap();
+        let multisig_address =
+            crate::WalletAddress::validate(script_hash_address(redeem_script.as_bytes()).unwrap())
+                .unwrap();
+
+        // The genesis coinbase funds the multisig address
+        GLOBAL_CONFIG.set_coinbase_maturity(0);
+        let db_path = create_unique_db_path();
+        unsafe {
+            std::env::set_var("TREE_DIR", &db_path);
+            std::env::set_var("BLOCKS_TREE", &db_path);
+        }
+        let blockchain = BlockchainService::initialize(&multisig_address)
+            .await
+            .expect("Failed to create blockchain");
+        let node = NodeContext::new(blockchain.clone());
+        let utxo_set = UTXOSet::new(blockchain);
+        assert_eq!(
+            utxo_set.get_balance(&multisig_address).await.unwrap(),
+            INITIAL_SUBSIDY
+        );
+
+        let mut tx = Transaction::new_multisig_transaction(
+            &multisig_address,
+            &redeem_script,
+            &generate_test_address(),
+            Amount::from_sat(4),
+            Amount::from_sat(1),
+            &utxo_set,
+        )
+        .await
+        .expect("Failed to create multisig transaction");
+        assert!(tx.not_coinbase());
+        // Handed to the signers with the transaction
+        let spent_outputs = utxo_set.get_spent_outputs(&tx).await.unwrap();
+
+        // One signature is not enough
+        tx.sign_multisig(signers[0].get_pkcs8(), &spent_outputs)
+            .unwrap();
+        assert!(matches!(
+            utxo_set.check_input_scripts(&tx).await,
+            Err(BtcError::InvalidTransactionSignature(_))
+        ));
+        assert!(
+            tx.sign_multisig(crate::Wallet::new().unwrap().get_pkcs8(), &spent_outputs)
+                .is_err()
+        );
+
+        // A second signer signs a copy, which is merged back
+        let mut copy = tx.clone();
+        copy.sign_multisig(signers[2].get_pkcs8(), &spent_outputs)
+            .unwrap();
+        tx.combine_multisig_signatures(&copy).unwrap();
+        assert!(tx.has_valid_id().unwrap());
+        utxo_set
+            .check_input_scripts(&tx)
+            .await
+            .expect("2-of-3 multisig spend rejected");
+
+        let block = block_on_tip(&node, std::slice::from_ref(&tx)).await;
+        UTXOSet::new(node.blockchain.clone())
+            .check_block_transactions(&block, 0, 0)
+            .await
+            .expect("Block with multisig spend rejected");
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_lock_time_keeps_transaction_out_until_height() {
+        let (blockchain, db_path, genesis_address) = setup_funded_blockchain().await;
+        let node = NodeContext::new(blockchain);
+        let addr_from = GLOBAL_CONFIG.get_node_addr();
+
+        // The next block is at height 2; a lock at height 2 needs height 3
+        let locked = node
+            .create_transaction_with_lock_time(
+                &genesis_address,
+                &generate_test_address(),
+                Amount::from_sat(4),
+                TxFee::Absolute(Amount::ZERO),
+                TxLockTime::at_height(2).unwrap(),
+            )
+            .await
+            .expect("Failed to create transaction");
+        assert_eq!(locked.get_lock_time(), 2);
+        assert!(matches!(
+            node.process_transaction(&addr_from, locked.clone()).await,
+            Err(BtcError::NonFinalTransaction(_))
+        ));
+        let block = block_on_tip(&node, std::slice::from_ref(&locked)).await;
+        let result = node.validate_block(&block).await;
+        assert!(matches!(result, Err(BtcError::NonFinalTransaction(_))));
+        assert!(result.unwrap_err().is_block_validation_error());
+
+        let unlocked = node
+            .create_transaction_with_lock_time(
+                &genesis_address,
+                &generate_test_address(),
+                Amount::from_sat(4),
+                TxFee::Absolute(Amount::ZERO),
+                TxLockTime::at_height(1).unwrap(),
+            )
+            .await
+            .expect("Failed to create transaction");
+        let block = block_on_tip(&node, &[unlocked]).await;
+        node.validate_block(&block)
+            .await
+            .expect("Final transaction rejected");
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_crowdfunding_transaction_accepted_once_funded() {
+        let (blockchain, db_path, genesis_address) = setup_funded_blockchain().await;
+        let node = NodeContext::new(blockchain.clone());
+        let addr_from = GLOBAL_CONFIG.get_node_addr();
+        let utxo_set = UTXOSet::new(blockchain);
+
+        let mut tx = Transaction::new_crowdfunding_transaction(
+            &generate_test_address(),
+            Amount::from_sat(4),
+        )
+        .expect("Failed to create transaction");
+        let contributed = tx
+            .add_wallet_inputs(
+                &genesis_address,
+                Amount::from_sat(4),
+                crate::script::SigHashType::AllPlusAnyoneCanPay,
+                &utxo_set,
+            )
+            .await
+            .expect("Failed to add inputs");
+        assert!(contributed >= Amount::from_sat(4));
+        assert!(tx.has_valid_id().unwrap());
+
+        node.process_transaction(&addr_from, tx.clone())
+            .await
+            .expect("Funded crowdfunding transaction rejected");
+        let block = block_on_tip(&node, &[tx]).await;
+        node.validate_block(&block)
+            .await
+            .expect("Block with crowdfunding transaction rejected");
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_relative_lock_time_needs_confirmations() {
+        let (blockchain, db_path, genesis_address) = setup_funded_blockchain().await;
+        let node = NodeContext::new(blockchain);
+        let addr_from = GLOBAL_CONFIG.get_node_addr();
+
+        // The genesis output (height 1) needs 2 confirmations: spendable from height 3
+        let tx = node
+            .create_transaction_with_lock_time(
+                &genesis_address,
+                &generate_test_address(),
+                Amount::from_sat(4),
+                TxFee::Absolute(Amount::ZERO),
+                TxLockTime::after_blocks(2),
+            )
+            .await
+            .expect("Failed to create transaction");
+        assert!(matches!(
+            node.process_transaction(&addr_from, tx.clone()).await,
+            Err(BtcError::SequenceLockNotSatisfied(..))
+        ));
+        let block = block_on_tip(&node, std::slice::from_ref(&tx)).await;
+        assert!(matches!(
+            node.validate_block(&block).await,
+            Err(BtcError::SequenceLockNotSatisfied(..))
+        ));
+
+        let coinbase = Transaction::new_coinbase_tx(&generate_test_address()).unwrap();
+        node.mine_block(&[coinbase]).await.unwrap();
+        let block = block_on_tip(&node, &[tx]).await;
+        node.validate_block(&block)
+            .await
+            .expect("Transaction rejected after its relative lock time");
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    /// Two transactions spending the same funded wallet output to different recipients
+    async fn conflicting_transactions(
+        node: &NodeContext,
+        from: &crate::WalletAddress,
+        lock: TxLockTime,
+        fees: (u64, u64),
+    ) -> (Transaction, Transaction) {
+        let mut txs = vec![];
+        for fee in [fees.0, fees.1] {
+            txs.push(
+                node.create_transaction_with_lock_time(
+                    from,
+                    &generate_test_address(),
+                    Amount::from_sat(4),
+                    TxFee::Absolute(Amount::from_sat(fee)),
+                    lock,
+                )
+                .await
+                .expect("Failed to create transaction"),
+            );
+        }
+        let replacement = txs.pop().unwrap();
+        (txs.pop().unwrap(), replacement)
+    }
+
+    #[tokio::test]
+    async fn test_replacement_paying_more_evicts_original() {
+        let (blockchain, db_path, genesis_address) = setup_funded_blockchain().await;
+        let node = NodeContext::new(blockchain);
+        let addr_from = GLOBAL_CONFIG.get_node_addr();
+
+        let (original, replacement) =
+            conflicting_transactions(&node, &genesis_address, TxLockTime::replaceable(), (1, 3))
+                .await;
+        node.process_transaction(&addr_from, original.clone())
+            .await
+            .expect("Original transaction rejected");
+        node.process_transaction(&addr_from, replacement.clone())
+            .await
+            .expect("Replacement paying a higher fee rejected");
+
+        assert!(!transaction_exists_in_pool(&original));
+        assert!(transaction_exists_in_pool(&replacement));
+
+        // The original cannot come back: it pays less than the transaction now in the pool
+        assert!(matches!(
+            node.process_transaction(&addr_from, original).await,
+            Err(BtcError::InvalidReplacement(..))
+        ));
+
+        node.remove_from_memory_pool(replacement);
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_replacement_rejected_without_signal_or_higher_fee() {
+        let (blockchain, db_path, genesis_address) = setup_funded_blockchain().await;
+        let node = NodeContext::new(blockchain);
+        let addr_from = GLOBAL_CONFIG.get_node_addr();
+
+        // The original does not opt in to replace-by-fee
+        let (original, replacement) =
+            conflicting_transactions(&node, &genesis_address, TxLockTime::none(), (1, 3)).await;
+        node.process_transaction(&addr_from, original.clone())
+            .await
+            .expect("Original transaction rejected");
+        assert!(matches!(
+            node.process_transaction(&addr_from, replacement).await,
+            Err(BtcError::TransactionConflictsWithMemoryPool(..))
+        ));
+        assert!(transaction_exists_in_pool(&original));
+        node.remove_from_memory_pool(original);
+
+        // The original opts in, but the replacement pays the same fee
+        let (original, replacement) =
+            conflicting_transactions(&node, &genesis_address, TxLockTime::replaceable(), (2, 2))
+                .await;
+        node.process_transaction(&addr_from, original.clone())
+            .await
+            .expect("Original transaction rejected");
+        assert!(matches!(
+            node.process_transaction(&addr_from, replacement).await,
+            Err(BtcError::InvalidReplacement(..))
+        ));
+        assert!(transaction_exists_in_pool(&original));
+        node.remove_from_memory_pool(original);
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_child_pays_for_unconfirmed_parent() {
+        let (blockchain, db_path, genesis_address) = setup_funded_blockchain().await;
+        let node = NodeContext::new(blockchain);
+        let addr_from = GLOBAL_CONFIG.get_node_addr();
+        let recipient = generate_test_address();
+
+        // A parent paying no fee, and a child spending its unconfirmed output with a fee
+        let parent = node
+            .create_transaction(
+                &genesis_address,
+                &recipient,
+                Amount::from_sat(6),
+                TxFee::Absolute(Amount::ZERO),
+            )
+            .await
+            .expect("Failed to create parent");
+        node.process_transaction(&addr_from, parent.clone())
+            .await
+            .expect("Parent rejected");
+        let child = node
+            .create_transaction(
+                &recipient,
+                &generate_test_address(),
+                Amount::from_sat(1),
+                TxFee::Absolute(Amount::from_sat(4)),
+            )
+            .await
+            .expect("Failed to spend an unconfirmed output");
+        assert_eq!(child.get_vin()[0].get_txid(), parent.get_id());
+        assert_eq!(
+            node.get_transaction_fee(&child).await.unwrap(),
+            Amount::from_sat(4)
+        );
+        node.process_transaction(&addr_from, child.clone())
+            .await
+            .expect("Child of a mempool transaction rejected");
+
+        let package = GLOBAL_MEMORY_POOL
+            .get_ancestor_package(&child.get_tx_id_hex())
+            .unwrap()
+            .unwrap();
+        assert_eq!(package.get_count(), 2);
+        assert_eq!(package.get_fee(), Amount::from_sat(4));
+
+        // The child pulls its parent into the template, parent first
+        let template = node.get_block_template().await.unwrap();
+        let ids: Vec<String> = template
+            .get_entries()
+            .iter()
+            .map(|entry| entry.get_transaction().get_tx_id_hex())
+            .collect();
+        assert_eq!(ids, vec![parent.get_tx_id_hex(), child.get_tx_id_hex()]);
+        assert_eq!(template.get_entries()[1].get_depends(), &[1]);
+        assert_eq!(template.get_total_fees().unwrap(), Amount::from_sat(4));
+
+        node.remove_from_memory_pool(child);
+        node.remove_from_memory_pool(parent);
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_spends_unconfirmed_change() {
+        let (blockchain, db_path, genesis_address) = setup_funded_blockchain().await;
+        let node = NodeContext::new(blockchain);
+        let addr_from = GLOBAL_CONFIG.get_node_addr();
+
+        // The first payment leaves 4 of the 10 coinbase satoshis as unconfirmed change
+        let first = node
+            .create_transaction(
+                &genesis_address,
+                &generate_test_address(),
+                Amount::from_sat(6),
+                TxFee::Absolute(Amount::ZERO),
+            )
+            .await
+            .expect("Failed to create transaction");
+        node.process_transaction(&addr_from, first.clone())
+            .await
+            .expect("First payment rejected");
+        let second = node
+            .create_transaction(
+                &genesis_address,
+                &generate_test_address(),
+                Amount::from_sat(3),
+                TxFee::Absolute(Amount::from_sat(1)),
+            )
+            .await
+            .expect("Failed to spend unconfirmed change");
+        assert_eq!(second.get_vin()[0].get_txid(), first.get_id());
+        node.process_transaction(&addr_from, second.clone())
+            .await
+            .expect("Spend of unconfirmed change rejected");
+
+        // Nothing is left to spend, confirmed or not
+        assert!(
+            node.create_transaction(
+                &genesis_address,
+                &generate_test_address(),
+                Amount::from_sat(1),
+                TxFee::Absolute(Amount::ZERO),
+            )
+            .await
+            .is_err()
+        );
+
+        node.remove_from_memory_pool(second);
+        node.remove_from_memory_pool(first);
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_saved_mempool_is_reloaded_and_revalidated() {
+        let (blockchain, db_path, genesis_address) = setup_funded_blockchain().await;
+        let node = NodeContext::new(blockchain);
+        let addr_from = GLOBAL_CONFIG.get_node_addr();
+
+        let (original, double_spend) =
+            conflicting_transactions(&node, &genesis_address, TxLockTime::none(), (1, 3)).await;
+        node.process_transaction(&addr_from, original.clone())
+            .await
+            .expect("Transaction rejected");
+        let txid = original.get_tx_id_hex();
+        let entry_time = GLOBAL_MEMORY_POOL
+            .get_entry(&txid)
+            .unwrap()
+            .unwrap()
+            .get_time();
+        assert!(node.save_mempool().await.unwrap() >= 1);
+
+        // A restart empties the mempool; loading brings the transaction back as it was
+        node.remove_from_memory_pool(original.clone());
+        assert!(!transaction_exists_in_pool(&original));
+        assert!(node.load_mempool().await.unwrap() >= 1);
+        let entry = GLOBAL_MEMORY_POOL.get_entry(&txid).unwrap().unwrap();
+        assert_eq!(entry.get_time(), entry_time);
+        assert_eq!(entry.get_fee(), Amount::from_sat(1));
+
+        // Double spent while the node was down, the saved transaction is dropped
+        node.remove_from_memory_pool(original.clone());
+        node.process_transaction(&addr_from, double_spend.clone())
+            .await
+            .expect("Double spend rejected");
+        node.load_mempool().await.unwrap();
+        assert!(!transaction_exists_in_pool(&original));
+        assert!(transaction_exists_in_pool(&double_spend));
+
+        node.remove_from_memory_pool(double_spend);
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_balance_splits_immature_coinbase() {
+        let (blockchain, db_path, genesis_address) = setup_funded_blockchain().await;
+        let utxo_set = UTXOSet::new(blockchain);
+
+        let balance = utxo_set
+            .get_wallet_balance(&genesis_address, 100)
+            .await
+            .unwrap();
+        assert_eq!(balance.get_spendable(), Amount::ZERO);
+        assert_eq!(balance.get_immature(), INITIAL_SUBSIDY);
+        assert_eq!(balance.get_total().unwrap(), INITIAL_SUBSIDY);
+
+        let balance = utxo_set
+            .get_wallet_balance(&genesis_address, 1)
+            .await
+            .unwrap();
+        assert_eq!(balance.get_spendable(), INITIAL_SUBSIDY);
+        assert_eq!(balance.get_immature(), Amount::ZERO);
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_validate_block_accepts_valid_spend() {
+        let (blockchain, db_path, genesis_address) = setup_funded_blockchain().await;
+        let node = NodeContext::new(blockchain.clone());
+        let utxo_set = UTXOSet::new(blockchain);
+
+        let tx = Transaction::new_utxo_transaction(
+            &genesis_address,
+            &generate_test_address(),
+            Amount::from_sat(4),
+            &utxo_set,
+        )
+        .await
+        .expect("Failed to create transaction");
+        let block = block_on_tip(&node, &[tx]).await;
+        node.validate_block(&block)
+            .await
+            .expect("Valid block rejected");
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_validate_block_rejects_double_spend_in_block() {
+        let (blockchain, db_path, genesis_address) = setup_funded_blockchain().await;
+        let node = NodeContext::new(blockchain.clone());
+        let utxo_set = UTXOSet::new(blockchain);
+
+        // Both transactions select the genesis coinbase output
+        let first = Transaction::new_utxo_transaction(
+            &genesis_address,
+            &generate_test_address(),
+            Amount::from_sat(4),
+            &utxo_set,
+        )
+        .await
+        .unwrap();
+        let second = Transaction::new_utxo_transaction(
+            &genesis_address,
+            &generate_test_address(),
+            Amount::from_sat(6),
+            &utxo_set,
+        )
+        .await
+        .unwrap();
+        let block = block_on_tip(&node, &[first, second]).await;
+        let result = node.validate_block(&block).await;
+        assert!(matches!(result, Err(BtcError::DoubleSpendInBlock(..))));
+        assert!(result.unwrap_err().is_block_validation_error());
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_validate_block_rejects_spent_input() {
+        let (blockchain, db_path, genesis_address) = setup_funded_blockchain().await;
+        let node = NodeContext::new(blockchain.clone());
+        let utxo_set = UTXOSet::new(blockchain);
+
+        let first = Transaction::new_utxo_transaction(
+            &genesis_address,
+            &generate_test_address(),
+            Amount::from_sat(4),
+            &utxo_set,
+        )
+        .await
+        .unwrap();
+        let second = Transaction::new_utxo_transaction(
+            &genesis_address,
+            &generate_test_address(),
+            Amount::from_sat(6),
+            &utxo_set,
+        )
+        .await
+        .unwrap();
+        let coinbase = Transaction::new_coinbase_tx(&generate_test_address()).unwrap();
+        node.mine_block(&[coinbase, first]).await.unwrap();
+
+        let block = block_on_tip(&node, &[second]).await;
+        assert!(matches!(
+            node.validate_block(&block).await,
+            Err(BtcError::MissingOrSpentInput(..))
+        ));
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_transaction_fee_is_inputs_minus_outputs() {
+        let (blockchain, db_path, genesis_address) = setup_funded_blockchain().await;
+        let node = NodeContext::new(blockchain);
+        let recipient = generate_test_address();
+
+        let tx = node
+            .create_transaction(
+                &genesis_address,
+                &recipient,
+                Amount::from_sat(3),
+                TxFee::Absolute(Amount::from_sat(2)),
+            )
+            .await
+            .expect("Failed to create transaction");
+        assert_eq!(
+            node.get_transaction_fee(&tx).await.unwrap(),
+            Amount::from_sat(2)
+        );
+        let values: Vec<u64> = tx
+            .get_vout()
+            .iter()
+            .map(|out| out.get_value().to_sat())
+            .collect();
+        assert_eq!(values, vec![3, INITIAL_SUBSIDY.to_sat() - 3 - 2]);
+
+        // The fee counts against the balance
+        assert!(matches!(
+            node.create_transaction(
+                &genesis_address,
+                &recipient,
+                Amount::from_sat(9),
+                TxFee::Absolute(Amount::from_sat(2))
+            )
+            .await,
+            Err(BtcError::NotEnoughFunds)
+        ));
+        let over_max = MAX_MONEY.checked_add(Amount::from_sat(1)).unwrap();
+        assert!(matches!(
+            node.create_transaction(
+                &genesis_address,
+                &recipient,
+                Amount::from_sat(4),
+                TxFee::Absolute(over_max)
+            )
+            .await,
+            Err(BtcError::InvalidFee(_))
+        ));
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_transaction_fee_per_byte() {
+        let (blockchain, db_path, genesis_address) = setup_funded_blockchain().await;
+        let node = NodeContext::new(blockchain);
+        let recipient = generate_test_address();
+
+        let tx = node
+            .create_transaction(
+                &genesis_address,
+                &recipient,
+                Amount::from_sat(4),
+                TxFee::PerByte(0),
+            )
+            .await
+            .unwrap();
+        assert_eq!(node.get_transaction_fee(&tx).await.unwrap(), Amount::ZERO);
+
+        // A serialized transaction is far larger than the subsidy, so 1 sat/byte is unaffordable
+        assert!(tx.get_size().unwrap() as u64 > INITIAL_SUBSIDY.to_sat());
+        assert!(matches!(
+            node.create_transaction(
+                &genesis_address,
+                &recipient,
+                Amount::from_sat(4),
+                TxFee::PerByte(1)
+            )
+            .await,
+            Err(BtcError::NotEnoughFunds)
+        ));
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_validate_block_coinbase_collects_fees() {
+        let (blockchain, db_path, genesis_address) = setup_funded_blockchain().await;
+        let node = NodeContext::new(blockchain);
+
+        let tx = node
+            .create_transaction(
+                &genesis_address,
+                &generate_test_address(),
+                Amount::from_sat(4),
+                TxFee::Absolute(Amount::from_sat(3)),
+            )
+            .await
+            .unwrap();
+
+        let tip_hash = node.get_blockchain().get_tip_hash().await.unwrap();
+        let height = node.get_blockchain_height().await.unwrap();
+        let coinbase = Transaction::new_coinbase_tx_for_height(
+            &generate_test_address(),
+            height + 1,
+            Amount::from_sat(3),
+        )
+        .unwrap();
+        let block = mine_on(&node, &tip_hash, &[coinbase, tx.clone()], height + 1).await;
+        node.validate_block(&block)
+            .await
+            .expect("Coinbase collecting the fee rejected");
+
+        let greedy = Transaction::new_coinbase_tx_for_height(
+            &generate_test_address(),
+            height + 1,
+            Amount::from_sat(4),
+        )
+        .unwrap();
+        let block = mine_on(&node, &tip_hash, &[greedy, tx], height + 1).await;
+        assert!(matches!(
+            node.validate_block(&block).await,
+            Err(BtcError::InvalidCoinbaseValue(..))
+        ));
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_transactions_report_fee() {
+        let (blockchain, db_path, genesis_address) = setup_funded_blockchain().await;
+        let node = NodeContext::new(blockchain);
+        let recipient = generate_test_address();
+
+        let tx = node
+            .create_transaction(
+                &genesis_address,
+                &recipient,
+                Amount::from_sat(3),
+                TxFee::Absolute(Amount::from_sat(2)),
+            )
+            .await
+            .unwrap();
+        let height = node.get_blockchain_height().await.unwrap();
+        let coinbase = Transaction::new_coinbase_tx_for_height(
+            &generate_test_address(),
+            height + 1,
+            Amount::from_sat(2),
+        )
+        .unwrap();
+        node.mine_block(&[coinbase, tx.clone()]).await.unwrap();
+
+        let received = node.find_user_transaction(&recipient).await.unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].get_tx_id(), tx.get_id());
+        assert_eq!(received[0].get_fee(), Amount::from_sat(2));
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_tx_out_set_info_tracks_issued_supply() {
+        let (blockchain, db_path, genesis_address) = setup_funded_blockchain().await;
+        let node = NodeContext::new(blockchain);
+
+        let info = node.get_tx_out_set_info().await.unwrap();
+        assert_eq!(info.get_height(), 1);
+        assert_eq!(info.get_txouts(), 1);
+        assert_eq!(info.get_total_amount(), INITIAL_SUBSIDY);
+        assert_eq!(info.get_total_subsidy(), INITIAL_SUBSIDY);
+
+        // Fees move value into the coinbase without changing the supply
+        let tx = node
+            .create_transaction(
+                &genesis_address,
+                &generate_test_address(),
+                Amount::from_sat(3),
+                TxFee::Absolute(Amount::from_sat(2)),
+            )
+            .await
+            .unwrap();
+        let coinbase = Transaction::new_coinbase_tx_for_height(
+            &generate_test_address(),
+            2,
+            Amount::from_sat(2),
+        )
+        .unwrap();
+        let block = node.mine_block(&[coinbase, tx]).await.unwrap();
+
+        let info = node.get_tx_out_set_info().await.unwrap();
+        assert_eq!(info.get_height(), 2);
+        assert_eq!(info.get_best_block_hash(), block.get_hash());
+        assert_eq!(info.get_transactions(), 2);
+        assert_eq!(info.get_txouts(), 3);
+        assert_eq!(info.get_total_amount(), info.get_total_subsidy());
+        assert_eq!(
+            info.get_total_subsidy(),
+            INITIAL_SUBSIDY.checked_mul(2).unwrap()
+        );
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_data_carrier_output_is_mined_but_not_stored() {
+        let (blockchain, db_path, genesis_address) = setup_funded_blockchain().await;
+        let node = NodeContext::new(blockchain);
+        let document_hash = crate::sha256_digest(b"document");
+
+        let tx = node
+            .create_data_transaction(
+                &genesis_address,
+                &document_hash,
+                TxFee::Absolute(Amount::from_sat(2)),
+            )
+            .await
+            .expect("Failed to create data transaction");
+        assert_eq!(
+            tx.get_vout()[0].get_data_carrier_payload(),
+            Some(document_hash.clone())
+        );
+        assert_eq!(tx.get_vout()[0].get_value(), Amount::ZERO);
+        assert_eq!(
+            node.get_transaction_fee(&tx).await.unwrap(),
+            Amount::from_sat(2)
+        );
+
+        // Without a fee there is nothing to select inputs for
+        assert!(matches!(
+            node.create_data_transaction(
+                &genesis_address,
+                &document_hash,
+                TxFee::Absolute(Amount::ZERO)
+            )
+            .await,
+            Err(BtcError::InvalidFee(_))
+        ));
+        assert!(matches!(
+            node.create_data_transaction(
+                &genesis_address,
+                &[0; crate::policy::MAX_DATA_CARRIER_SIZE + 1],
+                TxFee::Absolute(Amount::from_sat(2))
+            )
+            .await,
+            Err(BtcError::DataCarrierTooLarge(..))
+        ));
+
+        let coinbase = Transaction::new_coinbase_tx_for_height(
+            &generate_test_address(),
+            2,
+            Amount::from_sat(2),
+        )
+        .unwrap();
+        node.mine_block(&[coinbase, tx.clone()]).await.unwrap();
+
+        // Only the change of the data transaction and the new coinbase are unspent
+        let info = node.get_tx_out_set_info().await.unwrap();
+        assert_eq!(info.get_transactions(), 2);
+        assert_eq!(info.get_txouts(), 2);
+        let utxo_set = UTXOSet::new(node.get_blockchain().clone());
+        let data_input = crate::TXInput::new(tx.get_id(), 0);
+        assert!(
+            utxo_set
+                .get_unspent_output(&data_input)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_validate_block_rejects_coinbase_without_height() {
+        let (blockchain, db_path) = setup_test_blockchain().await;
+        let node = NodeContext::new(blockchain);
+
+        let tip_hash = node.get_blockchain().get_tip_hash().await.unwrap();
+        // A coinbase committing to height 1 in the block at height 2
+        let coinbase = Transaction::new_coinbase_tx(&generate_test_address()).unwrap();
+        let block = Block::new_block(&block_hash_from_hex(&tip_hash).unwrap(), &[coinbase], 2);
+        let result = node.validate_block(&block).await;
+        assert!(matches!(result, Err(BtcError::InvalidBlockCoinbase(..))));
+        assert!(result.unwrap_err().is_block_validation_error());
+
+        let block = block_on_tip(&node, &[]).await;
+        node.validate_block(&block)
+            .await
+            .expect("Block with height commitment rejected");
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_validate_block_rejects_genesis_of_another_network() {
+        let (blockchain, db_path) = setup_test_blockchain().await;
+        let node = NodeContext::new(blockchain);
+        let address = generate_test_address();
+
+        let other_network = REGTEST_PARAMS.get_genesis_message();
+        let coinbase = Transaction::new_coinbase_tx_with_extra_nonce(
+            &address,
+            1,
+            Amount::ZERO,
+            0,
+            other_network,
+        )
+        .unwrap();
+        let block = Block::new_block(&[0; 32], &[coinbase], 1);
+        let result = node.validate_block(&block).await;
+        assert!(matches!(result, Err(BtcError::InvalidBlockCoinbase(..))));
+
+        let coinbase = Transaction::new_genesis_coinbase_tx(&address).unwrap();
+        assert!(coinbase.is_genesis_coinbase(GLOBAL_CONFIG.get_chain_params()));
+        let block = Block::new_block(&[0; 32], &[coinbase], 1);
+        node.validate_block(&block)
+            .await
+            .expect("Genesis block of the configured network rejected");
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_validate_block_rejects_bad_height() {
+        let (blockchain, db_path) = setup_test_blockchain().await;
+        let node = NodeContext::new(blockchain);
+
+        let tip_hash = node.get_blockchain().get_tip_hash().await.unwrap();
+        let coinbase = Transaction::new_coinbase_tx(&generate_test_address()).unwrap();
+        let block = Block::new_block(&block_hash_from_hex(&tip_hash).unwrap(), &[coinbase], 5);
+        assert!(matches!(
+            node.validate_block(&block).await,
+            Err(BtcError::InvalidBlockHeight(1, 5))
+        ));
+
+        cleanup_test_blockchain(&db_path);
+    }
 }
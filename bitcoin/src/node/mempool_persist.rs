@@ -0,0 +1,99 @@
+//! Memory pool persistence (Bitcoin Core: node/mempool_persist.cpp)
+//!
+//! Unconfirmed transactions only live in memory, so the node saves them to the `mempool`
+//! tree of its database (Bitcoin Core's `mempool.dat`) on shutdown, periodically and on
+//! demand, and reloads them on startup. Reloaded transactions go through the same
+//! validation as new ones, against whatever chain tip the node starts on.
+
+use crate::BlockchainService;
+use crate::error::{BtcError, Result};
+use crate::node::GLOBAL_MEMORY_POOL;
+use crate::primitives::transaction::Transaction;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::info;
+
+const MEMPOOL_TREE: &str = "mempool";
+
+/// How often a running node saves its memory pool, besides on shutdown.
+pub const MEMPOOL_DUMP_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// A saved memory pool transaction and when it entered the pool, in milliseconds since the
+/// Unix epoch, so expiry carries over restarts.
+#[derive(Serialize, Deserialize)]
+struct MempoolDumpEntry {
+    time: i64,
+    tx: Vec<u8>,
+}
+
+/// Save every memory pool transaction, replacing the previous dump (Bitcoin Core's
+/// `DumpMempool`)
+///
+/// Transactions are stored parents first, so reloading them in order never meets a
+/// transaction before the pool transactions it spends from.
+///
+/// # Returns
+///
+/// * `Ok(count)` - How many transactions were saved
+/// * `Err(MempoolDumpError)` - The database could not be written
+pub async fn dump_mempool(blockchain: &BlockchainService) -> Result<usize> {
+    let mut entries = vec![];
+    for entry in GLOBAL_MEMORY_POOL.get_all_entries()? {
+        let depth = GLOBAL_MEMORY_POOL
+            .get_ancestors(&entry.get_transaction().get_tx_id_hex())?
+            .len();
+        entries.push((depth, entry));
+    }
+    // Sorted by txid already; a stable sort keeps that order within a depth
+    entries.sort_by_key(|(depth, _)| *depth);
+
+    let db = blockchain.get_db().await?;
+    let tree = db
+        .open_tree(MEMPOOL_TREE)
+        .map_err(|e| BtcError::MempoolDumpError(e.to_string()))?;
+    let mut batch = sled::Batch::default();
+    for key in tree.iter().keys() {
+        batch.remove(key.map_err(|e| BtcError::MempoolDumpError(e.to_string()))?);
+    }
+    for (index, (_, entry)) in entries.iter().enumerate() {
+        let dump_entry = MempoolDumpEntry {
+            time: entry.get_time(),
+            tx: entry.get_transaction().serialize()?,
+        };
+        let value = bincode::serde::encode_to_vec(&dump_entry, bincode::config::standard())
+            .map_err(|e| BtcError::MempoolDumpError(e.to_string()))?;
+        batch.insert(&(index as u64).to_be_bytes(), value);
+    }
+    tree.apply_batch(batch)
+        .map_err(|e| BtcError::MempoolDumpError(e.to_string()))?;
+    tree.flush_async()
+        .await
+        .map_err(|e| BtcError::MempoolDumpError(e.to_string()))?;
+
+    info!("Dumped {} memory pool transactions to disk", entries.len());
+    Ok(entries.len())
+}
+
+/// Read the transactions saved by `dump_mempool`, in the order they were saved (the
+/// reading half of Bitcoin Core's `LoadMempool`)
+///
+/// # Returns
+///
+/// * `Ok(entries)` - Each saved transaction with the time it entered the pool; empty if
+///   nothing was ever saved
+/// * `Err(MempoolDumpError)` - The database could not be read or holds a damaged entry
+pub async fn read_mempool(blockchain: &BlockchainService) -> Result<Vec<(Transaction, i64)>> {
+    let db = blockchain.get_db().await?;
+    let tree = db
+        .open_tree(MEMPOOL_TREE)
+        .map_err(|e| BtcError::MempoolDumpError(e.to_string()))?;
+    let mut entries = vec![];
+    for value in tree.iter().values() {
+        let value = value.map_err(|e| BtcError::MempoolDumpError(e.to_string()))?;
+        let (dump_entry, _): (MempoolDumpEntry, usize) =
+            bincode::serde::decode_from_slice(value.as_ref(), bincode::config::standard())
+                .map_err(|e| BtcError::MempoolDumpError(e.to_string()))?;
+        entries.push((Transaction::deserialize(&dump_entry.tx)?, dump_entry.time));
+    }
+    Ok(entries)
+}
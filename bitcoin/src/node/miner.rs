@@ -4,16 +4,18 @@
 //! Bitcoin Core's miner.cpp (BlockAssembler, CreateNewBlock)
 
 use super::txmempool::remove_from_memory_pool;
+use crate::chain::Coins;
+use crate::consensus::{Amount, is_final_tx};
 use crate::error::{BtcError, Result};
 use crate::net::net_processing::send_inv;
 use crate::node::{GLOBAL_MEMORY_POOL, GLOBAL_NODES, OpType};
-use crate::primitives::TXOutput;
-use crate::{Block, BlockchainService, GLOBAL_CONFIG, Transaction, WalletAddress};
+use crate::pow::MiningOptions;
+use crate::txmempool::TxMemPoolEntry;
+use crate::{Block, BlockchainService, GLOBAL_CONFIG, Transaction, UTXOSet, WalletAddress};
 use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicBool, Ordering};
-use tracing::{info, warn};
-
-const TRANSACTION_THRESHOLD: usize = 3;
+use tracing::{debug, info, warn};
 
 // Fix 3: Global flags for mining cancellation and concurrency control
 /// Global flag to signal mining cancellation when a competing block arrives
@@ -37,42 +39,241 @@ pub fn is_mining_cancelled() -> bool {
     MINING_CANCELLED.load(Ordering::SeqCst)
 }
 
-/// Create coinbase transaction for mining
-fn create_mining_coinbase_transaction(to: &WalletAddress) -> Result<Transaction> {
-    Transaction::new_coinbase_tx(to)
+/// Create coinbase transaction for the block extending the current tip, paying the
+/// subsidy of that height plus the collected fees
+async fn create_mining_coinbase_transaction(
+    to: &WalletAddress,
+    fees: Amount,
+    blockchain: &BlockchainService,
+) -> Result<Transaction> {
+    let height = blockchain.get_best_height().await? + 1;
+    Transaction::new_coinbase_tx_for_height(to, height, fees)
 }
 
-/// Check if mining should be triggered
+/// Check if mining should be triggered: the node mines and the memory pool holds the
+/// network's `mining_transaction_threshold` transactions. Never on networks that only
+/// mine on demand (regtest).
 pub fn should_trigger_mining() -> bool {
+    let Some(threshold) = GLOBAL_CONFIG
+        .get_chain_params()
+        .get_mining_transaction_threshold()
+    else {
+        return false;
+    };
     let pool_size = GLOBAL_MEMORY_POOL.len().expect("Memory pool length error");
     let is_miner = GLOBAL_CONFIG.is_miner();
-    pool_size >= TRANSACTION_THRESHOLD && is_miner
+    pool_size >= threshold && is_miner
 }
 
-/// Fix 2: Prepare UTXO for mining — validates inputs against current UTXO set
-/// Prepare transactions for mining by validating inputs against the UTXO set.
-///
-/// This function snapshots the memory pool and filters out any transactions whose
-/// inputs have already been spent (e.g., by a competing block that was accepted
-/// while transactions were waiting in the mempool). Stale transactions are removed
-/// from the mempool. A coinbase transaction is appended as the mining reward.
-///
-/// This is the first layer of stale-mining protection. A second validation pass
-/// runs inside `chainstate.rs::mine_block()` under the write lock to catch any
-/// remaining race conditions between this read and the actual mining.
+/// Largest block the assembler builds, in serialized bytes (the policy default of Bitcoin
+/// Core's `-blockmaxweight`; without witness data size and weight measure the same thing).
+pub const DEFAULT_BLOCK_MAX_SIZE: usize = 1_000_000;
+
+/// Bytes of every template kept free for the header, the height, the transaction count and
+/// the coinbase (Bitcoin Core reserves 4000 weight units for the coinbase).
+const BLOCK_RESERVED_SIZE: usize = 1_000;
+
+/// A mempool transaction that may go into a block template (Bitcoin Core's
+/// `CTxMemPoolEntry` as seen by `BlockAssembler`).
+struct TemplateCandidate {
+    tx: Transaction,
+    txid: String,
+    fee: Amount,
+    size: usize,
+    /// Mempool transactions whose outputs this one spends; they must come first.
+    parents: Vec<String>,
+}
+
+/// A transaction of a block template with what the miner needs to know about it.
+pub struct TemplateEntry {
+    tx: Transaction,
+    fee: Amount,
+    size: usize,
+    depends: Vec<usize>,
+}
+
+impl TemplateEntry {
+    pub fn get_transaction(&self) -> &Transaction {
+        &self.tx
+    }
+
+    pub fn get_fee(&self) -> Amount {
+        self.fee
+    }
+
+    /// Serialized size in bytes.
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    /// 1-based positions, among the template's transactions, of the transactions this one
+    /// spends from (`getblocktemplate`'s `depends`).
+    pub fn get_depends(&self) -> &[usize] {
+        &self.depends
+    }
+}
+
+/// The transactions chosen for the next block, without its coinbase (Bitcoin Core's
+/// `CBlockTemplate`).
+pub struct BlockTemplate {
+    height: usize,
+    pre_block_hash: String,
+    bits: u32,
+    min_time: i64,
+    max_block_size: usize,
+    entries: Vec<TemplateEntry>,
+    stale_transactions: Vec<Transaction>,
+}
+
+impl BlockTemplate {
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get_pre_block_hash(&self) -> &str {
+        &self.pre_block_hash
+    }
+
+    pub fn get_bits(&self) -> u32 {
+        self.bits
+    }
+
+    /// Earliest timestamp the block may have: one past the tip's median time past.
+    pub fn get_min_time(&self) -> i64 {
+        self.min_time
+    }
+
+    pub fn get_max_block_size(&self) -> usize {
+        self.max_block_size
+    }
+
+    pub fn get_entries(&self) -> &[TemplateEntry] {
+        &self.entries
+    }
+
+    /// Mempool transactions spending outputs that are no longer unspent; they can never be
+    /// mined and should leave the mempool.
+    pub fn get_stale_transactions(&self) -> &[Transaction] {
+        &self.stale_transactions
+    }
+
+    /// Fees of every transaction in the template, collected by the coinbase.
+    pub fn get_total_fees(&self) -> Result<Amount> {
+        self.entries.iter().try_fold(Amount::ZERO, |total, entry| {
+            total.checked_add(entry.fee).ok_or(BtcError::AmountOverflow)
+        })
+    }
+
+    /// Serialized size of the template's transactions.
+    pub fn get_transactions_size(&self) -> usize {
+        self.entries.iter().map(|entry| entry.size).sum()
+    }
+
+    /// The block's transactions: `coinbase` followed by the template in order.
+    pub fn get_block_transactions(&self, coinbase: Transaction) -> Vec<Transaction> {
+        let mut transactions = vec![coinbase];
+        transactions.extend(self.entries.iter().map(|entry| entry.tx.clone()));
+        transactions
+    }
+}
+
+/// Builds block templates from the memory pool (Bitcoin Core's `BlockAssembler`).
 ///
-/// # Arguments
-/// * `mining_address` - Wallet address to receive the coinbase mining reward
-/// * `blockchain` - Blockchain service for UTXO set access
+/// Transactions are chosen greedily by the fee rate of their ancestor package: a
+/// transaction is only added together with the mempool transactions it spends from, and the
+/// package with the highest combined fee per byte goes first, so a high fee child pulls in
+/// its low fee parent (Bitcoin Core's `addPackageTxs`). Packages that would push the block
+/// over `max_block_size` are skipped, and a transaction spending an output another template
+/// transaction already spends is rejected along with its descendants.
+pub struct BlockAssembler {
+    max_block_size: usize,
+}
+
+impl BlockAssembler {
+    pub fn new() -> BlockAssembler {
+        Self::new_with_max_block_size(DEFAULT_BLOCK_MAX_SIZE)
+    }
+
+    /// An assembler whose blocks stay at or below `max_block_size` serialized bytes.
+    pub fn new_with_max_block_size(max_block_size: usize) -> BlockAssembler {
+        BlockAssembler { max_block_size }
+    }
+
+    /// Build a template for the block extending the current tip (Bitcoin Core's
+    /// `CreateNewBlock`).
+    ///
+    /// Mempool transactions are candidates when every input spends an unspent output or an
+    /// output of another mempool transaction. Transactions whose absolute or relative lock
+    /// time does not allow them into the next block yet are left out for a later block;
+    /// transactions spending outputs that are gone are reported as stale.
+    ///
+    /// # Arguments
+    ///
+    /// * `blockchain` - Blockchain service for the tip and UTXO set.
+    ///
+    /// # Returns
+    ///
+    /// The template, or an error if the chain state cannot be read.
+    pub async fn create_block_template(
+        &self,
+        blockchain: &BlockchainService,
+    ) -> Result<BlockTemplate> {
+        let pre_block_hash = blockchain.get_tip_hash().await?;
+        let height = blockchain.get_best_height().await? + 1;
+        let spend_time = blockchain
+            .get_median_time_past(&pre_block_hash)
+            .await?
+            .unwrap_or_default();
+        let bits = blockchain
+            .get_next_work_required(&pre_block_hash)
+            .await?
+            .ok_or_else(|| {
+                BtcError::GetBlockchainError(format!(
+                    "retarget window for tip {} is incomplete",
+                    pre_block_hash
+                ))
+            })?;
+
+        let (candidates, stale_transactions) =
+            collect_candidates(blockchain, height, spend_time).await?;
+        let size_limit = self.max_block_size.saturating_sub(BLOCK_RESERVED_SIZE);
+        let entries = select_packages(candidates, size_limit);
+
+        Ok(BlockTemplate {
+            height,
+            pre_block_hash,
+            bits,
+            min_time: spend_time + 1,
+            max_block_size: self.max_block_size,
+            entries,
+            stale_transactions,
+        })
+    }
+}
+
+impl Default for BlockAssembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Turn the memory pool into template candidates. Fees, sizes and parents come from the
+/// pool's dependency graph; inputs that spend neither an unspent output nor an output of
+/// another mempool transaction make the transaction stale.
 ///
 /// # Returns
-/// * `Ok(transactions)` - Valid transactions + coinbase, ready for mining
-/// * `Err` - No valid transactions remain (all inputs already spent)
-pub async fn prepare_mining_utxo(
-    mining_address: &WalletAddress,
+///
+/// The candidates, sorted by txid, and the stale transactions.
+async fn collect_candidates(
     blockchain: &BlockchainService,
-) -> Result<Vec<Transaction>> {
-    let txs = GLOBAL_MEMORY_POOL.get_all()?;
+    spend_height: usize,
+    spend_time: i64,
+) -> Result<(Vec<TemplateCandidate>, Vec<Transaction>)> {
+    let entries: Vec<TxMemPoolEntry> = GLOBAL_MEMORY_POOL
+        .get_all_entries()?
+        .into_iter()
+        .filter(|entry| !entry.get_transaction().is_coinbase())
+        .collect();
 
     // Validate each transaction's inputs are still unspent in the UTXO set.
     // When multiple miners compete, a competing block may have already confirmed
@@ -82,59 +283,242 @@ pub async fn prepare_mining_utxo(
     let utxo_tree = db
         .open_tree("chainstate")
         .map_err(|e| BtcError::UTXODBconnection(e.to_string()))?;
+    let utxo_set = UTXOSet::new(blockchain.clone());
 
-    let mut valid_txs = Vec::new();
-    for tx in txs {
-        if tx.is_coinbase() {
+    let mut candidates = vec![];
+    let mut stale = vec![];
+    for entry in entries.iter() {
+        let tx = entry.get_transaction();
+        // Time-locked transactions wait in the mempool until they are final
+        if !is_final_tx(tx, spend_height, spend_time) {
+            info!(
+                "Skipping transaction {} until its lock time",
+                tx.get_tx_id_hex()
+            );
             continue;
         }
-        let mut inputs_valid = true;
+        let mut available = true;
+        let mut coin_heights = Vec::with_capacity(tx.get_vin().len());
         for input in tx.get_vin() {
-            match utxo_tree.get(input.get_txid()) {
-                Ok(Some(outs_bytes)) => {
-                    let outputs: Vec<TXOutput> = bincode::serde::decode_from_slice(
-                        outs_bytes.as_ref(),
-                        bincode::config::standard(),
-                    )
-                    .map(|(v, _)| v)
-                    .unwrap_or_default();
-                    if input.get_vout() >= outputs.len() {
-                        inputs_valid = false;
-                        break;
-                    }
-                }
-                _ => {
-                    inputs_valid = false;
-                    break;
-                }
+            if entry.get_parents().contains(&input.get_input_tx_id_hex()) {
+                // Outputs of mempool transactions would be created in the same block
+                coin_heights.push(spend_height);
+                continue;
             }
+            let coin = match utxo_tree.get(input.get_txid()) {
+                Ok(Some(outs_bytes)) => Coins::deserialize(outs_bytes.as_ref())
+                    .unwrap_or_default()
+                    .get_coin(input.get_vout()),
+                _ => None,
+            };
+            let Some(coin) = coin else {
+                available = false;
+                break;
+            };
+            coin_heights.push(coin.get_height());
         }
-        if inputs_valid {
-            valid_txs.push(tx);
-        } else {
+        if !available {
             info!(
                 "Skipping transaction with already-spent inputs: {}",
                 tx.get_tx_id_hex()
             );
-            remove_from_memory_pool(tx, blockchain).await;
+            stale.push(tx.clone());
+            continue;
+        }
+        if utxo_set
+            .check_sequence_locks(tx, &coin_heights, spend_height, spend_time)
+            .await
+            .is_err()
+        {
+            info!(
+                "Skipping transaction {} until its relative lock time",
+                tx.get_tx_id_hex()
+            );
+            continue;
         }
+        candidates.push(TemplateCandidate {
+            txid: tx.get_tx_id_hex(),
+            size: entry.get_size(),
+            tx: tx.clone(),
+            fee: entry.get_fee(),
+            parents: entry.get_parents().iter().cloned().collect(),
+        });
     }
+    Ok((candidates, stale))
+}
+
+/// The not yet selected ancestors of `txid` followed by `txid` itself, parents before
+/// children, or `None` if an ancestor was rejected.
+fn ancestor_package(
+    txid: &str,
+    remaining: &HashMap<String, TemplateCandidate>,
+    selected: &HashSet<String>,
+) -> Option<Vec<String>> {
+    fn visit(
+        txid: &str,
+        remaining: &HashMap<String, TemplateCandidate>,
+        selected: &HashSet<String>,
+        package: &mut Vec<String>,
+    ) -> bool {
+        if selected.contains(txid) || package.iter().any(|id| id == txid) {
+            return true;
+        }
+        let Some(candidate) = remaining.get(txid) else {
+            return false;
+        };
+        for parent in &candidate.parents {
+            if !visit(parent, remaining, selected, package) {
+                return false;
+            }
+        }
+        package.push(txid.to_string());
+        true
+    }
+    let mut package = vec![];
+    visit(txid, remaining, selected, &mut package).then_some(package)
+}
+
+/// Choose the template's transactions, best ancestor package fee rate first, within
+/// `size_limit` bytes (Bitcoin Core's `addPackageTxs`).
+fn select_packages(candidates: Vec<TemplateCandidate>, size_limit: usize) -> Vec<TemplateEntry> {
+    let mut order: Vec<String> = candidates.iter().map(|c| c.txid.clone()).collect();
+    order.sort();
+    let mut remaining: HashMap<String, TemplateCandidate> = candidates
+        .into_iter()
+        .map(|candidate| (candidate.txid.clone(), candidate))
+        .collect();
+    let mut selected_ids = HashSet::new();
+    let mut selected: Vec<TemplateCandidate> = vec![];
+    let mut spent_outpoints = HashSet::new();
+    let mut block_size = 0;
+
+    loop {
+        order.retain(|txid| remaining.contains_key(txid));
+        // Find the package with the best fee rate; descendants of rejected transactions
+        // can never be mined and are dropped
+        let mut best: Option<(Vec<String>, u128, usize)> = None;
+        let mut orphaned = vec![];
+        for txid in &order {
+            let Some(package) = ancestor_package(txid, &remaining, &selected_ids) else {
+                orphaned.push(txid.clone());
+                continue;
+            };
+            let (fee, size) = package.iter().fold((0u128, 0usize), |(fee, size), id| {
+                let candidate = &remaining[id];
+                (fee + candidate.fee.to_sat() as u128, size + candidate.size)
+            });
+            let better = best.as_ref().is_none_or(|(_, best_fee, best_size)| {
+                fee * *best_size as u128 > best_fee * size as u128
+            });
+            if better {
+                best = Some((package, fee, size));
+            }
+        }
+        for txid in orphaned {
+            remaining.remove(&txid);
+        }
+        let Some((package, _, package_size)) = best else {
+            break;
+        };
+        let tip = package.last().cloned().unwrap_or_default();
 
-    if valid_txs.is_empty() {
+        if block_size + package_size > size_limit {
+            debug!("Package of {} does not fit in the block", tip);
+            remaining.remove(&tip);
+            continue;
+        }
+        let mut package_outpoints = HashSet::new();
+        let conflicts = package.iter().any(|txid| {
+            remaining[txid].tx.get_vin().iter().any(|input| {
+                let outpoint = (input.get_txid().to_vec(), input.get_vout());
+                spent_outpoints.contains(&outpoint) || !package_outpoints.insert(outpoint)
+            })
+        });
+        if conflicts {
+            info!("Rejecting transaction {} with a conflicting spend", tip);
+            remaining.remove(&tip);
+            continue;
+        }
+
+        spent_outpoints.extend(package_outpoints);
+        block_size += package_size;
+        for txid in package {
+            if let Some(candidate) = remaining.remove(&txid) {
+                selected_ids.insert(txid);
+                selected.push(candidate);
+            }
+        }
+    }
+
+    let positions: HashMap<String, usize> = selected
+        .iter()
+        .enumerate()
+        .map(|(idx, candidate)| (candidate.txid.clone(), idx + 1))
+        .collect();
+    selected
+        .into_iter()
+        .map(|candidate| TemplateEntry {
+            depends: candidate
+                .parents
+                .iter()
+                .filter_map(|parent| positions.get(parent).copied())
+                .collect(),
+            tx: candidate.tx,
+            fee: candidate.fee,
+            size: candidate.size,
+        })
+        .collect()
+}
+
+/// Prepare transactions for mining from a block template.
+///
+/// This function asks the `BlockAssembler` for the best transactions of the memory pool
+/// within the block size limit, ordered so that parents come before children. Transactions
+/// whose inputs have already been spent (e.g., by a competing block that was accepted
+/// while transactions were waiting in the mempool) are removed from the mempool.
+/// Transactions whose absolute or relative lock time does not allow them into the next
+/// block yet are left in the mempool for a later block. A coinbase transaction is placed
+/// first as the mining reward; it pays the subsidy scheduled for the new block's height
+/// plus the fees (inputs − outputs) of every included transaction.
+///
+/// This is the first layer of stale-mining protection. A second validation pass
+/// runs inside `chainstate.rs::mine_block()` under the write lock to catch any
+/// remaining race conditions between this read and the actual mining.
+///
+/// # Arguments
+/// * `mining_address` - Wallet address to receive the coinbase mining reward
+/// * `blockchain` - Blockchain service for UTXO set access
+///
+/// # Returns
+/// * `Ok(transactions)` - Valid transactions + coinbase, ready for mining
+/// * `Err` - No valid transactions remain (all inputs already spent)
+pub async fn prepare_mining_utxo(
+    mining_address: &WalletAddress,
+    blockchain: &BlockchainService,
+) -> Result<Vec<Transaction>> {
+    let template = BlockAssembler::new()
+        .create_block_template(blockchain)
+        .await?;
+    for tx in template.get_stale_transactions() {
+        remove_from_memory_pool(tx.clone());
+    }
+
+    if template.get_entries().is_empty() {
         return Err(BtcError::InvalidValueForMiner(
             "No valid transactions to mine (all inputs already spent)".to_string(),
         ));
     }
 
+    let total_fees = template.get_total_fees()?;
     info!(
-        "Preparing to mine with {} valid transactions",
-        valid_txs.len()
+        "Preparing to mine with {} valid transactions paying {} in fees",
+        template.get_entries().len(),
+        total_fees
     );
-    let coinbase_tx = create_mining_coinbase_transaction(mining_address)?;
-    let mut final_txs = valid_txs;
-    final_txs.push(coinbase_tx);
-
-    Ok(final_txs)
+    // The coinbase must be the first transaction in the block (see `consensus::check_block`)
+    let coinbase_tx =
+        Transaction::new_coinbase_tx_for_height(mining_address, template.get_height(), total_fees)?;
+    Ok(template.get_block_transactions(coinbase_tx))
 }
 
 /// Process mining: create block, persist, clean mempool, and broadcast.
@@ -147,7 +531,9 @@ pub async fn prepare_mining_utxo(
 ///
 /// 2. **Cancellation check** (`MINING_CANCELLED`): Before mining starts, checks if
 ///    a competing block arrived (set by `cancel_current_mining()` in the
-///    `Package::Block` handler). If cancelled, mining is aborted.
+///    `Package::Block` handler). If cancelled, mining is aborted. The nonce search
+///    itself polls the same flag on every worker thread, so a competing block also
+///    stops a search in progress.
 ///
 /// **CRITICAL**: Cancellation is NOT checked after `mine_block()` completes.
 /// Once the block is created and added to the local chain (tip updated, UTXO
@@ -183,15 +569,17 @@ pub async fn process_mine_block(
 
         let my_node_addr = GLOBAL_CONFIG.get_node_addr();
 
-        // Mine a new block with the transactions in the memory pool
-        let new_block = blockchain.mine_block(&txs).await?;
+        // Mine a new block with the transactions in the memory pool. The nonce search polls
+        // the cancellation flag, so a competing block stops it before the block is stored.
+        let options = MiningOptions::new(GLOBAL_CONFIG.get_mining_threads(), &MINING_CANCELLED);
+        let new_block = blockchain.mine_block_with_options(&txs, &options).await?;
 
         // CRITICAL: Do NOT cancel after block creation. Once mine_block() completes,
         // the block is already in our local chain (tip updated, UTXO updated).
         // If we skip broadcasting, other nodes never learn about this block,
         // creating a permanent fork where this node has a block nobody else has.
         // The consensus mechanism (add_block on other nodes) will handle the
-        // tie-breaking and reorganization when they receive this block.
+        // fork choice and reorganization when they receive this block.
 
         info!(
             "New block {} is mined by node {}!",
@@ -201,7 +589,7 @@ pub async fn process_mine_block(
 
         // Remove transactions from memory pool functionally
         for tx in &txs {
-            remove_from_memory_pool(tx.clone(), blockchain).await;
+            remove_from_memory_pool(tx.clone());
         }
 
         // Broadcast new block to nodes
@@ -254,7 +642,8 @@ pub async fn mine_empty_block(
 ) -> Result<Block> {
     if GLOBAL_CONFIG.is_miner() {
         // Create only coinbase transaction for empty block
-        let coinbase_tx = create_mining_coinbase_transaction(wallet_address)?;
+        let coinbase_tx =
+            create_mining_coinbase_transaction(wallet_address, Amount::ZERO, blockchain).await?;
         let txs = vec![coinbase_tx];
 
         // Mine the block with only coinbase transaction
@@ -272,3 +661,111 @@ pub async fn cleanup_invalid_transactions() -> Result<()> {
     // This ensures the memory pool stays clean and doesn't accumulate invalid transactions
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::transaction::SEQUENCE_FINAL;
+
+    /// A candidate with the given fee and size spending `spends` (txid byte, output index),
+    /// with `parents` among the other candidates.
+    fn candidate(
+        txid: &str,
+        fee: u64,
+        size: usize,
+        spends: &[(u8, usize)],
+        parents: &[&str],
+    ) -> TemplateCandidate {
+        let tx = spends
+            .iter()
+            .fold(Transaction::test_builder(), |builder, (txid, vout)| {
+                builder.with_input(*txid, *vout, SEQUENCE_FINAL)
+            })
+            .build();
+        TemplateCandidate {
+            tx,
+            txid: txid.to_string(),
+            fee: Amount::from_sat(fee),
+            size,
+            parents: parents.iter().map(|parent| parent.to_string()).collect(),
+        }
+    }
+
+    fn fees(entries: &[TemplateEntry]) -> Vec<u64> {
+        entries
+            .iter()
+            .map(|entry| entry.get_fee().to_sat())
+            .collect()
+    }
+
+    #[test]
+    fn test_select_packages_orders_by_fee_rate() {
+        let entries = select_packages(
+            vec![
+                candidate("a", 100, 100, &[(1, 0)], &[]),
+                candidate("b", 300, 100, &[(2, 0)], &[]),
+                candidate("c", 201, 200, &[(3, 0)], &[]),
+            ],
+            10_000,
+        );
+        assert_eq!(fees(&entries), vec![300, 201, 100]);
+    }
+
+    #[test]
+    fn test_select_packages_respects_size_limit() {
+        let entries = select_packages(
+            vec![
+                candidate("a", 100, 100, &[(1, 0)], &[]),
+                candidate("b", 300, 100, &[(2, 0)], &[]),
+                // Best after `b`, but too large for what is left
+                candidate("c", 250, 200, &[(3, 0)], &[]),
+                candidate("d", 10, 50, &[(4, 0)], &[]),
+            ],
+            250,
+        );
+        assert_eq!(fees(&entries), vec![300, 100, 10]);
+        let size: usize = entries.iter().map(TemplateEntry::get_size).sum();
+        assert!(size <= 250);
+    }
+
+    #[test]
+    fn test_select_packages_pulls_in_parents_of_high_fee_children() {
+        let entries = select_packages(
+            vec![
+                candidate("parent", 0, 100, &[(1, 0)], &[]),
+                candidate("child", 1000, 100, &[(0xaa, 0)], &["parent"]),
+                candidate("other", 400, 100, &[(2, 0)], &[]),
+            ],
+            10_000,
+        );
+        // The parent and child pay 5 per byte together, more than `other`
+        assert_eq!(fees(&entries), vec![0, 1000, 400]);
+        assert_eq!(entries[0].get_depends(), &[] as &[usize]);
+        assert_eq!(entries[1].get_depends(), &[1]);
+
+        // A child whose parent does not fit cannot be included either
+        let entries = select_packages(
+            vec![
+                candidate("parent", 0, 300, &[(1, 0)], &[]),
+                candidate("child", 1000, 100, &[(0xaa, 0)], &["parent"]),
+            ],
+            200,
+        );
+        assert!(entries.is_empty());
+    }
+
+    #[test]
+    fn test_select_packages_rejects_conflicting_spends() {
+        let entries = select_packages(
+            vec![
+                candidate("y", 500, 100, &[(1, 0)], &[]),
+                candidate("z", 100, 100, &[(1, 0)], &[]),
+                candidate("w", 10, 100, &[(0xbb, 0)], &["z"]),
+                // Spends the same output twice
+                candidate("v", 900, 100, &[(2, 0), (2, 0)], &[]),
+            ],
+            10_000,
+        );
+        assert_eq!(fees(&entries), vec![500]);
+    }
+}
@@ -4,13 +4,18 @@
 //! with the blockchain node, following Bitcoin Core's architecture.
 
 pub mod context;
+pub mod mempool_persist;
 pub mod miner;
 pub mod peers;
 pub mod server;
 pub mod txmempool;
 
 pub use context::NodeContext;
-pub use miner::{mine_empty_block, prepare_mining_utxo, process_mine_block, should_trigger_mining};
+pub use mempool_persist::{MEMPOOL_DUMP_INTERVAL, dump_mempool, read_mempool};
+pub use miner::{
+    BlockAssembler, BlockTemplate, mine_empty_block, prepare_mining_utxo, process_mine_block,
+    should_trigger_mining,
+};
 pub use peers::{Node, Nodes};
 pub use server::*;
 pub use txmempool::{add_to_memory_pool, remove_from_memory_pool, transaction_exists_in_pool};
@@ -1,7 +1,7 @@
 use crate::net::net_processing;
 use crate::net::net_processing::{send_known_nodes, send_version};
 use crate::node::NodeContext;
-use crate::{BlockInTransit, MemoryPool, Nodes};
+use crate::{Amount, BlockInTransit, GLOBAL_CONFIG, MemoryPool, Nodes};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
@@ -13,15 +13,15 @@ use tracing::{error, info, instrument};
 
 pub const NODE_VERSION: usize = 1;
 
+/// The seed node every node registers with: `CENTERAL_NODE` when set, otherwise the
+/// loopback address on the default port of the configured network.
 pub static CENTERAL_NODE: Lazy<SocketAddr> = Lazy::new(|| {
-    let central_node_str =
-        env::var("CENTERAL_NODE").unwrap_or_else(|_| "127.0.0.1:2001".to_string());
+    let central_node_str = env::var("CENTERAL_NODE").unwrap_or_default();
 
     // Handle empty string case (when CENTERAL_NODE is set but empty)
     if central_node_str.is_empty() {
-        "127.0.0.1:2001"
-            .parse()
-            .expect("Failed to parse default CENTERAL_NODE address")
+        let port = GLOBAL_CONFIG.get_chain_params().get_default_port();
+        SocketAddr::from(([127, 0, 0, 1], port))
     } else {
         central_node_str
             .parse()
@@ -29,8 +29,6 @@ pub static CENTERAL_NODE: Lazy<SocketAddr> = Lazy::new(|| {
     }
 });
 
-pub const TRANSACTION_THRESHOLD: usize = 3;
-
 pub static GLOBAL_NODES: Lazy<Nodes> = Lazy::new(|| {
     let nodes = Nodes::new();
 
@@ -247,7 +245,7 @@ pub enum Package {
         addr_from: SocketAddr,
         wlt_frm_addr: String,
         wlt_to_addr: String,
-        amount: i32,
+        amount: Amount,
     },
     KnownNodes {
         addr_from: SocketAddr,
@@ -3,19 +3,23 @@
 //! This module handles the transaction memory pool data structure,
 //! similar to Bitcoin Core's txmempool.cpp (CTxMemPool class)
 
-use crate::error::Result;
+use crate::consensus::Amount;
+use crate::error::{BtcError, Result};
 use crate::node::GLOBAL_MEMORY_POOL;
-use crate::{BlockchainService, Transaction, UTXOSet};
-use tracing::debug;
+use crate::policy::{DEFAULT_ANCESTOR_LIMIT, check_min_fee, check_replacement, signals_opt_in_rbf};
+use crate::util::current_timestamp;
+use crate::{GLOBAL_CONFIG, Transaction};
+use std::collections::BTreeSet;
+use tracing::{debug, info};
 
 /// Add transaction to memory pool
 ///
-/// This is the core mempool operation that adds a transaction to the pool
-/// and updates UTXO set flags.
-pub async fn add_to_memory_pool(
-    tx: Transaction,
-    blockchain_service: &BlockchainService,
-) -> Result<()> {
+/// This is the core mempool operation that adds a transaction, paying `fee`, to the
+/// pool. The outputs it spends count as spent for the wallet from then on (see
+/// `MemoryPool::is_spent`); the UTXO set itself only changes when a block confirms it.
+/// `time` is when the transaction entered the pool, in milliseconds since the Unix
+/// epoch; it decides when the transaction expires.
+pub fn add_to_memory_pool(tx: Transaction, fee: Amount, time: i64) -> Result<()> {
     debug!("\n");
     debug!(
         "******************************************************************************************************"
@@ -27,30 +31,17 @@ pub async fn add_to_memory_pool(
     debug!(
         "******************************************************************************************************\n"
     );
-    GLOBAL_MEMORY_POOL
-        .add(tx.clone())
-        .expect("Memory pool add error");
-
-    let utxo_set = UTXOSet::new(blockchain_service.clone());
-    utxo_set.set_global_mem_pool_flag(&tx.clone(), true).await?;
-
-    Ok(())
+    GLOBAL_MEMORY_POOL.add_with_time(tx, fee, time)
 }
 
 /// Remove transaction from memory pool
 ///
-/// This is the core mempool operation that removes a transaction from the pool
-/// and updates UTXO set flags.
-pub async fn remove_from_memory_pool(tx: Transaction, blockchain: &BlockchainService) {
+/// This is the core mempool operation that removes a transaction from the pool,
+/// releasing the outputs it spends.
+pub fn remove_from_memory_pool(tx: Transaction) {
     GLOBAL_MEMORY_POOL
-        .remove(tx.clone())
+        .remove(tx)
         .expect("Memory pool remove error");
-
-    let utxo_set = UTXOSet::new(blockchain.clone());
-    utxo_set
-        .set_global_mem_pool_flag(&tx.clone(), false)
-        .await
-        .expect("Failed to get blockchain");
 }
 
 /// Check if transaction exists in memory pool
@@ -59,3 +50,169 @@ pub async fn remove_from_memory_pool(tx: Transaction, blockchain: &BlockchainSer
 pub fn transaction_exists_in_pool(tx: &Transaction) -> bool {
     GLOBAL_MEMORY_POOL.contains_transaction(tx).unwrap_or(false)
 }
+
+/// Check a transaction against the memory pool transactions it conflicts with
+///
+/// Similar to Bitcoin Core's `MemPoolAccept::ReplacementChecks`. A transaction that spends
+/// an output a pool transaction already spends may only replace it if every conflicting
+/// transaction signals opt-in replace-by-fee (BIP125) and the replacement pays more than
+/// everything it evicts (see `policy::check_replacement`).
+///
+/// # Arguments
+///
+/// * `tx` - The candidate replacement
+/// * `fee` - The fee `tx` pays
+///
+/// # Returns
+///
+/// * `Ok(evicted)` - The conflicting transactions followed by their descendants, which
+///   must leave the pool when `tx` enters it; empty if `tx` conflicts with nothing
+/// * `Err(TransactionConflictsWithMemoryPool)` - A conflicting transaction does not signal
+///   replaceability
+/// * `Err(InvalidReplacement)` - `tx` does not pay enough to replace the conflicts
+pub fn check_conflicts(tx: &Transaction, fee: Amount) -> Result<Vec<Transaction>> {
+    let conflicts = GLOBAL_MEMORY_POOL.get_conflicts(tx)?;
+    if conflicts.is_empty() {
+        return Ok(vec![]);
+    }
+    if let Some(original) = conflicts.iter().find(|tx| !signals_opt_in_rbf(tx)) {
+        return Err(BtcError::TransactionConflictsWithMemoryPool(
+            tx.get_tx_id_hex(),
+            original.get_tx_id_hex(),
+        ));
+    }
+
+    let mut descendants: Vec<Transaction> = vec![];
+    for conflict in conflicts.iter() {
+        for descendant in GLOBAL_MEMORY_POOL.get_descendants(&conflict.get_tx_id_hex())? {
+            let txid = descendant.get_tx_id_hex();
+            let seen = conflicts
+                .iter()
+                .chain(descendants.iter())
+                .any(|evicted| evicted.get_tx_id_hex() == txid);
+            if !seen {
+                descendants.push(descendant);
+            }
+        }
+    }
+
+    // The replacement cannot depend on what it evicts
+    let evicted_ids: BTreeSet<String> = conflicts
+        .iter()
+        .chain(descendants.iter())
+        .map(|evicted| evicted.get_tx_id_hex())
+        .collect();
+    if let Some(input) = tx
+        .get_vin()
+        .iter()
+        .find(|input| evicted_ids.contains(&input.get_input_tx_id_hex()))
+    {
+        return Err(BtcError::InvalidReplacement(
+            tx.get_tx_id_hex(),
+            format!(
+                "spends an output of {}, which it replaces",
+                input.get_input_tx_id_hex()
+            ),
+        ));
+    }
+
+    let fee_and_size = |txs: &[Transaction]| -> Result<Vec<(Amount, usize)>> {
+        let mut fees = Vec::with_capacity(txs.len());
+        for evicted in txs {
+            if let Some(entry) = GLOBAL_MEMORY_POOL.get_entry(&evicted.get_tx_id_hex())? {
+                fees.push((entry.get_fee(), entry.get_size()));
+            }
+        }
+        Ok(fees)
+    };
+    check_replacement(
+        &tx.get_tx_id_hex(),
+        fee,
+        tx.get_size()?,
+        &fee_and_size(&conflicts)?,
+        &fee_and_size(&descendants)?,
+    )?;
+
+    info!(
+        "Transaction {} replaces {} memory pool transactions",
+        tx.get_tx_id_hex(),
+        conflicts.len() + descendants.len()
+    );
+    let mut evicted = conflicts;
+    evicted.extend(descendants);
+    Ok(evicted)
+}
+
+/// Check that a transaction does not extend a chain of unconfirmed transactions beyond
+/// `DEFAULT_ANCESTOR_LIMIT` (Bitcoin Core's `CTxMemPool::CheckPackageLimits`)
+///
+/// # Returns
+///
+/// * `Ok(ancestors)` - How many pool transactions `tx` depends on, directly or not
+/// * `Err(TooLongMempoolChain)` - `tx` and its pool ancestors exceed the limit
+pub fn check_ancestor_limit(tx: &Transaction) -> Result<usize> {
+    let mut ancestors = BTreeSet::new();
+    for input in tx.get_vin() {
+        let parent = input.get_input_tx_id_hex();
+        if ancestors.contains(&parent) || !GLOBAL_MEMORY_POOL.contains(&parent)? {
+            continue;
+        }
+        ancestors.extend(GLOBAL_MEMORY_POOL.get_ancestors(&parent)?);
+        ancestors.insert(parent);
+    }
+    if ancestors.len() + 1 > DEFAULT_ANCESTOR_LIMIT {
+        return Err(BtcError::TooLongMempoolChain(
+            tx.get_tx_id_hex(),
+            DEFAULT_ANCESTOR_LIMIT,
+        ));
+    }
+    Ok(ancestors.len())
+}
+
+/// Check that a transaction pays the memory pool minimum fee rate, which is raised when a
+/// full pool evicts transactions (Bitcoin Core's `MemPoolAccept::CheckFeeRate`)
+///
+/// # Returns
+///
+/// * `Ok(())` - `tx` pays enough to enter the pool
+/// * `Err(MempoolMinFeeNotMet)` - `fee` is below the minimum for the size of `tx`
+pub fn check_mempool_min_fee(tx: &Transaction, fee: Amount) -> Result<()> {
+    let min_fee_rate = GLOBAL_MEMORY_POOL.get_min_fee_rate(GLOBAL_CONFIG.get_max_mempool_size())?;
+    check_min_fee(&tx.get_tx_id_hex(), fee, tx.get_size()?, min_fee_rate)
+}
+
+/// Entry time, in milliseconds since the Unix epoch, before which a memory pool transaction
+/// has waited longer than the configured expiry
+pub fn get_expiry_cutoff() -> i64 {
+    let expiry_ms = GLOBAL_CONFIG.get_mempool_expiry_hours() as i64 * 60 * 60 * 1000;
+    current_timestamp().saturating_sub(expiry_ms)
+}
+
+/// Keep the memory pool within its configured age and size limits
+///
+/// Similar to Bitcoin Core's `LimitMempoolSize`: transactions older than the configured
+/// expiry are dropped, then the lowest fee rate packages are evicted until the pool fits in
+/// the configured maximum size. The outputs spent by every removed transaction are no
+/// longer spent in the pool, so the wallet can spend them again.
+///
+/// # Returns
+///
+/// * `Ok(removed)` - The expired and evicted transactions
+pub fn limit_memory_pool() -> Result<Vec<Transaction>> {
+    let expired = GLOBAL_MEMORY_POOL.expire(get_expiry_cutoff())?;
+    if !expired.is_empty() {
+        info!("Expired {} memory pool transactions", expired.len());
+    }
+    let evicted = GLOBAL_MEMORY_POOL.trim_to_size(GLOBAL_CONFIG.get_max_mempool_size())?;
+    if !evicted.is_empty() {
+        info!(
+            "Evicted {} memory pool transactions to stay within {} bytes",
+            evicted.len(),
+            GLOBAL_CONFIG.get_max_mempool_size()
+        );
+    }
+
+    let mut removed = expired;
+    removed.extend(evicted);
+    Ok(removed)
+}
@@ -0,0 +1,286 @@
+//! Relay policy (Bitcoin Core: policy/policy.cpp)
+//!
+//! Standardness rules are stricter than consensus: a transaction that breaks them is still
+//! valid in a block, but this node neither accepts it into its memory pool nor relays it.
+//! They keep outputs that only carry data small, so the chain is not used as free storage.
+
+use crate::consensus::Amount;
+use crate::error::{BtcError, Result};
+use crate::primitives::transaction::{MAX_BIP125_RBF_SEQUENCE, Transaction};
+
+/// Largest payload of a data carrier output (Bitcoin Core's default `-datacarriersize` of 83
+/// script bytes, less `OP_RETURN` and the push opcodes).
+pub const MAX_DATA_CARRIER_SIZE: usize = 80;
+
+/// Most transactions in a chain of unconfirmed transactions: a memory pool transaction
+/// together with the pool transactions it depends on (Bitcoin Core's
+/// `DEFAULT_ANCESTOR_LIMIT`).
+pub const DEFAULT_ANCESTOR_LIMIT: usize = 25;
+
+/// Most memory pool transactions a single replacement may evict, counting the descendants of
+/// the transactions it conflicts with (BIP125 rule 5, Bitcoin Core `MAX_REPLACEMENT_CANDIDATES`).
+pub const MAX_REPLACEMENT_CANDIDATES: usize = 100;
+
+/// Default upper bound on the serialized size of all memory pool transactions, in bytes
+/// (Bitcoin Core's `DEFAULT_MAX_MEMPOOL_SIZE_MB`).
+pub const DEFAULT_MAX_MEMPOOL_SIZE: usize = 300_000_000;
+
+/// Default age, in hours, after which a transaction that was never mined leaves the memory
+/// pool (Bitcoin Core's `DEFAULT_MEMPOOL_EXPIRY_HOURS`).
+pub const DEFAULT_MEMPOOL_EXPIRY_HOURS: u64 = 336;
+
+/// Fee rate, in satoshis per 1000 bytes, a transaction must pay above the package evicted to
+/// make room for it; the memory pool minimum fee rate rises by this much on every eviction
+/// (Bitcoin Core's `DEFAULT_INCREMENTAL_RELAY_FEE`).
+pub const DEFAULT_INCREMENTAL_RELAY_FEE: u64 = 1_000;
+
+/// Check the outputs of `tx` against the relay policy (the output part of Bitcoin Core's
+/// `IsStandardTx`): a transaction may have at most one data carrier output, and its
+/// payload may not exceed `MAX_DATA_CARRIER_SIZE` bytes.
+///
+/// # Returns
+///
+/// `NonStandardTransaction` with the rule the transaction breaks.
+pub fn check_standard_outputs(tx: &Transaction) -> Result<()> {
+    let mut data_carriers = 0;
+    for output in tx.get_vout() {
+        let Some(payload) = output.get_data_carrier_payload() else {
+            continue;
+        };
+        data_carriers += 1;
+        if data_carriers > 1 {
+            return Err(BtcError::NonStandardTransaction(
+                tx.get_tx_id_hex(),
+                "more than one data carrier output".to_string(),
+            ));
+        }
+        if payload.len() > MAX_DATA_CARRIER_SIZE {
+            return Err(BtcError::NonStandardTransaction(
+                tx.get_tx_id_hex(),
+                format!(
+                    "data carrier payload of {} bytes exceeds {} bytes",
+                    payload.len(),
+                    MAX_DATA_CARRIER_SIZE
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Whether `tx` lets a transaction paying a higher fee replace it in the memory pool: at
+/// least one of its inputs has a sequence of at most `MAX_BIP125_RBF_SEQUENCE` (BIP125,
+/// Bitcoin Core's `SignalsOptInRBF`).
+pub fn signals_opt_in_rbf(tx: &Transaction) -> bool {
+    tx.get_vin()
+        .iter()
+        .any(|input| input.get_sequence() <= MAX_BIP125_RBF_SEQUENCE)
+}
+
+/// Check that a replacement pays for the memory pool transactions it evicts (BIP125 rules
+/// 3, 5 and 6, Bitcoin Core's `PaysMoreThanConflicts` and `PaysForRBF`).
+///
+/// The replacement must evict at most `MAX_REPLACEMENT_CANDIDATES` transactions, pay a
+/// strictly higher fee rate than every transaction it directly conflicts with, and a
+/// strictly higher absolute fee than all evicted transactions together.
+///
+/// # Arguments
+///
+/// * `txid` - Id of the replacement, for the error.
+/// * `fee` - Fee paid by the replacement.
+/// * `size` - Serialized size of the replacement in bytes.
+/// * `conflicts` - Fee and size of every transaction spending an output the replacement spends.
+/// * `descendants` - Fee and size of every other transaction evicted with the conflicts.
+///
+/// # Returns
+///
+/// `InvalidReplacement` with the rule the replacement breaks.
+pub fn check_replacement(
+    txid: &str,
+    fee: Amount,
+    size: usize,
+    conflicts: &[(Amount, usize)],
+    descendants: &[(Amount, usize)],
+) -> Result<()> {
+    let evicted = conflicts.len() + descendants.len();
+    if evicted > MAX_REPLACEMENT_CANDIDATES {
+        return Err(BtcError::InvalidReplacement(
+            txid.to_string(),
+            format!(
+                "evicts {} transactions, more than {}",
+                evicted, MAX_REPLACEMENT_CANDIDATES
+            ),
+        ));
+    }
+    // Fee rates are compared by cross-multiplying, so nothing is lost to rounding
+    for (conflict_fee, conflict_size) in conflicts {
+        if fee.to_sat() as u128 * *conflict_size as u128
+            <= conflict_fee.to_sat() as u128 * size as u128
+        {
+            return Err(BtcError::InvalidReplacement(
+                txid.to_string(),
+                format!(
+                    "fee rate {}/{} is not above the replaced {}/{}",
+                    fee, size, conflict_fee, conflict_size
+                ),
+            ));
+        }
+    }
+    let replaced_fees = Amount::checked_sum(
+        conflicts
+            .iter()
+            .chain(descendants.iter())
+            .map(|(fee, _)| *fee),
+    )
+    .ok_or(BtcError::AmountOverflow)?;
+    if fee <= replaced_fees {
+        return Err(BtcError::InvalidReplacement(
+            txid.to_string(),
+            format!(
+                "fee {} is not above the {} paid by the replaced transactions",
+                fee, replaced_fees
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Fee rate of a transaction paying `fee` for `size` bytes, in satoshis per 1000 bytes and
+/// rounded down (Bitcoin Core's `CFeeRate(nFeePaid, nBytes)`).
+pub fn get_fee_rate(fee: Amount, size: usize) -> u64 {
+    if size == 0 {
+        return 0;
+    }
+    let rate = fee.to_sat() as u128 * 1_000 / size as u128;
+    u64::try_from(rate).unwrap_or(u64::MAX)
+}
+
+/// Fee owed for `size` bytes at `fee_rate` satoshis per 1000 bytes, rounded up (Bitcoin
+/// Core's `CFeeRate::GetFee`).
+pub fn get_fee_for_size(fee_rate: u64, size: usize) -> Amount {
+    let fee = (fee_rate as u128 * size as u128).div_ceil(1_000);
+    Amount::from_sat(u64::try_from(fee).unwrap_or(u64::MAX))
+}
+
+/// Check that a transaction pays at least the memory pool minimum fee rate (Bitcoin Core's
+/// `CheckFeeRate`). The minimum is zero until a full pool has evicted transactions.
+///
+/// # Arguments
+///
+/// * `txid` - Id of the transaction, for the error.
+/// * `fee` - Fee paid by the transaction.
+/// * `size` - Serialized size of the transaction in bytes.
+/// * `min_fee_rate` - The memory pool minimum fee rate, in satoshis per 1000 bytes.
+///
+/// # Returns
+///
+/// `MempoolMinFeeNotMet` with the fee paid and the fee required.
+pub fn check_min_fee(txid: &str, fee: Amount, size: usize, min_fee_rate: u64) -> Result<()> {
+    let required = get_fee_for_size(min_fee_rate, size);
+    if fee < required {
+        return Err(BtcError::MempoolMinFeeNotMet(
+            txid.to_string(),
+            fee,
+            required,
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::transaction::{SEQUENCE_FINAL, TXOutput};
+
+    /// A transaction with the given outputs and one input.
+    fn transaction_with_outputs(outputs: Vec<TXOutput>) -> Transaction {
+        outputs
+            .into_iter()
+            .fold(
+                Transaction::test_builder().with_input(1, 0, SEQUENCE_FINAL),
+                |builder, output| builder.with_output(output),
+            )
+            .build()
+    }
+
+    fn data_carrier(data: &[u8]) -> TXOutput {
+        TXOutput::new_test_data_carrier(data)
+    }
+
+    #[test]
+    fn test_one_data_carrier_within_size_is_standard() {
+        let tx = transaction_with_outputs(vec![data_carrier(&[7; MAX_DATA_CARRIER_SIZE])]);
+        assert!(check_standard_outputs(&tx).is_ok());
+        assert!(check_standard_outputs(&transaction_with_outputs(vec![])).is_ok());
+    }
+
+    #[test]
+    fn test_oversized_or_repeated_data_carriers_are_not_standard() {
+        let oversized =
+            transaction_with_outputs(vec![data_carrier(&[7; MAX_DATA_CARRIER_SIZE + 1])]);
+        assert!(matches!(
+            check_standard_outputs(&oversized),
+            Err(BtcError::NonStandardTransaction(..))
+        ));
+        let repeated = transaction_with_outputs(vec![data_carrier(b"a"), data_carrier(b"b")]);
+        assert!(matches!(
+            check_standard_outputs(&repeated),
+            Err(BtcError::NonStandardTransaction(..))
+        ));
+    }
+
+    #[test]
+    fn test_signals_opt_in_rbf_checks_every_input_sequence() {
+        let final_input = Transaction::test_builder().with_input(1, 0, SEQUENCE_FINAL);
+        assert!(!signals_opt_in_rbf(&final_input.build()));
+        let tx = Transaction::test_builder()
+            .with_input(1, 0, SEQUENCE_FINAL)
+            .with_input(1, 1, MAX_BIP125_RBF_SEQUENCE)
+            .build();
+        assert!(signals_opt_in_rbf(&tx));
+    }
+
+    #[test]
+    fn test_replacement_must_pay_higher_fee_and_fee_rate() {
+        let sat = Amount::from_sat;
+        assert!(check_replacement("r", sat(30), 100, &[(sat(10), 100)], &[(sat(10), 50)]).is_ok());
+
+        // Higher absolute fee, but a lower fee rate than the conflict
+        let result = check_replacement("r", sat(30), 400, &[(sat(10), 100)], &[]);
+        assert!(matches!(result, Err(BtcError::InvalidReplacement(..))));
+
+        // Higher fee rate than the conflict, but not more than it and its descendant
+        let result = check_replacement("r", sat(20), 100, &[(sat(10), 100)], &[(sat(10), 50)]);
+        assert!(matches!(result, Err(BtcError::InvalidReplacement(..))));
+
+        // Equal fee and size is not an improvement
+        let result = check_replacement("r", sat(10), 100, &[(sat(10), 100)], &[]);
+        assert!(matches!(result, Err(BtcError::InvalidReplacement(..))));
+    }
+
+    #[test]
+    fn test_replacement_evicts_at_most_max_candidates() {
+        let sat = Amount::from_sat;
+        let descendants = vec![(Amount::ZERO, 100); MAX_REPLACEMENT_CANDIDATES];
+        let result = check_replacement("r", sat(1_000), 100, &[(sat(1), 100)], &descendants);
+        assert!(matches!(result, Err(BtcError::InvalidReplacement(..))));
+        let result = check_replacement("r", sat(1_000), 100, &[(sat(1), 100)], &descendants[1..]);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_min_fee_rounds_the_required_fee_up() {
+        let sat = Amount::from_sat;
+        assert_eq!(get_fee_rate(sat(3), 200), 15);
+        assert_eq!(get_fee_for_size(15, 200), sat(3));
+        assert_eq!(get_fee_for_size(1_000, 201), sat(201));
+        assert_eq!(get_fee_for_size(1, 200), sat(1));
+
+        assert!(check_min_fee("t", Amount::ZERO, 200, 0).is_ok());
+        assert!(check_min_fee("t", sat(3), 200, 15).is_ok());
+        assert!(matches!(
+            check_min_fee("t", sat(2), 200, 15),
+            Err(BtcError::MempoolMinFeeNotMet(..))
+        ));
+    }
+}
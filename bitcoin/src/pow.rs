@@ -1,71 +1,505 @@
-use super::block::Block;
+use super::block::{BLOCK_HASH_SIZE, BLOCK_HEADER_SIZE, Block, BlockHeader};
+use crate::consensus::ChainParams;
+use crate::crypto::sha256d;
+use crate::serialize;
 use data_encoding::HEXLOWER;
 use num_bigint::{BigInt, Sign};
-use std::borrow::Borrow;
-use std::ops::ShlAssign;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 use tracing::debug;
 
 pub struct ProofOfWork {
-    block: Block,
+    header: BlockHeader,
     target: BigInt,
 }
 
-const TARGET_BITS: i32 = 8;
-
 const MAX_NONCE: i64 = i64::MAX;
 
+/// The nonce is the last field of the encoded header.
+const NONCE_OFFSET: usize = BLOCK_HEADER_SIZE - 8;
+
+/// Number of nonces a worker tries between checks of the cancellation token.
+const CANCELLATION_POLL_INTERVAL: u64 = 4096;
+
+/// Shortest unsuccessful search whose hash rate is recorded; a search cancelled or
+/// exhausted sooner has too few hashes to measure the rate.
+const MIN_HASH_RATE_SAMPLE: Duration = Duration::from_secs(1);
+
+/// Hashes per second measured by the most recent nonce search, stored as `f64` bits.
+static HASH_RATE: AtomicU64 = AtomicU64::new(0);
+
+/// Hashes per second measured by the most recent successful, or long enough, nonce search
+/// on this node, reported as `networkhashps` by `getmininginfo`. Zero until the node has
+/// mined.
+pub fn get_hash_rate() -> f64 {
+    f64::from_bits(HASH_RATE.load(Ordering::Relaxed))
+}
+
+/// How a nonce search runs; the thread count plays the role of `genproclimit` in Bitcoin
+/// Core's old built-in miner.
+///
+/// `threads`: Number of worker threads; worker `i` tries nonces `i`, `i + threads`, ...
+/// `max_nonce`: Nonces from zero up to this bound (exclusive) are tried before the search
+/// reports the nonce space as exhausted.
+/// `cancel`: Token polled by every worker; once set the search stops (for example when a
+/// competing block arrives, see `miner::MINING_CANCELLED`).
+/// `min_time`: Earliest timestamp the block may carry; a clock behind it (such as a fixed
+/// mock time) is moved up to it, as in Bitcoin Core's `UpdateTime`.
+#[derive(Clone, Copy)]
+pub struct MiningOptions<'a> {
+    threads: usize,
+    max_nonce: i64,
+    cancel: &'a AtomicBool,
+    min_time: i64,
+}
+
+impl<'a> MiningOptions<'a> {
+    pub fn new(threads: usize, cancel: &'a AtomicBool) -> MiningOptions<'a> {
+        MiningOptions {
+            threads: threads.max(1),
+            max_nonce: MAX_NONCE,
+            cancel,
+            min_time: 0,
+        }
+    }
+
+    /// Search only nonces below `max_nonce` before rolling the header.
+    pub fn with_max_nonce(self, max_nonce: i64) -> MiningOptions<'a> {
+        MiningOptions { max_nonce, ..self }
+    }
+
+    /// Give the block a timestamp of at least `min_time` (one past the median time past of
+    /// its parent, so the block is not rejected as too old).
+    pub fn with_min_time(self, min_time: i64) -> MiningOptions<'a> {
+        MiningOptions { min_time, ..self }
+    }
+
+    pub fn get_min_time(&self) -> i64 {
+        self.min_time
+    }
+
+    pub fn get_threads(&self) -> usize {
+        self.threads
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+}
+
+/// Outcome of searching the nonce space of a header.
+#[derive(Debug, PartialEq)]
+pub enum NonceSearch {
+    /// The header hashes below the target with this nonce; `hash_rate` is the hashes per
+    /// second the search measured.
+    Found {
+        nonce: i64,
+        hash: Vec<u8>,
+        hash_rate: f64,
+    },
+    /// No nonce below `max_nonce` works; the header must change before searching again.
+    Exhausted,
+    /// The cancellation token was set.
+    Cancelled,
+}
+
+/// Compact (`nBits`) encoding of the easiest target a mainnet block may use: 2^248.
+///
+/// This is the old fixed `TARGET_BITS = 8` difficulty (eight leading zero bits),
+/// expressed in Bitcoin's compact format: exponent `0x20` (32 bytes) and mantissa `0x010000`.
+/// It is used for the genesis block and as the upper bound when retargeting.
+pub const POW_LIMIT_BITS: u32 = 0x2001_0000;
+
+/// Number of blocks between difficulty adjustments on mainnet (Bitcoin uses 2016).
+pub const DIFFICULTY_ADJUSTMENT_INTERVAL: usize = 10;
+
+/// Desired time between mainnet blocks in milliseconds (block timestamps are in
+/// milliseconds).
+pub const TARGET_BLOCK_SPACING_MS: i64 = 10_000;
+
+/// Expected duration of one retarget window (Bitcoin's `nPowTargetTimespan`).
+pub const TARGET_TIMESPAN_MS: i64 = TARGET_BLOCK_SPACING_MS * DIFFICULTY_ADJUSTMENT_INTERVAL as i64;
+
+/// A single retarget may move the target by at most this factor in either direction.
+const MAX_RETARGET_FACTOR: i64 = 4;
+
+/// Expand a compact `nBits` value into the full 256-bit target.
+///
+/// The compact format stores a 1-byte exponent (size in bytes) and a 3-byte mantissa:
+/// `target = mantissa * 256^(exponent - 3)`. Negative targets (sign bit `0x00800000` set)
+/// are not valid proof-of-work targets and decode to zero.
+///
+/// # Arguments
+///
+/// * `bits` - The compact target representation from the block header.
+///
+/// # Returns
+///
+/// The expanded target as a `BigInt`.
+pub fn compact_to_target(bits: u32) -> BigInt {
+    let exponent = bits >> 24;
+    let mantissa = bits & 0x007f_ffff;
+    if bits & 0x0080_0000 != 0 {
+        return BigInt::from(0);
+    }
+    if exponent <= 3 {
+        BigInt::from(mantissa >> (8 * (3 - exponent)))
+    } else {
+        BigInt::from(mantissa) << (8 * (exponent - 3))
+    }
+}
+
+/// Encode a 256-bit target into the compact `nBits` format.
+///
+/// The encoding is lossy: only the three most significant bytes of the target are kept,
+/// so `compact_to_target(target_to_compact(t))` may be slightly smaller than `t`.
+///
+/// # Arguments
+///
+/// * `target` - The full target value.
+///
+/// # Returns
+///
+/// The compact representation of the target.
+pub fn target_to_compact(target: &BigInt) -> u32 {
+    let mut size = target.bits().div_ceil(8) as u32;
+    let mut compact = if size <= 3 {
+        u32::try_from(target << (8 * (3 - size))).unwrap_or(0)
+    } else {
+        u32::try_from(target >> (8 * (size - 3))).unwrap_or(0)
+    };
+    // The mantissa is signed, so if its top bit is set move one byte into the exponent.
+    if compact & 0x0080_0000 != 0 {
+        compact >>= 8;
+        size += 1;
+    }
+    compact | (size << 24)
+}
+
+/// Calculate the compact target for the first block of a new retarget window.
+///
+/// This mirrors Bitcoin Core's `CalculateNextWorkRequired`: the previous target is scaled by
+/// the ratio of the actual window duration to the expected duration. The actual duration is
+/// clamped to a factor of 4 either side of the network's target timespan and the result
+/// never exceeds the network's proof-of-work limit. Networks without retargeting keep
+/// `last_bits`.
+///
+/// # Arguments
+///
+/// * `params` - Parameters of the network the block belongs to.
+/// * `last_bits` - The compact target of the last block in the window.
+/// * `first_timestamp` - Timestamp of the first block in the window.
+/// * `last_timestamp` - Timestamp of the last block in the window.
+///
+/// # Returns
+///
+/// The compact target the next block must use.
+pub fn calculate_next_work_required(
+    params: &ChainParams,
+    last_bits: u32,
+    first_timestamp: i64,
+    last_timestamp: i64,
+) -> u32 {
+    if params.is_pow_no_retargeting() {
+        return last_bits;
+    }
+    let target_timespan = params.get_target_timespan_ms();
+    let actual_timespan = (last_timestamp - first_timestamp).clamp(
+        target_timespan / MAX_RETARGET_FACTOR,
+        target_timespan * MAX_RETARGET_FACTOR,
+    );
+
+    let pow_limit = compact_to_target(params.get_pow_limit_bits());
+    let mut new_target = compact_to_target(last_bits) * BigInt::from(actual_timespan)
+        / BigInt::from(target_timespan);
+    if new_target > pow_limit {
+        new_target = pow_limit;
+    }
+
+    debug!(
+        "Retarget: actual timespan {} ms, expected {} ms, bits {:#010x} -> {:#010x}",
+        actual_timespan,
+        target_timespan,
+        last_bits,
+        target_to_compact(&new_target)
+    );
+    target_to_compact(&new_target)
+}
+
 impl ProofOfWork {
     pub fn new_proof_of_work(block: Block) -> ProofOfWork {
         // Target: This is a 256-bit number that all miners aim to find a hash value below.
-        let mut target = BigInt::from(1);
-
-        // Left shifting a number by n bits is equivalent to multiplying that number by 2^n.
-        // For example, x << 3 is the same as x * 8 (since 2^3 = 8).
-        // TARGET_BITS is the difficulty of the block.
-        // So, target << 256 - TARGET_BITS is the same as target * 2^(256 - TARGET_BITS).
-        // This is because the target is a 256-bit number and TARGET_BITS is the difficulty of the block.
-        // Here is a breakdown of the formula: (2^{256}):
-        // This represents the total range of possible outcomes for a 256-bit hash.
         // Bitcoin uses the SHA-256 algorithm, which produces a 256-bit hash value, or a number between 0 and (2^{256}-1).
-        // TARGET_BITS: This term is a bit misleading.
-        // The formula doesn't use TARGET_BITS directly but rather the exponent from the compact nBits format in the block header.
-        // The nBits(TARGET_BITS) field is a compact, 32-bit representation of the full 256-bit target.
-        // The exponent is the first byte of nBits, which indicates how many zeroes are at the end of the target.
-        // The calculation: The formula (2^{256-TARGET_BITS}) essentially approximates the number of hashes required, on average, to find a valid block.
-        // It expresses the mining difficulty relative to the maximum possible target.
-        target.shl_assign(256 - TARGET_BITS);
-        ProofOfWork { block, target }
-    }
-
-    fn prepare_data(&self, nonce: i64) -> Vec<u8> {
-        let pre_block_hash = self.block.get_pre_block_hash();
-        let transactions_hash = self.block.hash_transactions();
-        let timestamp = self.block.get_timestamp();
-        let mut data_bytes = vec![];
-        data_bytes.extend(pre_block_hash.as_bytes());
-        data_bytes.extend(transactions_hash);
-        data_bytes.extend(timestamp.to_be_bytes());
-        data_bytes.extend(TARGET_BITS.to_be_bytes());
-        data_bytes.extend(nonce.to_be_bytes());
-        data_bytes
-    }
-
-    pub fn run(&self) -> (i64, String) {
-        let mut nonce = 0;
-        let mut hash = Vec::new();
-        debug!("Mining the block");
-        while nonce < MAX_NONCE {
-            let data = self.prepare_data(nonce);
-            hash = crate::sha256_digest(data.as_slice());
-            let hash_int = BigInt::from_bytes_be(Sign::Plus, hash.as_slice());
-
-            if hash_int.lt(self.target.borrow()) {
+        // The target is not stored directly: the block header carries the compact nBits field,
+        // a 32-bit representation of the full 256-bit target (1-byte exponent, 3-byte mantissa).
+        // The smaller the target, the fewer hashes qualify and the more work is required on average
+        // (2^256 / target hashes) to find a valid block.
+        let target = compact_to_target(block.get_bits());
+        ProofOfWork {
+            header: block.get_header().clone(),
+            target,
+        }
+    }
+
+    /// Check that the block hash meets the block's target (Bitcoin Core's `CheckProofOfWork`).
+    ///
+    /// Only the header is hashed, so this is cheap enough to run on headers before their
+    /// transactions are fetched.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - Parameters of the network the block belongs to; its `pow_limit_bits`
+    ///   bound the target.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the target is within the proof-of-work limit and the header hash is below it.
+    pub fn validate(&self, params: &ChainParams) -> bool {
+        let hash = self.header.hash();
+        let pow_limit = compact_to_target(params.get_pow_limit_bits());
+        if self.target <= BigInt::from(0) || self.target > pow_limit {
+            return false;
+        }
+        BigInt::from_bytes_be(Sign::Plus, hash.as_slice()) < self.target
+    }
+
+    /// The target as 32 big-endian bytes, comparable with a block hash.
+    fn target_bytes(&self) -> [u8; BLOCK_HASH_SIZE] {
+        let (_, bytes) = self.target.to_bytes_be();
+        if bytes.len() > BLOCK_HASH_SIZE {
+            return [0xff; BLOCK_HASH_SIZE];
+        }
+        let mut target = [0; BLOCK_HASH_SIZE];
+        target[BLOCK_HASH_SIZE - bytes.len()..].copy_from_slice(&bytes);
+        target
+    }
+
+    /// Search the nonce space in parallel until the header hash is below the target
+    /// (Bitcoin Core's `GenerateBlock` loop).
+    ///
+    /// Each worker hashes its own copy of the encoded header, rewriting only the nonce.
+    /// Workers stop as soon as one of them finds a nonce, and poll the cancellation token
+    /// every `CANCELLATION_POLL_INTERVAL` hashes. The hash rate of a search that finds a
+    /// nonce, or runs for at least `MIN_HASH_RATE_SAMPLE`, is recorded for `get_hash_rate`.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Worker count, nonce bound and cancellation token.
+    ///
+    /// # Returns
+    ///
+    /// The nonce found with the resulting 32-byte block hash, or why none was found.
+    pub fn search(&self, options: &MiningOptions<'_>) -> NonceSearch {
+        let target = self.target_bytes();
+        let header = serialize::serialize(&self.header);
+        let stride = options.threads as i64;
+        let solved = AtomicBool::new(false);
+        let hashes = AtomicU64::new(0);
+        let found = Mutex::new(None);
+        let started = Instant::now();
+        debug!("Mining the block with {} threads", options.threads);
+
+        thread::scope(|scope| {
+            for worker in 0..stride {
+                let mut data = header.clone();
+                let (target, solved, hashes, found) = (&target, &solved, &hashes, &found);
+                scope.spawn(move || {
+                    let mut nonce = worker;
+                    let mut tried = 0;
+                    while nonce < options.max_nonce {
+                        if tried % CANCELLATION_POLL_INTERVAL == 0
+                            && (solved.load(Ordering::Relaxed) || options.is_cancelled())
+                        {
+                            break;
+                        }
+                        data[NONCE_OFFSET..].copy_from_slice(&nonce.to_le_bytes());
+                        let hash = sha256d(&data);
+                        tried += 1;
+                        if hash.as_slice() < target.as_slice() {
+                            solved.store(true, Ordering::Relaxed);
+                            found.lock().unwrap().get_or_insert((nonce, hash));
+                            break;
+                        }
+                        match nonce.checked_add(stride) {
+                            Some(next) => nonce = next,
+                            None => break,
+                        }
+                    }
+                    hashes.fetch_add(tried, Ordering::Relaxed);
+                });
+            }
+        });
+
+        let elapsed = started.elapsed();
+        let hash_rate = match elapsed.as_secs_f64() {
+            seconds if seconds > 0.0 => hashes.load(Ordering::Relaxed) as f64 / seconds,
+            _ => 0.0,
+        };
+        let found = found.into_inner().unwrap();
+        if hash_rate > 0.0 && (found.is_some() || elapsed >= MIN_HASH_RATE_SAMPLE) {
+            HASH_RATE.store(hash_rate.to_bits(), Ordering::Relaxed);
+        }
+        match found {
+            Some((nonce, hash)) => {
                 debug!("{}", HEXLOWER.encode(hash.as_slice()));
-                break;
-            } else {
-                nonce += 1;
+                NonceSearch::Found {
+                    nonce,
+                    hash,
+                    hash_rate,
+                }
             }
+            None if options.is_cancelled() => NonceSearch::Cancelled,
+            None => NonceSearch::Exhausted,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::{MAINNET_PARAMS, REGTEST_PARAMS};
+    use crate::primitives::block::BLOCK_HASH_SIZE;
+
+    /// Proof of work over a fresh genesis-style header with the given target.
+    fn proof_of_work(target: BigInt) -> ProofOfWork {
+        let block = Block::new_block(&[0; BLOCK_HASH_SIZE], &[], 1);
+        ProofOfWork {
+            header: block.get_header().clone(),
+            target,
         }
-        (nonce, HEXLOWER.encode(hash.as_slice()))
+    }
+
+    #[test]
+    fn test_parallel_search_finds_valid_nonce() {
+        let pow = proof_of_work(compact_to_target(POW_LIMIT_BITS));
+        let cancel = AtomicBool::new(false);
+        let NonceSearch::Found {
+            nonce,
+            hash,
+            hash_rate,
+        } = pow.search(&MiningOptions::new(4, &cancel))
+        else {
+            panic!("search at the proof-of-work limit must succeed");
+        };
+        let mut header = pow.header.clone();
+        header.set_nonce(nonce);
+        assert_eq!(header.hash(), hash);
+        assert!(BigInt::from_bytes_be(Sign::Plus, &hash) < pow.target);
+        assert!(hash_rate > 0.0);
+    }
+
+    #[test]
+    fn test_search_reports_exhausted_nonce_space() {
+        // Only a zero hash would meet a target of one
+        let pow = proof_of_work(BigInt::from(1));
+        let cancel = AtomicBool::new(false);
+        let options = MiningOptions::new(3, &cancel).with_max_nonce(1000);
+        assert_eq!(pow.search(&options), NonceSearch::Exhausted);
+    }
+
+    #[test]
+    fn test_search_stops_when_cancelled() {
+        let pow = proof_of_work(BigInt::from(1));
+        let cancel = AtomicBool::new(true);
+        assert_eq!(
+            pow.search(&MiningOptions::new(2, &cancel)),
+            NonceSearch::Cancelled
+        );
+    }
+
+    #[test]
+    fn test_pow_limit_bits_expand_to_2_pow_248() {
+        let target = compact_to_target(POW_LIMIT_BITS);
+        assert_eq!(target, BigInt::from(1) << 248);
+        assert_eq!(target_to_compact(&target), POW_LIMIT_BITS);
+    }
+
+    #[test]
+    fn test_compact_round_trip() {
+        // Bitcoin mainnet genesis bits
+        let bits = 0x1d00_ffff;
+        assert_eq!(target_to_compact(&compact_to_target(bits)), bits);
+
+        // Small targets are encoded with exponents below 3
+        let small = BigInt::from(0x12);
+        assert_eq!(target_to_compact(&small), 0x0112_0000);
+        assert_eq!(compact_to_target(0x0112_0000), small);
+
+        // A mantissa with the sign bit set is shifted into the next exponent
+        let signed = BigInt::from(0x80);
+        assert_eq!(target_to_compact(&signed), 0x0200_8000);
+        assert_eq!(compact_to_target(0x0200_8000), signed);
+    }
+
+    #[test]
+    fn test_negative_compact_target_is_zero() {
+        assert_eq!(compact_to_target(0x0480_0001), BigInt::from(0));
+    }
+
+    #[test]
+    fn test_retarget_on_schedule_keeps_bits() {
+        let bits =
+            calculate_next_work_required(&MAINNET_PARAMS, POW_LIMIT_BITS, 0, TARGET_TIMESPAN_MS);
+        assert_eq!(bits, POW_LIMIT_BITS);
+    }
+
+    #[test]
+    fn test_retarget_fast_blocks_clamped_to_factor_4() {
+        // Blocks found instantly: target shrinks by at most 4x
+        let bits = calculate_next_work_required(&MAINNET_PARAMS, POW_LIMIT_BITS, 1_000, 1_000);
+        assert_eq!(
+            compact_to_target(bits),
+            compact_to_target(POW_LIMIT_BITS) / 4
+        );
+    }
+
+    #[test]
+    fn test_retarget_slow_blocks_clamped_to_factor_4() {
+        let harder_bits = target_to_compact(&(compact_to_target(POW_LIMIT_BITS) >> 6));
+        let bits =
+            calculate_next_work_required(&MAINNET_PARAMS, harder_bits, 0, TARGET_TIMESPAN_MS * 100);
+        assert_eq!(compact_to_target(bits), compact_to_target(harder_bits) * 4);
+    }
+
+    #[test]
+    fn test_retarget_never_exceeds_pow_limit() {
+        let bits = calculate_next_work_required(
+            &MAINNET_PARAMS,
+            POW_LIMIT_BITS,
+            0,
+            TARGET_TIMESPAN_MS * 2,
+        );
+        assert_eq!(bits, POW_LIMIT_BITS);
+    }
+
+    #[test]
+    fn test_regtest_block_meets_regtest_pow_limit() {
+        let bits = REGTEST_PARAMS.get_pow_limit_bits();
+        assert!(compact_to_target(bits) > compact_to_target(POW_LIMIT_BITS));
+
+        let cancel = AtomicBool::new(false);
+        let block = Block::new_block_with_options(
+            &[0; BLOCK_HASH_SIZE],
+            &[],
+            1,
+            bits,
+            &MiningOptions::new(2, &cancel),
+        )
+        .unwrap();
+        let pow = ProofOfWork::new_proof_of_work(block);
+        assert!(pow.validate(&REGTEST_PARAMS));
+        // The regtest target is above the mainnet limit
+        assert!(!pow.validate(&MAINNET_PARAMS));
+    }
+
+    #[test]
+    fn test_regtest_never_retargets() {
+        let bits = REGTEST_PARAMS.get_pow_limit_bits();
+        assert_eq!(
+            calculate_next_work_required(&REGTEST_PARAMS, bits, 1_000, 1_000),
+            bits
+        );
     }
 }
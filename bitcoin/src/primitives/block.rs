@@ -3,81 +3,239 @@
 //! Block is a data structure that contains the data and operations on the block.
 //!
 
-extern crate bincode;
 use crate::WalletAddress;
+use crate::config::GLOBAL_CONFIG;
+use crate::consensus::Amount;
+use crate::consensus::merkle::compute_merkle_root;
+use crate::crypto::sha256d;
 use crate::crypto::signature::schnorr_sign_verify;
 use crate::error::{BtcError, Result};
-use crate::pow::ProofOfWork;
+use crate::pow::{MiningOptions, NonceSearch, ProofOfWork, compact_to_target};
 use crate::primitives::transaction::{Transaction, WalletTransaction, WalletTransactionType};
+use crate::serialize::{self, Decodable, Encodable, Reader, write_vec};
 use crate::wallet::{convert_address, get_pub_key_hash, hash_pub_key};
+use data_encoding::HEXLOWER;
 use num_bigint::BigInt;
 use serde::{Deserialize, Serialize};
 use sled::IVec;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
 
-pub const GENESIS_BLOCK_PRE_BLOCK_HASH: &str = "None";
+/// Size of a block hash, and of the previous block hash and Merkle root in a header.
+pub const BLOCK_HASH_SIZE: usize = 32;
 
-// Add a block header that contains timestamp, pre_block_hash, hash, nonce, height
-// Block to be composed of block header and transactions
+/// Size of an encoded block header: version (4), previous block hash (32), Merkle root (32),
+/// timestamp (8), bits (4) and nonce (8). Bitcoin's header is 80 bytes; timestamps and
+/// nonces are 64-bit here.
+pub const BLOCK_HEADER_SIZE: usize = 4 + BLOCK_HASH_SIZE + BLOCK_HASH_SIZE + 8 + 4 + 8;
+
+/// Hex form of the previous block hash of the genesis block: all zeros, as in Bitcoin.
+pub const GENESIS_BLOCK_PRE_BLOCK_HASH: &str =
+    "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Version of new block headers, the first field of their encoding (Bitcoin Core's
+/// `CBlockHeader::nVersion`).
+pub const BLOCK_VERSION: u32 = 1;
+
+/// Block header (Bitcoin Core's `CBlockHeader`)
+///
+/// The fixed-size part of a block that proof of work commits to. The block hash is the
+/// double SHA-256 of its encoding (see `BlockHeader::hash`), so headers can be checked and
+/// chained without their transactions.
+///
+/// `version`: The format version of the header (see `BLOCK_VERSION`).
+/// `pre_block_hash`: The 32-byte hash of the previous block in the blockchain (all zeros for the
+/// genesis block). This creates a link between blocks, ensuring the integrity of the blockchain.
+/// `merkle_root`: The root of the Merkle tree built over the block's transaction ids. It commits
+/// the header to every transaction and allows inclusion proofs without the full block.
+/// `timestamp`: An integer value that represents the time when the block was created, in
+/// milliseconds. It's used to track the chronological order of blocks in the blockchain.
+/// `bits`: The proof-of-work target in Bitcoin's compact `nBits` format. The block hash must be
+/// below this target, and it is recalculated every `DIFFICULTY_ADJUSTMENT_INTERVAL` blocks.
+/// `nonce`: A number used to ensure that the block is valid and has not been tampered with.
+/// Stands for number used only once
 #[derive(Clone, Serialize, Deserialize)]
 pub struct BlockHeader {
+    version: u32,
+    pre_block_hash: Vec<u8>,
+    merkle_root: Vec<u8>,
     timestamp: i64,
-    pre_block_hash: String,
-    hash: String,
+    bits: u32,
     nonce: i64,
-    height: usize,
+}
+
+impl BlockHeader {
+    /// The block hash: double SHA-256 of the encoded header (Bitcoin Core's
+    /// `CBlockHeader::GetHash`).
+    ///
+    /// Unlike Bitcoin, the digest is compared with the target as a big-endian number and
+    /// shown in hex in that same order, so hashes below the target start with zeros
+    /// without reversing the bytes.
+    pub fn hash(&self) -> Vec<u8> {
+        sha256d(&serialize::serialize(self))
+    }
+
+    /// Set the nonce while searching for proof of work.
+    pub fn set_nonce(&mut self, nonce: i64) {
+        self.nonce = nonce;
+    }
 }
 
 /// Block
 ///
-/// `timestamp`: An integer value that represents the time when the block was created. It's used
-/// to track the chronological order of blocks in the blockchain.
-/// `pre_block_hash`: A string containing the hash value of the previous block in the blockchain.
-/// This creates a link between blocks, ensuring the integrity of the blockchain.
-/// `hash`: String containing the hash value of the current block. This hash is generated based on
-/// the data within the current block, including transactions and other information.
+/// `header`: The header the block hash commits to (see `BlockHeader`).
+/// `height`: An integer value that represents the position of the block in the blockchain.
+/// It's used to track the chronological order of blocks in the blockchain. Indicates the position.
+/// It is not part of the header: the coinbase commits to it (BIP34).
 /// `transactions`: A vector or collection that holds the block transactions.
 /// Transactions can represent various types of data or actions, depending on the blockchain's
 /// purpose (for example, cryptocurrency transactions).
-/// `nonce`: A number used to ensure that the block is valid and has not been tampered with.
-/// Stands for number used only once
-/// `height`: An integer value that represents the position of the block in the blockchain.
-/// It's used to track the chronological order of blocks in the blockchain. Indicates the position.
-
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Block {
     header: BlockHeader,
+    height: usize,
     transactions: Vec<Transaction>,
 }
 
+/// Decode the hex form of a block hash (as returned by `Block::get_hash`) into the 32 bytes
+/// blocks are stored and relayed under.
+///
+/// # Returns
+///
+/// `None` if `hash` is not the hex encoding of 32 bytes.
+pub fn block_hash_from_hex(hash: &str) -> Option<Vec<u8>> {
+    HEXLOWER
+        .decode(hash.as_bytes())
+        .ok()
+        .filter(|bytes| bytes.len() == BLOCK_HASH_SIZE)
+}
+
 impl Block {
-    pub fn new_block(pre_block_hash: String, transactions: &[Transaction], height: usize) -> Block {
+    /// Mine a new block at the minimum difficulty of the configured network (its
+    /// `pow_limit_bits`).
+    pub fn new_block(pre_block_hash: &[u8], transactions: &[Transaction], height: usize) -> Block {
+        let bits = GLOBAL_CONFIG.get_chain_params().get_pow_limit_bits();
+        Self::new_block_with_bits(pre_block_hash, transactions, height, bits)
+    }
+
+    /// Mine a new block against the target encoded by `bits`, on the configured number of
+    /// threads and without a way to cancel. See `new_block_with_options`.
+    ///
+    /// # Arguments
+    ///
+    /// * `pre_block_hash` - The 32-byte hash of the parent block.
+    /// * `transactions` - Transactions to include in the block.
+    /// * `height` - Height of the new block.
+    /// * `bits` - Compact proof-of-work target the block must satisfy.
+    pub fn new_block_with_bits(
+        pre_block_hash: &[u8],
+        transactions: &[Transaction],
+        height: usize,
+        bits: u32,
+    ) -> Block {
+        let never_cancelled = AtomicBool::new(false);
+        let options = MiningOptions::new(GLOBAL_CONFIG.get_mining_threads(), &never_cancelled);
+        Self::new_block_with_options(pre_block_hash, transactions, height, bits, &options)
+            .expect("Mining without cancellation always finds a block")
+    }
+
+    /// Mine a new block against the target encoded by `bits`.
+    ///
+    /// The nonce search runs as `options` describe, and the block is timestamped no earlier
+    /// than their `min_time`. Whenever it exhausts the nonce space the header is rolled
+    /// (see `roll_header`) and the search starts over.
+    ///
+    /// # Arguments
+    ///
+    /// * `pre_block_hash` - The 32-byte hash of the parent block.
+    /// * `transactions` - Transactions to include in the block.
+    /// * `height` - Height of the new block.
+    /// * `bits` - Compact proof-of-work target the block must satisfy.
+    /// * `options` - Worker count, nonce bound, cancellation token and minimum timestamp.
+    ///
+    /// # Returns
+    ///
+    /// The mined block, or `InvalidValueForMiner` if mining was cancelled.
+    pub fn new_block_with_options(
+        pre_block_hash: &[u8],
+        transactions: &[Transaction],
+        height: usize,
+        bits: u32,
+        options: &MiningOptions<'_>,
+    ) -> Result<Block> {
+        let mut block = Self::new_block_template(pre_block_hash, transactions, height, bits);
+        block.header.timestamp = block.header.timestamp.max(options.get_min_time());
+        loop {
+            match ProofOfWork::new_proof_of_work(block.clone()).search(options) {
+                NonceSearch::Found { nonce, .. } => {
+                    block.header.nonce = nonce;
+                    return Ok(block);
+                }
+                NonceSearch::Cancelled => {
+                    return Err(BtcError::InvalidValueForMiner(
+                        "Mining cancelled".to_string(),
+                    ));
+                }
+                NonceSearch::Exhausted => block.roll_header(),
+            }
+        }
+    }
+
+    /// An unmined block: its header commits to the transactions with a zero nonce.
+    fn new_block_template(
+        pre_block_hash: &[u8],
+        transactions: &[Transaction],
+        height: usize,
+        bits: u32,
+    ) -> Block {
         let header = BlockHeader {
+            version: BLOCK_VERSION,
+            pre_block_hash: pre_block_hash.to_vec(),
+            merkle_root: vec![], // to be filled in the next step
             timestamp: crate::current_timestamp(),
-            pre_block_hash,
-            hash: String::new(), // to be filled in the next step
+            bits,
             nonce: 0,
-            height,
         };
         let mut block = Block {
             header,
+            height,
             transactions: transactions.to_vec(),
         };
-        let pow = ProofOfWork::new_proof_of_work(block.clone());
-        let (nonce, hash) = pow.run();
-        block.header.nonce = nonce;
-        block.header.hash = hash;
+        block.header.merkle_root = block.hash_transactions();
         block
     }
 
+    /// Give the header a fresh nonce space after every nonce failed: move the timestamp up
+    /// to the current time (Bitcoin Core's `UpdateTime`) or, if the clock has not advanced,
+    /// increment the coinbase extra nonce and recompute the Merkle root
+    /// (`IncrementExtraNonce`). Without a coinbase to roll the timestamp is bumped instead.
+    fn roll_header(&mut self) {
+        let now = crate::current_timestamp();
+        if now > self.header.timestamp {
+            self.header.timestamp = now;
+            return;
+        }
+        let rolled = self
+            .transactions
+            .first_mut()
+            .filter(|coinbase| coinbase.is_coinbase())
+            .is_some_and(|coinbase| coinbase.increment_extra_nonce().is_ok());
+        if rolled {
+            self.header.merkle_root = self.hash_transactions();
+        } else {
+            self.header.timestamp += 1;
+        }
+    }
+
+    /// Decode a block from its consensus encoding (see `Encodable for Block`).
     pub fn deserialize(bytes: &[u8]) -> Result<Block> {
-        bincode::serde::decode_from_slice(bytes, bincode::config::standard())
+        serialize::deserialize(bytes)
             .map_err(|e| BtcError::BlockDeserializationError(e.to_string()))
-            .map(|(block, _)| block)
     }
 
+    /// The consensus encoding of the block, used for storage, relay and its size.
     pub fn serialize(&self) -> Result<Vec<u8>> {
-        bincode::serde::encode_to_vec(self, bincode::config::standard())
-            .map_err(|e| BtcError::BlockSerializationError(e.to_string()))
+        Ok(serialize::serialize(self))
     }
 
     pub async fn get_transactions(&self) -> Result<&[Transaction]> {
@@ -117,6 +275,31 @@ impl Block {
     pub async fn get_user_transactions(
         &self,
         wlt_address: &WalletAddress,
+    ) -> Result<Vec<WalletTransaction>> {
+        self.get_user_transactions_with_fees(wlt_address, &HashMap::new())
+            .await
+    }
+
+    /// Get all transactions relevant to a specific wallet address, reporting their fees
+    ///
+    /// Same as `get_user_transactions`, but each entry carries the fee its transaction
+    /// paid. Fees depend on the outputs a transaction spends, which live in earlier
+    /// blocks, so the caller supplies them.
+    ///
+    /// # Arguments
+    ///
+    /// * `wlt_address` - The wallet address to filter transactions for
+    /// * `fees` - Fee paid by each transaction, keyed by transaction id. Missing
+    ///   transactions (and coinbases) are reported with a fee of 0
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<WalletTransaction>)` - Vector of wallet transactions
+    /// * `Err(BtcError)` - If address is invalid or processing fails
+    pub async fn get_user_transactions_with_fees(
+        &self,
+        wlt_address: &WalletAddress,
+        fees: &HashMap<Vec<u8>, Amount>,
     ) -> Result<Vec<WalletTransaction>> {
         // Extract public key hash from address
         let req_addr_pub_key_hash = get_pub_key_hash(wlt_address)?;
@@ -139,7 +322,7 @@ impl Block {
                                 None, // Coinbase has no sender
                                 WalletTransactionType::Credit,
                                 index,
-                                0,
+                                Amount::ZERO,
                                 self.header.timestamp,
                             )?]
                         } else {
@@ -155,6 +338,7 @@ impl Block {
                                 let tx_pub_key_hash = hash_pub_key(tx_public_key);
                                 let signature = vin.get_signature();
                                 let vout = tx.get_vout();
+                                let fee = fees.get(tx.get_id()).copied().unwrap_or(Amount::ZERO);
 
                                 // Check if we're the sender by comparing public key hashes
                                 if tx_pub_key_hash == req_addr_pub_key_hash {
@@ -176,7 +360,7 @@ impl Block {
                                                     Some(wlt_address.clone()),
                                                     WalletTransactionType::Debit,
                                                     index,
-                                                    0,
+                                                    fee,
                                                     self.header.timestamp,
                                                 )
                                             })
@@ -200,7 +384,7 @@ impl Block {
                                                 Some(from_addr),
                                                 WalletTransactionType::Credit,
                                                 index,
-                                                0,
+                                                fee,
                                                 self.header.timestamp,
                                             )
                                         })
@@ -220,30 +404,65 @@ impl Block {
         Ok(wallet_txs)
     }
 
+    /// Get the hex form of the previous block hash
     pub fn get_pre_block_hash(&self) -> String {
-        self.header.pre_block_hash.clone()
+        HEXLOWER.encode(&self.header.pre_block_hash)
     }
 
-    pub fn get_hash(&self) -> &str {
-        self.header.hash.as_str()
+    /// Get the 32-byte previous block hash
+    pub fn get_pre_block_hash_bytes(&self) -> &[u8] {
+        self.header.pre_block_hash.as_slice()
     }
 
+    /// Get the hex form of the block hash
+    pub fn get_hash(&self) -> String {
+        HEXLOWER.encode(&self.header.hash())
+    }
+
+    /// Get the 32-byte block hash, the key blocks are stored and relayed under
     pub fn get_hash_bytes(&self) -> Vec<u8> {
-        self.header.hash.as_bytes().to_vec()
+        self.header.hash()
+    }
+
+    pub fn get_header(&self) -> &BlockHeader {
+        &self.header
     }
 
     pub fn get_timestamp(&self) -> i64 {
         self.header.timestamp
     }
 
+    /// Get the header version
+    pub fn get_version(&self) -> u32 {
+        self.header.version
+    }
+
     pub fn get_height(&self) -> usize {
-        self.header.height
+        self.height
+    }
+
+    /// Get the Merkle root of the block's transactions from the block header
+    pub fn get_merkle_root(&self) -> &[u8] {
+        self.header.merkle_root.as_slice()
     }
 
+    /// Get the compact proof-of-work target (`nBits`) from the block header
+    pub fn get_bits(&self) -> u32 {
+        self.header.bits
+    }
+
+    /// Get the difficulty of this block relative to the minimum difficulty of the
+    /// configured network
+    ///
+    /// Matches Bitcoin's definition: `difficulty = pow_limit_target / block_target`,
+    /// so a block mined at the network's `pow_limit_bits` has difficulty 1.
     pub fn get_difficulty(&self) -> u32 {
-        // For now, return a constant difficulty
-        // In a real implementation, this would be calculated based on the block's proof-of-work
-        1
+        let target = self.get_target();
+        if target <= BigInt::from(0) {
+            return 0;
+        }
+        let pow_limit = compact_to_target(GLOBAL_CONFIG.get_chain_params().get_pow_limit_bits());
+        u32::try_from(pow_limit / target).unwrap_or(u32::MAX)
     }
 
     /// Get the nonce value from the block header
@@ -253,89 +472,139 @@ impl Block {
 
     /// Get the hash as a string for comparison
     pub fn get_hash_string(&self) -> String {
-        self.header.hash.clone()
+        self.get_hash()
     }
 
     /// Calculate work for this block based on proof-of-work difficulty
     ///
-    /// In Bitcoin, work is calculated as: 2^256 / (target + 1)
-    /// Where target = 2^(256 - TARGET_BITS)
+    /// Work is the expected number of hashes needed to find a block hash below the target:
+    /// `work = 2^256 / target`, where the target is expanded from the block's `bits`.
     ///
-    /// For our implementation with TARGET_BITS = 8:
-    /// - Target = 2^(256 - 8) = 2^248
-    /// - Work = 2^256 / (2^248 + 1) ≈ 2^8 = 256
+    /// For a block at the minimum difficulty (`POW_LIMIT_BITS`):
+    /// - Target = 2^248
+    /// - Work = 2^256 / 2^248 = 2^8 = 256
     ///
     /// # Returns
-    /// * `u64` - The work value for this block
+    /// * `u64` - The work value for this block (saturates at `u64::MAX`)
     ///
     /// # Note
-    /// This implements the same work calculation as Bitcoin's consensus mechanism.
-    /// Higher work values indicate more difficult proof-of-work, which means
-    /// the block required more computational effort to mine.
+    /// Bitcoin Core uses `2^256 / (target + 1)`; the difference is negligible for consensus
+    /// comparisons since every block is measured the same way. Higher work values indicate
+    /// more difficult proof-of-work, which means the block required more computational effort.
     pub fn get_work(&self) -> u64 {
-        // Work is calculated as: 2^256 / (target + 1)
-        // Where target = 2^(256 - TARGET_BITS)
-        // For TARGET_BITS = 8: target = 2^248, work = 2^256 / (2^248 + 1) ≈ 2^8 = 256
-        const TARGET_BITS: u32 = 8;
-        const WORK_BITS: u32 = 256 - TARGET_BITS; // 256 - 8 = 248
-
-        // Calculate 2^WORK_BITS, but cap it to prevent overflow
-        if WORK_BITS >= 64 {
-            u64::MAX / 1000 // Large but manageable value
-        } else {
-            2u64.pow(WORK_BITS)
+        let target = self.get_target();
+        if target <= BigInt::from(0) {
+            return 0;
         }
+        let work = (BigInt::from(1) << 256) / target;
+        u64::try_from(work).unwrap_or(u64::MAX)
     }
 
-    /// Get the target bits used for proof-of-work calculation
+    /// Get the number of leading zero bits required by this block's target
     ///
-    /// This method provides access to the TARGET_BITS constant used
-    /// in the proof-of-work calculation, allowing other parts of the
-    /// system to understand the difficulty level.
+    /// This is `256 - log2(target)`, i.e. 8 for a block at the minimum difficulty
+    /// and increasing by 2 each time the target shrinks by the maximum retarget factor of 4.
     ///
     /// # Returns
-    /// * `u32` - The target bits value (currently 8)
+    /// * `u32` - The target bits value
     pub fn get_target_bits(&self) -> u32 {
-        // This should match the TARGET_BITS constant in proof_of_work.rs
-        8
+        let target_bit_len = self.get_target().bits() as u32;
+        257u32.saturating_sub(target_bit_len)
     }
 
     /// Calculate the actual target value used in proof-of-work
     ///
-    /// This method calculates the exact target value that was used
-    /// during the proof-of-work mining process for this block.
+    /// This method expands the compact `bits` field from the block header into
+    /// the exact target value that was used during the proof-of-work mining process.
     ///
     /// # Returns
     /// * `BigInt` - The target value as a BigInt
     pub fn get_target(&self) -> BigInt {
-        use std::ops::ShlAssign;
+        compact_to_target(self.header.bits)
+    }
 
-        let target_bits = self.get_target_bits();
-        let mut target = BigInt::from(1);
-        target.shl_assign(256 - target_bits as i32);
-        target
+    /// Get the transaction ids in block order (the leaves of the Merkle tree)
+    pub fn get_tx_ids(&self) -> Vec<Vec<u8>> {
+        self.transactions
+            .iter()
+            .map(|transaction| transaction.get_id().to_vec())
+            .collect()
     }
 
+    /// Compute the Merkle root of the block's transactions
+    ///
+    /// Bitcoin Core's `BlockMerkleRoot`: builds the Merkle tree over the txids using the
+    /// duplicate-last-leaf rule. For a well-formed block this equals `get_merkle_root()`.
     pub fn hash_transactions(&self) -> Vec<u8> {
-        let mut txhashs = vec![];
-        for transaction in &self.transactions {
-            txhashs.extend(transaction.get_id());
-        }
-        crate::sha256_digest(txhashs.as_slice())
+        compute_merkle_root(&self.get_tx_ids())
     }
 
+    /// Mine the genesis block of the configured network around its genesis coinbase (see
+    /// `Transaction::new_genesis_coinbase_tx`), at the network's proof-of-work limit.
     pub fn generate_genesis_block(transaction: &Transaction) -> Block {
         let transactions = vec![transaction.clone()];
-        Block::new_block(GENESIS_BLOCK_PRE_BLOCK_HASH.to_string(), &transactions, 1)
+        Block::new_block(&[0; BLOCK_HASH_SIZE], &transactions, 1)
     }
 }
 
 impl TryFrom<Block> for IVec {
     type Error = BtcError;
     fn try_from(b: Block) -> Result<Self> {
-        let bytes = bincode::serde::encode_to_vec(&b, bincode::config::standard())
-            .map_err(|e| BtcError::BlockSerializationError(e.to_string()))?;
-        Ok(Self::from(bytes))
+        Ok(Self::from(b.serialize()?))
+    }
+}
+
+/// Header encoding, always `BLOCK_HEADER_SIZE` bytes:
+///
+/// | Field          | Encoding                          |
+/// |----------------|-----------------------------------|
+/// | version        | `u32` little-endian               |
+/// | pre_block_hash | 32 bytes                          |
+/// | merkle_root    | 32 bytes                          |
+/// | timestamp      | `i64` little-endian, milliseconds |
+/// | bits           | `u32` little-endian               |
+/// | nonce          | `i64` little-endian               |
+impl Encodable for BlockHeader {
+    fn consensus_encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.version.to_le_bytes());
+        buf.extend_from_slice(&self.pre_block_hash);
+        buf.extend_from_slice(&self.merkle_root);
+        buf.extend_from_slice(&self.timestamp.to_le_bytes());
+        buf.extend_from_slice(&self.bits.to_le_bytes());
+        buf.extend_from_slice(&self.nonce.to_le_bytes());
+    }
+}
+
+impl Decodable for BlockHeader {
+    fn consensus_decode(reader: &mut Reader<'_>) -> Result<BlockHeader> {
+        Ok(BlockHeader {
+            version: reader.read_u32()?,
+            pre_block_hash: reader.read_slice(BLOCK_HASH_SIZE)?.to_vec(),
+            merkle_root: reader.read_slice(BLOCK_HASH_SIZE)?.to_vec(),
+            timestamp: reader.read_i64()?,
+            bits: reader.read_u32()?,
+            nonce: reader.read_i64()?,
+        })
+    }
+}
+
+/// Block encoding: the header, the height as a `u64` little-endian, then a CompactSize count
+/// and the transactions.
+impl Encodable for Block {
+    fn consensus_encode(&self, buf: &mut Vec<u8>) {
+        self.header.consensus_encode(buf);
+        buf.extend_from_slice(&(self.height as u64).to_le_bytes());
+        write_vec(buf, &self.transactions);
+    }
+}
+
+impl Decodable for Block {
+    fn consensus_decode(reader: &mut Reader<'_>) -> Result<Block> {
+        Ok(Block {
+            header: BlockHeader::consensus_decode(reader)?,
+            height: reader.read_u64()? as usize,
+            transactions: reader.read_vec()?,
+        })
     }
 }
 
@@ -343,7 +612,13 @@ impl TryFrom<Block> for IVec {
 mod tests {
     use super::*;
     use crate::Wallet;
-    use crate::primitives::transaction::{Transaction, WalletTransactionStatus};
+    use crate::node::GLOBAL_MEMORY_POOL;
+    use crate::pow::POW_LIMIT_BITS;
+    use crate::primitives::transaction::{SEQUENCE_FINAL, Transaction, WalletTransactionStatus};
+    use num_bigint::Sign;
+
+    /// Parent hash of the blocks built by these tests.
+    const PREV_HASH: [u8; BLOCK_HASH_SIZE] = [0x11; BLOCK_HASH_SIZE];
 
     fn generate_test_genesis_address() -> crate::WalletAddress {
         // Create a wallet to get a valid Bitcoin address
@@ -354,15 +629,14 @@ mod tests {
     #[test]
     fn test_block_creation() {
         let transactions = vec![];
-        let prev_block_hash = "previous_hash".to_string();
         let height = 1;
 
-        let block = Block::new_block(prev_block_hash.clone(), transactions.as_slice(), height);
+        let block = Block::new_block(&PREV_HASH, transactions.as_slice(), height);
 
-        assert_eq!(block.header.pre_block_hash, prev_block_hash);
+        assert_eq!(block.header.pre_block_hash, PREV_HASH);
         assert_eq!(block.transactions.len(), 0);
-        assert_eq!(block.header.height, height);
-        assert!(!block.header.hash.is_empty()); // Should be filled by PoW
+        assert_eq!(block.height, height);
+        assert_eq!(block.get_hash_bytes().len(), BLOCK_HASH_SIZE); // Should be filled by PoW
         assert!(block.header.nonce >= 0);
     }
 
@@ -372,7 +646,7 @@ mod tests {
         let coinbase_tx = Transaction::new_coinbase_tx(&genesis_address.clone())
             .expect("Failed to create coinbase tx");
         let transactions = vec![coinbase_tx];
-        let block = Block::new_block("prev_hash".to_string(), transactions.as_slice(), 1);
+        let block = Block::new_block(&PREV_HASH, transactions.as_slice(), 1);
 
         let serialized = block.serialize().expect("Serialization failed");
         let deserialized = Block::deserialize(&serialized).expect("Deserialization failed");
@@ -382,16 +656,71 @@ mod tests {
             block.header.pre_block_hash,
             deserialized.header.pre_block_hash
         );
-        assert_eq!(block.header.hash, deserialized.header.hash);
+        assert_eq!(block.get_hash(), deserialized.get_hash());
         assert_eq!(block.header.nonce, deserialized.header.nonce);
-        assert_eq!(block.header.height, deserialized.header.height);
+        assert_eq!(block.height, deserialized.height);
+    }
+
+    #[tokio::test]
+    async fn test_block_consensus_encoding_golden_vector() {
+        // The transaction of the transaction module's golden vector
+        let tx_hex = concat!(
+            "0200000001201111111111111111111111111111111111111111111111111111111111111111",
+            "010000000002aabb020203feffffff01881300000000000004cccccccc0064000000",
+        );
+        let tx = Transaction::deserialize(&HEXLOWER.decode(tx_hex.as_bytes()).unwrap()).unwrap();
+        let header_hex = concat!(
+            "01000000",                                                         // version
+            "0000000000000000000000000000000000000000000000000000000000000000", // pre_block_hash
+            "2dc93d40d9f0d314b0fb7931695b4f13ff5eb70b6c50868fc1bde513d1d6212c", // merkle_root
+            "0068e5cf8b010000",                                                 // timestamp
+            "00000120",                                                         // bits
+            "2a00000000000000",                                                 // nonce
+        );
+        let block = Block {
+            header: BlockHeader {
+                version: BLOCK_VERSION,
+                pre_block_hash: vec![0; BLOCK_HASH_SIZE],
+                merkle_root: tx.get_id().to_vec(),
+                timestamp: 1_700_000_000_000,
+                bits: POW_LIMIT_BITS,
+                nonce: 42,
+            },
+            height: 2,
+            transactions: vec![tx],
+        };
+        assert_eq!(block.hash_transactions(), block.get_merkle_root());
+
+        let header = serialize::serialize(block.get_header());
+        assert_eq!(header.len(), BLOCK_HEADER_SIZE);
+        assert_eq!(HEXLOWER.encode(&header), header_hex);
+        // Double SHA-256 of the header
+        assert_eq!(
+            block.get_hash(),
+            "93fbc652aa62e4a843ffb8d87fc321555eeb5ab3dae706d62259d675992236fb"
+        );
+        assert_eq!(block.get_pre_block_hash(), GENESIS_BLOCK_PRE_BLOCK_HASH);
+
+        let encoded = block.serialize().unwrap();
+        assert_eq!(
+            HEXLOWER.encode(&encoded),
+            format!("{}020000000000000001{}", header_hex, tx_hex)
+        );
+        let decoded = Block::deserialize(&encoded).unwrap();
+        assert_eq!(decoded.get_version(), BLOCK_VERSION);
+        assert_eq!(decoded.get_height(), 2);
+        assert_eq!(decoded.get_hash(), block.get_hash());
+        assert_eq!(decoded.get_tx_ids(), block.get_tx_ids());
+        assert_eq!(decoded.serialize().unwrap(), encoded);
+        assert!(Block::deserialize(&encoded[..encoded.len() - 1]).is_err());
     }
 
     #[tokio::test]
     async fn test_block_getters() {
-        let block = Block::new_block("prev_hash".to_string(), &[], 1);
+        let block = Block::new_block(&PREV_HASH, &[], 1);
 
-        assert_eq!(block.get_pre_block_hash(), "prev_hash");
+        assert_eq!(block.get_pre_block_hash(), HEXLOWER.encode(&PREV_HASH));
+        assert_eq!(block.get_pre_block_hash_bytes(), PREV_HASH);
         assert!(!block.get_hash().is_empty());
         assert_eq!(block.get_height(), 1);
         assert!(block.get_timestamp() > 0);
@@ -405,7 +734,7 @@ mod tests {
             .expect("Failed to create coinbase tx");
         let transactions = vec![coinbase_tx];
 
-        let block = Block::new_block("prev_hash".to_string(), transactions.as_slice(), 1);
+        let block = Block::new_block(&PREV_HASH, transactions.as_slice(), 1);
 
         assert_eq!(block.transactions.len(), 1);
         assert_eq!(block.get_transactions().await.unwrap().len(), 1);
@@ -413,16 +742,154 @@ mod tests {
 
     #[test]
     fn test_block_hash_bytes() {
-        let block = Block::new_block("prev_hash".to_string(), &[], 1);
+        let block = Block::new_block(&PREV_HASH, &[], 1);
         let hash_bytes = block.get_hash_bytes();
-        assert!(!hash_bytes.is_empty());
-        assert_eq!(hash_bytes, block.header.hash.as_bytes());
+        assert_eq!(hash_bytes.len(), BLOCK_HASH_SIZE);
+        assert_eq!(hash_bytes, sha256d(&serialize::serialize(&block.header)));
+        assert_eq!(HEXLOWER.encode(&hash_bytes), block.get_hash());
+        assert_eq!(block_hash_from_hex(&block.get_hash()), Some(hash_bytes));
+        assert_eq!(block_hash_from_hex("prev_hash"), None);
+        assert_eq!(block_hash_from_hex("00ab"), None);
+    }
+
+    #[test]
+    fn test_block_header_commits_to_merkle_root() {
+        let genesis_address = generate_test_genesis_address();
+        let transactions: Vec<Transaction> = (0..3)
+            .map(|_| Transaction::new_coinbase_tx(&genesis_address).unwrap())
+            .collect();
+        let block = Block::new_block(&PREV_HASH, transactions.as_slice(), 1);
+
+        assert_eq!(
+            block.get_merkle_root(),
+            block.hash_transactions().as_slice()
+        );
+        assert_eq!(
+            block.get_merkle_root(),
+            compute_merkle_root(&block.get_tx_ids()).as_slice()
+        );
+
+        // A single transaction is its own Merkle root
+        let single = Block::new_block(&PREV_HASH, &transactions[..1], 1);
+        assert_eq!(single.get_merkle_root(), transactions[0].get_id());
+    }
+
+    #[test]
+    fn test_proof_of_work_validation_detects_tampering() {
+        let block = Block::new_block(&PREV_HASH, &[], 1);
+        assert!(
+            ProofOfWork::new_proof_of_work(block.clone())
+                .validate(GLOBAL_CONFIG.get_chain_params())
+        );
+
+        // Every header field is hashed: changing one gives another block, which
+        // needs its own proof of work
+        let mut tampered = block.clone();
+        tampered.header.timestamp += 1;
+        assert_ne!(tampered.get_hash(), block.get_hash());
+        let mut tampered = block.clone();
+        tampered.header.merkle_root = vec![0xff; BLOCK_HASH_SIZE];
+        assert_ne!(tampered.get_hash(), block.get_hash());
+        let mut tampered = block.clone();
+        while ProofOfWork::new_proof_of_work(tampered.clone())
+            .validate(GLOBAL_CONFIG.get_chain_params())
+        {
+            tampered.header.nonce += 1;
+        }
+        assert!(
+            BigInt::from_bytes_be(Sign::Plus, &tampered.get_hash_bytes()) >= block.get_target()
+        );
+
+        // A target no hash can meet
+        let mut tampered = block.clone();
+        tampered.header.bits = 0x0300_0001;
+        assert!(
+            !ProofOfWork::new_proof_of_work(tampered).validate(GLOBAL_CONFIG.get_chain_params())
+        );
+
+        // A target above the proof-of-work limit is never valid
+        let mut tampered = block;
+        tampered.header.bits = 0x2100_ffff;
+        assert!(
+            !ProofOfWork::new_proof_of_work(tampered).validate(GLOBAL_CONFIG.get_chain_params())
+        );
+    }
+
+    #[test]
+    fn test_mining_rolls_header_when_nonce_space_is_exhausted() {
+        let coinbase = Transaction::new_coinbase_tx_for_height(
+            &generate_test_genesis_address(),
+            2,
+            Amount::ZERO,
+        )
+        .unwrap();
+        let cancel = AtomicBool::new(false);
+        // Two nonces per header: most headers need rolling before one meets the target
+        let options = MiningOptions::new(2, &cancel).with_max_nonce(2);
+        let block =
+            Block::new_block_with_options(&PREV_HASH, &[coinbase], 2, POW_LIMIT_BITS, &options)
+                .unwrap();
+        assert!(
+            ProofOfWork::new_proof_of_work(block.clone())
+                .validate(GLOBAL_CONFIG.get_chain_params())
+        );
+        assert_eq!(
+            block.get_merkle_root(),
+            block.hash_transactions().as_slice()
+        );
+        assert!(block.transactions[0].commits_to_height(2));
+    }
+
+    #[test]
+    fn test_roll_header_increments_extra_nonce_when_clock_has_not_advanced() {
+        let coinbase = Transaction::new_coinbase_tx_for_height(
+            &generate_test_genesis_address(),
+            2,
+            Amount::ZERO,
+        )
+        .unwrap();
+        let mut block = Block::new_block(&PREV_HASH, std::slice::from_ref(&coinbase), 2);
+        block.header.timestamp = i64::MAX;
+        block.roll_header();
+        assert_eq!(block.header.timestamp, i64::MAX);
+        assert_ne!(block.transactions[0].get_id(), coinbase.get_id());
+        assert_eq!(block.get_merkle_root(), block.transactions[0].get_id());
+
+        // Without a coinbase only the timestamp can change
+        let mut empty = Block::new_block(&PREV_HASH, &[], 2);
+        empty.header.timestamp = i64::MAX - 1;
+        empty.roll_header();
+        assert_eq!(empty.header.timestamp, i64::MAX);
+
+        let mut stale = Block::new_block(&PREV_HASH, &[], 2);
+        stale.header.timestamp = 0;
+        stale.roll_header();
+        assert!(stale.header.timestamp > 0);
+    }
+
+    #[test]
+    fn test_mining_never_stamps_a_block_before_min_time() {
+        let cancel = AtomicBool::new(false);
+        let min_time = crate::util::current_timestamp() + 3_600_000;
+        let options = MiningOptions::new(1, &cancel).with_min_time(min_time);
+        let block =
+            Block::new_block_with_options(&PREV_HASH, &[], 2, POW_LIMIT_BITS, &options).unwrap();
+        assert!(block.get_timestamp() >= min_time);
+    }
+
+    #[test]
+    fn test_cancelled_mining_returns_error() {
+        let cancel = AtomicBool::new(true);
+        let options = MiningOptions::new(2, &cancel);
+        // A target only a zero hash meets, so the search can only end by cancellation
+        let result = Block::new_block_with_options(&PREV_HASH, &[], 2, 0x0300_0001, &options);
+        assert!(matches!(result, Err(BtcError::InvalidValueForMiner(_))));
     }
 
     #[test]
     fn test_work_calculation() {
         // Create a test block
-        let block = Block::new_block("prev_hash".to_string(), &[], 1);
+        let block = Block::new_block(&PREV_HASH, &[], 1);
 
         // Test work calculation
         let work = block.get_work();
@@ -453,13 +920,13 @@ mod tests {
         let mut total_work = 0u64;
 
         for i in 1..=5 {
-            let block = Block::new_block(format!("prev_hash_{}", i), &[], i);
+            let block = Block::new_block(&[i as u8; BLOCK_HASH_SIZE], &[], i);
             let work = block.get_work();
             total_work += work;
         }
 
         // Total work should be 5 times the individual block work
-        let single_block_work = Block::new_block("test".to_string(), &[], 1).get_work();
+        let single_block_work = Block::new_block(&PREV_HASH, &[], 1).get_work();
         let expected_total = single_block_work * 5;
 
         assert_eq!(
@@ -484,7 +951,7 @@ mod tests {
             Transaction::new_coinbase_tx(&wallet_address).expect("Failed to create coinbase tx");
 
         // Create a block with this transaction
-        let block = Block::new_block("prev_hash".to_string(), &[coinbase_tx], 1);
+        let block = Block::new_block(&PREV_HASH, &[coinbase_tx], 1);
 
         // Get user transactions using address (not wallet!)
         let user_txs = block
@@ -501,7 +968,10 @@ mod tests {
         assert_eq!(*tx.get_status(), WalletTransactionStatus::Confirmed);
         assert_eq!(*tx.get_from_wlt_addr(), None); // Coinbase has no sender
         assert_eq!(*tx.get_to_wlt_addr(), wallet_address);
-        assert!(tx.get_value() > 0, "Coinbase amount should be positive");
+        assert!(
+            tx.get_value() > Amount::ZERO,
+            "Coinbase amount should be positive"
+        );
     }
 
     /// Test coinbase transaction NOT to wallet (should return empty)
@@ -518,7 +988,7 @@ mod tests {
             Transaction::new_coinbase_tx(&wallet2_address).expect("Failed to create coinbase tx");
 
         // Create a block with this transaction
-        let block = Block::new_block("prev_hash".to_string(), &[coinbase_tx], 1);
+        let block = Block::new_block(&PREV_HASH, &[coinbase_tx], 1);
 
         // Get user transactions for wallet1 address (should be empty)
         let user_txs = block
@@ -546,7 +1016,7 @@ mod tests {
         // First, create a coinbase to give sender some funds
         let coinbase_tx =
             Transaction::new_coinbase_tx(&sender_address).expect("Failed to create coinbase tx");
-        let funding_block = Block::new_block("prev_hash".to_string(), &[coinbase_tx], 1);
+        let funding_block = Block::new_block(&PREV_HASH, &[coinbase_tx], 1);
 
         // Verify sender received the coinbase (using address)
         let funding_txs = funding_block
@@ -572,7 +1042,7 @@ mod tests {
         // Create a coinbase (which is a credit transaction)
         let coinbase_tx =
             Transaction::new_coinbase_tx(&wallet_address).expect("Failed to create coinbase tx");
-        let block = Block::new_block("prev_hash".to_string(), &[coinbase_tx], 1);
+        let block = Block::new_block(&PREV_HASH, &[coinbase_tx], 1);
 
         let user_txs = block
             .get_user_transactions(&wallet_address)
@@ -592,19 +1062,18 @@ mod tests {
         let wallet = Wallet::new().expect("Failed to create wallet");
         let wallet_address = wallet.get_address().expect("Failed to get address");
 
-        // Create multiple coinbase transactions to same address
-        let coinbase_tx1 =
-            Transaction::new_coinbase_tx(&wallet_address).expect("Failed to create coinbase tx 1");
-        let coinbase_tx2 =
-            Transaction::new_coinbase_tx(&wallet_address).expect("Failed to create coinbase tx 2");
-        let coinbase_tx3 =
-            Transaction::new_coinbase_tx(&wallet_address).expect("Failed to create coinbase tx 3");
-
-        let block = Block::new_block(
-            "prev_hash".to_string(),
-            &[coinbase_tx1, coinbase_tx2, coinbase_tx3],
-            1,
-        );
+        // Create multiple coinbase transactions to same address; each has its own extra
+        // nonce, so the block holds three distinct transactions
+        let coinbase_tx1 = Transaction::new_test_coinbase(&wallet_address, 1)
+            .expect("Failed to create coinbase tx 1");
+        let coinbase_tx2 = Transaction::new_test_coinbase(&wallet_address, 1)
+            .expect("Failed to create coinbase tx 2");
+        let coinbase_tx3 = Transaction::new_test_coinbase(&wallet_address, 1)
+            .expect("Failed to create coinbase tx 3");
+
+        let block = Block::new_block(&PREV_HASH, &[coinbase_tx1, coinbase_tx2, coinbase_tx3], 1);
+        let tx_ids = block.get_tx_ids();
+        assert!(tx_ids[0] != tx_ids[1] && tx_ids[1] != tx_ids[2] && tx_ids[0] != tx_ids[2]);
 
         let user_txs = block
             .get_user_transactions(&wallet_address)
@@ -629,19 +1098,15 @@ mod tests {
         let wallet2 = Wallet::new().expect("Failed to create wallet 2");
         let wallet2_address = wallet2.get_address().expect("Failed to get address 2");
 
-        // Create transactions to different addresses
-        let coinbase_tx1 =
-            Transaction::new_coinbase_tx(&wallet1_address).expect("Failed to create coinbase tx 1");
-        let coinbase_tx2 =
-            Transaction::new_coinbase_tx(&wallet2_address).expect("Failed to create coinbase tx 2");
-        let coinbase_tx3 =
-            Transaction::new_coinbase_tx(&wallet1_address).expect("Failed to create coinbase tx 3");
-
-        let block = Block::new_block(
-            "prev_hash".to_string(),
-            &[coinbase_tx1, coinbase_tx2, coinbase_tx3],
-            1,
-        );
+        // Create transactions to different addresses, each with its own extra nonce
+        let coinbase_tx1 = Transaction::new_test_coinbase(&wallet1_address, 1)
+            .expect("Failed to create coinbase tx 1");
+        let coinbase_tx2 = Transaction::new_test_coinbase(&wallet2_address, 1)
+            .expect("Failed to create coinbase tx 2");
+        let coinbase_tx3 = Transaction::new_test_coinbase(&wallet1_address, 1)
+            .expect("Failed to create coinbase tx 3");
+
+        let block = Block::new_block(&PREV_HASH, &[coinbase_tx1, coinbase_tx2, coinbase_tx3], 1);
 
         // Get transactions for wallet1 address
         let wallet1_txs = block
@@ -667,7 +1132,7 @@ mod tests {
     async fn test_get_user_transactions_empty_block() {
         let wallet = Wallet::new().expect("Failed to create wallet");
         let wallet_address = wallet.get_address().expect("Failed to get address");
-        let block = Block::new_block("prev_hash".to_string(), &[], 1);
+        let block = Block::new_block(&PREV_HASH, &[], 1);
 
         let user_txs = block
             .get_user_transactions(&wallet_address)
@@ -686,7 +1151,7 @@ mod tests {
 
         let coinbase_tx =
             Transaction::new_coinbase_tx(&wallet_address).expect("Failed to create coinbase tx");
-        let block = Block::new_block("prev_hash".to_string(), &[coinbase_tx], 1);
+        let block = Block::new_block(&PREV_HASH, &[coinbase_tx], 1);
 
         let user_txs = block
             .get_user_transactions(&wallet_address)
@@ -712,7 +1177,7 @@ mod tests {
             Transaction::new_coinbase_tx(&wallet_address).expect("Failed to create coinbase tx");
         let expected_tx_id = coinbase_tx.get_id().to_vec();
 
-        let block = Block::new_block("prev_hash".to_string(), &[coinbase_tx], 1);
+        let block = Block::new_block(&PREV_HASH, &[coinbase_tx], 1);
 
         let user_txs = block
             .get_user_transactions(&wallet_address)
@@ -727,9 +1192,7 @@ mod tests {
         );
     }
 
-    /// Test confirmed vs pending status
-    /// Note: In practice, transactions in a block are confirmed.
-    /// Pending status is tested through mempool integration tests.
+    /// Test confirmed status of block transactions
     #[tokio::test]
     async fn test_get_user_transactions_confirmed_status() {
         let wallet = Wallet::new().expect("Failed to create wallet");
@@ -737,7 +1200,7 @@ mod tests {
 
         let coinbase_tx =
             Transaction::new_coinbase_tx(&wallet_address).expect("Failed to create coinbase tx");
-        let block = Block::new_block("prev_hash".to_string(), &[coinbase_tx], 1);
+        let block = Block::new_block(&PREV_HASH, &[coinbase_tx], 1);
 
         let user_txs = block
             .get_user_transactions(&wallet_address)
@@ -752,4 +1215,36 @@ mod tests {
             "Block transactions should be confirmed"
         );
     }
+
+    /// Test pending status of an output a mempool transaction spends
+    #[tokio::test]
+    async fn test_get_user_transactions_pending_status() {
+        let wallet = Wallet::new().expect("Failed to create wallet");
+        let wallet_address = wallet.get_address().expect("Failed to get address");
+
+        let coinbase_tx = Transaction::new_test_coinbase(&wallet_address, 1)
+            .expect("Failed to create coinbase tx");
+        let spender = Transaction::test_builder()
+            .spending(coinbase_tx.get_id(), 0, SEQUENCE_FINAL)
+            .build();
+        let block = Block::new_block(&PREV_HASH, &[coinbase_tx], 1);
+
+        GLOBAL_MEMORY_POOL
+            .add(spender.clone(), Amount::ZERO)
+            .expect("Failed to add spender to mempool");
+        let user_txs = block
+            .get_user_transactions(&wallet_address)
+            .await
+            .expect("Failed to get user transactions");
+        GLOBAL_MEMORY_POOL
+            .remove(spender)
+            .expect("Failed to remove spender from mempool");
+
+        assert_eq!(user_txs.len(), 1);
+        assert_eq!(
+            *user_txs[0].get_status(),
+            WalletTransactionStatus::Pending,
+            "An output spent in the mempool should be pending"
+        );
+    }
 }
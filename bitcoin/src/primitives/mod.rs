@@ -12,11 +12,14 @@ pub mod blockchain;
 pub mod transaction;
 
 // Re-export the core types
-pub use block::{Block, GENESIS_BLOCK_PRE_BLOCK_HASH};
+pub use block::{
+    BLOCK_HASH_SIZE, BLOCK_HEADER_SIZE, BLOCK_VERSION, Block, BlockHeader,
+    GENESIS_BLOCK_PRE_BLOCK_HASH, block_hash_from_hex,
+};
 pub use blockchain::Blockchain;
 pub use transaction::{
-    TXInput, TXOutput, Transaction, WalletTransaction, WalletTransactionStatus,
-    WalletTransactionType,
+    LOCKTIME_THRESHOLD, SEQUENCE_FINAL, TX_VERSION, TXInput, TXOutput, Transaction, TxFee,
+    TxLockTime, WalletTransaction, WalletTransactionStatus, WalletTransactionType,
 };
 
 // Re-exports for moved modules (backward compatibility)
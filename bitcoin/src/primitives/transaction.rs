@@ -1,16 +1,174 @@
 use crate::WalletAddress;
 use crate::chain::BlockchainService;
 use crate::chain::UTXOSet;
+use crate::config::GLOBAL_CONFIG;
+use crate::consensus::validation::MAX_COINBASE_SCRIPT_SIG_SIZE;
+use crate::consensus::{Amount, ChainParams};
+use crate::crypto::address::get_script_hash;
 use crate::crypto::hash::sha256_digest;
-use crate::crypto::signature::{schnorr_sign_digest, schnorr_sign_verify};
+use crate::crypto::keypair::get_schnorr_public_key;
+use crate::crypto::signature::{get_xonly_public_key, schnorr_sign_digest};
 use crate::error::{BtcError, Result};
+use crate::node::GLOBAL_MEMORY_POOL;
+use crate::policy::MAX_DATA_CARRIER_SIZE;
+use crate::script::opcodes::OP_RETURN;
+use crate::script::scriptbuf::decode_script_num;
+use crate::script::{
+    Instruction, Script, SigHashType, TransactionSignatureChecker, TxoutType, build_script_sig,
+    multisig_script_sig, p2pkh_script, p2sh_script, p2tr_script, parse_multisig_script_sig,
+    sighash, solver, verify_script,
+};
+use crate::serialize::{self, Decodable, Encodable, Reader, write_var_bytes, write_vec};
 use crate::wallet::{WalletService, convert_address, get_pub_key_hash, hash_pub_key};
 use data_encoding::HEXLOWER;
 use serde::{Deserialize, Serialize};
 use tracing::debug;
-use uuid::Uuid;
 
-const SUBSIDY: i32 = 10;
+/// Fee the sender of a transaction is willing to pay to the miner.
+///
+/// The fee is not stored in the transaction: it is the difference between the value of the
+/// outputs being spent and the value of the new outputs, and is collected by the coinbase.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TxFee {
+    /// A fixed fee for the whole transaction.
+    Absolute(Amount),
+    /// A fee in satoshis for every byte of the serialized, signed transaction.
+    PerByte(u64),
+}
+
+/// Version of new transactions, the first field of their encoding (Bitcoin Core
+/// `CTransaction::CURRENT_VERSION`).
+pub const TX_VERSION: u32 = 2;
+
+/// Sequence of an input that opts out of relative lock times; a transaction whose inputs
+/// all carry it ignores its absolute lock time too (Bitcoin Core `CTxIn::SEQUENCE_FINAL`).
+pub const SEQUENCE_FINAL: u32 = 0xffff_ffff;
+
+/// Largest sequence that still enables the transaction's absolute lock time without
+/// setting a relative one (Bitcoin Core `CTxIn::MAX_SEQUENCE_NONFINAL`).
+pub const MAX_SEQUENCE_NONFINAL: u32 = SEQUENCE_FINAL - 1;
+
+/// Largest sequence that signals the transaction may be replaced by one paying a higher
+/// fee (BIP125, Bitcoin Core `MAX_BIP125_RBF_SEQUENCE`). It has the disable flag set, so it
+/// carries no relative lock time.
+pub const MAX_BIP125_RBF_SEQUENCE: u32 = SEQUENCE_FINAL - 2;
+
+/// When set, the sequence is not a relative lock time (BIP68).
+pub const SEQUENCE_LOCKTIME_DISABLE_FLAG: u32 = 1 << 31;
+
+/// When set, the relative lock time counts units of 512 seconds; otherwise it counts
+/// blocks (BIP68).
+pub const SEQUENCE_LOCKTIME_TYPE_FLAG: u32 = 1 << 22;
+
+/// Bits of the sequence holding the relative lock time value (BIP68).
+pub const SEQUENCE_LOCKTIME_MASK: u32 = 0x0000_ffff;
+
+/// Time-based relative lock times count units of `1 << SEQUENCE_LOCKTIME_GRANULARITY`
+/// (512) seconds (BIP68).
+pub const SEQUENCE_LOCKTIME_GRANULARITY: u32 = 9;
+
+/// Absolute lock times below this value are block heights, the others UNIX timestamps in
+/// seconds (Bitcoin Core `LOCKTIME_THRESHOLD`, Tue Nov 5 00:53:20 1985 UTC).
+pub const LOCKTIME_THRESHOLD: u32 = 500_000_000;
+
+/// Absolute and relative lock time of a new transaction.
+///
+/// The lock time keeps the whole transaction out of blocks until a height or a time
+/// (nLockTime); the sequence of every input keeps it out until the spent outputs have been
+/// confirmed for a number of blocks or seconds (BIP68). Together they make escrow and
+/// vesting payouts possible.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxLockTime {
+    lock_time: u32,
+    sequence: u32,
+}
+
+impl Default for TxLockTime {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl TxLockTime {
+    /// No lock: the transaction may be mined right away.
+    pub fn none() -> TxLockTime {
+        TxLockTime {
+            lock_time: 0,
+            sequence: SEQUENCE_FINAL,
+        }
+    }
+
+    /// No lock, but every input signals that the transaction may be replaced in the
+    /// memory pool by one paying a higher fee (BIP125 opt-in replace-by-fee).
+    pub fn replaceable() -> TxLockTime {
+        TxLockTime {
+            lock_time: 0,
+            sequence: MAX_BIP125_RBF_SEQUENCE,
+        }
+    }
+
+    /// Not mineable before the block at `height`; the transaction may be included in
+    /// blocks above `height - 1`.
+    pub fn at_height(height: u32) -> Result<TxLockTime> {
+        if height >= LOCKTIME_THRESHOLD {
+            return Err(BtcError::InvalidLockTime(format!(
+                "height {} is not below {}",
+                height, LOCKTIME_THRESHOLD
+            )));
+        }
+        Ok(TxLockTime {
+            lock_time: height,
+            sequence: MAX_SEQUENCE_NONFINAL,
+        })
+    }
+
+    /// Not mineable until the median time past of the chain is after `timestamp`
+    /// (UNIX seconds, BIP113).
+    pub fn at_time(timestamp: u32) -> Result<TxLockTime> {
+        if timestamp < LOCKTIME_THRESHOLD {
+            return Err(BtcError::InvalidLockTime(format!(
+                "timestamp {} is below {}",
+                timestamp, LOCKTIME_THRESHOLD
+            )));
+        }
+        Ok(TxLockTime {
+            lock_time: timestamp,
+            sequence: MAX_SEQUENCE_NONFINAL,
+        })
+    }
+
+    /// Not mineable until every spent output has `blocks` confirmations.
+    pub fn after_blocks(blocks: u16) -> TxLockTime {
+        TxLockTime {
+            lock_time: 0,
+            sequence: u32::from(blocks),
+        }
+    }
+
+    /// Not mineable until every spent output has been confirmed for `seconds`, rounded up
+    /// to a multiple of 512 seconds.
+    pub fn after_seconds(seconds: u32) -> Result<TxLockTime> {
+        let units = seconds.div_ceil(1 << SEQUENCE_LOCKTIME_GRANULARITY);
+        if units > SEQUENCE_LOCKTIME_MASK {
+            return Err(BtcError::InvalidLockTime(format!(
+                "relative lock of {} seconds is too long",
+                seconds
+            )));
+        }
+        Ok(TxLockTime {
+            lock_time: 0,
+            sequence: SEQUENCE_LOCKTIME_TYPE_FLAG | units,
+        })
+    }
+
+    pub fn get_lock_time(&self) -> u32 {
+        self.lock_time
+    }
+
+    pub fn get_sequence(&self) -> u32 {
+        self.sequence
+    }
+}
 
 #[derive(Clone, Default, Serialize, Deserialize)]
 pub struct TXInput {
@@ -18,6 +176,11 @@ pub struct TXInput {
     vout: usize,
     signature: Vec<u8>,
     pub_key: Vec<u8>,
+    // Unlocking script; empty for inputs spending a key-hash output, which are unlocked
+    // by `<signature> <pub_key>`
+    script_sig: Script,
+    // Relative lock time (BIP68), or `SEQUENCE_FINAL` for none
+    sequence: u32,
 }
 
 impl TXInput {
@@ -27,6 +190,8 @@ impl TXInput {
             vout,
             signature: vec![],
             pub_key: vec![],
+            script_sig: Script::new(),
+            sequence: SEQUENCE_FINAL,
         }
     }
 
@@ -50,6 +215,22 @@ impl TXInput {
         self.signature.as_slice()
     }
 
+    pub fn get_sequence(&self) -> u32 {
+        self.sequence
+    }
+
+    /// The script evaluated against the spent output's locking script. Inputs without an
+    /// explicit `script_sig` push their signature and public key, which is what a
+    /// pay-to-pubkey-hash output expects.
+    pub fn get_script_sig(&self) -> Script {
+        if self.script_sig.is_empty() {
+            return Script::new()
+                .push_slice(&self.signature)
+                .push_slice(&self.pub_key);
+        }
+        self.script_sig.clone()
+    }
+
     pub fn uses_key(&self, pub_key_hash: &[u8]) -> bool {
         let locking_hash = hash_pub_key(self.pub_key.as_slice());
         locking_hash.eq(pub_key_hash)
@@ -58,23 +239,98 @@ impl TXInput {
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct TXOutput {
-    value: i32,
-    in_global_mem_pool: bool,
+    value: Amount,
     pub_key_hash: Vec<u8>,
+    // Locking script; empty for key-hash outputs, which are locked by the
+    // pay-to-pubkey-hash template over `pub_key_hash`
+    script_pubkey: Script,
 }
 
 impl TXOutput {
-    pub fn new(value: i32, address: &WalletAddress) -> Result<TXOutput> {
+    pub fn new(value: Amount, address: &WalletAddress) -> Result<TXOutput> {
         let mut output = TXOutput {
             value,
-            in_global_mem_pool: false,
             pub_key_hash: vec![],
+            script_pubkey: Script::new(),
         };
         output.lock(address)?;
         Ok(output)
     }
 
-    pub fn get_value(&self) -> i32 {
+    ///
+    /// Create a pay-to-taproot output spendable with a Schnorr signature by `pub_key`
+    /// alone (key path).
+    ///
+    /// The output also records the hash of `pub_key`, so the owner's wallet finds it
+    /// like any other output paid to its address.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value of the output.
+    /// * `pub_key` - The 33-byte compressed public key of the recipient.
+    pub fn new_p2tr(value: Amount, pub_key: &[u8]) -> Result<TXOutput> {
+        Ok(TXOutput {
+            value,
+            pub_key_hash: hash_pub_key(pub_key),
+            script_pubkey: p2tr_script(&get_xonly_public_key(pub_key)?),
+        })
+    }
+
+    ///
+    /// Create a data carrier output, `OP_RETURN <data>`, for anchoring a document hash or
+    /// other small payload on chain (Bitcoin Core's `nulldata` outputs).
+    ///
+    /// The output pays nothing and its locking script fails as soon as it is evaluated, so
+    /// it can never be spent and is never added to the UTXO set.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The payload, at most `MAX_DATA_CARRIER_SIZE` bytes.
+    pub fn new_data_carrier(data: &[u8]) -> Result<TXOutput> {
+        if data.len() > MAX_DATA_CARRIER_SIZE {
+            return Err(BtcError::DataCarrierTooLarge(
+                data.len(),
+                MAX_DATA_CARRIER_SIZE,
+            ));
+        }
+        Ok(TXOutput {
+            value: Amount::ZERO,
+            pub_key_hash: vec![],
+            script_pubkey: Script::new().push_opcode(OP_RETURN).push_slice(data),
+        })
+    }
+
+    /// Whether the output can never be spent (Bitcoin Core's `CScript::IsUnspendable`);
+    /// such outputs are left out of the UTXO set.
+    pub fn is_unspendable(&self) -> bool {
+        self.script_pubkey.is_unspendable()
+    }
+
+    /// The payload of a data carrier output: the data pushed after `OP_RETURN`, or `None`
+    /// for any other output.
+    pub fn get_data_carrier_payload(&self) -> Option<Vec<u8>> {
+        match solver(&self.script_pubkey) {
+            (TxoutType::NullData, pushes) => Some(pushes.concat()),
+            _ => None,
+        }
+    }
+
+    /// An output that pays nobody, standing in for spent outputs in the UTXO set (Bitcoin
+    /// Core's null `CTxOut`).
+    pub fn null() -> TXOutput {
+        TXOutput {
+            value: Amount::ZERO,
+            pub_key_hash: vec![],
+            script_pubkey: Script::new(),
+        }
+    }
+
+    /// Whether this is the null output of `TXOutput::null`.
+    pub fn is_null(&self) -> bool {
+        self.value == Amount::ZERO && self.pub_key_hash.is_empty() && self.script_pubkey.is_empty()
+    }
+
+    pub fn get_value(&self) -> Amount {
         self.value
     }
 
@@ -82,12 +338,34 @@ impl TXOutput {
         self.pub_key_hash.as_slice()
     }
 
+    /// The locking script of the output. Key-hash outputs map onto the
+    /// pay-to-pubkey-hash template over their `pub_key_hash`.
+    pub fn get_script_pubkey(&self) -> Script {
+        if self.script_pubkey.is_empty() {
+            return p2pkh_script(&self.pub_key_hash);
+        }
+        self.script_pubkey.clone()
+    }
+
+    /// Whether the output carries an explicit locking script rather than the key-hash
+    /// template.
+    pub fn has_script_pubkey(&self) -> bool {
+        !self.script_pubkey.is_empty()
+    }
+
     // The `lock` function locks the output to the address.
     // It uses the `base58_decode` function to decode the address.
     // It uses the `ADDRESS_CHECK_SUM_LEN` constant to get the length of the address check sum.
     // It uses the `pub_key_hash` field to store the public key hash.
+    // A pay-to-script-hash (multisig) address also sets the locking script; its script hash
+    // is stored as the `pub_key_hash` so the address's outputs are found like any other.
     // It returns the new output.
     fn lock(&mut self, address: &WalletAddress) -> Result<()> {
+        if let Some(script_hash) = get_script_hash(address.as_str()) {
+            self.script_pubkey = p2sh_script(&script_hash);
+            self.pub_key_hash = script_hash;
+            return Ok(());
+        }
         let pub_key_hash = get_pub_key_hash(address)?;
         self.pub_key_hash = pub_key_hash;
         Ok(())
@@ -100,54 +378,110 @@ impl TXOutput {
     pub fn not_locked_with_key(&self, pub_key_hash: &[u8]) -> bool {
         self.pub_key_hash.ne(pub_key_hash)
     }
-
-    pub fn set_in_global_mem_pool(&mut self, value: bool) {
-        self.in_global_mem_pool = value;
-    }
-
-    pub fn is_in_global_mem_pool(&self) -> bool {
-        self.in_global_mem_pool
-    }
-    pub fn not_in_global_mem_pool(&self) -> bool {
-        !self.in_global_mem_pool
-    }
 }
 
 #[derive(Clone, Default, Serialize, Deserialize)]
 pub struct Transaction {
+    // Format version (see `TX_VERSION`)
+    version: u32,
     id: Vec<u8>,
     vin: Vec<TXInput>,
     vout: Vec<TXOutput>,
+    // Height or time before which the transaction cannot be mined (see `LOCKTIME_THRESHOLD`)
+    lock_time: u32,
 }
 
 impl Transaction {
     // The `new_coinbase_tx` function creates a new coinbase transaction.
-    // It uses the `SUBSIDY` constant to set the value of the transaction.
+    // It pays the subsidy of the first era (see `consensus::subsidy`) without fees.
     // It uses the `to` parameter to set the address of the recipient.
     // It returns the new transaction.
     pub fn new_coinbase_tx(to: &WalletAddress) -> Result<Transaction> {
-        let txout = TXOutput::new(SUBSIDY, to)?;
+        Self::new_coinbase_tx_for_height(to, 1, Amount::ZERO)
+    }
+
+    /// Create the coinbase transaction of the block at `height`, paying the subsidy of
+    /// that height on the configured network plus the fees of the block.
+    /// See `new_coinbase_tx_with_extra_nonce`.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` - The address of the miner receiving the reward.
+    /// * `height` - Height of the block the coinbase is created for.
+    /// * `fees` - Total fees of the other transactions in the block.
+    pub fn new_coinbase_tx_for_height(
+        to: &WalletAddress,
+        height: usize,
+        fees: Amount,
+    ) -> Result<Transaction> {
+        Self::new_coinbase_tx_with_extra_nonce(to, height, fees, 0, &[])
+    }
+
+    /// Create the coinbase transaction of the block at `height` with an extra nonce and
+    /// a miner tag.
+    ///
+    /// The unlocking script of the coinbase input commits to the block height (BIP34),
+    /// followed by the extra nonce and the tag: `<height> <extra_nonce> [<miner_tag>]`.
+    /// Coinbases of different heights therefore have different ids, while the same
+    /// height, reward and extra data always yield the same id. Miners sharing an address
+    /// vary the extra nonce or tag to tell their coinbases apart.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` - The address of the miner receiving the reward.
+    /// * `height` - Height of the block the coinbase is created for.
+    /// * `fees` - Total fees of the other transactions in the block.
+    /// * `extra_nonce` - Any number chosen by the miner.
+    /// * `miner_tag` - Optional data identifying the miner; empty for none.
+    ///
+    /// # Returns
+    ///
+    /// The coinbase, or `InvalidCoinbaseScript` if the unlocking script would exceed
+    /// `MAX_COINBASE_SCRIPT_SIG_SIZE` bytes.
+    pub fn new_coinbase_tx_with_extra_nonce(
+        to: &WalletAddress,
+        height: usize,
+        fees: Amount,
+        extra_nonce: u64,
+        miner_tag: &[u8],
+    ) -> Result<Transaction> {
+        let subsidy = GLOBAL_CONFIG.get_network().get_block_subsidy(height);
+        let reward = subsidy.checked_add(fees).ok_or(BtcError::AmountOverflow)?;
+        let txout = TXOutput::new(reward, to)?;
+        let script_sig = coinbase_script_sig(height, extra_nonce, miner_tag)?;
         let tx_input = TXInput {
-            signature: Uuid::new_v4().as_bytes().to_vec(),
+            script_sig,
+            sequence: SEQUENCE_FINAL,
             ..Default::default()
         };
 
         let mut tx = Transaction {
+            version: TX_VERSION,
             id: vec![],
             vin: vec![tx_input],
             vout: vec![txout],
+            lock_time: 0,
         };
 
         tx.id = tx.hash()?;
         Ok(tx)
     }
 
+    /// Create the coinbase of the genesis block of the configured network. It pays the
+    /// first subsidy to `to` and tags the coinbase with the network's genesis message, so
+    /// the genesis block of one network is never mistaken for another's.
     ///
-    /// This function constructs a new UTXO-based transaction
-    /// by selecting spendable outputs and creating inputs for the transaction.
-    /// It calculates the inputs required based on available outputs,
-    /// manages outputs for the recipient and change, signs the transaction,
-    /// and computes its ID through hashing:
+    /// # Arguments
+    ///
+    /// * `to` - The address receiving the genesis reward.
+    pub fn new_genesis_coinbase_tx(to: &WalletAddress) -> Result<Transaction> {
+        let genesis_message = GLOBAL_CONFIG.get_chain_params().get_genesis_message();
+        Self::new_coinbase_tx_with_extra_nonce(to, 1, Amount::ZERO, 0, genesis_message)
+    }
+
+    ///
+    /// This function constructs a new UTXO-based transaction without a fee.
+    /// See `new_utxo_transaction_with_fee`.
     ///
     /// # Arguments
     ///
@@ -156,7 +490,160 @@ impl Transaction {
     pub async fn new_utxo_transaction(
         from_wlt_addr: &WalletAddress,
         to_wlt_addr: &WalletAddress,
-        tx_amount: i32,
+        tx_amount: Amount,
+        utxo_set: &UTXOSet,
+    ) -> Result<Transaction> {
+        Self::new_utxo_transaction_with_fee(
+            from_wlt_addr,
+            to_wlt_addr,
+            tx_amount,
+            TxFee::Absolute(Amount::ZERO),
+            utxo_set,
+        )
+        .await
+    }
+
+    ///
+    /// This function constructs a new UTXO-based transaction that pays a fee to the miner
+    /// and can be mined right away.
+    /// See `new_utxo_transaction_with_lock_time`.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The address of the sender.
+    /// * `to` - The address of the recipient.
+    /// * `tx_amount` - The amount paid to the recipient.
+    /// * `fee` - The fee paid to the miner.
+    /// * `utxo_set` - The UTXO set to select spendable outputs from.
+    pub async fn new_utxo_transaction_with_fee(
+        from_wlt_addr: &WalletAddress,
+        to_wlt_addr: &WalletAddress,
+        tx_amount: Amount,
+        fee: TxFee,
+        utxo_set: &UTXOSet,
+    ) -> Result<Transaction> {
+        Self::new_utxo_transaction_with_lock_time(
+            from_wlt_addr,
+            to_wlt_addr,
+            tx_amount,
+            fee,
+            TxLockTime::none(),
+            utxo_set,
+        )
+        .await
+    }
+
+    ///
+    /// This function constructs a new UTXO-based transaction that pays a fee to the miner.
+    /// Spendable outputs worth at least `tx_amount` plus the fee are selected, and whatever is
+    /// left after paying the recipient and the fee is returned to the sender as change.
+    ///
+    /// A per-byte fee depends on the size of the signed transaction, which in turn depends on
+    /// how many inputs the fee requires, so the transaction is rebuilt until the fee it pays
+    /// covers its own size.
+    ///
+    /// The lock time is stored in the transaction and the sequence in every input, both
+    /// covered by the signatures.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The address of the sender.
+    /// * `to` - The address of the recipient.
+    /// * `tx_amount` - The amount paid to the recipient.
+    /// * `fee` - The fee paid to the miner.
+    /// * `lock` - The absolute or relative lock time keeping the transaction out of blocks.
+    /// * `utxo_set` - The UTXO set to select spendable outputs from.
+    pub async fn new_utxo_transaction_with_lock_time(
+        from_wlt_addr: &WalletAddress,
+        to_wlt_addr: &WalletAddress,
+        tx_amount: Amount,
+        fee: TxFee,
+        lock: TxLockTime,
+        utxo_set: &UTXOSet,
+    ) -> Result<Transaction> {
+        if !tx_amount.is_money_range() {
+            return Err(BtcError::InvalidFee(format!("{:?}", fee)));
+        }
+        let payment = TXOutput::new(tx_amount, to_wlt_addr)?;
+        Self::new_funded_transaction(from_wlt_addr, payment, fee, lock, utxo_set).await
+    }
+
+    ///
+    /// This function constructs a transaction anchoring `data` on chain in a data carrier
+    /// output (see `TXOutput::new_data_carrier`). The sender's spendable outputs pay the
+    /// fee and the rest is returned as change.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The address of the sender, which pays the fee.
+    /// * `data` - The payload, at most `MAX_DATA_CARRIER_SIZE` bytes.
+    /// * `fee` - The fee paid to the miner; it must not be zero, since it is what the
+    ///   transaction's inputs are selected for.
+    /// * `utxo_set` - The UTXO set to select spendable outputs from.
+    pub async fn new_data_carrier_transaction(
+        from_wlt_addr: &WalletAddress,
+        data: &[u8],
+        fee: TxFee,
+        utxo_set: &UTXOSet,
+    ) -> Result<Transaction> {
+        let payment = TXOutput::new_data_carrier(data)?;
+        let tx =
+            Self::new_funded_transaction(from_wlt_addr, payment, fee, TxLockTime::none(), utxo_set)
+                .await?;
+        if tx.vin.is_empty() {
+            return Err(BtcError::InvalidFee(
+                "a data carrier transaction must pay a fee".to_string(),
+            ));
+        }
+        Ok(tx)
+    }
+
+    ///
+    /// Fund and sign a transaction creating `payment`, with inputs of `from_wlt_addr` worth at
+    /// least its value plus the fee and the change returned to the sender. A per-byte fee is
+    /// met by rebuilding the transaction until the fee covers its size.
+    async fn new_funded_transaction(
+        from_wlt_addr: &WalletAddress,
+        payment: TXOutput,
+        fee: TxFee,
+        lock: TxLockTime,
+        utxo_set: &UTXOSet,
+    ) -> Result<Transaction> {
+        let mut fee_amount = match fee {
+            TxFee::Absolute(fee_amount) => fee_amount,
+            TxFee::PerByte(_) => Amount::ZERO,
+        };
+        if !fee_amount.is_money_range() {
+            return Err(BtcError::InvalidFee(format!("{:?}", fee)));
+        }
+        loop {
+            let tx =
+                Self::build_utxo_transaction(from_wlt_addr, &payment, fee_amount, lock, utxo_set)
+                    .await?;
+            let TxFee::PerByte(fee_rate) = fee else {
+                return Ok(tx);
+            };
+            let required_fee = Amount::from_sat(tx.get_size()? as u64)
+                .checked_mul(fee_rate)
+                .ok_or(BtcError::NotEnoughFunds)?;
+            if fee_amount >= required_fee {
+                return Ok(tx);
+            }
+            fee_amount = required_fee;
+        }
+    }
+
+    ///
+    /// This function builds and signs a transaction creating `payment` and leaving
+    /// `fee_amount` to the miner.
+    /// It calculates the inputs required based on available outputs,
+    /// manages outputs for the recipient and change, signs the transaction,
+    /// and computes its ID through hashing.
+    async fn build_utxo_transaction(
+        from_wlt_addr: &WalletAddress,
+        payment: &TXOutput,
+        fee_amount: Amount,
+        lock: TxLockTime,
         utxo_set: &UTXOSet,
     ) -> Result<Transaction> {
         let wallets = WalletService::new()?;
@@ -165,22 +652,114 @@ impl Transaction {
             .ok_or_else(|| BtcError::UTXONotFoundError(from_wlt_addr.as_string()))?;
         let from_public_key_hash = hash_pub_key(from_wallet.get_public_key());
 
-        let (available_funds, valid_outputs) = utxo_set
-            .find_spendable_outputs(from_public_key_hash.as_slice(), tx_amount)
-            .await?;
+        let mut tx = Self::build_unsigned_transaction(
+            from_public_key_hash.as_slice(),
+            from_wallet.get_public_key(),
+            from_wlt_addr,
+            payment.clone(),
+            fee_amount,
+            utxo_set,
+        )
+        .await?
+        .with_lock_time(lock)?;
+        let spent_outputs = utxo_set.get_spent_outputs(&tx).await?;
+        tx.sign_inputs(
+            from_wallet.get_pkcs8(),
+            &spent_outputs,
+            0..tx.vin.len(),
+            SigHashType::All,
+        )?;
+        Ok(tx)
+    }
 
+    ///
+    /// Select outputs locked with `lock_hash` worth at least the value of `payment` plus
+    /// `fee_amount` and build an unsigned transaction spending them, creating `payment` and
+    /// returning the change to `from_wlt_addr`. The id is computed before any input is signed.
+    ///
+    /// # Arguments
+    ///
+    /// * `lock_hash` - The public key hash or script hash the spent outputs are locked with.
+    /// * `input_pub_key` - The public key recorded in every input; empty for multisig inputs.
+    /// * `from_wlt_addr` - The address receiving the change.
+    /// * `payment` - The output paying the recipient, or a data carrier output.
+    /// * `fee_amount` - The fee left to the miner.
+    /// * `utxo_set` - The UTXO set to select spendable outputs from.
+    async fn build_unsigned_transaction(
+        lock_hash: &[u8],
+        input_pub_key: &[u8],
+        from_wlt_addr: &WalletAddress,
+        payment: TXOutput,
+        fee_amount: Amount,
+        utxo_set: &UTXOSet,
+    ) -> Result<Transaction> {
+        let required_funds = payment
+            .get_value()
+            .checked_add(fee_amount)
+            .ok_or(BtcError::NotEnoughFunds)?;
         debug!(
-            "Transaction creation: from={}, to={}, amount={}",
+            "Transaction creation: from={}, to={}, amount={}, fee={}",
             from_wlt_addr.as_str(),
-            to_wlt_addr.as_str(),
-            tx_amount
+            payment.get_script_pubkey(),
+            payment.get_value(),
+            fee_amount
         );
+        let (available_funds, inputs) =
+            Self::select_inputs(lock_hash, input_pub_key, required_funds, utxo_set).await?;
+
+        let mut outputs = vec![payment];
+
+        if available_funds > required_funds {
+            let change = available_funds
+                .checked_sub(required_funds)
+                .ok_or(BtcError::NotEnoughFunds)?;
+            debug!(
+                "Creating change output: {} to {}",
+                change,
+                from_wlt_addr.as_str()
+            );
+            outputs.push(TXOutput::new(change, from_wlt_addr)?); // to: Return change to the sender
+        }
+
+        // Create a new transaction with the spent inputs and unspent outputs
+        let mut tx = Transaction {
+            version: TX_VERSION,
+            id: vec![],
+            vin: inputs,
+            vout: outputs,
+            lock_time: 0,
+        };
+        tx.id = tx.hash()?;
+        debug!(
+            "Created transaction with {} inputs and {} outputs",
+            tx.get_vin().len(),
+            tx.get_vout().len()
+        );
+        Ok(tx)
+    }
+
+    ///
+    /// Select outputs locked with `lock_hash` worth at least `required_funds` and build
+    /// unsigned inputs spending them.
+    ///
+    /// # Returns
+    ///
+    /// The total value of the selected outputs and the inputs, or `NotEnoughFunds`.
+    async fn select_inputs(
+        lock_hash: &[u8],
+        input_pub_key: &[u8],
+        required_funds: Amount,
+        utxo_set: &UTXOSet,
+    ) -> Result<(Amount, Vec<TXInput>)> {
+        let (available_funds, valid_outputs) = utxo_set
+            .find_spendable_outputs(lock_hash, required_funds)
+            .await?;
         debug!(
             "Found spendable outputs: accumulated={}, valid_outputs={:?}",
             available_funds, valid_outputs
         );
 
-        if available_funds < tx_amount {
+        if available_funds < required_funds {
             return Err(BtcError::NotEnoughFunds);
         }
 
@@ -194,109 +773,347 @@ impl Transaction {
                     txid: txid.clone(), // txid is the hash of the previous transaction or transaction that contains the output that is being spent
                     vout: current_out_index, // vout is the index of the output that is being spent in the previous transaction or transaction that contains the output that is being spent
                     signature: vec![],
-                    pub_key: from_wallet.get_public_key().to_vec(),
+                    pub_key: input_pub_key.to_vec(),
+                    script_sig: Script::new(),
+                    sequence: SEQUENCE_FINAL,
                 };
                 inputs.push(input);
             }
         }
+        Ok((available_funds, inputs))
+    }
 
-        let mut outputs = vec![TXOutput::new(tx_amount, to_wlt_addr)?];
-
-        if available_funds > tx_amount {
-            let change = available_funds - tx_amount;
-            debug!(
-                "Creating change output: {} to {}",
-                change,
-                from_wlt_addr.as_str()
-            );
-            outputs.push(TXOutput::new(change, from_wlt_addr)?); // to: Return change to the sender
+    ///
+    /// Set the lock time of an unsigned transaction and the sequence of all its inputs,
+    /// recomputing its id.
+    fn with_lock_time(mut self, lock: TxLockTime) -> Result<Transaction> {
+        self.lock_time = lock.get_lock_time();
+        for vin in self.vin.iter_mut() {
+            vin.sequence = lock.get_sequence();
         }
+        self.id = self.hash()?;
+        Ok(self)
+    }
 
-        // Create a new transaction with the spent inputs and unspent outputs
+    ///
+    /// This function constructs a crowdfunding transaction: it pays `target` to `to_wlt_addr`
+    /// but has no inputs yet. Contributors add and sign their own inputs with
+    /// `add_wallet_inputs` using `SigHashType::AllPlusAnyoneCanPay`, which commits each
+    /// signature to the outputs but not to the other inputs. The transaction is only valid
+    /// once the inputs cover the target, so no contribution can be spent before then.
+    ///
+    /// # Arguments
+    ///
+    /// * `to_wlt_addr` - The address of the recipient.
+    /// * `target` - The amount the contributors must raise.
+    pub fn new_crowdfunding_transaction(
+        to_wlt_addr: &WalletAddress,
+        target: Amount,
+    ) -> Result<Transaction> {
+        if !target.is_money_range() {
+            return Err(BtcError::InvalidTransactionOutput);
+        }
         let mut tx = Transaction {
+            version: TX_VERSION,
             id: vec![],
-            vin: inputs,
-            vout: outputs,
+            vin: vec![],
+            vout: vec![TXOutput::new(target, to_wlt_addr)?],
+            lock_time: 0,
         };
         tx.id = tx.hash()?;
-        debug!(
-            "Created transaction with {} inputs and {} outputs",
-            tx.get_vin().len(),
-            tx.get_vout().len()
-        );
-        tx.sign(utxo_set.get_blockchain(), from_wallet.get_pkcs8())
-            .await?;
         Ok(tx)
     }
 
     ///
-    /// `trimmed_copy` is an internal function that creates a trimmed copy of the transaction,
-    /// excluding signatures, enabling signature verification without modifying
-    /// the original transaction
+    /// Append an output paying `value` to `to_wlt_addr`, recomputing the id.
+    ///
+    /// Signatures made with `SigHashType::All` commit to every output, so outputs should be
+    /// added before such inputs are signed; `None` signatures, and `Single` signatures of
+    /// inputs at another index, stay valid.
+    pub fn add_output(&mut self, value: Amount, to_wlt_addr: &WalletAddress) -> Result<()> {
+        self.vout.push(TXOutput::new(value, to_wlt_addr)?);
+        self.id = self.unsigned_hash()?;
+        Ok(())
+    }
+
+    ///
+    /// This function adds inputs from the wallet `from_wlt_addr` worth at least `amount` to
+    /// an existing transaction and signs only those inputs with `sighash_type`. Inputs other
+    /// wallets have already added and signed are left untouched, so with an
+    /// `ANYONECANPAY` hash type each contributor can add funds in turn.
+    ///
+    /// No change output is added, since it would invalidate the signatures of earlier
+    /// contributors committing to all outputs: whatever the selected outputs hold beyond
+    /// `amount` is left to the miner as fee.
+    ///
+    /// # Arguments
+    ///
+    /// * `from_wlt_addr` - The address of the contributing wallet.
+    /// * `amount` - The amount the wallet contributes.
+    /// * `sighash_type` - The parts of the transaction the new signatures commit to.
+    /// * `utxo_set` - The UTXO set to select spendable outputs from.
     ///
     /// # Returns
     ///
-    /// A trimmed copy of the transaction.
-    fn trimmed_copy(&self) -> Transaction {
-        let mut inputs = vec![];
-        let mut outputs = vec![];
-        for input in &self.vin {
-            let txinput = TXInput::new(input.get_txid(), input.get_vout());
-            inputs.push(txinput);
+    /// The value of the added inputs.
+    pub async fn add_wallet_inputs(
+        &mut self,
+        from_wlt_addr: &WalletAddress,
+        amount: Amount,
+        sighash_type: SigHashType,
+        utxo_set: &UTXOSet,
+    ) -> Result<Amount> {
+        let wallets = WalletService::new()?;
+        let from_wallet = wallets
+            .get_wallet(from_wlt_addr)
+            .ok_or_else(|| BtcError::UTXONotFoundError(from_wlt_addr.as_string()))?;
+        let from_public_key_hash = hash_pub_key(from_wallet.get_public_key());
+
+        let (available_funds, inputs) = Self::select_inputs(
+            from_public_key_hash.as_slice(),
+            from_wallet.get_public_key(),
+            amount,
+            utxo_set,
+        )
+        .await?;
+        let first_new_input = self.vin.len();
+        self.vin.extend(inputs);
+        self.id = self.unsigned_hash()?;
+        let spent_outputs = utxo_set.get_spent_outputs(self).await?;
+        self.sign_inputs(
+            from_wallet.get_pkcs8(),
+            &spent_outputs,
+            first_new_input..self.vin.len(),
+            sighash_type,
+        )?;
+        Ok(available_funds)
+    }
+
+    ///
+    /// This function constructs an unsigned transaction spending funds held by a multisig
+    /// address, paying `tx_amount` to the recipient and the change back to the multisig
+    /// address.
+    ///
+    /// Every input starts with an empty signature slot for each key of `redeem_script`.
+    /// The signers then add their signatures with `sign_multisig`, one after another or on
+    /// copies that are merged with `combine_multisig_signatures`; once enough slots are
+    /// filled the transaction verifies like any other.
+    ///
+    /// # Arguments
+    ///
+    /// * `multisig_wlt_addr` - The multisig address holding the funds.
+    /// * `redeem_script` - The multisig script the address was derived from.
+    /// * `to_wlt_addr` - The address of the recipient.
+    /// * `tx_amount` - The amount paid to the recipient.
+    /// * `fee_amount` - The fee paid to the miner.
+    /// * `utxo_set` - The UTXO set to select spendable outputs from.
+    pub async fn new_multisig_transaction(
+        multisig_wlt_addr: &WalletAddress,
+        redeem_script: &Script,
+        to_wlt_addr: &WalletAddress,
+        tx_amount: Amount,
+        fee_amount: Amount,
+        utxo_set: &UTXOSet,
+    ) -> Result<Transaction> {
+        let (TxoutType::MultiSig, solutions) = solver(redeem_script) else {
+            return Err(BtcError::InvalidMultisig(format!(
+                "{} is not a multisig script",
+                redeem_script
+            )));
+        };
+        let script_hash = sha256_digest(redeem_script.as_bytes());
+        if get_script_hash(multisig_wlt_addr.as_str()).as_ref() != Some(&script_hash) {
+            return Err(BtcError::InvalidMultisig(format!(
+                "{} is not the address of the redeem script",
+                multisig_wlt_addr.as_str()
+            )));
+        }
+        if !tx_amount.is_money_range() || !fee_amount.is_money_range() {
+            return Err(BtcError::InvalidFee(fee_amount.to_string()));
+        }
+
+        let mut tx = Self::build_unsigned_transaction(
+            script_hash.as_slice(),
+            &[],
+            multisig_wlt_addr,
+            TXOutput::new(tx_amount, to_wlt_addr)?,
+            fee_amount,
+            utxo_set,
+        )
+        .await?;
+        let empty_slots = vec![vec![]; solutions.len() - 2];
+        for vin in tx.vin.iter_mut() {
+            vin.script_sig = multisig_script_sig(&empty_slots, redeem_script);
+        }
+        Ok(tx)
+    }
+
+    ///
+    /// Add the signature of one multisig signer to every input that spends the multisig
+    /// script of that signer.
+    ///
+    /// The inputs are recognized by their unlocking script, which carries the redeem
+    /// script, and the outputs they spend are handed over with the transaction (see
+    /// `UTXOSet::get_spent_outputs`), so no chain lookups are needed and each signer can sign
+    /// offline.
+    ///
+    /// # Arguments
+    ///
+    /// * `private_key` - The signer's private key; its public key must be one of the keys
+    ///   of the redeem script.
+    /// * `spent_outputs` - The output spent by each input, in input order.
+    ///
+    /// # Returns
+    ///
+    /// The number of inputs signed.
+    pub fn sign_multisig(
+        &mut self,
+        private_key: &[u8],
+        spent_outputs: &[TXOutput],
+    ) -> Result<usize> {
+        let pub_key = get_schnorr_public_key(private_key)?;
+        let mut signed = 0;
+        for idx in 0..self.vin.len() {
+            let Some((mut slots, redeem_script)) =
+                parse_multisig_script_sig(&self.vin[idx].script_sig)
+            else {
+                continue;
+            };
+            let (_, solutions) = solver(&redeem_script);
+            let Some(key_index) = solutions[1..solutions.len() - 1]
+                .iter()
+                .position(|key| *key == pub_key)
+            else {
+                continue;
+            };
+            let sighash =
+                self.signature_hash(idx, &redeem_script, spent_outputs, SigHashType::All)?;
+            let mut signature = schnorr_sign_digest(private_key, &sighash)?;
+            signature.push(SigHashType::All.to_u8());
+            slots[key_index] = signature;
+            self.vin[idx].script_sig = multisig_script_sig(&slots, &redeem_script);
+            signed += 1;
+        }
+        if signed == 0 {
+            return Err(BtcError::InvalidMultisig(
+                "the key does not belong to any multisig input".to_string(),
+            ));
+        }
+        Ok(signed)
+    }
+
+    ///
+    /// Merge the multisig signatures of `other`, a copy of this transaction signed by other
+    /// signers, into this transaction (like combining partially signed transactions in
+    /// Bitcoin Core).
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The same transaction carrying other signatures.
+    pub fn combine_multisig_signatures(&mut self, other: &Transaction) -> Result<()> {
+        if self.id != other.id || self.vin.len() != other.vin.len() {
+            return Err(BtcError::InvalidMultisig(format!(
+                "cannot combine signatures of {} with {}",
+                self.get_tx_id_hex(),
+                other.get_tx_id_hex()
+            )));
         }
-        for output in &self.vout {
-            outputs.push(output.clone());
-        }
-        Transaction {
-            id: self.id.clone(),
-            vin: inputs,
-            vout: outputs,
+        for (vin, other_vin) in self.vin.iter_mut().zip(&other.vin) {
+            let (Some((mut slots, redeem_script)), Some((other_slots, other_script))) = (
+                parse_multisig_script_sig(&vin.script_sig),
+                parse_multisig_script_sig(&other_vin.script_sig),
+            ) else {
+                continue;
+            };
+            if redeem_script != other_script {
+                continue;
+            }
+            for (slot, other_slot) in slots.iter_mut().zip(other_slots) {
+                if slot.is_empty() {
+                    *slot = other_slot;
+                }
+            }
+            vin.script_sig = multisig_script_sig(&slots, &redeem_script);
         }
+        Ok(())
     }
 
     ///
-    /// The `sign` function signs the transaction inputs using Schnorr signatures with secp256k1.
-    /// This is the signature scheme used by P2TR (Pay-to-Taproot) addresses.
-    /// It retrieves previous transactions, prepares a copy for signature verification,
-    /// signs inputs with the corresponding private keys, and updates the transaction with signatures.
+    /// The `sign_inputs` function signs the inputs in `inputs` using Schnorr signatures with
+    /// secp256k1. This is the signature scheme used by P2TR (Pay-to-Taproot) addresses.
+    /// It computes the signature hash of each input for `sighash_type`, which commits to the
+    /// amounts and locking scripts of `spent_outputs`, and appends the hash type byte to every
+    /// signature. Inputs outside `inputs` (for example those of other contributors) are left
+    /// untouched.
+    ///
+    /// The spent outputs are supplied by the caller (usually from the UTXO set), so signing
+    /// needs no access to the chain and can happen offline.
     ///
     /// # Arguments
     ///
-    /// * `blockchain` - A reference to the blockchain.
     /// * `private_key` - A reference to the private key.
-    ///
-    /// # Returns
-    ///
-    /// A signed transaction.
-    async fn sign(&mut self, blockchain: &BlockchainService, private_key: &[u8]) -> Result<()> {
-        let mut tx_copy = self.trimmed_copy();
-
-        for (idx, vin) in self.vin.iter_mut().enumerate() {
-            let prev_tx_option = blockchain.find_transaction(vin.get_txid()).await?;
-            let prev_tx = match prev_tx_option {
-                Some(tx) => tx,
-                None => {
-                    return Err(BtcError::TransactionNotFoundError(
-                        "(sign) Previous transaction is not correct".to_string(),
-                    ));
-                }
-            };
-
-            tx_copy.vin[idx].signature = vec![];
-            tx_copy.vin[idx].pub_key = prev_tx.vout[vin.vout].pub_key_hash.clone();
-            tx_copy.id = tx_copy.hash()?;
-            tx_copy.vin[idx].pub_key = vec![];
-
-            let signature = schnorr_sign_digest(private_key, tx_copy.get_id())?;
-            vin.signature = signature;
+    /// * `spent_outputs` - The output spent by each input, in input order.
+    /// * `inputs` - The indexes of the inputs to sign.
+    /// * `sighash_type` - The parts of the transaction the signatures commit to.
+    pub fn sign_inputs(
+        &mut self,
+        private_key: &[u8],
+        spent_outputs: &[TXOutput],
+        inputs: std::ops::Range<usize>,
+        sighash_type: SigHashType,
+    ) -> Result<()> {
+        for idx in inputs {
+            let spent_output = spent_outputs
+                .get(idx)
+                .ok_or(BtcError::InvalidTransactionInput)?;
+            let script_pubkey = spent_output.get_script_pubkey();
+
+            let sighash = self.signature_hash(idx, &script_pubkey, spent_outputs, sighash_type)?;
+            let mut signature = schnorr_sign_digest(private_key, &sighash)?;
+            signature.push(sighash_type.to_u8());
+            // Key-hash outputs are unlocked by the signature and public key fields alone
+            if spent_output.has_script_pubkey() {
+                self.vin[idx].script_sig =
+                    build_script_sig(&script_pubkey, &signature, &self.vin[idx].pub_key)?;
+            }
+            self.vin[idx].signature = signature;
         }
         Ok(())
     }
 
+    ///
+    /// The message signed by input `input_index`; see `script::sighash::signature_hash`.
+    /// With `SigHashType::All` the signature commits to every input and output; the other
+    /// hash types leave parts of the transaction open:
+    ///
+    /// * `None` - No output is committed to.
+    /// * `Single` - Only the output at `input_index` is committed to.
+    /// * `...PlusAnyoneCanPay` - Only the signed input is committed to.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_index` - The input being signed.
+    /// * `script_code` - The locking script of the output the input spends.
+    /// * `spent_outputs` - The output spent by each input, in input order.
+    /// * `sighash_type` - The parts of the transaction the signature commits to.
+    pub fn signature_hash(
+        &self,
+        input_index: usize,
+        script_code: &Script,
+        spent_outputs: &[TXOutput],
+        sighash_type: SigHashType,
+    ) -> Result<Vec<u8>> {
+        sighash::signature_hash(self, input_index, script_code, spent_outputs, sighash_type)
+    }
+
     ///
     /// This function verifies transaction signatures against corresponding public keys using Schnorr signatures.
-    /// It checks for Coinbase transactions, prepares a trimmed copy,
-    /// validates Schnorr signatures against public keys, and ensures the transaction is valid.
+    /// It checks for Coinbase transactions, looks up the outputs being spent and validates the
+    /// Schnorr signatures against them with `verify_signatures`.
+    ///
+    /// Unspent outputs are read straight from the `chainstate` tree; only outputs that have
+    /// already been spent (when verifying a confirmed transaction) fall back to searching the
+    /// chain for the transaction that created them.
     ///
     /// # Arguments
     ///
@@ -308,8 +1125,13 @@ impl Transaction {
         if self.is_coinbase() {
             return Ok(true);
         }
-        let mut trimmed_self_copy = self.trimmed_copy();
-        for (idx, vin) in self.vin.iter().enumerate() {
+        let utxo_set = UTXOSet::new(blockchain.clone());
+        let mut spent_outputs = vec![];
+        for vin in self.vin.iter() {
+            if let Some(spent_output) = utxo_set.get_unspent_output(vin).await? {
+                spent_outputs.push(spent_output);
+                continue;
+            }
             let current_vin_tx_option = blockchain.find_transaction(vin.get_txid()).await?;
             let current_vin_tx = match current_vin_tx_option {
                 Some(tx) => tx,
@@ -319,51 +1141,169 @@ impl Transaction {
                     ));
                 }
             };
+            let spent_output = current_vin_tx
+                .vout
+                .get(vin.vout)
+                .ok_or(BtcError::InvalidTransactionInput)?;
+            spent_outputs.push(spent_output.clone());
+        }
+        self.verify_signatures(&spent_outputs)
+    }
 
-            trimmed_self_copy.vin[idx].signature = vec![];
-            trimmed_self_copy.vin[idx].pub_key = current_vin_tx.vout[vin.vout].pub_key_hash.clone();
-            trimmed_self_copy.id = trimmed_self_copy.hash()?;
-            trimmed_self_copy.vin[idx].pub_key = vec![];
-
-            let verify = schnorr_sign_verify(
-                vin.get_pub_key(),
-                vin.get_signature(),
-                trimmed_self_copy.get_id(),
-            );
-            if !verify {
+    ///
+    /// Verify every input against the output it spends by running the input's unlocking
+    /// script and the output's locking script through the script interpreter.
+    ///
+    /// Key-hash outputs are checked as pay-to-pubkey-hash: the input's public key must hash
+    /// to the output's `pub_key_hash`, and its Schnorr signature must be valid for the
+    /// signature hash that commits to that output. Callers resolve `spent_outputs`
+    /// themselves (from the chain, the UTXO set or earlier transactions in the same block),
+    /// so no chain lookups happen here.
+    ///
+    /// # Arguments
+    ///
+    /// * `spent_outputs` - The output spent by each input, in input order.
+    ///
+    /// # Returns
+    ///
+    /// `true` if every input satisfies the locking script of the output it spends.
+    pub fn verify_signatures(&self, spent_outputs: &[TXOutput]) -> Result<bool> {
+        if self.is_coinbase() {
+            return Ok(true);
+        }
+        if spent_outputs.len() != self.vin.len() {
+            return Ok(false);
+        }
+        for (idx, (vin, spent_output)) in self.vin.iter().zip(spent_outputs).enumerate() {
+            let checker = TransactionSignatureChecker::new(self, idx, spent_outputs);
+            if let Err(e) = verify_script(
+                &vin.get_script_sig(),
+                &spent_output.get_script_pubkey(),
+                &checker,
+            ) {
+                debug!(
+                    "Script verification failed for input {} of {}: {}",
+                    idx,
+                    self.get_tx_id_hex(),
+                    e
+                );
                 return Ok(false);
             }
         }
         Ok(true)
     }
 
+    /// A coinbase has a single input that spends no previous output (Bitcoin Core's
+    /// `IsCoinBase`: a null prevout). Multisig inputs carry no public key, so the
+    /// spent transaction id is what tells them apart.
     pub fn is_coinbase(&self) -> bool {
         self.vin.len() == 1
             && self
                 .vin
                 .first()
                 .iter()
-                .any(|tx_in| tx_in.get_pub_key().is_empty())
+                .any(|tx_in| tx_in.get_txid().is_empty() && tx_in.get_pub_key().is_empty())
     }
 
     pub fn not_coinbase(&self) -> bool {
         !self.is_coinbase()
     }
 
+    /// Move a coinbase created by `new_coinbase_tx_with_extra_nonce` to the next extra nonce,
+    /// keeping its height and miner tag, and recompute its id (Bitcoin Core's
+    /// `IncrementExtraNonce`).
     ///
-    /// The `hash` function generates the transaction's hash by creating a copy without the ID,
-    /// serializing it, and computing its SHA-256 digest
+    /// Miners do this once every header nonce has failed: the new id changes the Merkle
+    /// root, which gives the header a fresh nonce space.
     ///
     /// # Returns
     ///
-    /// The transaction's hash.
-    fn hash(&mut self) -> Result<Vec<u8>> {
-        let tx_copy = Transaction {
-            id: vec![],
-            vin: self.vin.clone(),
-            vout: self.vout.clone(),
+    /// `InvalidCoinbaseScript` if the transaction is not such a coinbase or its unlocking
+    /// script would grow past `MAX_COINBASE_SCRIPT_SIG_SIZE` bytes.
+    pub fn increment_extra_nonce(&mut self) -> Result<()> {
+        if !self.is_coinbase() {
+            return Err(BtcError::InvalidCoinbaseScript(
+                "not a coinbase".to_string(),
+            ));
+        }
+        let pushes = self.vin[0]
+            .script_sig
+            .instructions()
+            .map(|instruction| match instruction {
+                Ok(Instruction::Push(data)) => Some(data),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>();
+        let (height, extra_nonce, miner_tag) = match pushes.as_deref() {
+            Some([height, extra_nonce]) => (*height, *extra_nonce, &[][..]),
+            Some([height, extra_nonce, miner_tag]) => (*height, *extra_nonce, *miner_tag),
+            _ => {
+                return Err(BtcError::InvalidCoinbaseScript(
+                    "expected <height> <extra_nonce> [<miner_tag>]".to_string(),
+                ));
+            }
         };
-        Ok(sha256_digest(tx_copy.serialize()?.as_slice()))
+        let height = decode_script_num(height);
+        let extra_nonce = decode_script_num(extra_nonce).and_then(|n| n.checked_add(1));
+        let (Some(height), Some(extra_nonce)) = (height, extra_nonce) else {
+            return Err(BtcError::InvalidCoinbaseScript(
+                "invalid height or extra nonce".to_string(),
+            ));
+        };
+        self.vin[0].script_sig = coinbase_script_sig(height as usize, extra_nonce, miner_tag)?;
+        self.id = self.hash()?;
+        Ok(())
+    }
+
+    /// Whether the coinbase's unlocking script starts with a push of `height` (BIP34).
+    pub fn commits_to_height(&self, height: usize) -> bool {
+        let expected = coinbase_height_script(height);
+        self.is_coinbase()
+            && self.vin[0]
+                .script_sig
+                .as_bytes()
+                .starts_with(expected.as_bytes())
+    }
+
+    /// Whether this is the genesis coinbase of the network described by `params`: its
+    /// unlocking script is the one `new_genesis_coinbase_tx` writes.
+    pub fn is_genesis_coinbase(&self, params: &ChainParams) -> bool {
+        self.is_coinbase()
+            && coinbase_script_sig(1, 0, params.get_genesis_message())
+                .is_ok_and(|expected| self.vin[0].script_sig == expected)
+    }
+
+    ///
+    /// The `hash` function generates the transaction's hash: the SHA-256 digest of its
+    /// consensus encoding, which does not include the ID
+    ///
+    /// # Returns
+    ///
+    /// The transaction's hash.
+    fn hash(&self) -> Result<Vec<u8>> {
+        Ok(sha256_digest(self.serialize()?.as_slice()))
+    }
+
+    /// Check that the stored id is the hash of the transaction's contents.
+    ///
+    /// Transactions received from peers carry their id, so it must be recomputed
+    /// before it is trusted (for example as a Merkle tree leaf). A regular transaction's id
+    /// is computed before it is signed, so signatures are cleared first (like a segwit txid);
+    /// a coinbase id covers the height and extra nonce in its input.
+    pub fn has_valid_id(&self) -> Result<bool> {
+        Ok(self.unsigned_hash()? == self.id)
+    }
+
+    /// The hash of the transaction with signatures cleared, which is its id.
+    fn unsigned_hash(&self) -> Result<Vec<u8>> {
+        let mut unsigned = self.clone();
+        if unsigned.not_coinbase() {
+            for vin in unsigned.vin.iter_mut() {
+                vin.signature = vec![];
+                vin.script_sig = Script::new();
+            }
+        }
+        unsigned.hash()
     }
 
     // get the transaction id as a bytes vector
@@ -392,18 +1332,246 @@ impl Transaction {
         self.vout.as_slice()
     }
 
+    pub fn get_lock_time(&self) -> u32 {
+        self.lock_time
+    }
+
+    pub fn get_version(&self) -> u32 {
+        self.version
+    }
+
+    /// The consensus encoding of the transaction (see `Encodable for Transaction`).
     pub fn serialize(&self) -> Result<Vec<u8>> {
-        bincode::serde::encode_to_vec(self, bincode::config::standard())
-            .map_err(|e| BtcError::TransactionSerializationError(e.to_string()))
+        Ok(serialize::serialize(self))
+    }
+
+    /// Total value of the transaction's outputs.
+    ///
+    /// # Returns
+    ///
+    /// The sum, or `AmountOverflow` if it does not fit in an `Amount`.
+    pub fn get_output_value(&self) -> Result<Amount> {
+        Amount::checked_sum(self.vout.iter().map(|out| out.get_value()))
+            .ok_or(BtcError::AmountOverflow)
+    }
+
+    /// Size of the serialized transaction in bytes, used for per-byte fees.
+    pub fn get_size(&self) -> Result<usize> {
+        Ok(self.serialize()?.len())
     }
 
+    /// Decode a transaction from its consensus encoding; its id is computed from the
+    /// decoded contents.
     pub fn deserialize(bytes: &[u8]) -> Result<Transaction> {
-        bincode::serde::decode_from_slice(bytes, bincode::config::standard())
+        serialize::deserialize(bytes)
             .map_err(|e| BtcError::TransactionDeserializationError(e.to_string()))
-            .map(|(transaction, _)| transaction)
     }
 }
 
+#[cfg(test)]
+impl TXOutput {
+    /// A data carrier output without the `MAX_DATA_CARRIER_SIZE` limit of
+    /// `new_data_carrier`, for testing the policies that enforce it.
+    pub(crate) fn new_test_data_carrier(data: &[u8]) -> TXOutput {
+        TXOutput {
+            value: Amount::ZERO,
+            pub_key_hash: vec![],
+            script_pubkey: Script::new().push_opcode(OP_RETURN).push_slice(data),
+        }
+    }
+}
+
+/// Fixtures shared by the unit tests of every module.
+#[cfg(test)]
+impl Transaction {
+    /// A coinbase for the block at `height` with a fresh extra nonce, so that blocks mined
+    /// at the same height for the same address (as competing nodes would) pay distinct
+    /// coinbase transactions
+    pub(crate) fn new_test_coinbase(to: &WalletAddress, height: usize) -> Result<Transaction> {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        static EXTRA_NONCE: AtomicU64 = AtomicU64::new(1);
+        let extra_nonce = EXTRA_NONCE.fetch_add(1, Ordering::Relaxed);
+        Self::new_coinbase_tx_with_extra_nonce(to, height, Amount::ZERO, extra_nonce, &[])
+    }
+
+    /// An unsigned transaction with no inputs or outputs; see `TestTransactionBuilder`.
+    pub(crate) fn test_builder() -> TestTransactionBuilder {
+        TestTransactionBuilder {
+            tx: Transaction {
+                version: TX_VERSION,
+                ..Transaction::default()
+            },
+            id: None,
+        }
+    }
+}
+
+/// Builds the unsigned transactions of unit tests that only look at the outpoints a
+/// transaction spends, the outputs it creates and its lock time.
+#[cfg(test)]
+pub(crate) struct TestTransactionBuilder {
+    tx: Transaction,
+    id: Option<Vec<u8>>,
+}
+
+#[cfg(test)]
+impl TestTransactionBuilder {
+    /// Spend output `vout` of the transaction whose id is 32 bytes of `txid`.
+    pub(crate) fn with_input(self, txid: u8, vout: usize, sequence: u32) -> Self {
+        self.spending(&[txid; 32], vout, sequence)
+    }
+
+    /// Spend output `vout` of the transaction `txid`.
+    pub(crate) fn spending(mut self, txid: &[u8], vout: usize, sequence: u32) -> Self {
+        let mut input = TXInput::new(txid, vout);
+        input.sequence = sequence;
+        self.tx.vin.push(input);
+        self
+    }
+
+    pub(crate) fn with_output(mut self, output: TXOutput) -> Self {
+        self.tx.vout.push(output);
+        self
+    }
+
+    pub(crate) fn with_lock_time(mut self, lock_time: u32) -> Self {
+        self.tx.lock_time = lock_time;
+        self
+    }
+
+    /// Give the transaction an id of 32 bytes of `id` instead of its hash, so tests can
+    /// refer to it, and spend it, by a fixed id.
+    pub(crate) fn with_id(self, id: u8) -> Self {
+        TestTransactionBuilder {
+            id: Some(vec![id; 32]),
+            ..self
+        }
+    }
+
+    pub(crate) fn build(self) -> Transaction {
+        let mut tx = self.tx;
+        tx.id = match self.id {
+            Some(id) => id,
+            None => tx.hash().expect("Failed to hash test transaction"),
+        };
+        tx
+    }
+}
+
+/// Input encoding:
+///
+/// | Field      | Encoding                                   |
+/// |------------|--------------------------------------------|
+/// | txid       | var bytes (32 bytes, empty for a coinbase) |
+/// | vout       | `u32` little-endian                        |
+/// | script_sig | var bytes                                  |
+/// | signature  | var bytes                                  |
+/// | pub_key    | var bytes                                  |
+/// | sequence   | `u32` little-endian                        |
+impl Encodable for TXInput {
+    fn consensus_encode(&self, buf: &mut Vec<u8>) {
+        write_var_bytes(buf, &self.txid);
+        buf.extend_from_slice(&(self.vout as u32).to_le_bytes());
+        write_var_bytes(buf, self.script_sig.as_bytes());
+        write_var_bytes(buf, &self.signature);
+        write_var_bytes(buf, &self.pub_key);
+        buf.extend_from_slice(&self.sequence.to_le_bytes());
+    }
+}
+
+impl Decodable for TXInput {
+    fn consensus_decode(reader: &mut Reader<'_>) -> Result<TXInput> {
+        Ok(TXInput {
+            txid: reader.read_var_bytes()?,
+            vout: reader.read_u32()? as usize,
+            script_sig: Script::from_bytes(reader.read_var_bytes()?),
+            signature: reader.read_var_bytes()?,
+            pub_key: reader.read_var_bytes()?,
+            sequence: reader.read_u32()?,
+        })
+    }
+}
+
+/// Output encoding; every field of the output is encoded:
+///
+/// | Field         | Encoding                                   |
+/// |---------------|--------------------------------------------|
+/// | value         | `u64` little-endian, in satoshis           |
+/// | pub_key_hash  | var bytes (empty for data carrier outputs) |
+/// | script_pubkey | var bytes (empty for key-hash outputs)     |
+impl Encodable for TXOutput {
+    fn consensus_encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.value.to_sat().to_le_bytes());
+        write_var_bytes(buf, &self.pub_key_hash);
+        write_var_bytes(buf, self.script_pubkey.as_bytes());
+    }
+}
+
+impl Decodable for TXOutput {
+    fn consensus_decode(reader: &mut Reader<'_>) -> Result<TXOutput> {
+        Ok(TXOutput {
+            value: Amount::from_sat(reader.read_u64()?),
+            pub_key_hash: reader.read_var_bytes()?,
+            script_pubkey: Script::from_bytes(reader.read_var_bytes()?),
+        })
+    }
+}
+
+/// Transaction encoding (Bitcoin Core's `SerializeTransaction` without witness data). The id
+/// is not encoded: it is the SHA-256 of this encoding with every input's `script_sig` and
+/// `signature` emptied (the coinbase keeps its `script_sig`).
+///
+/// | Field     | Encoding                        |
+/// |-----------|---------------------------------|
+/// | version   | `u32` little-endian             |
+/// | vin       | CompactSize count, then inputs  |
+/// | vout      | CompactSize count, then outputs |
+/// | lock_time | `u32` little-endian             |
+impl Encodable for Transaction {
+    fn consensus_encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.version.to_le_bytes());
+        write_vec(buf, &self.vin);
+        write_vec(buf, &self.vout);
+        buf.extend_from_slice(&self.lock_time.to_le_bytes());
+    }
+}
+
+impl Decodable for Transaction {
+    fn consensus_decode(reader: &mut Reader<'_>) -> Result<Transaction> {
+        let mut tx = Transaction {
+            version: reader.read_u32()?,
+            id: vec![],
+            vin: reader.read_vec()?,
+            vout: reader.read_vec()?,
+            lock_time: reader.read_u32()?,
+        };
+        tx.id = tx.unsigned_hash()?;
+        Ok(tx)
+    }
+}
+
+/// The push of the block height that starts every coinbase unlocking script
+/// (Bitcoin Core's `CScript() << nHeight`).
+fn coinbase_height_script(height: usize) -> Script {
+    Script::new().push_int(height as u64)
+}
+
+/// The unlocking script of a coinbase: `<height> <extra_nonce> [<miner_tag>]`.
+fn coinbase_script_sig(height: usize, extra_nonce: u64, miner_tag: &[u8]) -> Result<Script> {
+    let mut script_sig = coinbase_height_script(height).push_int(extra_nonce);
+    if !miner_tag.is_empty() {
+        script_sig = script_sig.push_slice(miner_tag);
+    }
+    if script_sig.len() > MAX_COINBASE_SCRIPT_SIG_SIZE {
+        return Err(BtcError::InvalidCoinbaseScript(format!(
+            "{} bytes exceeds {}",
+            script_sig.len(),
+            MAX_COINBASE_SCRIPT_SIG_SIZE
+        )));
+    }
+    Ok(script_sig)
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct TxInputSummary {
     txid_hex: String,
@@ -430,19 +1598,39 @@ impl TxInputSummary {
 }
 #[derive(Clone, Serialize, Deserialize)]
 pub struct TxOutputSummary {
-    wlt_addr: WalletAddress,
-    value: i32,
+    // Recipient; data carrier outputs pay nobody
+    wlt_addr: Option<WalletAddress>,
+    value: Amount,
+    // Payload of a data carrier output
+    #[serde(default)]
+    data: Option<Vec<u8>>,
 }
 impl TxOutputSummary {
-    pub fn new(wlt_addr: WalletAddress, value: i32) -> TxOutputSummary {
-        TxOutputSummary { wlt_addr, value }
+    pub fn new(wlt_addr: WalletAddress, value: Amount) -> TxOutputSummary {
+        TxOutputSummary {
+            wlt_addr: Some(wlt_addr),
+            value,
+            data: None,
+        }
     }
-    pub fn get_wlt_addr(&self) -> &WalletAddress {
-        &self.wlt_addr
+    /// Summary of a data carrier output with payload `data`, which has no recipient.
+    pub fn new_data_carrier(value: Amount, data: Vec<u8>) -> TxOutputSummary {
+        TxOutputSummary {
+            wlt_addr: None,
+            value,
+            data: Some(data),
+        }
     }
-    pub fn get_value(&self) -> i32 {
+    /// The recipient, `None` for a data carrier output.
+    pub fn get_wlt_addr(&self) -> Option<&WalletAddress> {
+        self.wlt_addr.as_ref()
+    }
+    pub fn get_value(&self) -> Amount {
         self.value
     }
+    pub fn get_data(&self) -> Option<&[u8]> {
+        self.data.as_deref()
+    }
 }
 #[derive(Clone, Serialize, Deserialize)]
 pub struct TxSummary {
@@ -495,29 +1683,32 @@ pub struct WalletTransaction {
     tx_id: Vec<u8>,
     from_wlt_addr: Option<WalletAddress>,
     to_wlt_addr: WalletAddress,
-    value: i32,
+    value: Amount,
     transaction_type: WalletTransactionType,
     status: WalletTransactionStatus,
     vout: usize,
     is_coinbase: bool,
     input_count: usize,
     output_count: usize,
-    total_output_value: i32,
-    fee: i32,
+    total_output_value: Amount,
+    fee: Amount,
     timestamp: i64,
     size_bytes: usize,
 }
 impl WalletTransaction {
+    /// The wallet's view of output `vout_index` of `tx`, a transaction in a block. Its
+    /// status is `Pending` while a mempool transaction spends the output, `Confirmed`
+    /// otherwise.
     pub fn new(
         tx: Transaction,
         tx_output: &TXOutput,
         from_wlt_addr: Option<WalletAddress>,
         transaction_type: WalletTransactionType,
         vout_index: usize,
-        fee: i32,
+        fee: Amount,
         timestamp: i64,
     ) -> Result<WalletTransaction> {
-        let status = if tx_output.is_in_global_mem_pool() {
+        let status = if GLOBAL_MEMORY_POOL.is_spent(tx.get_id(), vout_index)? {
             WalletTransactionStatus::Pending
         } else {
             WalletTransactionStatus::Confirmed
@@ -533,7 +1724,7 @@ impl WalletTransaction {
             is_coinbase: tx.is_coinbase(),
             input_count: tx.get_vin().len(),
             output_count: tx.get_vout().len(),
-            total_output_value: tx.get_vout().iter().map(|v| v.get_value()).sum(),
+            total_output_value: tx.get_output_value()?,
             fee,
             timestamp,
             size_bytes: tx.serialize().unwrap_or_default().len(),
@@ -548,7 +1739,7 @@ impl WalletTransaction {
     pub fn get_to_wlt_addr(&self) -> &WalletAddress {
         &self.to_wlt_addr
     }
-    pub fn get_value(&self) -> i32 {
+    pub fn get_value(&self) -> Amount {
         self.value
     }
     pub fn get_transaction_type(&self) -> &WalletTransactionType {
@@ -569,10 +1760,10 @@ impl WalletTransaction {
     pub fn get_output_count(&self) -> usize {
         self.output_count
     }
-    pub fn get_total_output_value(&self) -> i32 {
+    pub fn get_total_output_value(&self) -> Amount {
         self.total_output_value
     }
-    pub fn get_fee(&self) -> i32 {
+    pub fn get_fee(&self) -> Amount {
         self.fee
     }
     pub fn get_timestamp(&self) -> i64 {
@@ -589,6 +1780,8 @@ impl WalletTransaction {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::consensus::INITIAL_SUBSIDY;
+    use crate::script::interpreter::SCHNORR_SIGNATURE_SIZE;
 
     fn generate_test_genesis_address() -> crate::WalletAddress {
         // Create a wallet to get a valid Bitcoin address
@@ -607,7 +1800,82 @@ mod tests {
         assert_eq!(tx.get_vin().len(), 1);
 
         let output = &tx.get_vout()[0];
-        assert_eq!(output.get_value(), 10); // Default coinbase reward
+        assert_eq!(output.get_value(), INITIAL_SUBSIDY); // Default coinbase reward
+    }
+
+    #[test]
+    fn test_coinbase_transaction_for_height_pays_halved_subsidy_and_fees() {
+        let address = generate_test_genesis_address();
+        let interval = GLOBAL_CONFIG.get_network().get_subsidy_halving_interval();
+        let fees = Amount::from_sat(3);
+
+        let tx = Transaction::new_coinbase_tx_for_height(&address, interval + 1, fees)
+            .expect("Failed to create coinbase transaction");
+
+        let expected = Amount::from_sat(INITIAL_SUBSIDY.to_sat() / 2)
+            .checked_add(fees)
+            .unwrap();
+        assert_eq!(tx.get_output_value().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_coinbase_commits_to_height_deterministically() {
+        let address = generate_test_genesis_address();
+        let tx = Transaction::new_coinbase_tx_for_height(&address, 500, Amount::ZERO).unwrap();
+        let again = Transaction::new_coinbase_tx_for_height(&address, 500, Amount::ZERO).unwrap();
+        assert_eq!(tx.get_id(), again.get_id());
+        assert!(tx.commits_to_height(500));
+        assert!(!tx.commits_to_height(501));
+        assert!(tx.has_valid_id().unwrap());
+
+        // The extra nonce and miner tag follow the height and change the id
+        let tagged =
+            Transaction::new_coinbase_tx_with_extra_nonce(&address, 500, Amount::ZERO, 7, b"pool")
+                .unwrap();
+        assert!(tagged.commits_to_height(500));
+        assert_ne!(tagged.get_id(), tx.get_id());
+        assert_eq!(
+            tagged.get_vin()[0].get_script_sig(),
+            Script::new().push_int(500).push_int(7).push_slice(b"pool")
+        );
+
+        assert!(matches!(
+            Transaction::new_coinbase_tx_with_extra_nonce(&address, 500, Amount::ZERO, 0, &[0; 99]),
+            Err(BtcError::InvalidCoinbaseScript(_))
+        ));
+    }
+
+    #[test]
+    fn test_increment_extra_nonce_keeps_height_and_tag() {
+        let address = generate_test_genesis_address();
+        let mut tx = Transaction::new_coinbase_tx_with_extra_nonce(
+            &address,
+            500,
+            Amount::ZERO,
+            255,
+            b"pool",
+        )
+        .unwrap();
+        let id = tx.get_id().to_vec();
+        tx.increment_extra_nonce().unwrap();
+        assert_eq!(
+            tx.get_vin()[0].get_script_sig(),
+            Script::new()
+                .push_int(500)
+                .push_int(256)
+                .push_slice(b"pool")
+        );
+        assert_ne!(tx.get_id(), id.as_slice());
+        assert!(tx.commits_to_height(500));
+        assert!(tx.has_valid_id().unwrap());
+
+        let mut untagged =
+            Transaction::new_coinbase_tx_for_height(&address, 7, Amount::ZERO).unwrap();
+        untagged.increment_extra_nonce().unwrap();
+        assert_eq!(
+            untagged.get_vin()[0].get_script_sig(),
+            Script::new().push_int(7).push_int(1)
+        );
     }
 
     #[test]
@@ -624,6 +1892,83 @@ mod tests {
         assert_eq!(tx.get_vout().len(), deserialized.get_vout().len());
     }
 
+    /// Consensus encoding of `golden_transaction()`, for other implementations to check
+    /// against.
+    const GOLDEN_TRANSACTION_HEX: &str = concat!(
+        "02000000",                                                         // version
+        "01",                                                               // vin count
+        "20",                                                               // txid length
+        "1111111111111111111111111111111111111111111111111111111111111111", // txid
+        "01000000",                                                         // vout
+        "00",                                                               // script_sig
+        "02aabb",                                                           // signature
+        "020203",                                                           // pub_key
+        "feffffff",                                                         // sequence
+        "01",                                                               // vout count
+        "8813000000000000",                                                 // value
+        "04cccccccc",                                                       // pub_key_hash
+        "00",                                                               // script_pubkey
+        "64000000",                                                         // lock_time
+    );
+
+    /// SHA-256 of the encoding above with the signature emptied.
+    const GOLDEN_TRANSACTION_ID_HEX: &str =
+        "2dc93d40d9f0d314b0fb7931695b4f13ff5eb70b6c50868fc1bde513d1d6212c";
+
+    fn golden_transaction() -> Transaction {
+        let mut tx = Transaction {
+            version: TX_VERSION,
+            id: vec![],
+            vin: vec![TXInput {
+                txid: vec![0x11; 32],
+                vout: 1,
+                signature: vec![0xaa, 0xbb],
+                pub_key: vec![0x02, 0x03],
+                script_sig: Script::new(),
+                sequence: MAX_SEQUENCE_NONFINAL,
+            }],
+            vout: vec![TXOutput {
+                value: Amount::from_sat(5000),
+                pub_key_hash: vec![0xcc; 4],
+                script_pubkey: Script::new(),
+            }],
+            lock_time: 100,
+        };
+        tx.id = tx.unsigned_hash().unwrap();
+        tx
+    }
+
+    #[test]
+    fn test_transaction_consensus_encoding_golden_vector() {
+        let tx = golden_transaction();
+        let encoded = tx.serialize().unwrap();
+        assert_eq!(HEXLOWER.encode(&encoded), GOLDEN_TRANSACTION_HEX);
+        assert_eq!(tx.get_tx_id_hex(), GOLDEN_TRANSACTION_ID_HEX);
+
+        // Decoding recomputes the id
+        let decoded = Transaction::deserialize(&encoded).unwrap();
+        assert_eq!(decoded.get_tx_id_hex(), GOLDEN_TRANSACTION_ID_HEX);
+        assert_eq!(decoded.get_version(), TX_VERSION);
+        assert_eq!(decoded.get_vin()[0].get_signature(), &[0xaa, 0xbb]);
+        assert_eq!(decoded.get_vin()[0].get_sequence(), MAX_SEQUENCE_NONFINAL);
+        assert_eq!(decoded.serialize().unwrap(), encoded);
+    }
+
+    #[test]
+    fn test_transaction_decoding_rejects_malformed_bytes() {
+        let encoded = golden_transaction().serialize().unwrap();
+        // Truncated
+        assert!(Transaction::deserialize(&encoded[..encoded.len() - 1]).is_err());
+        // Trailing data
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        assert!(Transaction::deserialize(&trailing).is_err());
+        // An input count larger than the data
+        let mut too_many_inputs = encoded;
+        too_many_inputs[4] = 0xfc;
+        assert!(Transaction::deserialize(&too_many_inputs).is_err());
+    }
+
     #[test]
     fn test_transaction_id() {
         let genesis_address = generate_test_genesis_address();
@@ -664,7 +2009,7 @@ mod tests {
             .expect("Failed to create coinbase transaction");
         let output = &tx.get_vout()[0];
 
-        assert_eq!(output.get_value(), 10);
+        assert_eq!(output.get_value(), Amount::from_sat(10));
         assert!(!output.get_pub_key_hash().is_empty());
     }
 
@@ -682,7 +2027,7 @@ mod tests {
 
     #[test]
     fn test_transaction_output_creation() {
-        let value = 100;
+        let value = Amount::from_sat(100);
         let address = generate_test_genesis_address();
 
         let tx_output = TXOutput::new(value, &address.clone()).expect("Failed to create output");
@@ -693,7 +2038,7 @@ mod tests {
 
     #[test]
     fn test_transaction_output_lock_unlock() {
-        let value = 100;
+        let value = Amount::from_sat(100);
         let address = generate_test_genesis_address();
         let tx_output = TXOutput::new(value, &address.clone()).expect("Failed to create output");
 
@@ -706,6 +2051,342 @@ mod tests {
         assert!(!tx_output.is_locked_with_key(&wrong_pub_key_hash));
     }
 
+    #[test]
+    fn test_data_carrier_output() {
+        let output = TXOutput::new_data_carrier(b"document hash").unwrap();
+        assert_eq!(output.get_value(), Amount::ZERO);
+        assert!(output.is_unspendable());
+        assert!(!output.is_null());
+        assert_eq!(
+            output.get_data_carrier_payload(),
+            Some(b"document hash".to_vec())
+        );
+        let decoded: TXOutput = serialize::deserialize(&serialize::serialize(&output)).unwrap();
+        assert_eq!(
+            decoded.get_data_carrier_payload(),
+            Some(b"document hash".to_vec())
+        );
+
+        let payment = TXOutput::new(Amount::from_sat(1), &generate_test_genesis_address()).unwrap();
+        assert!(!payment.is_unspendable());
+        assert_eq!(payment.get_data_carrier_payload(), None);
+
+        assert!(TXOutput::new_data_carrier(&[7; MAX_DATA_CARRIER_SIZE]).is_ok());
+        assert!(matches!(
+            TXOutput::new_data_carrier(&[7; MAX_DATA_CARRIER_SIZE + 1]),
+            Err(BtcError::DataCarrierTooLarge(81, 80))
+        ));
+
+        // No unlocking script gets past OP_RETURN
+        let private_key = crate::new_schnorr_key_pair().unwrap();
+        let pub_key = get_schnorr_public_key(&private_key).unwrap();
+        let mut tx = spending_transaction(&[1; 32], &pub_key);
+        tx.vin[0].script_sig = Script::new().push_slice(&[7; 64]).push_slice(&pub_key);
+        assert!(!matches!(tx.verify_signatures(&[output]), Ok(true)));
+    }
+
+    /// A key and a transaction spending output 0 of `prev_tx_id`, unsigned.
+    fn spending_transaction(prev_tx_id: &[u8], pub_key: &[u8]) -> Transaction {
+        let mut input = TXInput::new(prev_tx_id, 0);
+        input.pub_key = pub_key.to_vec();
+        let mut tx = Transaction {
+            version: TX_VERSION,
+            id: vec![],
+            vin: vec![input],
+            vout: vec![
+                TXOutput::new(Amount::from_sat(4), &generate_test_genesis_address())
+                    .expect("Failed to create output"),
+            ],
+            lock_time: 0,
+        };
+        tx.id = tx.hash().expect("Failed to hash transaction");
+        tx
+    }
+
+    /// Sign input 0 of `tx`, its only input, against `spent_output`.
+    fn sign_input(tx: &mut Transaction, spent_output: &TXOutput, private_key: &[u8]) {
+        tx.sign_inputs(
+            private_key,
+            std::slice::from_ref(spent_output),
+            0..1,
+            SigHashType::All,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_key_hash_output_verifies_as_p2pkh() {
+        let private_key = crate::new_schnorr_key_pair().unwrap();
+        let pub_key = crate::get_schnorr_public_key(&private_key).unwrap();
+        let address = convert_address(&hash_pub_key(&pub_key)).unwrap();
+        let spent_output = TXOutput::new(Amount::from_sat(5), &address).unwrap();
+        assert_eq!(
+            spent_output.get_script_pubkey(),
+            p2pkh_script(spent_output.get_pub_key_hash())
+        );
+
+        let mut tx = spending_transaction(&[1; 32], &pub_key);
+        sign_input(&mut tx, &spent_output, &private_key);
+        assert!(tx.vin[0].script_sig.is_empty());
+        assert!(
+            tx.verify_signatures(std::slice::from_ref(&spent_output))
+                .unwrap()
+        );
+        assert!(tx.has_valid_id().unwrap());
+
+        // Someone else's output
+        let other_output =
+            TXOutput::new(Amount::from_sat(5), &generate_test_genesis_address()).unwrap();
+        assert!(!tx.verify_signatures(&[other_output]).unwrap());
+
+        // A tampered transaction no longer matches the signature hash
+        tx.vout[0].value = Amount::from_sat(5);
+        assert!(!tx.verify_signatures(&[spent_output]).unwrap());
+    }
+
+    #[test]
+    fn test_p2tr_output_spent_with_key_path_signature() {
+        let private_key = crate::new_schnorr_key_pair().unwrap();
+        let pub_key = crate::get_schnorr_public_key(&private_key).unwrap();
+        let spent_output = TXOutput::new_p2tr(Amount::from_sat(5), &pub_key).unwrap();
+        assert!(spent_output.is_locked_with_key(&hash_pub_key(&pub_key)));
+        assert_eq!(
+            crate::script::solver(&spent_output.get_script_pubkey()).0,
+            crate::script::TxoutType::WitnessV1Taproot
+        );
+
+        let mut tx = spending_transaction(&[1; 32], &pub_key);
+        sign_input(&mut tx, &spent_output, &private_key);
+        assert_eq!(
+            tx.vin[0].get_script_sig(),
+            Script::new().push_slice(tx.vin[0].get_signature())
+        );
+        assert!(
+            tx.verify_signatures(std::slice::from_ref(&spent_output))
+                .unwrap()
+        );
+        assert!(tx.has_valid_id().unwrap());
+
+        // Signed by another key
+        let other_key = crate::new_schnorr_key_pair().unwrap();
+        let mut forged = spending_transaction(&[1; 32], &pub_key);
+        sign_input(&mut forged, &spent_output, &other_key);
+        assert!(!forged.verify_signatures(&[spent_output]).unwrap());
+    }
+
+    #[test]
+    fn test_lock_time_constructors() {
+        let none = TxLockTime::default();
+        assert_eq!(none.get_lock_time(), 0);
+        assert_eq!(none.get_sequence(), SEQUENCE_FINAL);
+
+        // Absolute locks need a non-final sequence to take effect
+        let by_height = TxLockTime::at_height(120).unwrap();
+        assert_eq!(by_height.get_lock_time(), 120);
+        assert_eq!(by_height.get_sequence(), MAX_SEQUENCE_NONFINAL);
+        assert!(TxLockTime::at_height(LOCKTIME_THRESHOLD).is_err());
+        assert!(TxLockTime::at_time(LOCKTIME_THRESHOLD).is_ok());
+        assert!(TxLockTime::at_time(120).is_err());
+
+        // Signalling replaceability sets no relative lock
+        let replaceable = TxLockTime::replaceable();
+        assert_eq!(replaceable.get_lock_time(), 0);
+        assert_eq!(replaceable.get_sequence(), MAX_BIP125_RBF_SEQUENCE);
+        assert_ne!(
+            replaceable.get_sequence() & SEQUENCE_LOCKTIME_DISABLE_FLAG,
+            0
+        );
+
+        assert_eq!(TxLockTime::after_blocks(6).get_sequence(), 6);
+        // Relative time locks round up to units of 512 seconds
+        assert_eq!(
+            TxLockTime::after_seconds(1025).unwrap().get_sequence(),
+            SEQUENCE_LOCKTIME_TYPE_FLAG | 3
+        );
+        assert!(TxLockTime::after_seconds(u32::MAX).is_err());
+    }
+
+    #[test]
+    fn test_signature_commits_to_lock_time_and_sequence() {
+        let private_key = crate::new_schnorr_key_pair().unwrap();
+        let pub_key = crate::get_schnorr_public_key(&private_key).unwrap();
+        let spent_output = TXOutput::new_p2tr(Amount::from_sat(5), &pub_key).unwrap();
+
+        let mut tx = spending_transaction(&[1; 32], &pub_key);
+        tx.lock_time = 100;
+        tx.vin[0].sequence = MAX_SEQUENCE_NONFINAL;
+        sign_input(&mut tx, &spent_output, &private_key);
+        assert!(
+            tx.verify_signatures(std::slice::from_ref(&spent_output))
+                .unwrap()
+        );
+
+        let mut unlocked = tx.clone();
+        unlocked.lock_time = 0;
+        assert!(
+            !unlocked
+                .verify_signatures(std::slice::from_ref(&spent_output))
+                .unwrap()
+        );
+        let mut final_input = tx.clone();
+        final_input.vin[0].sequence = SEQUENCE_FINAL;
+        assert!(!final_input.verify_signatures(&[spent_output]).unwrap());
+    }
+
+    #[test]
+    fn test_signature_commits_to_spent_amount() {
+        let private_key = crate::new_schnorr_key_pair().unwrap();
+        let pub_key = crate::get_schnorr_public_key(&private_key).unwrap();
+        let spent_output = TXOutput::new_p2tr(Amount::from_sat(5), &pub_key).unwrap();
+
+        let mut tx = spending_transaction(&[1; 32], &pub_key);
+        sign_input(&mut tx, &spent_output, &private_key);
+        assert!(
+            tx.verify_signatures(std::slice::from_ref(&spent_output))
+                .unwrap()
+        );
+
+        // Same locking script, different amount
+        let other_amount = TXOutput::new_p2tr(Amount::from_sat(6), &pub_key).unwrap();
+        assert!(!tx.verify_signatures(&[other_amount]).unwrap());
+        // Signing needs the output of every input
+        assert!(
+            tx.clone()
+                .sign_inputs(&private_key, &[], 0..1, SigHashType::All)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_signature_hash_type_byte_cannot_be_stripped() {
+        let private_key = crate::new_schnorr_key_pair().unwrap();
+        let pub_key = crate::get_schnorr_public_key(&private_key).unwrap();
+        let spent_output = TXOutput::new_p2tr(Amount::from_sat(5), &pub_key).unwrap();
+        let mut tx = spending_transaction(&[1; 32], &pub_key);
+        sign_input(&mut tx, &spent_output, &private_key);
+
+        // The same signature without its SIGHASH_ALL byte signs the default hash type
+        let bare = tx.vin[0].signature[..SCHNORR_SIGNATURE_SIZE].to_vec();
+        tx.vin[0].script_sig =
+            build_script_sig(&spent_output.get_script_pubkey(), &bare, &pub_key).unwrap();
+        tx.vin[0].signature = bare;
+        assert!(!tx.verify_signatures(&[spent_output]).unwrap());
+    }
+
+    #[test]
+    fn test_signature_commits_to_output_pub_key_hash() {
+        let private_key = crate::new_schnorr_key_pair().unwrap();
+        let pub_key = crate::get_schnorr_public_key(&private_key).unwrap();
+        let spent_output = TXOutput::new_p2tr(Amount::from_sat(5), &pub_key).unwrap();
+        let mut tx = spending_transaction(&[1; 32], &pub_key);
+        tx.vout[0] = TXOutput::new_p2tr(Amount::from_sat(4), &pub_key).unwrap();
+        sign_input(&mut tx, &spent_output, &private_key);
+        assert!(
+            tx.verify_signatures(std::slice::from_ref(&spent_output))
+                .unwrap()
+        );
+
+        // A taproot output is locked by its script alone, but its public key hash decides
+        // whose balance it counts towards
+        tx.vout[0].pub_key_hash = vec![9; 20];
+        assert!(!tx.verify_signatures(&[spent_output]).unwrap());
+    }
+
+    #[test]
+    fn test_anyone_can_pay_signature_lets_others_add_inputs() {
+        let first_key = crate::new_schnorr_key_pair().unwrap();
+        let first_pub_key = crate::get_schnorr_public_key(&first_key).unwrap();
+        let first_output = TXOutput::new_p2tr(Amount::from_sat(2), &first_pub_key).unwrap();
+        let second_key = crate::new_schnorr_key_pair().unwrap();
+        let second_pub_key = crate::get_schnorr_public_key(&second_key).unwrap();
+        let second_output = TXOutput::new_p2tr(Amount::from_sat(2), &second_pub_key).unwrap();
+        let spent_outputs = [first_output.clone(), second_output.clone()];
+
+        let mut tx = spending_transaction(&[1; 32], &first_pub_key);
+        let mut all = tx.clone();
+        tx.sign_inputs(
+            &first_key,
+            std::slice::from_ref(&first_output),
+            0..1,
+            SigHashType::AllPlusAnyoneCanPay,
+        )
+        .unwrap();
+        sign_input(&mut all, &first_output, &first_key);
+
+        // A second contributor adds and signs an input of their own
+        for tx in [&mut tx, &mut all] {
+            let mut input = TXInput::new(&[2; 32], 0);
+            input.pub_key = second_pub_key.clone();
+            tx.vin.push(input);
+            tx.id = tx.unsigned_hash().unwrap();
+            tx.sign_inputs(
+                &second_key,
+                &spent_outputs,
+                1..2,
+                SigHashType::AllPlusAnyoneCanPay,
+            )
+            .unwrap();
+        }
+        assert!(tx.verify_signatures(&spent_outputs).unwrap());
+        assert!(tx.has_valid_id().unwrap());
+        // SIGHASH_ALL committed to the single input
+        assert!(!all.verify_signatures(&spent_outputs).unwrap());
+
+        // Outputs are still covered
+        tx.vout[0].value = Amount::from_sat(3);
+        assert!(!tx.verify_signatures(&spent_outputs).unwrap());
+    }
+
+    #[test]
+    fn test_sighash_none_and_single_leave_outputs_open() {
+        let private_key = crate::new_schnorr_key_pair().unwrap();
+        let pub_key = crate::get_schnorr_public_key(&private_key).unwrap();
+        let spent_output = TXOutput::new_p2tr(Amount::from_sat(5), &pub_key).unwrap();
+        let extra_output =
+            TXOutput::new(Amount::from_sat(1), &generate_test_genesis_address()).unwrap();
+
+        let mut none = spending_transaction(&[1; 32], &pub_key);
+        let spent_outputs = std::slice::from_ref(&spent_output);
+        none.sign_inputs(&private_key, spent_outputs, 0..1, SigHashType::None)
+            .unwrap();
+        none.vout[0].value = Amount::from_sat(1);
+        none.vout.push(extra_output.clone());
+        assert!(
+            none.verify_signatures(std::slice::from_ref(&spent_output))
+                .unwrap()
+        );
+
+        // SINGLE covers the output at the input's index only
+        let mut single = spending_transaction(&[1; 32], &pub_key);
+        single
+            .sign_inputs(&private_key, spent_outputs, 0..1, SigHashType::Single)
+            .unwrap();
+        single.vout.push(extra_output);
+        assert!(
+            single
+                .verify_signatures(std::slice::from_ref(&spent_output))
+                .unwrap()
+        );
+        single.vout[0].value = Amount::from_sat(1);
+        assert!(
+            !single
+                .verify_signatures(std::slice::from_ref(&spent_output))
+                .unwrap()
+        );
+
+        // No output at the input's index
+        single.vout.clear();
+        assert!(matches!(
+            single.signature_hash(
+                0,
+                &spent_output.get_script_pubkey(),
+                std::slice::from_ref(&spent_output),
+                SigHashType::Single
+            ),
+            Err(BtcError::InvalidTransactionOutput)
+        ));
+    }
+
     #[test]
     fn test_transaction_input_can_unlock() {
         let tx_id = vec![1, 2, 3, 4];
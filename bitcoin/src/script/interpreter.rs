@@ -0,0 +1,554 @@
+//! Script interpreter (Bitcoin Core: script/interpreter.cpp)
+//!
+//! `verify_script` decides whether an input may spend an output: the unlocking script of
+//! the input is evaluated first, leaving its pushes on the stack, then the locking script
+//! of the output runs on that stack and must finish with a true value on top.
+//!
+//! Pay-to-taproot outputs are not evaluated as a script. Like Bitcoin Core's witness v1
+//! key path, the unlocking script must push exactly one Schnorr signature, which is
+//! checked against the 32-byte output key.
+//!
+//! Pay-to-script-hash outputs (BIP16) only commit to the hash of a redeem script. The
+//! unlocking script pushes the redeem script last; once it hashes to the committed value,
+//! the redeem script itself runs on the remaining pushes. Multisig addresses use this so
+//! the payer does not need to know the signers' keys.
+//!
+//! Signatures are checked through a [`SignatureChecker`] so the interpreter stays
+//! independent of how the signed message is built; [`TransactionSignatureChecker`] checks
+//! them against the signature hash of one input of a transaction. A signature may carry a
+//! hash type byte after its 64 bytes (see [`SigHashType`]).
+
+use crate::crypto::signature::{schnorr_sign_verify, schnorr_sign_verify_xonly};
+use crate::primitives::transaction::{TXOutput, Transaction};
+use crate::script::ScriptError;
+use crate::script::opcodes::*;
+use crate::script::scriptbuf::{Instruction, MAX_SCRIPT_ELEMENT_SIZE, MAX_SCRIPT_SIZE, Script};
+use crate::script::sighash::SigHashType;
+use crate::script::standard::{TxoutType, solver};
+use crate::sha256_digest;
+
+/// Most non-push opcodes a script may execute (Bitcoin Core's `MAX_OPS_PER_SCRIPT`).
+pub const MAX_OPS_PER_SCRIPT: usize = 201;
+/// Most elements the stack may hold (Bitcoin Core's `MAX_STACK_SIZE`).
+pub const MAX_STACK_SIZE: usize = 1000;
+/// Size of a Schnorr signature (BIP340).
+pub const SCHNORR_SIGNATURE_SIZE: usize = 64;
+/// Most keys `OP_CHECKMULTISIG` accepts (Bitcoin Core's `MAX_PUBKEYS_PER_MULTISIG`).
+pub const MAX_PUBKEYS_PER_MULTISIG: usize = 20;
+
+/// Checks signatures on behalf of the interpreter (Bitcoin Core's
+/// `BaseSignatureChecker`).
+pub trait SignatureChecker {
+    /// Whether `signature` is a valid Schnorr signature by `pub_key` (33-byte compressed
+    /// or 32-byte x-only) over the message committed to by `script_code` and the
+    /// signature's hash type.
+    fn check_schnorr_signature(
+        &self,
+        signature: &[u8],
+        pub_key: &[u8],
+        script_code: &Script,
+    ) -> bool;
+}
+
+/// Checks signatures against the signature hash of one transaction input
+/// (Bitcoin Core's `GenericTransactionSignatureChecker`). The signature hash commits to
+/// the outputs the transaction spends, which the caller supplies.
+pub struct TransactionSignatureChecker<'a> {
+    tx: &'a Transaction,
+    input_index: usize,
+    spent_outputs: &'a [TXOutput],
+}
+
+impl<'a> TransactionSignatureChecker<'a> {
+    pub fn new(
+        tx: &'a Transaction,
+        input_index: usize,
+        spent_outputs: &'a [TXOutput],
+    ) -> TransactionSignatureChecker<'a> {
+        TransactionSignatureChecker {
+            tx,
+            input_index,
+            spent_outputs,
+        }
+    }
+}
+
+impl SignatureChecker for TransactionSignatureChecker<'_> {
+    fn check_schnorr_signature(
+        &self,
+        signature: &[u8],
+        pub_key: &[u8],
+        script_code: &Script,
+    ) -> bool {
+        let Some((signature, hash_type)) = SigHashType::split_signature(signature) else {
+            return false;
+        };
+        let Ok(sighash) =
+            self.tx
+                .signature_hash(self.input_index, script_code, self.spent_outputs, hash_type)
+        else {
+            return false;
+        };
+        match pub_key.len() {
+            32 => schnorr_sign_verify_xonly(pub_key, signature, &sighash),
+            _ => schnorr_sign_verify(pub_key, signature, &sighash),
+        }
+    }
+}
+
+/// Interpret a stack element as a boolean (Bitcoin Core's `CastToBool`): false is any
+/// run of zero bytes, optionally ending in the negative sign bit `0x80`.
+pub fn cast_to_bool(value: &[u8]) -> bool {
+    value
+        .iter()
+        .enumerate()
+        .any(|(i, byte)| *byte != 0 && !(i == value.len() - 1 && *byte == 0x80))
+}
+
+fn pop(stack: &mut Vec<Vec<u8>>) -> Result<Vec<u8>, ScriptError> {
+    stack.pop().ok_or(ScriptError::InvalidStackOperation)
+}
+
+/// Remove the top `count` elements, keeping their order.
+fn pop_n(stack: &mut Vec<Vec<u8>>, count: usize) -> Result<Vec<Vec<u8>>, ScriptError> {
+    let start = stack
+        .len()
+        .checked_sub(count)
+        .ok_or(ScriptError::InvalidStackOperation)?;
+    Ok(stack.split_off(start))
+}
+
+/// Decode a non-negative count pushed by `OP_0`..`OP_16` or a minimal one-byte push.
+fn pop_count(stack: &mut Vec<Vec<u8>>, error: ScriptError) -> Result<usize, ScriptError> {
+    match pop(stack)?.as_slice() {
+        [] => Ok(0),
+        [n] if *n < 0x80 && *n != 0 => Ok(*n as usize),
+        _ => Err(error),
+    }
+}
+
+fn check_top(stack: &[Vec<u8>]) -> Result<(), ScriptError> {
+    match stack.last() {
+        Some(top) if cast_to_bool(top) => Ok(()),
+        _ => Err(ScriptError::EvalFalse),
+    }
+}
+
+/// Verify a Schnorr signature for `OP_CHECKSIG`. An empty signature is a valid way to
+/// fail the check; anything else must be a correctly sized, valid signature, optionally
+/// followed by a defined hash type byte (the tapscript rules of BIP342).
+fn check_signature(
+    signature: &[u8],
+    pub_key: &[u8],
+    script_code: &Script,
+    checker: &dyn SignatureChecker,
+) -> Result<bool, ScriptError> {
+    if signature.is_empty() {
+        return Ok(false);
+    }
+    if signature.len() != SCHNORR_SIGNATURE_SIZE && signature.len() != SCHNORR_SIGNATURE_SIZE + 1 {
+        return Err(ScriptError::SchnorrSigSize);
+    }
+    if SigHashType::split_signature(signature).is_none() {
+        return Err(ScriptError::SchnorrSigHashType);
+    }
+    if !checker.check_schnorr_signature(signature, pub_key, script_code) {
+        return Err(ScriptError::SchnorrSig);
+    }
+    Ok(true)
+}
+
+/// Run `script` on `stack` (Bitcoin Core's `EvalScript`).
+///
+/// # Arguments
+///
+/// * `stack` - The stack to run on; left holding the results.
+/// * `script` - The script to run. It is also the script code signatures commit to.
+/// * `checker` - Verifies the signatures of `OP_CHECKSIG`.
+pub fn eval_script(
+    stack: &mut Vec<Vec<u8>>,
+    script: &Script,
+    checker: &dyn SignatureChecker,
+) -> Result<(), ScriptError> {
+    if script.len() > MAX_SCRIPT_SIZE {
+        return Err(ScriptError::ScriptSize);
+    }
+    let mut op_count = 0;
+    for instruction in script.instructions() {
+        match instruction? {
+            Instruction::Push(data) => {
+                if data.len() > MAX_SCRIPT_ELEMENT_SIZE {
+                    return Err(ScriptError::PushSize);
+                }
+                stack.push(data.to_vec());
+            }
+            Instruction::Op(opcode) => {
+                op_count += 1;
+                if op_count > MAX_OPS_PER_SCRIPT {
+                    return Err(ScriptError::OpCount);
+                }
+                match opcode {
+                    OP_NOP => {}
+                    OP_VERIFY => {
+                        if !cast_to_bool(&pop(stack)?) {
+                            return Err(ScriptError::Verify);
+                        }
+                    }
+                    OP_RETURN => return Err(ScriptError::OpReturn),
+                    OP_DROP => {
+                        pop(stack)?;
+                    }
+                    OP_DUP => {
+                        let top = stack.last().ok_or(ScriptError::InvalidStackOperation)?;
+                        stack.push(top.clone());
+                    }
+                    OP_EQUAL | OP_EQUALVERIFY => {
+                        let a = pop(stack)?;
+                        let b = pop(stack)?;
+                        if opcode == OP_EQUALVERIFY {
+                            if a != b {
+                                return Err(ScriptError::EqualVerify);
+                            }
+                        } else {
+                            stack.push(if a == b { vec![1] } else { vec![] });
+                        }
+                    }
+                    OP_SHA256 => {
+                        let value = pop(stack)?;
+                        stack.push(sha256_digest(&value));
+                    }
+                    OP_CHECKSIG | OP_CHECKSIGVERIFY => {
+                        let pub_key = pop(stack)?;
+                        let signature = pop(stack)?;
+                        let valid = check_signature(&signature, &pub_key, script, checker)?;
+                        if opcode == OP_CHECKSIGVERIFY {
+                            if !valid {
+                                return Err(ScriptError::CheckSigVerify);
+                            }
+                        } else {
+                            stack.push(if valid { vec![1] } else { vec![] });
+                        }
+                    }
+                    OP_CHECKMULTISIG | OP_CHECKMULTISIGVERIFY => {
+                        // <sig_1> .. <sig_n> <m> <key_1> .. <key_n> <n>: one signature slot per
+                        // key, in key order, left empty by keys that do not sign
+                        let key_count = pop_count(stack, ScriptError::PubKeyCount)?;
+                        if key_count > MAX_PUBKEYS_PER_MULTISIG {
+                            return Err(ScriptError::PubKeyCount);
+                        }
+                        op_count += key_count;
+                        if op_count > MAX_OPS_PER_SCRIPT {
+                            return Err(ScriptError::OpCount);
+                        }
+                        let pub_keys = pop_n(stack, key_count)?;
+                        let required = pop_count(stack, ScriptError::SigCount)?;
+                        if required > key_count {
+                            return Err(ScriptError::SigCount);
+                        }
+                        let signatures = pop_n(stack, key_count)?;
+                        let mut valid = 0;
+                        for (signature, pub_key) in signatures.iter().zip(&pub_keys) {
+                            if check_signature(signature, pub_key, script, checker)? {
+                                valid += 1;
+                            }
+                        }
+                        let success = valid >= required;
+                        if opcode == OP_CHECKMULTISIGVERIFY {
+                            if !success {
+                                return Err(ScriptError::CheckMultiSigVerify);
+                            }
+                        } else {
+                            stack.push(if success { vec![1] } else { vec![] });
+                        }
+                    }
+                    _ => return Err(ScriptError::BadOpcode),
+                }
+            }
+        }
+        if stack.len() > MAX_STACK_SIZE {
+            return Err(ScriptError::StackSize);
+        }
+    }
+    Ok(())
+}
+
+/// Check that `script_sig` unlocks `script_pubkey` (Bitcoin Core's `VerifyScript`).
+///
+/// # Arguments
+///
+/// * `script_sig` - The unlocking script of the input; may only push data.
+/// * `script_pubkey` - The locking script of the output being spent.
+/// * `checker` - Verifies signatures against the spending transaction.
+///
+/// # Returns
+///
+/// `Ok(())` if the output may be spent, otherwise the reason it may not.
+pub fn verify_script(
+    script_sig: &Script,
+    script_pubkey: &Script,
+    checker: &dyn SignatureChecker,
+) -> Result<(), ScriptError> {
+    if !script_sig.is_push_only() {
+        return Err(ScriptError::SigPushOnly);
+    }
+    let mut stack = vec![];
+    eval_script(&mut stack, script_sig, checker)?;
+
+    let (txout_type, solutions) = solver(script_pubkey);
+    if txout_type == TxoutType::WitnessV1Taproot {
+        // Key path spend: the only element is the signature for the output key
+        let [signature] = stack.as_slice() else {
+            return Err(ScriptError::InvalidStackOperation);
+        };
+        return match check_signature(signature, &solutions[0], script_pubkey, checker)? {
+            true => Ok(()),
+            false => Err(ScriptError::EvalFalse),
+        };
+    }
+
+    // The redeem script runs on the pushes as they were before the hash check
+    let redeem_stack = (txout_type == TxoutType::ScriptHash).then(|| stack.clone());
+
+    eval_script(&mut stack, script_pubkey, checker)?;
+    check_top(&stack)?;
+
+    if let Some(mut stack) = redeem_stack {
+        let redeem_script = Script::from_bytes(pop(&mut stack)?);
+        eval_script(&mut stack, &redeem_script, checker)?;
+        check_top(&stack)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::script::standard::{multisig_script, p2pkh_script, p2sh_script, p2tr_script};
+
+    /// Accepts exactly one signature by one key.
+    struct FixedChecker {
+        signature: Vec<u8>,
+        pub_key: Vec<u8>,
+    }
+
+    impl SignatureChecker for FixedChecker {
+        fn check_schnorr_signature(&self, signature: &[u8], pub_key: &[u8], _: &Script) -> bool {
+            signature == self.signature.as_slice() && pub_key == self.pub_key.as_slice()
+        }
+    }
+
+    fn checker() -> FixedChecker {
+        FixedChecker {
+            signature: vec![7; SCHNORR_SIGNATURE_SIZE],
+            pub_key: vec![2; 33],
+        }
+    }
+
+    #[test]
+    fn test_cast_to_bool() {
+        assert!(!cast_to_bool(&[]));
+        assert!(!cast_to_bool(&[0, 0]));
+        assert!(!cast_to_bool(&[0, 0x80]));
+        assert!(cast_to_bool(&[0x80, 0]));
+        assert!(cast_to_bool(&[1]));
+    }
+
+    #[test]
+    fn test_p2pkh_spend() {
+        let checker = checker();
+        let script_pubkey = p2pkh_script(&sha256_digest(&checker.pub_key));
+        let script_sig = Script::new()
+            .push_slice(&checker.signature)
+            .push_slice(&checker.pub_key);
+        assert_eq!(verify_script(&script_sig, &script_pubkey, &checker), Ok(()));
+
+        // A different key does not hash to the locked hash
+        let wrong_key = Script::new()
+            .push_slice(&checker.signature)
+            .push_slice(&[3; 33]);
+        assert_eq!(
+            verify_script(&wrong_key, &script_pubkey, &checker),
+            Err(ScriptError::EqualVerify)
+        );
+
+        // A non-empty invalid signature fails evaluation, an empty one leaves false
+        let wrong_sig = Script::new()
+            .push_slice(&[8; SCHNORR_SIGNATURE_SIZE])
+            .push_slice(&checker.pub_key);
+        assert_eq!(
+            verify_script(&wrong_sig, &script_pubkey, &checker),
+            Err(ScriptError::SchnorrSig)
+        );
+        let empty_sig = Script::new().push_slice(&[]).push_slice(&checker.pub_key);
+        assert_eq!(
+            verify_script(&empty_sig, &script_pubkey, &checker),
+            Err(ScriptError::EvalFalse)
+        );
+    }
+
+    #[test]
+    fn test_p2tr_key_path_spend() {
+        let checker = FixedChecker {
+            signature: vec![7; SCHNORR_SIGNATURE_SIZE],
+            pub_key: vec![4; 32],
+        };
+        let script_pubkey = p2tr_script(&checker.pub_key);
+        let script_sig = Script::new().push_slice(&checker.signature);
+        assert_eq!(verify_script(&script_sig, &script_pubkey, &checker), Ok(()));
+
+        let extra_push = script_sig.clone().push_slice(&[1]);
+        assert_eq!(
+            verify_script(&extra_push, &script_pubkey, &checker),
+            Err(ScriptError::InvalidStackOperation)
+        );
+        let short_sig = Script::new().push_slice(&[7; 10]);
+        assert_eq!(
+            verify_script(&short_sig, &script_pubkey, &checker),
+            Err(ScriptError::SchnorrSigSize)
+        );
+        let mut bad_hash_type = checker.signature.clone();
+        bad_hash_type.push(0x04);
+        assert_eq!(
+            verify_script(
+                &Script::new().push_slice(&bad_hash_type),
+                &script_pubkey,
+                &checker
+            ),
+            Err(ScriptError::SchnorrSigHashType)
+        );
+    }
+
+    #[test]
+    fn test_script_failures() {
+        let checker = checker();
+        let true_sig = Script::new().push_opcode(OP_1);
+
+        let op_return = Script::new().push_opcode(OP_RETURN);
+        assert_eq!(
+            verify_script(&true_sig, &op_return, &checker),
+            Err(ScriptError::OpReturn)
+        );
+
+        let underflow = Script::new().push_opcode(OP_DROP).push_opcode(OP_DROP);
+        assert_eq!(
+            verify_script(&true_sig, &underflow, &checker),
+            Err(ScriptError::InvalidStackOperation)
+        );
+
+        let unknown = Script::from_bytes(vec![0xff]);
+        assert_eq!(
+            verify_script(&true_sig, &unknown, &checker),
+            Err(ScriptError::BadOpcode)
+        );
+
+        let not_push_only = Script::new().push_opcode(OP_1).push_opcode(OP_DUP);
+        assert_eq!(
+            verify_script(&not_push_only, &Script::new(), &checker),
+            Err(ScriptError::SigPushOnly)
+        );
+
+        let equal = Script::new().push_slice(b"abc").push_opcode(OP_EQUAL);
+        assert_eq!(
+            verify_script(&Script::new().push_slice(b"abc"), &equal, &checker),
+            Ok(())
+        );
+        assert_eq!(
+            verify_script(&Script::new().push_slice(b"abd"), &equal, &checker),
+            Err(ScriptError::EvalFalse)
+        );
+    }
+
+    /// Accepts any signature whose bytes are the key's first byte repeated.
+    struct KeyTagChecker;
+
+    impl SignatureChecker for KeyTagChecker {
+        fn check_schnorr_signature(&self, signature: &[u8], pub_key: &[u8], _: &Script) -> bool {
+            signature.iter().all(|byte| *byte == pub_key[0])
+        }
+    }
+
+    fn tagged_keys() -> Vec<Vec<u8>> {
+        (1..=3).map(|tag| vec![tag; 33]).collect()
+    }
+
+    fn signature_by(tag: u8) -> Vec<u8> {
+        vec![tag; SCHNORR_SIGNATURE_SIZE]
+    }
+
+    #[test]
+    fn test_bare_multisig_2_of_3() {
+        let script_pubkey = multisig_script(2, &tagged_keys()).unwrap();
+        let spend = |slots: [Vec<u8>; 3]| {
+            let script_sig = slots
+                .iter()
+                .fold(Script::new(), |script, slot| script.push_slice(slot));
+            verify_script(&script_sig, &script_pubkey, &KeyTagChecker)
+        };
+
+        assert_eq!(spend([signature_by(1), vec![], signature_by(3)]), Ok(()));
+        assert_eq!(
+            spend([signature_by(1), signature_by(2), signature_by(3)]),
+            Ok(())
+        );
+        assert_eq!(
+            spend([vec![], vec![], signature_by(3)]),
+            Err(ScriptError::EvalFalse)
+        );
+        // Signatures must sit in the slot of their key
+        assert_eq!(
+            spend([signature_by(2), signature_by(1), vec![]]),
+            Err(ScriptError::SchnorrSig)
+        );
+    }
+
+    #[test]
+    fn test_p2sh_multisig_spend() {
+        let redeem_script = multisig_script(2, &tagged_keys()).unwrap();
+        let script_pubkey = p2sh_script(&sha256_digest(redeem_script.as_bytes()));
+        let script_sig = Script::new()
+            .push_slice(&signature_by(1))
+            .push_slice(&signature_by(2))
+            .push_slice(&[])
+            .push_slice(redeem_script.as_bytes());
+        assert_eq!(
+            verify_script(&script_sig, &script_pubkey, &KeyTagChecker),
+            Ok(())
+        );
+
+        // Only one signature: the hash matches but the redeem script fails
+        let one_signature = Script::new()
+            .push_slice(&signature_by(1))
+            .push_slice(&[])
+            .push_slice(&[])
+            .push_slice(redeem_script.as_bytes());
+        assert_eq!(
+            verify_script(&one_signature, &script_pubkey, &KeyTagChecker),
+            Err(ScriptError::EvalFalse)
+        );
+
+        // A different redeem script does not match the committed hash
+        let other_script = multisig_script(1, &tagged_keys()).unwrap();
+        let wrong_script = Script::new()
+            .push_slice(&signature_by(1))
+            .push_slice(&[])
+            .push_slice(&[])
+            .push_slice(other_script.as_bytes());
+        assert_eq!(
+            verify_script(&wrong_script, &script_pubkey, &KeyTagChecker),
+            Err(ScriptError::EvalFalse)
+        );
+    }
+
+    #[test]
+    fn test_op_count_limit() {
+        let checker = checker();
+        let mut script = Script::new().push_opcode(OP_1);
+        for _ in 0..=MAX_OPS_PER_SCRIPT {
+            script = script.push_opcode(OP_NOP);
+        }
+        assert_eq!(
+            verify_script(&Script::new(), &script, &checker),
+            Err(ScriptError::OpCount)
+        );
+    }
+}
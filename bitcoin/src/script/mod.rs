@@ -0,0 +1,26 @@
+//! Script module (Bitcoin Core alignment)
+//!
+//! Locking and unlocking scripts and the stack machine that evaluates them, following
+//! Bitcoin Core's `script/` directory:
+//! - script/script.h - Opcodes and the `CScript` byte code type
+//! - script/script_error.h - Evaluation failures
+//! - script/interpreter.cpp - `EvalScript`, `VerifyScript` and signature checking
+//! - script/interpreter.cpp / interpreter.h - Signature hash types (`SIGHASH_*`) and the
+//!   signed message (`SignatureHashSchnorr`)
+//! - script/solver.cpp - Standard output templates
+
+pub mod interpreter;
+pub mod opcodes;
+pub mod script_error;
+pub mod scriptbuf;
+pub mod sighash;
+pub mod standard;
+
+pub use interpreter::{SignatureChecker, TransactionSignatureChecker, eval_script, verify_script};
+pub use script_error::ScriptError;
+pub use scriptbuf::{Instruction, Script};
+pub use sighash::SigHashType;
+pub use standard::{
+    TxoutType, build_script_sig, multisig_script, multisig_script_sig, p2pkh_script, p2sh_script,
+    p2tr_script, parse_multisig_script_sig, solver,
+};
@@ -0,0 +1,45 @@
+//! Opcodes (Bitcoin Core: script/script.h `opcodetype`)
+//!
+//! Only the opcodes the interpreter evaluates are defined; any other byte above the push
+//! range is an unknown opcode and fails evaluation.
+
+/// Push an empty byte vector (also `OP_FALSE`)
+pub const OP_0: u8 = 0x00;
+/// The next byte is the length of the data to push
+pub const OP_PUSHDATA1: u8 = 0x4c;
+/// The next two bytes (little-endian) are the length of the data to push
+pub const OP_PUSHDATA2: u8 = 0x4d;
+/// Push the number 1 (also `OP_TRUE`); `OP_2`..`OP_16` follow consecutively
+pub const OP_1: u8 = 0x51;
+pub const OP_16: u8 = 0x60;
+pub const OP_NOP: u8 = 0x61;
+pub const OP_VERIFY: u8 = 0x69;
+pub const OP_RETURN: u8 = 0x6a;
+pub const OP_DROP: u8 = 0x75;
+pub const OP_DUP: u8 = 0x76;
+pub const OP_EQUAL: u8 = 0x87;
+pub const OP_EQUALVERIFY: u8 = 0x88;
+pub const OP_SHA256: u8 = 0xa8;
+pub const OP_CHECKSIG: u8 = 0xac;
+pub const OP_CHECKSIGVERIFY: u8 = 0xad;
+pub const OP_CHECKMULTISIG: u8 = 0xae;
+pub const OP_CHECKMULTISIGVERIFY: u8 = 0xaf;
+
+/// Name of an opcode for display, e.g. `OP_CHECKSIG`.
+pub fn opcode_name(opcode: u8) -> String {
+    match opcode {
+        OP_NOP => "OP_NOP".to_string(),
+        OP_VERIFY => "OP_VERIFY".to_string(),
+        OP_RETURN => "OP_RETURN".to_string(),
+        OP_DROP => "OP_DROP".to_string(),
+        OP_DUP => "OP_DUP".to_string(),
+        OP_EQUAL => "OP_EQUAL".to_string(),
+        OP_EQUALVERIFY => "OP_EQUALVERIFY".to_string(),
+        OP_SHA256 => "OP_SHA256".to_string(),
+        OP_CHECKSIG => "OP_CHECKSIG".to_string(),
+        OP_CHECKSIGVERIFY => "OP_CHECKSIGVERIFY".to_string(),
+        OP_CHECKMULTISIG => "OP_CHECKMULTISIG".to_string(),
+        OP_CHECKMULTISIGVERIFY => "OP_CHECKMULTISIGVERIFY".to_string(),
+        _ => format!("OP_UNKNOWN<0x{:02x}>", opcode),
+    }
+}
@@ -0,0 +1,44 @@
+//! Script evaluation failures (Bitcoin Core: script/script_error.h)
+
+use thiserror::Error;
+
+/// Why a script failed to evaluate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Error)]
+pub enum ScriptError {
+    #[error("Script evaluated without error but finished with a false/empty top stack element")]
+    EvalFalse,
+    #[error("OP_RETURN was encountered")]
+    OpReturn,
+    #[error("Script is too big")]
+    ScriptSize,
+    #[error("Push value size limit exceeded")]
+    PushSize,
+    #[error("Operation limit exceeded")]
+    OpCount,
+    #[error("Stack size limit exceeded")]
+    StackSize,
+    #[error("Script failed an OP_VERIFY operation")]
+    Verify,
+    #[error("Script failed an OP_EQUALVERIFY operation")]
+    EqualVerify,
+    #[error("Script failed an OP_CHECKSIGVERIFY operation")]
+    CheckSigVerify,
+    #[error("Script failed an OP_CHECKMULTISIGVERIFY operation")]
+    CheckMultiSigVerify,
+    #[error("Pubkey count negative or limit exceeded")]
+    PubKeyCount,
+    #[error("Signature count negative or greater than pubkey count")]
+    SigCount,
+    #[error("Opcode missing or not understood")]
+    BadOpcode,
+    #[error("Operation not valid with the current stack size")]
+    InvalidStackOperation,
+    #[error("Only push operators allowed in signatures")]
+    SigPushOnly,
+    #[error("Invalid Schnorr signature size")]
+    SchnorrSigSize,
+    #[error("Invalid Schnorr signature hash type")]
+    SchnorrSigHashType,
+    #[error("Invalid Schnorr signature")]
+    SchnorrSig,
+}
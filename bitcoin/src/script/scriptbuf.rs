@@ -0,0 +1,308 @@
+//! Script byte code (Bitcoin Core: script/script.h `CScript`)
+//!
+//! A script is a sequence of opcodes and data pushes. Outputs carry a locking script
+//! (`script_pubkey`) and inputs an unlocking script (`script_sig`) that pushes the data
+//! the locking script needs, such as a signature and a public key.
+//!
+//! Only the subset of opcodes the standard templates need is supported; every other
+//! opcode fails evaluation.
+
+use crate::script::ScriptError;
+use crate::script::opcodes::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Largest script accepted for evaluation (Bitcoin Core's `MAX_SCRIPT_SIZE`).
+pub const MAX_SCRIPT_SIZE: usize = 10_000;
+/// Largest data push (Bitcoin Core's `MAX_SCRIPT_ELEMENT_SIZE`).
+pub const MAX_SCRIPT_ELEMENT_SIZE: usize = 520;
+
+/// One decoded element of a script.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction<'a> {
+    /// Data pushed onto the stack, including `OP_0` (empty) and `OP_1`..`OP_16`
+    /// (a single byte holding the number).
+    Push(&'a [u8]),
+    /// Any other opcode
+    Op(u8),
+}
+
+/// A locking or unlocking script (Bitcoin Core's `CScript`).
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Script(Vec<u8>);
+
+impl Script {
+    pub fn new() -> Script {
+        Script(vec![])
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Script {
+        Script(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Append a bare opcode.
+    pub fn push_opcode(mut self, opcode: u8) -> Script {
+        self.0.push(opcode);
+        self
+    }
+
+    /// Append a push of `data` using the smallest push opcode that fits
+    /// (Bitcoin Core's `CScript::operator<<(std::vector<unsigned char>)`).
+    pub fn push_slice(mut self, data: &[u8]) -> Script {
+        match data.len() {
+            0 => self.0.push(OP_0),
+            len if len < OP_PUSHDATA1 as usize => self.0.push(len as u8),
+            len if len <= u8::MAX as usize => {
+                self.0.push(OP_PUSHDATA1);
+                self.0.push(len as u8);
+            }
+            len => {
+                self.0.push(OP_PUSHDATA2);
+                self.0.extend_from_slice(&(len as u16).to_le_bytes());
+            }
+        }
+        self.0.extend_from_slice(data);
+        self
+    }
+
+    /// Append a push of the non-negative number `n`: `OP_0`, `OP_1`..`OP_16`, or its minimal
+    /// script number encoding (Bitcoin Core's `CScript::push_int64`).
+    pub fn push_int(self, n: u64) -> Script {
+        match n {
+            0 => self.push_opcode(OP_0),
+            1..=16 => self.push_opcode(OP_1 + (n as u8 - 1)),
+            _ => self.push_slice(&encode_script_num(n)),
+        }
+    }
+
+    /// Iterate over the opcodes and pushes of the script. A truncated push yields
+    /// `ScriptError::BadOpcode` and ends the iteration.
+    pub fn instructions(&self) -> Instructions<'_> {
+        Instructions {
+            bytes: self.as_bytes(),
+            pc: 0,
+        }
+    }
+
+    /// Whether the script only pushes data (required of unlocking scripts).
+    pub fn is_push_only(&self) -> bool {
+        self.instructions()
+            .all(|instruction| matches!(instruction, Ok(Instruction::Push(_))))
+    }
+
+    /// Whether the output can never be spent (Bitcoin Core's `CScript::IsUnspendable`).
+    pub fn is_unspendable(&self) -> bool {
+        self.0.first() == Some(&OP_RETURN) || self.len() > MAX_SCRIPT_SIZE
+    }
+}
+
+/// Minimal little-endian encoding of a non-negative script number (Bitcoin Core's
+/// `CScriptNum::serialize`). A zero byte is appended when the top bit is set, since it
+/// would otherwise be read as the sign.
+pub fn encode_script_num(n: u64) -> Vec<u8> {
+    let mut bytes: Vec<u8> = n.to_le_bytes().into_iter().collect();
+    while bytes.last() == Some(&0) {
+        bytes.pop();
+    }
+    if bytes.last().is_some_and(|byte| byte & 0x80 != 0) {
+        bytes.push(0);
+    }
+    bytes
+}
+
+/// Read back a non-negative script number written by `encode_script_num` (Bitcoin Core's
+/// `CScriptNum` constructor).
+///
+/// # Returns
+///
+/// `None` for negative numbers and numbers that do not fit in a `u64`.
+pub fn decode_script_num(bytes: &[u8]) -> Option<u64> {
+    if bytes.last().is_some_and(|byte| byte & 0x80 != 0) || bytes.len() > 9 {
+        return None;
+    }
+    if bytes.len() == 9 && bytes[8] != 0 {
+        return None;
+    }
+    Some(
+        bytes
+            .iter()
+            .take(8)
+            .enumerate()
+            .fold(0, |n, (idx, byte)| n | (u64::from(*byte) << (8 * idx))),
+    )
+}
+
+impl fmt::Debug for Script {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Script({})", self)
+    }
+}
+
+/// Human-readable form, pushes in hex and opcodes by name (like `ScriptToAsmStr`).
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = vec![];
+        for instruction in self.instructions() {
+            match instruction {
+                Ok(Instruction::Push(data)) => parts.push(data_encoding::HEXLOWER.encode(data)),
+                Ok(Instruction::Op(opcode)) => parts.push(opcode_name(opcode)),
+                Err(_) => {
+                    parts.push("[error]".to_string());
+                    break;
+                }
+            }
+        }
+        f.write_str(&parts.join(" "))
+    }
+}
+
+/// Iterator returned by [`Script::instructions`].
+pub struct Instructions<'a> {
+    bytes: &'a [u8],
+    pc: usize,
+}
+
+/// Single-byte values pushed by `OP_1`..`OP_16`.
+const SMALL_INTEGERS: [u8; 16] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+
+impl<'a> Instructions<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let end = self.pc.checked_add(len)?;
+        let data = self.bytes.get(self.pc..end)?;
+        self.pc = end;
+        Some(data)
+    }
+
+    fn read_push(&mut self, opcode: u8) -> Option<&'a [u8]> {
+        let len = match opcode {
+            OP_PUSHDATA1 => self.take(1)?[0] as usize,
+            OP_PUSHDATA2 => {
+                let len = self.take(2)?;
+                u16::from_le_bytes([len[0], len[1]]) as usize
+            }
+            len => len as usize,
+        };
+        self.take(len)
+    }
+}
+
+impl<'a> Iterator for Instructions<'a> {
+    type Item = Result<Instruction<'a>, ScriptError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let opcode = *self.bytes.get(self.pc)?;
+        self.pc += 1;
+        let instruction = match opcode {
+            OP_0 => Instruction::Push(&[]),
+            OP_1..=OP_16 => {
+                let n = (opcode - OP_1) as usize;
+                Instruction::Push(&SMALL_INTEGERS[n..=n])
+            }
+            len if len <= OP_PUSHDATA2 => match self.read_push(len) {
+                Some(data) => Instruction::Push(data),
+                None => {
+                    // Stop after reporting the truncated push
+                    self.pc = self.bytes.len();
+                    return Some(Err(ScriptError::BadOpcode));
+                }
+            },
+            _ => Instruction::Op(opcode),
+        };
+        Some(Ok(instruction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_int_uses_small_integer_opcodes() {
+        assert_eq!(Script::new().push_int(0).as_bytes(), &[OP_0]);
+        assert_eq!(Script::new().push_int(16).as_bytes(), &[OP_16]);
+        assert_eq!(Script::new().push_int(17).as_bytes(), &[1, 17]);
+        // 128 needs a sign byte; 500_000 is 0x07a120
+        assert_eq!(Script::new().push_int(128).as_bytes(), &[2, 0x80, 0x00]);
+        assert_eq!(
+            Script::new().push_int(500_000).as_bytes(),
+            &[3, 0x20, 0xa1, 0x07]
+        );
+        assert_eq!(encode_script_num(0), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_script_num_round_trip() {
+        for n in [0, 1, 127, 128, 255, 500_000, u64::MAX] {
+            assert_eq!(decode_script_num(&encode_script_num(n)), Some(n));
+        }
+        // Sign bit set: negative
+        assert_eq!(decode_script_num(&[0x81]), None);
+        assert_eq!(decode_script_num(&[0; 10]), None);
+    }
+
+    #[test]
+    fn test_push_slice_uses_smallest_push() {
+        assert_eq!(Script::new().push_slice(&[]).as_bytes(), &[OP_0]);
+        assert_eq!(Script::new().push_slice(&[7; 3]).as_bytes()[0], 3);
+        let script = Script::new().push_slice(&[7; 80]);
+        assert_eq!(&script.as_bytes()[..2], &[OP_PUSHDATA1, 80]);
+        let script = Script::new().push_slice(&[7; 300]);
+        assert_eq!(&script.as_bytes()[..3], &[OP_PUSHDATA2, 0x2c, 0x01]);
+
+        for data in [vec![], vec![7; 3], vec![7; 80], vec![7; 300]] {
+            let script = Script::new().push_slice(&data);
+            let instructions: Vec<_> = script.instructions().collect();
+            assert_eq!(instructions, vec![Ok(Instruction::Push(data.as_slice()))]);
+        }
+    }
+
+    #[test]
+    fn test_instructions_decode_opcodes_and_small_integers() {
+        let script = Script::new()
+            .push_opcode(OP_1)
+            .push_opcode(OP_16)
+            .push_opcode(OP_DUP);
+        let instructions: Vec<_> = script.instructions().collect();
+        assert_eq!(
+            instructions,
+            vec![
+                Ok(Instruction::Push(&[1])),
+                Ok(Instruction::Push(&[16])),
+                Ok(Instruction::Op(OP_DUP))
+            ]
+        );
+        assert!(!script.is_push_only());
+        assert_eq!(script.to_string(), "01 10 OP_DUP");
+    }
+
+    #[test]
+    fn test_truncated_push_is_an_error() {
+        let script = Script::from_bytes(vec![5, 1, 2]);
+        let instructions: Vec<_> = script.instructions().collect();
+        assert_eq!(instructions, vec![Err(ScriptError::BadOpcode)]);
+        assert!(!script.is_push_only());
+    }
+
+    #[test]
+    fn test_op_return_is_unspendable() {
+        assert!(
+            Script::new()
+                .push_opcode(OP_RETURN)
+                .push_slice(b"data")
+                .is_unspendable()
+        );
+        assert!(!Script::new().push_opcode(OP_1).is_unspendable());
+    }
+}
@@ -0,0 +1,273 @@
+//! Signature hash types (Bitcoin Core: script/interpreter.h `SIGHASH_*`)
+//!
+//! A signature normally commits to every input and output of its transaction. The hash
+//! type appended to it can narrow that: `NONE` leaves the outputs open, `SINGLE` only
+//! covers the output at the signed input's index, and `ANYONECANPAY` only covers the signed
+//! input, so others may add inputs of their own (crowdfunding, "add your own input"
+//! transactions).
+//!
+//! The signed message follows BIP341 (and BIP143 before it): instead of hashing a modified
+//! copy of the transaction, it hashes the parts the hash type selects, together with the
+//! amount and locking script of every output being spent. Signers and verifiers get those
+//! from the UTXO set, so neither has to look up the transactions that created them, and a
+//! signature over a wrong amount is invalid.
+
+use crate::error::{BtcError, Result};
+use crate::primitives::transaction::{TXInput, TXOutput, Transaction};
+use crate::script::Script;
+use crate::script::interpreter::SCHNORR_SIGNATURE_SIZE;
+use crate::serialize::{Encodable, write_var_bytes};
+use crate::sha256_digest;
+use serde::{Deserialize, Serialize};
+
+/// Hash type of a bare signature without a hash type byte; commits like `SIGHASH_ALL`
+/// but is never written out (BIP341's `SIGHASH_DEFAULT`).
+pub const SIGHASH_DEFAULT: u8 = 0x00;
+/// Sign every output.
+pub const SIGHASH_ALL: u8 = 0x01;
+/// Sign no output.
+pub const SIGHASH_NONE: u8 = 0x02;
+/// Sign the output at the index of the signed input.
+pub const SIGHASH_SINGLE: u8 = 0x03;
+/// Flag: sign only the signed input.
+pub const SIGHASH_ANYONECANPAY: u8 = 0x80;
+
+/// Which parts of a transaction a signature commits to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SigHashType {
+    /// All inputs and all outputs, signed by a bare 64-byte signature.
+    Default,
+    /// All inputs and all outputs.
+    #[default]
+    All,
+    /// All inputs and no output.
+    None,
+    /// All inputs and the output at the signed input's index.
+    Single,
+    /// The signed input and all outputs.
+    AllPlusAnyoneCanPay,
+    /// The signed input and no output.
+    NonePlusAnyoneCanPay,
+    /// The signed input and the output at its index.
+    SinglePlusAnyoneCanPay,
+}
+
+impl SigHashType {
+    /// Decode the hash type byte of a signature; undefined values are rejected, and so is
+    /// `SIGHASH_DEFAULT`, which is only implied by a signature without the byte.
+    pub fn from_u8(value: u8) -> Option<SigHashType> {
+        match value {
+            SIGHASH_ALL => Some(SigHashType::All),
+            SIGHASH_NONE => Some(SigHashType::None),
+            SIGHASH_SINGLE => Some(SigHashType::Single),
+            v if v == SIGHASH_ALL | SIGHASH_ANYONECANPAY => Some(SigHashType::AllPlusAnyoneCanPay),
+            v if v == SIGHASH_NONE | SIGHASH_ANYONECANPAY => {
+                Some(SigHashType::NonePlusAnyoneCanPay)
+            }
+            v if v == SIGHASH_SINGLE | SIGHASH_ANYONECANPAY => {
+                Some(SigHashType::SinglePlusAnyoneCanPay)
+            }
+            _ => None,
+        }
+    }
+
+    /// The hash type byte appended to signatures.
+    pub fn to_u8(self) -> u8 {
+        match self {
+            SigHashType::Default => SIGHASH_DEFAULT,
+            SigHashType::All => SIGHASH_ALL,
+            SigHashType::None => SIGHASH_NONE,
+            SigHashType::Single => SIGHASH_SINGLE,
+            SigHashType::AllPlusAnyoneCanPay => SIGHASH_ALL | SIGHASH_ANYONECANPAY,
+            SigHashType::NonePlusAnyoneCanPay => SIGHASH_NONE | SIGHASH_ANYONECANPAY,
+            SigHashType::SinglePlusAnyoneCanPay => SIGHASH_SINGLE | SIGHASH_ANYONECANPAY,
+        }
+    }
+
+    /// Whether only the signed input is committed to.
+    pub fn is_anyone_can_pay(self) -> bool {
+        self.to_u8() & SIGHASH_ANYONECANPAY != 0
+    }
+
+    /// The output mode without the `ANYONECANPAY` flag.
+    pub fn outputs(self) -> SigHashType {
+        match self {
+            SigHashType::Default | SigHashType::All | SigHashType::AllPlusAnyoneCanPay => {
+                SigHashType::All
+            }
+            SigHashType::None | SigHashType::NonePlusAnyoneCanPay => SigHashType::None,
+            SigHashType::Single | SigHashType::SinglePlusAnyoneCanPay => SigHashType::Single,
+        }
+    }
+
+    /// Split a signature into the Schnorr signature and its hash type. A bare 64-byte
+    /// signature has the `Default` type, which commits to everything like `All` but signs a
+    /// different hash type byte, so appending or stripping an `All` byte invalidates the
+    /// signature instead of re-encoding it (BIP341).
+    ///
+    /// # Returns
+    ///
+    /// `None` if the signature has the wrong size or an undefined hash type.
+    pub fn split_signature(signature: &[u8]) -> Option<(&[u8], SigHashType)> {
+        match signature.len() {
+            SCHNORR_SIGNATURE_SIZE => Some((signature, SigHashType::Default)),
+            len if len == SCHNORR_SIGNATURE_SIZE + 1 => {
+                let hash_type = SigHashType::from_u8(signature[SCHNORR_SIGNATURE_SIZE])?;
+                Some((&signature[..SCHNORR_SIGNATURE_SIZE], hash_type))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The message signed by input `input_index` of `tx` (BIP341's `SigMsg`, Bitcoin Core's
+/// `SignatureHashSchnorr`), committing to:
+///
+/// * the hash type and the lock time;
+/// * unless `ANYONECANPAY`: the outpoints, spent amounts, spent locking scripts and
+///   sequences of every input;
+/// * with `ALL`: every output, in its consensus encoding;
+/// * the signed input: its index, or with `ANYONECANPAY` its outpoint, spent amount, spent
+///   locking script and sequence;
+/// * with `SINGLE`: the output at the signed input's index;
+/// * `script_code`, the script the signature is checked against (BIP143's `scriptCode`).
+///
+/// # Arguments
+///
+/// * `tx` - The transaction being signed.
+/// * `input_index` - The input being signed.
+/// * `script_code` - The script the signature is checked against.
+/// * `spent_outputs` - The output spent by each input, in input order.
+/// * `sighash_type` - The parts of the transaction the signature commits to.
+///
+/// # Returns
+///
+/// The 32-byte signature hash, `InvalidTransactionInput` if the input or a spent output is
+/// missing, or `InvalidTransactionOutput` for `Single` without a matching output.
+pub fn signature_hash(
+    tx: &Transaction,
+    input_index: usize,
+    script_code: &Script,
+    spent_outputs: &[TXOutput],
+    sighash_type: SigHashType,
+) -> Result<Vec<u8>> {
+    let inputs = tx.get_vin();
+    let input = inputs
+        .get(input_index)
+        .ok_or(BtcError::InvalidTransactionInput)?;
+    if spent_outputs.len() != inputs.len() {
+        return Err(BtcError::InvalidTransactionInput);
+    }
+
+    let mut msg = vec![sighash_type.to_u8()];
+    msg.extend_from_slice(&tx.get_lock_time().to_le_bytes());
+    if !sighash_type.is_anyone_can_pay() {
+        let mut prevouts = vec![];
+        let mut amounts = vec![];
+        let mut script_pubkeys = vec![];
+        let mut sequences = vec![];
+        for (input, spent_output) in inputs.iter().zip(spent_outputs) {
+            write_outpoint(&mut prevouts, input);
+            amounts.extend_from_slice(&spent_output.get_value().to_sat().to_le_bytes());
+            write_script(&mut script_pubkeys, &spent_output.get_script_pubkey());
+            sequences.extend_from_slice(&input.get_sequence().to_le_bytes());
+        }
+        msg.extend(sha256_digest(&prevouts));
+        msg.extend(sha256_digest(&amounts));
+        msg.extend(sha256_digest(&script_pubkeys));
+        msg.extend(sha256_digest(&sequences));
+    }
+    if sighash_type.outputs() == SigHashType::All {
+        let mut outputs = vec![];
+        for output in tx.get_vout() {
+            write_output(&mut outputs, output);
+        }
+        msg.extend(sha256_digest(&outputs));
+    }
+
+    if sighash_type.is_anyone_can_pay() {
+        let spent_output = &spent_outputs[input_index];
+        write_outpoint(&mut msg, input);
+        msg.extend_from_slice(&spent_output.get_value().to_sat().to_le_bytes());
+        write_script(&mut msg, &spent_output.get_script_pubkey());
+        msg.extend_from_slice(&input.get_sequence().to_le_bytes());
+    } else {
+        msg.extend_from_slice(&(input_index as u32).to_le_bytes());
+    }
+    if sighash_type.outputs() == SigHashType::Single {
+        let output = tx
+            .get_vout()
+            .get(input_index)
+            .ok_or(BtcError::InvalidTransactionOutput)?;
+        let mut single = vec![];
+        write_output(&mut single, output);
+        msg.extend(sha256_digest(&single));
+    }
+    write_script(&mut msg, script_code);
+    Ok(sha256_digest(&msg))
+}
+
+/// The outpoint spent by `input`: its transaction id and output index.
+fn write_outpoint(buf: &mut Vec<u8>, input: &TXInput) {
+    buf.extend_from_slice(input.get_txid());
+    buf.extend_from_slice(&(input.get_vout() as u32).to_le_bytes());
+}
+
+/// An output in its consensus encoding: its amount, public key hash and locking script.
+/// The public key hash is part of the txid and decides whose balance the output counts
+/// towards, so it must be signed even where the locking script does not use it.
+fn write_output(buf: &mut Vec<u8>, output: &TXOutput) {
+    output.consensus_encode(buf);
+}
+
+/// A script with its CompactSize length.
+fn write_script(buf: &mut Vec<u8>, script: &Script) {
+    write_var_bytes(buf, script.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_type_byte_round_trip() {
+        for hash_type in [
+            SigHashType::All,
+            SigHashType::None,
+            SigHashType::Single,
+            SigHashType::AllPlusAnyoneCanPay,
+            SigHashType::NonePlusAnyoneCanPay,
+            SigHashType::SinglePlusAnyoneCanPay,
+        ] {
+            assert_eq!(SigHashType::from_u8(hash_type.to_u8()), Some(hash_type));
+        }
+        assert_eq!(SigHashType::from_u8(SIGHASH_DEFAULT), None);
+        assert_eq!(SigHashType::Default.outputs(), SigHashType::All);
+        assert!(!SigHashType::Default.is_anyone_can_pay());
+        assert_eq!(SigHashType::from_u8(0x04), None);
+        assert_eq!(SigHashType::from_u8(0x81 | 0x40), None);
+        assert!(SigHashType::SinglePlusAnyoneCanPay.is_anyone_can_pay());
+        assert_eq!(
+            SigHashType::SinglePlusAnyoneCanPay.outputs(),
+            SigHashType::Single
+        );
+    }
+
+    #[test]
+    fn test_split_signature() {
+        let bare = [7; SCHNORR_SIGNATURE_SIZE];
+        assert_eq!(
+            SigHashType::split_signature(&bare),
+            Some((&bare[..], SigHashType::Default))
+        );
+        let mut typed = bare.to_vec();
+        typed.push(SIGHASH_NONE | SIGHASH_ANYONECANPAY);
+        assert_eq!(
+            SigHashType::split_signature(&typed),
+            Some((&bare[..], SigHashType::NonePlusAnyoneCanPay))
+        );
+        typed[SCHNORR_SIGNATURE_SIZE] = 0x05;
+        assert_eq!(SigHashType::split_signature(&typed), None);
+        assert_eq!(SigHashType::split_signature(&bare[..10]), None);
+    }
+}
@@ -0,0 +1,313 @@
+//! Standard script templates (Bitcoin Core: script/solver.cpp, script/sign.cpp)
+//!
+//! - Pay-to-pubkey-hash: `OP_DUP OP_SHA256 <pub key hash> OP_EQUALVERIFY OP_CHECKSIG`,
+//!   unlocked by `<signature> <pub key>`. The hash is the one wallet addresses encode
+//!   (`hash_pub_key`), so every output that only records a `pub_key_hash` is treated as
+//!   this template.
+//! - Pay-to-taproot (key path): `OP_1 <32-byte x-only output key>`, unlocked by
+//!   `<signature>`. The output key is the wallet's key itself; there is no tweak and no
+//!   script path.
+//! - Multisig: `OP_m <key_1> .. <key_n> OP_n OP_CHECKMULTISIG`, unlocked by one signature
+//!   slot per key, in key order, with the slots of keys that do not sign left empty.
+//! - Pay-to-script-hash: `OP_SHA256 <script hash> OP_EQUAL`, unlocked by the pushes the
+//!   redeem script needs followed by the redeem script. Multisig addresses pay to the hash
+//!   of a multisig redeem script.
+//! - Null data: `OP_RETURN <data>`, which cannot be spent and only carries data (see
+//!   `TXOutput::new_data_carrier`).
+
+use crate::error::{BtcError, Result};
+use crate::script::opcodes::*;
+use crate::script::scriptbuf::{Instruction, Script};
+
+/// Size of a BIP340 x-only public key, the program of a taproot output.
+pub const TAPROOT_OUTPUT_KEY_SIZE: usize = 32;
+/// Size of the compressed public keys of a multisig script.
+pub const COMPRESSED_PUBLIC_KEY_SIZE: usize = 33;
+/// Most keys of a multisig redeem script, so that the script fits in a single push
+/// (Bitcoin Core's `MAX_P2SH_SIGOPS` for standard P2SH multisig).
+pub const MAX_MULTISIG_KEYS: usize = 15;
+
+/// Kind of locking script (Bitcoin Core's `TxoutType`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxoutType {
+    NonStandard,
+    PubKeyHash,
+    ScriptHash,
+    MultiSig,
+    NullData,
+    WitnessV1Taproot,
+}
+
+/// Classify a locking script and extract its data (Bitcoin Core's `Solver`).
+///
+/// # Returns
+///
+/// The template and its solutions: the public key hash for `PubKeyHash`, the script hash
+/// for `ScriptHash`, `m`, the keys and `n` for `MultiSig`, the output key for
+/// `WitnessV1Taproot`, the pushed data for `NullData`, nothing for `NonStandard`.
+pub fn solver(script_pubkey: &Script) -> (TxoutType, Vec<Vec<u8>>) {
+    let instructions: Option<Vec<Instruction>> =
+        script_pubkey.instructions().map(|i| i.ok()).collect();
+    match instructions.as_deref() {
+        Some(
+            [
+                Instruction::Op(OP_DUP),
+                Instruction::Op(OP_SHA256),
+                Instruction::Push(hash),
+                Instruction::Op(OP_EQUALVERIFY),
+                Instruction::Op(OP_CHECKSIG),
+            ],
+        ) if !hash.is_empty() => (TxoutType::PubKeyHash, vec![hash.to_vec()]),
+        Some(
+            [
+                Instruction::Op(OP_SHA256),
+                Instruction::Push(hash),
+                Instruction::Op(OP_EQUAL),
+            ],
+        ) if hash.len() == SCRIPT_HASH_SIZE => (TxoutType::ScriptHash, vec![hash.to_vec()]),
+        Some(
+            [
+                Instruction::Push(required),
+                pub_keys @ ..,
+                Instruction::Push(key_count),
+                Instruction::Op(OP_CHECKMULTISIG),
+            ],
+        ) if is_small_integer(script_pubkey.as_bytes()[0])
+            && is_small_integer(script_pubkey.as_bytes()[script_pubkey.len() - 2])
+            && (1..=key_count[0]).contains(&required[0])
+            && key_count[0] as usize == pub_keys.len()
+            && pub_keys.iter().all(|key| {
+                matches!(key, Instruction::Push(key) if key.len() == COMPRESSED_PUBLIC_KEY_SIZE)
+            }) =>
+        {
+            let mut solutions = vec![required.to_vec()];
+            for key in pub_keys {
+                if let Instruction::Push(key) = key {
+                    solutions.push(key.to_vec());
+                }
+            }
+            solutions.push(key_count.to_vec());
+            (TxoutType::MultiSig, solutions)
+        }
+        Some([Instruction::Op(OP_RETURN), pushes @ ..])
+            if pushes
+                .iter()
+                .all(|push| matches!(push, Instruction::Push(_))) =>
+        {
+            let data = pushes
+                .iter()
+                .filter_map(|push| match push {
+                    Instruction::Push(data) => Some(data.to_vec()),
+                    Instruction::Op(_) => None,
+                })
+                .collect();
+            (TxoutType::NullData, data)
+        }
+        Some([Instruction::Push(version), Instruction::Push(program)])
+            if script_pubkey.as_bytes()[0] == OP_1
+                && version == &[1]
+                && program.len() == TAPROOT_OUTPUT_KEY_SIZE =>
+        {
+            (TxoutType::WitnessV1Taproot, vec![program.to_vec()])
+        }
+        _ => (TxoutType::NonStandard, vec![]),
+    }
+}
+
+/// Size of the hash a pay-to-script-hash output commits to (SHA-256).
+pub const SCRIPT_HASH_SIZE: usize = 32;
+
+/// Whether `opcode` is one of `OP_1`..`OP_16`.
+fn is_small_integer(opcode: u8) -> bool {
+    (OP_1..=OP_16).contains(&opcode)
+}
+
+/// Locking script paying to the owner of `pub_key_hash`.
+pub fn p2pkh_script(pub_key_hash: &[u8]) -> Script {
+    Script::new()
+        .push_opcode(OP_DUP)
+        .push_opcode(OP_SHA256)
+        .push_slice(pub_key_hash)
+        .push_opcode(OP_EQUALVERIFY)
+        .push_opcode(OP_CHECKSIG)
+}
+
+/// Locking script paying to the 32-byte x-only `output_key`.
+pub fn p2tr_script(output_key: &[u8]) -> Script {
+    Script::new().push_opcode(OP_1).push_slice(output_key)
+}
+
+/// Locking script paying to the redeem script hashing to `script_hash`.
+pub fn p2sh_script(script_hash: &[u8]) -> Script {
+    Script::new()
+        .push_opcode(OP_SHA256)
+        .push_slice(script_hash)
+        .push_opcode(OP_EQUAL)
+}
+
+/// Multisig script requiring `required` signatures by the 33-byte compressed
+/// `pub_keys` (Bitcoin Core's `GetScriptForMultisig`).
+///
+/// # Errors
+///
+/// `InvalidMultisig` unless `1 <= required <= pub_keys.len() <= MAX_MULTISIG_KEYS` and
+/// every key is a compressed public key.
+pub fn multisig_script(required: usize, pub_keys: &[Vec<u8>]) -> Result<Script> {
+    if required == 0 || required > pub_keys.len() || pub_keys.len() > MAX_MULTISIG_KEYS {
+        return Err(BtcError::InvalidMultisig(format!(
+            "{}-of-{} (at most {} keys)",
+            required,
+            pub_keys.len(),
+            MAX_MULTISIG_KEYS
+        )));
+    }
+    if pub_keys
+        .iter()
+        .any(|key| key.len() != COMPRESSED_PUBLIC_KEY_SIZE)
+    {
+        return Err(BtcError::InvalidMultisig(
+            "keys must be compressed public keys".to_string(),
+        ));
+    }
+    let script = pub_keys.iter().fold(
+        Script::new().push_opcode(OP_1 + required as u8 - 1),
+        |script, key| script.push_slice(key),
+    );
+    Ok(script
+        .push_opcode(OP_1 + pub_keys.len() as u8 - 1)
+        .push_opcode(OP_CHECKMULTISIG))
+}
+
+/// Unlocking script of a pay-to-script-hash multisig input: one slot per key of
+/// `redeem_script`, empty where the key has not signed yet, followed by the redeem script.
+pub fn multisig_script_sig(signatures: &[Vec<u8>], redeem_script: &Script) -> Script {
+    signatures
+        .iter()
+        .fold(Script::new(), |script, signature| {
+            script.push_slice(signature)
+        })
+        .push_slice(redeem_script.as_bytes())
+}
+
+/// Split a [`multisig_script_sig`] back into its signature slots and redeem script.
+///
+/// # Returns
+///
+/// `None` if `script_sig` does not end in a multisig redeem script preceded by one slot
+/// per key.
+pub fn parse_multisig_script_sig(script_sig: &Script) -> Option<(Vec<Vec<u8>>, Script)> {
+    let mut pushes = vec![];
+    for instruction in script_sig.instructions() {
+        match instruction.ok()? {
+            Instruction::Push(data) => pushes.push(data.to_vec()),
+            Instruction::Op(_) => return None,
+        }
+    }
+    let redeem_script = Script::from_bytes(pushes.pop()?);
+    let (TxoutType::MultiSig, solutions) = solver(&redeem_script) else {
+        return None;
+    };
+    (pushes.len() == solutions.len() - 2).then_some((pushes, redeem_script))
+}
+
+/// Unlocking script spending `script_pubkey` with `signature` by `pub_key`
+/// (the standard-template part of Bitcoin Core's `ProduceSignature`).
+///
+/// # Errors
+///
+/// `TransactionSignatureError` if `script_pubkey` is not a standard template.
+pub fn build_script_sig(
+    script_pubkey: &Script,
+    signature: &[u8],
+    pub_key: &[u8],
+) -> Result<Script> {
+    match solver(script_pubkey).0 {
+        TxoutType::PubKeyHash => Ok(Script::new().push_slice(signature).push_slice(pub_key)),
+        TxoutType::WitnessV1Taproot => Ok(Script::new().push_slice(signature)),
+        TxoutType::ScriptHash
+        | TxoutType::MultiSig
+        | TxoutType::NullData
+        | TxoutType::NonStandard => Err(BtcError::TransactionSignatureError(format!(
+            "Cannot sign script {} with a single key",
+            script_pubkey
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solver_recognizes_templates() {
+        let hash = vec![9; 32];
+        assert_eq!(
+            solver(&p2pkh_script(&hash)),
+            (TxoutType::PubKeyHash, vec![hash.clone()])
+        );
+        let key = vec![4; TAPROOT_OUTPUT_KEY_SIZE];
+        assert_eq!(
+            solver(&p2tr_script(&key)),
+            (TxoutType::WitnessV1Taproot, vec![key.clone()])
+        );
+
+        // Right shape, wrong program size or a pushed 1 instead of OP_1
+        assert_eq!(solver(&p2tr_script(&[4; 20])).0, TxoutType::NonStandard);
+        let pushed_one = Script::new().push_slice(&[1]).push_slice(&key);
+        assert_eq!(solver(&pushed_one).0, TxoutType::NonStandard);
+        assert_eq!(solver(&Script::new()).0, TxoutType::NonStandard);
+
+        let null_data = Script::new().push_opcode(OP_RETURN).push_slice(b"anchor");
+        assert_eq!(
+            solver(&null_data),
+            (TxoutType::NullData, vec![b"anchor".to_vec()])
+        );
+        let bare_op_return = Script::new().push_opcode(OP_RETURN);
+        assert_eq!(solver(&bare_op_return), (TxoutType::NullData, vec![]));
+        // Only pushes may follow OP_RETURN
+        let with_opcode = Script::new().push_opcode(OP_RETURN).push_opcode(OP_DUP);
+        assert_eq!(solver(&with_opcode).0, TxoutType::NonStandard);
+    }
+
+    #[test]
+    fn test_build_script_sig() {
+        let sig = vec![7; 64];
+        let pub_key = vec![2; 33];
+        let p2pkh = build_script_sig(&p2pkh_script(&[9; 32]), &sig, &pub_key).unwrap();
+        assert_eq!(p2pkh, Script::new().push_slice(&sig).push_slice(&pub_key));
+        let p2tr = build_script_sig(&p2tr_script(&[4; 32]), &sig, &pub_key).unwrap();
+        assert_eq!(p2tr, Script::new().push_slice(&sig));
+        assert!(build_script_sig(&Script::new().push_opcode(OP_1), &sig, &pub_key).is_err());
+    }
+
+    #[test]
+    fn test_multisig_templates() {
+        let keys: Vec<Vec<u8>> = (1..=3).map(|tag| vec![tag; 33]).collect();
+        let redeem_script = multisig_script(2, &keys).unwrap();
+        let (txout_type, solutions) = solver(&redeem_script);
+        assert_eq!(txout_type, TxoutType::MultiSig);
+        assert_eq!(solutions.first(), Some(&vec![2]));
+        assert_eq!(&solutions[1..4], keys.as_slice());
+        assert_eq!(solutions.last(), Some(&vec![3]));
+        assert_eq!(
+            solver(&p2sh_script(&[9; 32])),
+            (TxoutType::ScriptHash, vec![vec![9; 32]])
+        );
+
+        assert!(multisig_script(0, &keys).is_err());
+        assert!(multisig_script(4, &keys).is_err());
+        assert!(multisig_script(1, &[vec![1; 32]]).is_err());
+        assert!(multisig_script(1, &vec![vec![1; 33]; MAX_MULTISIG_KEYS + 1]).is_err());
+        let largest = multisig_script(1, &vec![vec![1; 33]; MAX_MULTISIG_KEYS]).unwrap();
+        assert!(largest.len() <= crate::script::scriptbuf::MAX_SCRIPT_ELEMENT_SIZE);
+
+        let slots = vec![vec![7; 64], vec![], vec![8; 64]];
+        let script_sig = multisig_script_sig(&slots, &redeem_script);
+        assert_eq!(
+            parse_multisig_script_sig(&script_sig),
+            Some((slots, redeem_script.clone()))
+        );
+        let missing_slot = multisig_script_sig(&[vec![7; 64]], &redeem_script);
+        assert_eq!(parse_multisig_script_sig(&missing_slot), None);
+    }
+}
@@ -0,0 +1,266 @@
+//! Consensus serialization (Bitcoin Core alignment: serialize.h)
+//!
+//! Blocks and transactions are hashed, stored and relayed in an explicit byte encoding
+//! that does not depend on how the Rust structs are laid out, so that other
+//! implementations can produce the same transaction ids and block bytes:
+//!
+//! - Integers are fixed-width little-endian (`u32`, `u64`, `i64`).
+//! - Counts and lengths are CompactSize varints: one byte below `0xfd`, otherwise a
+//!   `0xfd`/`0xfe`/`0xff` marker followed by a 2, 4 or 8 byte little-endian value. Only
+//!   the shortest form is accepted.
+//! - Byte strings (hashes, keys, signatures, scripts) are a CompactSize length followed by
+//!   the bytes.
+//!
+//! The layouts of the individual structures are documented on their `Encodable`
+//! implementations (`Transaction`, `TXInput`, `TXOutput`, `BlockHeader` and `Block`).
+
+use crate::error::{BtcError, Result};
+
+/// Largest length or count a CompactSize may carry when decoding (Bitcoin Core's
+/// `MAX_SIZE`).
+pub const MAX_SIZE: u64 = 0x0200_0000;
+
+/// A value with a consensus encoding (Bitcoin Core's `Serialize`).
+pub trait Encodable {
+    /// Append the encoding of `self` to `buf`.
+    fn consensus_encode(&self, buf: &mut Vec<u8>);
+}
+
+/// A value that can be read back from its consensus encoding (Bitcoin Core's
+/// `Unserialize`).
+pub trait Decodable: Sized {
+    /// Read one value from `reader`.
+    fn consensus_decode(reader: &mut Reader<'_>) -> Result<Self>;
+}
+
+/// Encode `value` into a new buffer.
+pub fn serialize<T: Encodable>(value: &T) -> Vec<u8> {
+    let mut buf = vec![];
+    value.consensus_encode(&mut buf);
+    buf
+}
+
+/// Decode a value that must span all of `bytes`.
+///
+/// # Returns
+///
+/// The value, or `ConsensusDecodeError` if the bytes are malformed or not fully consumed.
+pub fn deserialize<T: Decodable>(bytes: &[u8]) -> Result<T> {
+    let mut reader = Reader::new(bytes);
+    let value = T::consensus_decode(&mut reader)?;
+    if !reader.is_empty() {
+        return Err(BtcError::ConsensusDecodeError(format!(
+            "{} trailing bytes",
+            reader.remaining()
+        )));
+    }
+    Ok(value)
+}
+
+/// Append a CompactSize (Bitcoin Core's `WriteCompactSize`).
+pub fn write_compact_size(buf: &mut Vec<u8>, n: u64) {
+    match n {
+        0..0xfd => buf.push(n as u8),
+        0xfd..=0xffff => {
+            buf.push(0xfd);
+            buf.extend_from_slice(&(n as u16).to_le_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            buf.push(0xfe);
+            buf.extend_from_slice(&(n as u32).to_le_bytes());
+        }
+        _ => {
+            buf.push(0xff);
+            buf.extend_from_slice(&n.to_le_bytes());
+        }
+    }
+}
+
+/// Append a length-prefixed byte string.
+pub fn write_var_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    write_compact_size(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+/// Append a CompactSize count followed by every item.
+pub fn write_vec<T: Encodable>(buf: &mut Vec<u8>, items: &[T]) {
+    write_compact_size(buf, items.len() as u64);
+    for item in items {
+        item.consensus_encode(buf);
+    }
+}
+
+/// A cursor over encoded bytes.
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Reader<'a> {
+        Reader { bytes }
+    }
+
+    /// Number of bytes not read yet.
+    pub fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Read exactly `len` bytes.
+    pub fn read_slice(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.bytes.len() {
+            return Err(BtcError::ConsensusDecodeError(format!(
+                "unexpected end of data: need {} bytes, have {}",
+                len,
+                self.bytes.len()
+            )));
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.read_slice(N)?);
+        Ok(array)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_i64(&mut self) -> Result<i64> {
+        Ok(i64::from_le_bytes(self.read_array()?))
+    }
+
+    /// Read a CompactSize (Bitcoin Core's `ReadCompactSize`), rejecting non-canonical
+    /// encodings and values above `MAX_SIZE`.
+    pub fn read_compact_size(&mut self) -> Result<u64> {
+        let (n, min) = match self.read_u8()? {
+            0xfd => (u64::from(self.read_u16()?), 0xfd),
+            0xfe => (u64::from(self.read_u32()?), 0x1_0000),
+            0xff => (self.read_u64()?, 0x1_0000_0000),
+            n => return Ok(u64::from(n)),
+        };
+        if n < min {
+            return Err(BtcError::ConsensusDecodeError(
+                "non-canonical CompactSize".to_string(),
+            ));
+        }
+        if n > MAX_SIZE {
+            return Err(BtcError::ConsensusDecodeError(format!(
+                "CompactSize {} exceeds MAX_SIZE",
+                n
+            )));
+        }
+        Ok(n)
+    }
+
+    /// Read a length-prefixed byte string.
+    pub fn read_var_bytes(&mut self) -> Result<Vec<u8>> {
+        let len = self.read_compact_size()? as usize;
+        Ok(self.read_slice(len)?.to_vec())
+    }
+
+    /// Read a length-prefixed UTF-8 string.
+    pub fn read_var_string(&mut self) -> Result<String> {
+        String::from_utf8(self.read_var_bytes()?)
+            .map_err(|e| BtcError::ConsensusDecodeError(e.to_string()))
+    }
+
+    /// Read a CompactSize count followed by that many items.
+    pub fn read_vec<T: Decodable>(&mut self) -> Result<Vec<T>> {
+        let count = self.read_compact_size()? as usize;
+        // Every item takes at least one byte, so a count above the remaining bytes is
+        // malformed; checking first avoids allocating for it
+        if count > self.remaining() {
+            return Err(BtcError::ConsensusDecodeError(format!(
+                "{} items do not fit in {} bytes",
+                count,
+                self.remaining()
+            )));
+        }
+        let mut items = Vec::with_capacity(count);
+        for _ in 0..count {
+            items.push(T::consensus_decode(self)?);
+        }
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compact_size(n: u64) -> Vec<u8> {
+        let mut buf = vec![];
+        write_compact_size(&mut buf, n);
+        buf
+    }
+
+    #[test]
+    fn test_compact_size_encoding() {
+        assert_eq!(compact_size(0), vec![0x00]);
+        assert_eq!(compact_size(0xfc), vec![0xfc]);
+        assert_eq!(compact_size(0xfd), vec![0xfd, 0xfd, 0x00]);
+        assert_eq!(compact_size(0xffff), vec![0xfd, 0xff, 0xff]);
+        assert_eq!(compact_size(0x1_0000), vec![0xfe, 0x00, 0x00, 0x01, 0x00]);
+        assert_eq!(
+            compact_size(0x1_0000_0000),
+            vec![0xff, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00]
+        );
+        for n in [0, 0xfc, 0xfd, 0xffff, 0x1_0000, MAX_SIZE] {
+            assert_eq!(
+                Reader::new(&compact_size(n)).read_compact_size().unwrap(),
+                n
+            );
+        }
+    }
+
+    #[test]
+    fn test_compact_size_rejects_non_canonical_and_oversized() {
+        assert!(
+            Reader::new(&[0xfd, 0xfc, 0x00])
+                .read_compact_size()
+                .is_err()
+        );
+        assert!(
+            Reader::new(&[0xfe, 0xff, 0xff, 0x00, 0x00])
+                .read_compact_size()
+                .is_err()
+        );
+        assert!(
+            Reader::new(&compact_size(MAX_SIZE + 1))
+                .read_compact_size()
+                .is_err()
+        );
+        assert!(Reader::new(&[0xfd, 0x00]).read_compact_size().is_err());
+    }
+
+    #[test]
+    fn test_var_bytes_need_their_length() {
+        let mut buf = vec![];
+        write_var_bytes(&mut buf, &[1, 2, 3]);
+        assert_eq!(buf, vec![3, 1, 2, 3]);
+        let mut reader = Reader::new(&buf);
+        assert_eq!(reader.read_var_bytes().unwrap(), vec![1, 2, 3]);
+        assert!(reader.is_empty());
+        assert!(Reader::new(&buf[..3]).read_var_bytes().is_err());
+    }
+}
@@ -1,20 +1,28 @@
+use crate::chain::coins::Coins;
+use crate::chain::{BlockchainService, UTXOSet};
+use crate::config::GLOBAL_CONFIG;
+use crate::consensus::Amount;
+use crate::consensus::validation::{MEDIAN_TIME_SPAN, median_time_past};
 use crate::error::{BtcError, Result};
-use crate::primitives::block::{Block, GENESIS_BLOCK_PRE_BLOCK_HASH};
+use crate::pow::{MiningOptions, calculate_next_work_required};
+use crate::primitives::block::{Block, GENESIS_BLOCK_PRE_BLOCK_HASH, block_hash_from_hex};
 use crate::primitives::blockchain::Blockchain;
 use crate::primitives::transaction::{
-    TXOutput, Transaction, TxInputSummary, TxOutputSummary, TxSummary, WalletTransaction,
+    Transaction, TxInputSummary, TxOutputSummary, TxSummary, WalletTransaction,
 };
 use crate::wallet::WalletAddress;
 use crate::wallet::{convert_address, hash_pub_key};
-use sled::transaction::{TransactionResult, UnabortableTransactionError};
+use data_encoding::HEXLOWER;
+use sled::transaction::TransactionResult;
 use sled::{Db, IVec, Tree};
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::env;
 use std::env::current_dir;
 use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 use tokio::sync::RwLock as TokioRwLock;
-use tracing::info;
+use tracing::{info, warn};
 
 const DEFAULT_TIP_BLOCK_HASH_KEY: &str = "tip_block_hash";
 const DEFAULT_EMPTY_TIP_BLOCK_HASH_VALUE: &str = "empty";
@@ -45,14 +53,13 @@ impl BlockchainFileSystem {
             .map_err(|e| BtcError::GetBlockchainError(e.to_string()))?;
         let mut genesis_block_to_index: Option<Block> = None;
         let tip_hash = if let Some(data) = data {
-            String::from_utf8(data.to_vec())
-                .map_err(|e| BtcError::BlockChainTipHashError(e.to_string()))?
+            HEXLOWER.encode(data.as_ref())
         } else {
-            let coinbase_tx = Transaction::new_coinbase_tx(genesis_address)?;
+            let coinbase_tx = Transaction::new_genesis_coinbase_tx(genesis_address)?;
             let block = Block::generate_genesis_block(&coinbase_tx);
             Self::update_blocks_tree(&blocks_tree, &block).await?;
             genesis_block_to_index = Some(block.clone());
-            String::from(block.get_hash())
+            block.get_hash()
         };
 
         let blockchain_fs = BlockchainFileSystem {
@@ -73,11 +80,11 @@ impl BlockchainFileSystem {
     }
 
     async fn update_blocks_tree(blocks_tree: &Tree, block: &Block) -> Result<()> {
-        let block_hash = block.get_hash();
+        let block_hash = block.get_hash_bytes();
         let block_ivec = IVec::try_from(block.clone())?;
         let transaction_result: TransactionResult<(), ()> = blocks_tree.transaction(|tx_db| {
-            let _ = tx_db.insert(block_hash, block_ivec.clone())?;
-            let _ = tx_db.insert(DEFAULT_TIP_BLOCK_HASH_KEY, block_hash)?;
+            let _ = tx_db.insert(block_hash.as_slice(), block_ivec.clone())?;
+            let _ = tx_db.insert(DEFAULT_TIP_BLOCK_HASH_KEY, block_hash.as_slice())?;
             Ok(())
         });
         transaction_result
@@ -104,8 +111,7 @@ impl BlockchainFileSystem {
                 "No existing blockchain found. Connect to a blcock chain cluster first."
                     .to_string(),
             ))?;
-        let tip_hash = String::from_utf8(tip_bytes.to_vec())
-            .map_err(|e| BtcError::BlockChainTipHashError(e.to_string()))?;
+        let tip_hash = HEXLOWER.encode(tip_bytes.as_ref());
         Ok(BlockchainFileSystem {
             blockchain: Blockchain {
                 tip_hash: Arc::new(TokioRwLock::new(tip_hash)),
@@ -157,13 +163,20 @@ impl BlockchainFileSystem {
 
     pub async fn get_last_block(&self) -> Result<Option<Block>> {
         let tip_hash = self.get_tip_hash().await?;
-        let block = self.get_block(tip_hash.as_bytes()).await?;
+        let block = self.get_block_by_hash(&tip_hash).await?;
         Ok(block)
     }
 
-    pub async fn get_block_by_hash(&self, block_hash: &[u8]) -> Result<Option<Block>> {
-        let block = self.get_block(block_hash).await?;
-        Ok(block)
+    /// Look up a block by the hex form of its hash (see `Block::get_hash`).
+    ///
+    /// # Returns
+    ///
+    /// The block, or `None` if it is not stored or `block_hash` is not a valid hash.
+    pub async fn get_block_by_hash(&self, block_hash: &str) -> Result<Option<Block>> {
+        match block_hash_from_hex(block_hash) {
+            Some(block_hash) => self.get_block(&block_hash).await,
+            None => Ok(None),
+        }
     }
 
     fn set_not_empty(&mut self) {
@@ -183,7 +196,7 @@ impl BlockchainFileSystem {
     /// (strictly higher than the current tip).
     ///
     /// Remote blocks received from the network go through `add_block()` instead, which
-    /// runs the full three-level consensus hierarchy (height → work → tie-break).
+    /// runs the most-work fork choice.
     ///
     /// The race condition where two nodes mine simultaneously is handled by:
     /// - **Fix 1**: UTXO rollback correctly restores fully-spent outputs during reorg
@@ -192,9 +205,52 @@ impl BlockchainFileSystem {
     /// - **Stale mining check**: `chainstate.rs::mine_block` re-validates inputs under
     ///   the write lock before calling this method
     pub async fn mine_block(&self, transactions: &[Transaction]) -> Result<Block> {
+        let never_cancelled = AtomicBool::new(false);
+        let options = MiningOptions::new(GLOBAL_CONFIG.get_mining_threads(), &never_cancelled);
+        self.mine_block_with_options(transactions, &options).await
+    }
+
+    /// Mine a new block as `mine_block` does, with the nonce search run as `options`
+    /// describe. The block is timestamped at least one past the median time past of the
+    /// tip, even when the clock is mocked. A cancelled search leaves the chain unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `transactions` - Transactions of the new block, coinbase first.
+    /// * `options` - Worker count, nonce bound and cancellation token of the search.
+    pub async fn mine_block_with_options(
+        &self,
+        transactions: &[Transaction],
+        options: &MiningOptions<'_>,
+    ) -> Result<Block> {
         let best_height = self.get_best_height().await?;
+        let tip_hash = self.get_tip_hash().await?;
+        let bits = self
+            .get_next_work_required(&tip_hash)
+            .await?
+            .ok_or_else(|| {
+                BtcError::GetBlockchainError(format!(
+                    "retarget window for tip {} is incomplete",
+                    tip_hash
+                ))
+            })?;
 
-        let block = Block::new_block(self.get_tip_hash().await?, transactions, best_height + 1);
+        // Stay past the median time past even when the clock stands still (mock time)
+        let min_time = self
+            .get_median_time_past(&tip_hash)
+            .await?
+            .map_or(0, |median_time_past| median_time_past + 1);
+        let options = options.with_min_time(min_time);
+
+        let pre_block_hash = block_hash_from_hex(&tip_hash)
+            .ok_or_else(|| BtcError::BlockChainTipHashError(tip_hash.clone()))?;
+        let block = Block::new_block_with_options(
+            &pre_block_hash,
+            transactions,
+            best_height + 1,
+            bits,
+            &options,
+        )?;
         let block_hash = block.get_hash();
 
         let blocks_tree = self
@@ -203,7 +259,7 @@ impl BlockchainFileSystem {
             .open_tree(self.get_blocks_tree_path())
             .map_err(|e| BtcError::BlockchainDBconnection(e.to_string()))?;
         Self::update_blocks_tree(&blocks_tree, &block).await?;
-        self.set_tip_hash(block_hash).await?;
+        self.set_tip_hash(&block_hash).await?;
 
         // Update UTXO set when mining a block
         self.update_utxo_set(&block).await?;
@@ -214,7 +270,7 @@ impl BlockchainFileSystem {
     pub async fn iterator(&self) -> Result<BlockchainIterator> {
         let hash = self.get_tip_hash().await?;
         Ok(BlockchainIterator::new(
-            hash,
+            block_hash_from_hex(&hash).unwrap_or_default(),
             self.blockchain.db.clone(),
             self.get_blocks_tree_path(),
         ))
@@ -225,10 +281,11 @@ impl BlockchainFileSystem {
     ///
     /// # Returns
     ///
-    /// A HashMap containing transaction IDs as keys and vectors of TXOutput as values.
+    /// A HashMap containing transaction IDs as keys and the unspent outputs of each
+    /// transaction, with the height of its block, as values.
     ///
-    pub async fn find_utxo(&self) -> Result<HashMap<String, Vec<TXOutput>>> {
-        let mut utxo: HashMap<String, Vec<TXOutput>> = HashMap::new();
+    pub async fn find_utxo(&self) -> Result<HashMap<String, Coins>> {
+        let mut utxo: HashMap<String, Coins> = HashMap::new();
         let mut spent_txos: HashMap<String, Vec<usize>> = HashMap::new();
         let mut iterator = self.iterator().await?;
 
@@ -238,20 +295,11 @@ impl BlockchainFileSystem {
                 None => break,
                 Some(block) => {
                     for tx in block.get_transactions().await? {
-                        let txid_hex = tx.get_tx_id_hex();
-
-                        // Add all outputs to UTXO set
-                        for tx_out in tx.get_vout() {
-                            if utxo.contains_key(txid_hex.as_str()) {
-                                utxo.get_mut(txid_hex.as_str())
-                                    .ok_or(BtcError::UTXONotFoundError(format!(
-                                        "UTXO not found for transaction {}",
-                                        txid_hex
-                                    )))?
-                                    .push(tx_out.clone());
-                            } else {
-                                utxo.insert(txid_hex.clone(), vec![tx_out.clone()]);
-                            }
+                        // Add all spendable outputs to UTXO set, remembering the block that
+                        // created them
+                        let coins = Coins::from_transaction(tx, block.get_height());
+                        if !coins.is_pruned() {
+                            utxo.insert(tx.get_tx_id_hex(), coins);
                         }
                     }
                 }
@@ -292,21 +340,14 @@ impl BlockchainFileSystem {
             // Checks if this transaction still exists in the UTXO set
             // Gets a mutable reference to the outputs vector
             // If the transaction doesn't exist, we skip it (it was already fully spent)
-            if let Some(outputs) = utxo.get_mut(&txid_hex) {
-                // Remove spent outputs in reverse order to maintain indices
-                // Why reverse order? Because when we remove elements from a vector,
-                // the indices of subsequent elements shift down.
-                // By removing from the end first, we don't affect the indices of elements we haven't processed yet.
-                // We dont want to mess the indices of spent outputs since they are used to identify the outputs in the transaction.
-                for &spent_idx in spent_indices.iter().rev() {
-                    // The check here is just a safety check to ensure the index is valid
-                    // Prevents panic if there's a mismatch between tracked spent outputs and actual outputs
-                    if spent_idx < outputs.len() {
-                        outputs.remove(spent_idx);
-                    }
+            if let Some(coins) = utxo.get_mut(&txid_hex) {
+                // Spent outputs are nulled in place, so the indices of the others (which
+                // inputs use to identify them) do not shift
+                for &spent_idx in spent_indices.iter() {
+                    coins.spend(spent_idx);
                 }
                 // Remove empty transaction entries
-                if outputs.is_empty() {
+                if coins.is_pruned() {
                     utxo.remove(&txid_hex);
                 }
             }
@@ -315,22 +356,63 @@ impl BlockchainFileSystem {
         Ok(utxo)
     }
 
+    /// Find every transaction on the active chain that involves `address`
+    ///
+    /// Entries report the fee their transaction paid. Fees are computed from the output
+    /// values of all transactions on the chain, so the chain is walked twice: once to
+    /// collect output values, then to compute fees and collect the wallet's entries.
     pub async fn find_user_transaction(
         &self,
         address: &WalletAddress,
     ) -> Result<Vec<WalletTransaction>> {
-        let mut iterator = self.iterator().await?;
-        let mut user_transactions = Vec::new();
-        loop {
-            match iterator.next() {
-                None => break,
-                Some(block) => {
-                    for transaction in block.get_user_transactions(address).await? {
-                        user_transactions.push(transaction);
-                    }
+        let blocks: Vec<Block> = self.iterator().await?.collect();
+
+        // First pass: output values of every transaction, by transaction id
+        let mut output_values: HashMap<Vec<u8>, Vec<Amount>> = HashMap::new();
+        for block in &blocks {
+            for tx in block.get_transactions().await? {
+                output_values.insert(
+                    tx.get_id().to_vec(),
+                    tx.get_vout().iter().map(|out| out.get_value()).collect(),
+                );
+            }
+        }
+
+        // Second pass: fee (inputs − outputs) of every non-coinbase transaction
+        let mut fees: HashMap<Vec<u8>, Amount> = HashMap::new();
+        for block in &blocks {
+            for tx in block.get_transactions().await? {
+                if tx.is_coinbase() {
+                    continue;
+                }
+                let input_values: Option<Vec<Amount>> = tx
+                    .get_vin()
+                    .iter()
+                    .map(|vin| {
+                        output_values
+                            .get(vin.get_txid())
+                            .and_then(|values| values.get(vin.get_vout()).copied())
+                    })
+                    .collect();
+                let fee = input_values
+                    .and_then(Amount::checked_sum)
+                    .zip(tx.get_output_value().ok())
+                    .and_then(|(input_value, output_value)| input_value.checked_sub(output_value));
+                if let Some(fee) = fee {
+                    fees.insert(tx.get_id().to_vec(), fee);
                 }
             }
         }
+
+        let mut user_transactions = Vec::new();
+        for block in &blocks {
+            for transaction in block
+                .get_user_transactions_with_fees(address, &fees)
+                .await?
+            {
+                user_transactions.push(transaction);
+            }
+        }
         Ok(user_transactions)
     }
 
@@ -351,6 +433,42 @@ impl BlockchainFileSystem {
         Ok(None)
     }
 
+    /// Find the block on the active chain that contains the transaction `txid`
+    ///
+    /// Used to build Merkle inclusion proofs (Bitcoin Core's `gettxoutproof` without a block hash).
+    pub async fn find_transaction_block(&self, txid: &[u8]) -> Result<Option<Block>> {
+        for block in self.iterator().await? {
+            if block
+                .get_transactions()
+                .await?
+                .iter()
+                .any(|transaction| txid.eq(transaction.get_id()))
+            {
+                return Ok(Some(block));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Check whether the block with `block_hash` is part of the active chain
+    ///
+    /// Blocks on side branches stay in the database for future reorganizations,
+    /// so a block being stored does not mean it is on the best chain.
+    pub async fn is_in_best_chain(&self, block_hash: &str) -> Result<bool> {
+        let Some(block) = self.get_block_by_hash(block_hash).await? else {
+            return Ok(false);
+        };
+        for chain_block in self.iterator().await? {
+            if chain_block.get_height() < block.get_height() {
+                break;
+            }
+            if chain_block.get_hash() == block_hash {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
     pub async fn find_all_transactions(&self) -> Result<HashMap<String, TxSummary>> {
         let mut transactions = HashMap::new();
         let mut iterator = self.iterator().await?;
@@ -378,11 +496,20 @@ impl BlockchainFileSystem {
                         }
 
                         for output in tx.get_vout() {
-                            let pub_key_hash = output.get_pub_key_hash();
-                            let address =
-                                convert_address(pub_key_hash).expect("Convert address error");
-                            current_transactions_summary
-                                .add_output(TxOutputSummary::new(address, output.get_value()));
+                            // Data carrier outputs have no public key hash to make an
+                            // address from
+                            let output_summary = match output.get_data_carrier_payload() {
+                                Some(payload) => {
+                                    TxOutputSummary::new_data_carrier(output.get_value(), payload)
+                                }
+                                None => {
+                                    let pub_key_hash = output.get_pub_key_hash();
+                                    let address = convert_address(pub_key_hash)
+                                        .expect("Convert address error");
+                                    TxOutputSummary::new(address, output.get_value())
+                                }
+                            };
+                            current_transactions_summary.add_output(output_summary);
                         }
                         transactions.insert(cur_txid_hex, current_transactions_summary);
                     }
@@ -395,39 +522,37 @@ impl BlockchainFileSystem {
     /// Add a block to the blockchain using Bitcoin's consensus mechanism
     ///
     /// This function implements the core blockchain consensus mechanism that determines
-    /// whether to accept or reject a new block. The consensus follows Bitcoin's "longest
-    /// chain rule" with deterministic tie-breaking to ensure all nodes reach the same
-    /// decision about which blocks to accept.
+    /// whether to accept or reject a new block. The consensus follows Bitcoin's "most work
+    /// chain rule": the active chain is the branch with the most cumulative proof-of-work.
     ///
     /// ## Consensus Algorithm Overview:
     ///
-    /// 1. **Longest Chain Rule**: Blocks with higher height are always accepted
-    ///    - Higher height = more cumulative proof-of-work = stronger chain
-    ///    - This is the primary consensus rule that prevents chain splits
+    /// 1. **Most Work Rule**: A block whose branch has more cumulative work than the
+    ///    active chain (`get_chain_work`) becomes the tip
+    ///    - Extending the tip connects the block directly
+    ///    - A block on another branch triggers a reorganization
+    ///    - Height does not matter: many easy blocks do not outweigh fewer hard ones
     ///
-    /// 2. **Cumulative Work Comparison**: When heights are equal, compare total work
-    ///    - Higher cumulative work = stronger chain = accepted
-    ///    - Lower cumulative work = weaker chain = rejected
+    /// 2. **First Seen Wins**: A branch with exactly as much work as the active chain does
+    ///    not replace it, so the block a node saw first keeps the tip
+    ///    - Nodes converge once the next block extends one of the branches
     ///
-    /// 3. **Deterministic Tie-Breaking**: When work is equal, use hash comparison
-    ///    - Ensures all nodes reach the same decision
-    ///    - Prevents network splits and consensus failures
+    /// 3. **Weaker Branches**: Blocks on a branch with less work are stored but not
+    ///    activated, so a later block on their branch can still reorganize onto them
     ///
     /// ## Network Convergence:
     ///
     /// When multiple nodes mine competing blocks simultaneously:
-    /// - All nodes apply the same consensus rules
-    /// - All nodes reach the same decision about which block wins
-    /// - The network converges on a single canonical chain
-    /// - Mining rewards are distributed correctly (only winning blocks get rewards)
+    /// - Each node keeps the block it saw first
+    /// - The next block found extends one branch and gives it more work
+    /// - All nodes then reorganize onto that branch
+    /// - Mining rewards are distributed correctly (only blocks on the active chain get rewards)
     ///
-    /// ## Database Transaction Safety:
+    /// ## Database Safety:
     ///
-    /// The function uses database transactions to ensure atomicity:
     /// - Blocks are added to database before consensus decisions
-    /// - Rejected blocks are removed from database
     /// - UTXO set is updated only for accepted blocks
-    /// - Chain reorganization is atomic
+    /// - The stored tip is updated only after the block has been connected
     ///
     /// ## Error Handling:
     ///
@@ -454,7 +579,8 @@ impl BlockchainFileSystem {
     /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
     /// let wallet_address = WalletAddress::validate("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa".to_string())?;
     /// let mut blockchain = BlockchainFileSystem::create_blockchain(&wallet_address).await?;
-    /// let block = Block::new_block("prev_hash".to_string(), &[], 1);
+    /// let tip = blockchain.get_last_block().await?.expect("genesis block");
+    /// let block = Block::new_block(&tip.get_hash_bytes(), &[], tip.get_height() + 1);
     /// blockchain.add_block(&block).await?;
     /// # Ok(())
     /// # }
@@ -467,13 +593,33 @@ impl BlockchainFileSystem {
             .open_tree(self.get_blocks_tree_path())
             .map_err(|e| BtcError::OpenBlockchainTreeError(e.to_string()))?;
 
+        // DIFFICULTY CHECK: The block must commit to the target our retarget rules expect.
+        // If the parent (or part of its retarget window) is not stored yet we cannot compute
+        // the expected target; such blocks are kept for later reorganizations as before.
+        if let Some(expected_bits) = self
+            .get_next_work_required(&new_block.get_pre_block_hash())
+            .await?
+            && new_block.get_bits() != expected_bits
+        {
+            info!(
+                "Block {} rejected: bits {:#010x} do not match expected {:#010x}",
+                new_block.get_hash(),
+                new_block.get_bits(),
+                expected_bits
+            );
+            return Err(BtcError::InvalidBlockBits(
+                expected_bits,
+                new_block.get_bits(),
+            ));
+        }
+
         if self.is_empty() {
             info!("Blockchain is empty, adding block");
 
             self.set_not_empty();
             info!("Blockchain is now not empty");
             Self::update_blocks_tree(&block_tree, new_block).await?;
-            self.set_tip_hash(new_block.get_hash()).await?;
+            self.set_tip_hash(&new_block.get_hash()).await?;
 
             // Update UTXO set when adding block to empty blockchain
             self.update_utxo_set(new_block).await?;
@@ -487,7 +633,7 @@ impl BlockchainFileSystem {
         } else {
             // Check if block already exists
             let block_bytes = block_tree
-                .get(new_block.get_hash())
+                .get(new_block.get_hash_bytes())
                 .map_err(|e| BtcError::GetBlockchainError(e.to_string()))?;
             // If the block is already in the blockchain, return Ok(())
             if block_bytes.is_some() {
@@ -499,228 +645,81 @@ impl BlockchainFileSystem {
             // B) "Not already spent"
             // See *Building a Blockchain in Rust*, Chapter "Block Acceptance"
             // (Whitepaper §5, Step 5).
-            let block_bytes = new_block.serialize()?;
-            let tip_hash = self.get_tip_hash().await?;
-            let transaction_result: TransactionResult<(), ()> =
-                block_tree.transaction(|transaction| {
-                    let _ = transaction.insert(new_block.get_hash(), block_bytes.clone())?;
-
-                    let tip_block_bytes = transaction.get(tip_hash.clone())?.ok_or(
-                        UnabortableTransactionError::Storage(sled::Error::CollectionNotFound(
-                            IVec::from(tip_hash.as_bytes()),
-                        )),
-                    )?;
-
-                    let tip_block = Block::deserialize(tip_block_bytes.as_ref()).map_err(|e| {
-                        UnabortableTransactionError::Storage(sled::Error::Unsupported(
-                            e.to_string(),
-                        ))
-                    })?;
-
-                    if self.is_empty() || new_block.get_height() > tip_block.get_height() {
-                        info!("Block height is higher, updating tip in transaction");
-                        let _ =
-                            transaction.insert(DEFAULT_TIP_BLOCK_HASH_KEY, new_block.get_hash())?;
-                    } else {
-                        info!("Block height is same or lower, will use tie-breaking logic");
-                        // See tie-breaking logic/consensus logic after the database transaction.
-                        // The consensus logic is done in a separate section below since its not part of the database transacion.
-                        // The consensus logic modifies the blockchain state (calls set_tip_hash and reorganize_chain)
-                        // The transaction closure is synchronous, but the consensus logic needs to be async (calls get_chain_work, reorganize_chain, etc.)
-                        info!(
-                            "Block {:?} not added because its height is less than mine",
-                            new_block.get_hash()
-                        );
-                    }
-
-                    Ok(())
-                });
+            let block_hash = new_block.get_hash_bytes();
+            let current_tip = self.get_tip_hash().await?;
 
-            // Check if transaction succeeded
-            if transaction_result.is_err() {
-                return Err(BtcError::BlockchainDBconnection(format!(
-                    "Transaction failed: {:?}",
-                    transaction_result
-                )));
-            }
+            // Store the block whatever the fork choice decides, so a later block on its
+            // branch can still reorganize onto it.
+            block_tree
+                .insert(block_hash.as_slice(), new_block.serialize()?)
+                .map_err(|e| BtcError::BlockchainDBconnection(e.to_string()))?;
 
             // BLOCKCHAIN CONSENSUS MECHANISM
-            // This implements the core blockchain consensus algorithm that determines whether to accept
-            // or reject a new block. The consensus mechanism follows Bitcoin's "longest chain rule"
-            // with deterministic tie-breaking to ensure network-wide agreement on block acceptance.
-            //
-            // The consensus operates on three hierarchical levels:
-            // 1. Height-based selection (longest chain rule)
-            // 2. Work-based comparison (cumulative proof-of-work)
-            // 3. Deterministic tie-breaking (hash comparison)
-            if !self.is_empty() {
-                let current_tip = self.get_tip_hash().await?;
-                let current_height = self.get_best_height().await?;
-
-                // CONSENSUS LEVEL 1: Block Height Comparison (Longest Chain Rule)
-                // The primary consensus mechanism is the "longest chain rule" - blocks with higher
-                // height are always accepted because they represent more cumulative proof-of-work.
-                // This rule ensures that the blockchain follows the chain with the most computational
-                // effort invested, making it the most secure and authoritative chain.
-                match new_block.get_height().cmp(&current_height) {
-                    Ordering::Greater => {
-                        // HIGHER HEIGHT: Accept block (longest chain rule)
-                        // Check if the new block extends our current chain directly,
-                        // or if it's on a different branch (requires reorganization).
-                        if new_block.get_pre_block_hash() == current_tip {
-                            // Normal case: block extends our current chain
-                            self.set_tip_hash(new_block.get_hash()).await?;
-                            self.update_utxo_set(new_block).await?;
-
-                            info!(
-                                "Block {} accepted: higher height ({} > {}) - extends current chain",
-                                new_block.get_hash(),
-                                new_block.get_height(),
-                                current_height
-                            );
-                        } else {
-                            // FORK: Block is at a higher height but on a DIFFERENT branch.
-                            // This happens when block relay delivers blocks out of order,
-                            // or when a longer competing chain is discovered.
-                            // Must reorganize: rollback our current branch and apply the new one.
-                            // Without this, the old branch's UTXO (including coinbase subsidies)
-                            // would remain, creating money out of thin air.
-                            info!(
-                                "Block {} at higher height ({} > {}) is on a different branch (parent {} != tip {}), reorganizing",
-                                new_block.get_hash(),
-                                new_block.get_height(),
-                                current_height,
-                                new_block.get_pre_block_hash(),
-                                current_tip
-                            );
-                            self.reorganize_chain(new_block.get_hash()).await?;
-                        }
-                    }
-                    Ordering::Equal => {
-                        // SAME HEIGHT: Competing blocks at identical height require deeper analysis
-                        // When blocks have equal height, we must compare their cumulative work
-                        // and potentially reorganize the chain to follow the stronger branch
-
-                        // Check if the new block references our current tip as its parent
-                        // If it does, this is NOT a competing block - it's the next block in our chain
-                        if new_block.get_pre_block_hash() == current_tip {
-                            info!(
-                                "Block {} is the next block in our chain (height {}), accepting without reorganization",
-                                new_block.get_hash(),
-                                new_block.get_height()
-                            );
-                            // This is the next block in our chain, not a competing block
-                            // We should accept it without reorganization
-                            return Ok(());
-                        }
+            // The active chain is the one with the most cumulative proof-of-work (Bitcoin
+            // Core's `FindMostWorkChain`), not the longest one: a long branch of easy blocks
+            // must not replace a shorter branch that took more work to build. A branch with
+            // exactly as much work as the active chain does not replace it; the block seen
+            // first keeps the tip, as in Bitcoin Core.
+            let current_work = self.get_chain_work(&current_tip).await?;
+            let new_work = self.get_chain_work(&new_block.get_hash()).await?;
+
+            match new_work.cmp(&current_work) {
+                Ordering::Greater => {
+                    // MORE WORK: Accept block.
+                    // Check if the new block extends our current chain directly,
+                    // or if it's on a different branch (requires reorganization).
+                    if new_block.get_pre_block_hash() == current_tip {
+                        // Normal case: block extends our current chain
+                        self.set_tip_hash(&new_block.get_hash()).await?;
+                        self.update_utxo_set(new_block).await?;
 
-                        // If we reach here, we have competing blocks at the same height
-                        // Now we need to compare their cumulative work
-                        let current_work = self.get_chain_work(&current_tip).await?;
-
-                        // Check if block is already in database (from transaction above)
-                        let block_already_exists = self
-                            .get_block(new_block.get_hash().as_bytes())
-                            .await?
-                            .is_some();
-
-                        // TEMPORARY BLOCK INSERTION FOR WORK CALCULATION
-                        // We need to temporarily add the block to the database so we can calculate
-                        // its cumulative work (which requires traversing the entire chain)
-                        let temp_block_tree = if !block_already_exists {
-                            let block_bytes = new_block.serialize()?;
-                            let tree = self
-                                .blockchain
-                                .db
-                                .open_tree(self.get_blocks_tree_path())
-                                .map_err(|e| BtcError::OpenBlockchainTreeError(e.to_string()))?;
-                            tree.insert(new_block.get_hash(), block_bytes)
-                                .map_err(|e| BtcError::BlockchainDBconnection(e.to_string()))?;
-                            Some(tree)
-                        } else {
-                            None
-                        };
-
-                        let new_work = self.get_chain_work(new_block.get_hash()).await?;
-
-                        // CONSENSUS LEVEL 2: Cumulative Work Comparison for Competing Blocks
-                        // When blocks have equal height, we compare their cumulative proof-of-work
-                        // to determine which chain represents more computational effort and is stronger
-                        match new_work.cmp(&current_work) {
-                            Ordering::Greater => {
-                                // HIGHER WORK: Reorganize to the stronger chain
-                                // The new block's chain has more cumulative proof-of-work,
-                                // so we reorganize our blockchain to follow the stronger chain
-                                info!(
-                                    "Reorganizing chain: new work {} > current work {} - stronger competing chain",
-                                    new_work, current_work
-                                );
-                                self.reorganize_chain(new_block.get_hash()).await?;
-                            }
-                            Ordering::Equal => {
-                                // CONSENSUS LEVEL 3: Deterministic Tie-Breaking for Equal Work
-                                // When both chains have identical cumulative proof-of-work,
-                                // we employ deterministic tie-breaking to ensure all nodes
-                                // reach the same consensus decision and maintain network convergence
-                                if self
-                                    .accept_new_block_tie_break(new_block, &current_tip)
-                                    .await?
-                                {
-                                    info!(
-                                        "Reorganizing chain via tie-breaking: new work {} == current work {}",
-                                        new_work, current_work
-                                    );
-                                    // Reorganize chain based on tie-breaking decision
-                                    self.reorganize_chain(new_block.get_hash()).await?;
-                                    info!(
-                                        "Block {} accepted via tie-breaking",
-                                        new_block.get_hash()
-                                    );
-                                } else {
-                                    info!(
-                                        "Block {} rejected via tie-breaking",
-                                        new_block.get_hash()
-                                    );
-                                    // Remove the block from database since it was rejected (only if we added it)
-                                    if let Some(tree) = &temp_block_tree {
-                                        tree.remove(new_block.get_hash()).map_err(|e| {
-                                            BtcError::BlockchainDBconnection(e.to_string())
-                                        })?;
-                                    }
-                                }
-                            }
-                            Ordering::Less => {
-                                // LOWER WORK: Reject the weaker chain
-                                // The new block's chain has less cumulative proof-of-work,
-                                // so we reject it and keep our current (stronger) chain
-                                info!(
-                                    "Block {} rejected: work {} < current work {} - weaker competing chain",
-                                    new_block.get_hash(),
-                                    new_work,
-                                    current_work
-                                );
-                                // Remove the block from database since it was rejected (only if we added it)
-                                if let Some(tree) = &temp_block_tree {
-                                    tree.remove(new_block.get_hash()).map_err(|e| {
-                                        BtcError::BlockchainDBconnection(e.to_string())
-                                    })?;
-                                }
-                            }
-                        }
-                    }
-                    Ordering::Less => {
-                        // LOWER HEIGHT: Block is on a shorter chain.
-                        // The block is already stored in the DB (inserted by the Sled
-                        // transaction above) so it's available for future reorganizations.
-                        // When a later block on this branch arrives at our height or higher,
-                        // the Equal/Greater cases will handle the reorganization.
                         info!(
-                            "Block {} rejected: height {} < current height {} - shorter chain",
+                            "Block {} accepted: more work ({} > {}) - extends current chain",
                             new_block.get_hash(),
-                            new_block.get_height(),
-                            current_height
+                            new_work,
+                            current_work
                         );
+                    } else {
+                        // FORK: Block has more work but is on a DIFFERENT branch.
+                        // This happens when block relay delivers blocks out of order,
+                        // or when a stronger competing chain is discovered.
+                        // Must reorganize: rollback our current branch and apply the new one.
+                        // Without this, the old branch's UTXO (including coinbase subsidies)
+                        // would remain, creating money out of thin air.
+                        info!(
+                            "Block {} with more work ({} > {}) is on a different branch (parent {} != tip {}), reorganizing",
+                            new_block.get_hash(),
+                            new_work,
+                            current_work,
+                            new_block.get_pre_block_hash(),
+                            current_tip
+                        );
+                        self.reorganize_chain(&new_block.get_hash()).await?;
                     }
+                    block_tree
+                        .insert(DEFAULT_TIP_BLOCK_HASH_KEY, block_hash.as_slice())
+                        .map_err(|e| BtcError::BlockchainDBconnection(e.to_string()))?;
+                }
+                Ordering::Equal => {
+                    // EQUAL WORK: The block seen first keeps the tip. The new block stays
+                    // stored, and the next block on its branch will carry more work.
+                    info!(
+                        "Block {} not activated: work {} == current work {} - first seen block keeps the tip",
+                        new_block.get_hash(),
+                        new_work,
+                        current_work
+                    );
+                }
+                Ordering::Less => {
+                    // LESS WORK: Block is on a weaker branch.
+                    // The block is already stored in the DB so it's available for future
+                    // reorganizations once its branch overtakes ours.
+                    info!(
+                        "Block {} not activated: work {} < current work {} - weaker chain",
+                        new_block.get_hash(),
+                        new_work,
+                        current_work
+                    );
                 }
             }
         }
@@ -737,8 +736,9 @@ impl BlockchainFileSystem {
                 .db
                 .open_tree(self.get_blocks_tree_path())
                 .map_err(|e| BtcError::OpenBlockchainTreeError(e.to_string()))?;
+            let tip_hash = self.get_tip_hash().await?;
             let tip_block_bytes = block_tree
-                .get(self.get_tip_hash().await?)
+                .get(block_hash_from_hex(&tip_hash).unwrap_or_default())
                 .map_err(|e| BtcError::GetBlockchainError(e.to_string()))?
                 .ok_or(BtcError::GetBlockchainError("tip is invalid".to_string()))?;
             let tip_block = Block::deserialize(tip_block_bytes.as_ref())?;
@@ -809,7 +809,7 @@ impl BlockchainFileSystem {
         let mut work = 0u64;
         let mut current_hash = block_hash.to_string();
 
-        while let Some(block) = self.get_block(current_hash.as_bytes()).await? {
+        while let Some(block) = self.get_block_by_hash(&current_hash).await? {
             // Add this block's work
             work += block.get_work();
             current_hash = block.get_pre_block_hash();
@@ -822,91 +822,86 @@ impl BlockchainFileSystem {
         Ok(work)
     }
 
-    /// Consensus tie-breaking mechanism for blocks with equal work
+    /// Calculate the compact target (`bits`) required for a block whose parent is `pre_block_hash`
     ///
-    /// This function implements the consensus mechanism when two blocks have the same
-    /// proof-of-work (chain work). The consensus must be deterministic to ensure all
-    /// nodes in the network reach the same decision about which block to accept.
+    /// ## Retargeting Rules (Bitcoin Core's `GetNextWorkRequired`):
+    /// 1. The genesis block uses the network's proof-of-work limit
+    /// 2. Blocks inside a retarget window inherit their parent's `bits`
+    /// 3. The first block of every new window (every `difficulty_adjustment_interval` blocks
+    ///    of the network's `ChainParams`) gets a target scaled by how long the previous
+    ///    window actually took, clamped to a factor of 4 in either direction
+    /// 4. On networks without retargeting (regtest) every block keeps the genesis target
     ///
-    /// ## Consensus Requirements:
-    /// 1. **Deterministic**: Same inputs must always produce the same output
-    /// 2. **Unbiased**: No node should have an inherent advantage
-    /// 3. **Consistent**: All nodes must reach the same decision
+    /// The window is walked through `pre_block_hash` links instead of the active chain,
+    /// so the expected target is also correct for blocks on competing branches.
     ///
-    /// ## How Consensus Works:
-    /// When two blocks have equal work (same cumulative proof-of-work), we need a
-    /// tie-breaking mechanism. Bitcoin uses deterministic criteria to ensure all
-    /// nodes converge on the same block.
+    /// # Arguments
     ///
-    /// ## Tie-Breaking Strategy:
-    /// We use lexicographic hash comparison because:
-    /// - **Deterministic**: Hash comparison always produces the same result
-    /// - **Unbiased**: No node has advantage based on network timing or processing order
-    /// - **Simple**: Single comparison criterion eliminates complexity
-    /// - **Consistent**: All nodes will reach identical decisions
+    /// * `pre_block_hash` - Hash of the parent of the block being mined or validated
     ///
-    /// ## Network Convergence:
-    /// When Node A and Node B both mine competing blocks:
-    /// - Node A: Compares Block_A_hash vs Block_B_hash → Decision X
-    /// - Node B: Compares Block_A_hash vs Block_B_hash → Decision X (same)
-    /// - Result: Both nodes converge on the same winning block
+    /// # Returns
     ///
-    /// ## Return Values:
-    /// - `true`: New block wins, current block should be replaced
-    /// - `false`: Current block wins, new block should be rejected
-    async fn accept_new_block_tie_break(
-        &self,
-        new_block: &Block,
-        current_tip: &str,
-    ) -> Result<bool> {
-        // Get the current tip block for comparison
-        let current_block = self
-            .get_block(current_tip.as_bytes())
-            .await?
-            .ok_or_else(|| {
-                BtcError::GetBlockchainError("Current tip block not found".to_string())
-            })?;
+    /// * `Ok(Some(bits))` - The compact target the next block must use
+    /// * `Ok(None)` - The parent or an ancestor in its retarget window is not stored yet
+    pub async fn get_next_work_required(&self, pre_block_hash: &str) -> Result<Option<u32>> {
+        let params = GLOBAL_CONFIG.get_chain_params();
+        if pre_block_hash == GENESIS_BLOCK_PRE_BLOCK_HASH {
+            return Ok(Some(params.get_pow_limit_bits()));
+        }
 
-        info!("Consensus tie-breaking between competing blocks:");
-        info!(
-            "  New block: hash={}, timestamp={}, nonce={}",
-            new_block.get_hash(),
-            new_block.get_timestamp(),
-            new_block.get_nonce()
-        );
-        info!(
-            "  Current block: hash={}, timestamp={}, nonce={}",
-            current_block.get_hash(),
-            current_block.get_timestamp(),
-            current_block.get_nonce()
-        );
+        let Some(parent) = self.get_block_by_hash(pre_block_hash).await? else {
+            return Ok(None);
+        };
 
-        // CONSENSUS MECHANISM: Deterministic Hash-Based Tie-Breaking
-        // This mechanism ensures all nodes reach identical consensus decisions regardless of:
-        // - Network propagation timing and order
-        // - Block processing sequence variations
-        // - Which node performs the comparison
-        // - Local blockchain state differences
-        // - Mining timing and network topology
-        let new_hash = new_block.get_hash_string();
-        let current_hash = current_block.get_hash_string();
-
-        // Deterministic Lexicographic Hash Comparison
-        // This creates a consistent ordering that all nodes can independently compute
-        // and agree upon, ensuring network-wide consensus convergence
-        if new_hash > current_hash {
-            info!(
-                "  Consensus decision: New block wins (hash: {} > {})",
-                new_hash, current_hash
-            );
-            Ok(true)
-        } else {
-            info!(
-                "  Consensus decision: Current block wins (hash: {} <= {})",
-                new_hash, current_hash
-            );
-            Ok(false)
+        // Only change the target at the start of a new window. Genesis is at height 1,
+        // so the parent closes a window when its height is a multiple of the interval.
+        let interval = params.get_difficulty_adjustment_interval();
+        if params.is_pow_no_retargeting() || parent.get_height() % interval != 0 {
+            return Ok(Some(parent.get_bits()));
+        }
+
+        // Walk back to the first block of the window that the parent closes
+        let mut first = parent.clone();
+        for _ in 1..interval {
+            match self.get_block_by_hash(&first.get_pre_block_hash()).await? {
+                Some(block) => first = block,
+                None => return Ok(None),
+            }
         }
+
+        Ok(Some(calculate_next_work_required(
+            params,
+            parent.get_bits(),
+            first.get_timestamp(),
+            parent.get_timestamp(),
+        )))
+    }
+
+    /// Median timestamp of the block `block_hash` and up to `MEDIAN_TIME_SPAN - 1` of its ancestors
+    ///
+    /// A new block's timestamp must be strictly greater than the median time past of its
+    /// parent (Bitcoin Core's `GetMedianTimePast`). Like `get_next_work_required`, the window
+    /// follows `pre_block_hash` links so it is also correct on competing branches.
+    ///
+    /// # Arguments
+    ///
+    /// * `block_hash` - Hash of the newest block in the window (the parent of a new block)
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(timestamp))` - The median timestamp in milliseconds
+    /// * `Ok(None)` - The block is not stored
+    pub async fn get_median_time_past(&self, block_hash: &str) -> Result<Option<i64>> {
+        let mut timestamps = Vec::with_capacity(MEDIAN_TIME_SPAN);
+        let mut current_hash = block_hash.to_string();
+        while timestamps.len() < MEDIAN_TIME_SPAN {
+            let Some(block) = self.get_block_by_hash(&current_hash).await? else {
+                break;
+            };
+            timestamps.push(block.get_timestamp());
+            current_hash = block.get_pre_block_hash();
+        }
+        Ok(median_time_past(&timestamps))
     }
 
     /// Perform blockchain reorganization to switch to a stronger chain
@@ -919,9 +914,13 @@ impl BlockchainFileSystem {
     /// ## Reorganization Process:
     /// 1. Find the common ancestor between current chain and new chain
     /// 2. Rollback the UTXO set from current tip to common ancestor
-    /// 3. Apply the new chain from common ancestor to new tip
+    /// 3. Apply the new chain from common ancestor to new tip, checking the transactions of
+    ///    each block against the UTXO set as it is connected
     /// 4. Update the blockchain tip to point to the new chain
     ///
+    /// If a block of the new chain is invalid, the blocks connected so far are rolled back,
+    /// the previous chain is connected again and the block's error is returned.
+    ///
     /// ## Consensus Integration:
     /// This function is called when the consensus mechanism determines that a competing
     /// chain has higher cumulative work and should become the new canonical chain.
@@ -944,9 +943,21 @@ impl BlockchainFileSystem {
             // Rollback from current tip to common ancestor
             self.rollback_to_block(&ancestor).await?;
 
-            // Apply new chain from common ancestor to new tip
-            self.apply_chain_from_ancestor(&ancestor, new_tip_hash)
-                .await?;
+            // Apply new chain from common ancestor to new tip. If one of its blocks is
+            // invalid, disconnect the blocks connected so far and restore the previous chain
+            if let Err(e) = self
+                .apply_chain_from_ancestor(&ancestor, new_tip_hash, true)
+                .await
+            {
+                warn!(
+                    "Chain reorganization to {} aborted, restoring {}: {}",
+                    new_tip_hash, current_tip, e
+                );
+                self.rollback_to_block(&ancestor).await?;
+                self.apply_chain_from_ancestor(&ancestor, &current_tip, false)
+                    .await?;
+                return Err(e);
+            }
 
             info!("Chain reorganization completed");
         } else {
@@ -1002,7 +1013,7 @@ impl BlockchainFileSystem {
 
         loop {
             chain_hashes.push(current_hash.clone());
-            if let Some(block) = self.get_block(current_hash.as_bytes()).await? {
+            if let Some(block) = self.get_block_by_hash(&current_hash).await? {
                 current_hash = block.get_pre_block_hash();
                 if current_hash == GENESIS_BLOCK_PRE_BLOCK_HASH || current_hash.is_empty() {
                     break;
@@ -1049,9 +1060,9 @@ impl BlockchainFileSystem {
 
         // Rollback from current tip to target block
         while current_tip != target_hash && rollback_count < MAX_ROLLBACK_ATTEMPTS {
-            if let Some(block) = self.get_block(current_tip.as_bytes()).await? {
+            if let Some(block) = self.get_block_by_hash(&current_tip).await? {
                 // SAFETY CHECK: Never delete the genesis block
-                // Genesis block is identified by having pre_block_hash == "None"
+                // Genesis block is identified by having an all-zero pre_block_hash
                 if block.get_pre_block_hash() == GENESIS_BLOCK_PRE_BLOCK_HASH {
                     info!(
                         "Rollback reached genesis block, stopping rollback to prevent blockchain corruption"
@@ -1110,7 +1121,7 @@ impl BlockchainFileSystem {
         let remaining_tip = self.get_tip_hash().await?;
         if remaining_tip == target_hash {
             // Check if the target block exists
-            if self.get_block(target_hash.as_bytes()).await?.is_none() {
+            if self.get_block_by_hash(target_hash).await?.is_none() {
                 // Only mark as empty if target is NOT the genesis block
                 // Rolling back to genesis block is a valid state during chain reorganization
                 if target_hash != GENESIS_BLOCK_PRE_BLOCK_HASH {
@@ -1184,39 +1195,34 @@ impl BlockchainFileSystem {
                         if let Some(output) = input_tx.get_vout().get(curr_blc_tx_inpt.get_vout()) {
                             // Check if this transaction already has other unspent outputs
                             // We need to merge the restored output with existing ones
-                            let outs_to_restore = if let Some(existing_outs_bytes) = utxo_tree
+                            let coins_to_restore = if let Some(existing_outs_bytes) = utxo_tree
                                 .get(curr_blc_tx_inpt.get_txid())
                                 .map_err(|e| BtcError::GettingUTXOError(e.to_string()))?
                             {
                                 // Deserialize existing outputs for this transaction
-                                let mut existing_outs: Vec<TXOutput> =
-                                    bincode::serde::decode_from_slice(
-                                        existing_outs_bytes.as_ref(),
-                                        bincode::config::standard(),
-                                    )
-                                    .map_err(|e| {
-                                        BtcError::TransactionDeserializationError(e.to_string())
-                                    })?
-                                    .0;
-
-                                // Insert the restored output at the correct position (vout index)
+                                let mut existing_coins =
+                                    Coins::deserialize(existing_outs_bytes.as_ref())?;
+
+                                // Put the restored output back at its position (vout index)
                                 // This ensures outputs are in the same order as when created
-                                existing_outs.insert(curr_blc_tx_inpt.get_vout(), output.clone());
-                                existing_outs
+                                existing_coins.restore(curr_blc_tx_inpt.get_vout(), output.clone());
+                                existing_coins
                             } else {
-                                // No existing outputs, just restore this one
-                                vec![output.clone()]
+                                // No existing outputs, just restore this one with the height
+                                // of the block that created it
+                                let height = self
+                                    .find_transaction_block(curr_blc_tx_inpt.get_txid())
+                                    .await?
+                                    .map(|input_block| input_block.get_height())
+                                    .unwrap_or_default();
+                                let mut coins = Coins::new(height, input_tx.is_coinbase(), vec![]);
+                                coins.restore(curr_blc_tx_inpt.get_vout(), output.clone());
+                                coins
                             };
 
                             // Save the restored UTXOs back to the database
-                            let outs_bytes = bincode::serde::encode_to_vec(
-                                &outs_to_restore,
-                                bincode::config::standard(),
-                            )
-                            .map_err(|e| BtcError::TransactionSerializationError(e.to_string()))?;
-
                             utxo_tree
-                                .insert(curr_blc_tx_inpt.get_txid(), outs_bytes)
+                                .insert(curr_blc_tx_inpt.get_txid(), coins_to_restore.serialize()?)
                                 .map_err(|e| BtcError::SavingUTXOError(e.to_string()))?;
                         }
                     }
@@ -1273,58 +1279,34 @@ impl BlockchainFileSystem {
                         )))?;
 
                     // Deserialize the UTXO list
-                    let curr_blc_tx_inpt_utxo_list: Vec<TXOutput> =
-                        bincode::serde::decode_from_slice(
-                            curr_blc_tx_inpt_utxo_ivec.as_ref(),
-                            bincode::config::standard(),
-                        )
-                        .map_err(|e| BtcError::TransactionDeserializationError(e.to_string()))?
-                        .0;
+                    let mut coins = Coins::deserialize(curr_blc_tx_inpt_utxo_ivec.as_ref())?;
 
-                    // Create updated UTXO list (excluding the spent output)
-                    let mut updated_outs = vec![];
-                    for (utxo_curr_utxo_idx, db_curr_utxo) in
-                        curr_blc_tx_inpt_utxo_list.iter().enumerate()
-                    {
-                        // Keep all outputs except the one being spent
-                        if utxo_curr_utxo_idx != curr_blc_tx_inpt.get_vout() {
-                            updated_outs.push(db_curr_utxo.clone())
-                        }
-                    }
+                    // Mark the spent output, keeping the positions of the others
+                    coins.spend(curr_blc_tx_inpt.get_vout());
 
                     // Update or remove the UTXO entry
-                    if updated_outs.is_empty() {
+                    if coins.is_pruned() {
                         // No outputs left, remove the entire UTXO entry
                         utxo_tree
                             .remove(curr_blc_tx_inpt.get_txid())
                             .map_err(|e| BtcError::RemovingUTXOError(e.to_string()))?;
                     } else {
-                        // Update with remaining unspent outputs
-                        let outs_bytes = bincode::serde::encode_to_vec(
-                            &updated_outs,
-                            bincode::config::standard(),
-                        )
-                        .map_err(|e| BtcError::TransactionSerializationError(e.to_string()))?;
+                        // Update with remaining unspent outputs, keeping their creation height
                         utxo_tree
-                            .insert(curr_blc_tx_inpt.get_txid(), outs_bytes)
+                            .insert(curr_blc_tx_inpt.get_txid(), coins.serialize()?)
                             .map_err(|e| BtcError::SavingUTXOError(e.to_string()))?;
                     }
                 }
             }
 
-            // Add new outputs to UTXO set (for both coinbase and regular transactions)
-            let mut new_outputs = vec![];
-            for curr_tx_out in curr_block_tx.get_vout() {
-                new_outputs.push(curr_tx_out.clone())
+            // Add new outputs to UTXO set (for both coinbase and regular transactions),
+            // recording the block height so coinbase maturity can be enforced
+            let new_coins = Coins::from_transaction(curr_block_tx, block.get_height());
+            if !new_coins.is_pruned() {
+                let _ = utxo_tree
+                    .insert(curr_block_tx.get_id(), new_coins.serialize()?)
+                    .map_err(|e| BtcError::SavingUTXOError(e.to_string()))?;
             }
-
-            // Serialize and store the new outputs
-            let outs_bytes =
-                bincode::serde::encode_to_vec(&new_outputs, bincode::config::standard())
-                    .map_err(|e| BtcError::TransactionSerializationError(e.to_string()))?;
-            let _ = utxo_tree
-                .insert(curr_block_tx.get_id(), outs_bytes)
-                .map_err(|e| BtcError::SavingUTXOError(e.to_string()))?;
         }
         Ok(())
     }
@@ -1333,13 +1315,18 @@ impl BlockchainFileSystem {
     ///
     /// This method applies a new chain segment during reorganization by:
     /// 1. Building the chain of blocks from ancestor to new tip
-    /// 2. Validating each block in the new chain
+    /// 2. Checking each block's transactions against the UTXO set (`check_block_transactions`)
     /// 3. Applying UTXO updates for each block in order
-    /// 4. Setting the new blockchain tip
+    /// 4. Moving the tip to each block as it is connected, so the lock times of the next
+    ///    block are measured against the new chain
+    ///
+    /// It stops at the first invalid block, leaving the blocks before it connected.
     ///
     /// # Arguments
     /// * `ancestor_hash` - The hash of the common ancestor block
     /// * `new_tip_hash` - The hash of the new tip block to apply
+    /// * `check_transactions` - Whether to check transactions; `false` when reconnecting a
+    ///   chain that was already validated
     ///
     /// # Returns
     /// * `Result<()>` - Ok if chain application succeeded, Err if any step failed
@@ -1351,6 +1338,7 @@ impl BlockchainFileSystem {
         &mut self,
         ancestor_hash: &str,
         new_tip_hash: &str,
+        check_transactions: bool,
     ) -> Result<()> {
         // Build the chain of block hashes from ancestor to new tip
         let mut chain_hashes = Vec::new();
@@ -1359,7 +1347,7 @@ impl BlockchainFileSystem {
         // Walk backwards from new tip to ancestor to build the chain
         while current_hash != ancestor_hash {
             chain_hashes.push(current_hash.clone());
-            if let Some(block) = self.get_block(current_hash.as_bytes()).await? {
+            if let Some(block) = self.get_block_by_hash(&current_hash).await? {
                 current_hash = block.get_pre_block_hash();
             } else {
                 return Err(BtcError::InvalidValueForMiner(
@@ -1374,10 +1362,24 @@ impl BlockchainFileSystem {
         // Apply each block in the new chain
         // This ensures UTXO set is updated in the correct order
         for block_hash in chain_hashes {
-            if let Some(block) = self.get_block(block_hash.as_bytes()).await? {
+            if let Some(block) = self.get_block_by_hash(&block_hash).await? {
+                if check_transactions {
+                    let block_time = self
+                        .get_median_time_past(&block.get_pre_block_hash())
+                        .await?
+                        .unwrap_or_default();
+                    UTXOSet::new(BlockchainService::from_blockchain_file_system(self.clone()))
+                        .check_block_transactions(
+                            &block,
+                            block_time,
+                            GLOBAL_CONFIG.get_coinbase_maturity(),
+                        )
+                        .await?;
+                }
                 // Update UTXO set for each block in the new chain
                 // This adds new UTXOs and marks spent ones as consumed
                 self.update_utxo_set(&block).await?;
+                self.set_tip_hash(&block_hash).await?;
             }
         }
 
@@ -1390,11 +1392,11 @@ impl BlockchainFileSystem {
 pub struct BlockchainIterator {
     db: Db,
     file_system_blocks_tree: String,
-    current_hash: String,
+    current_hash: Vec<u8>,
 }
 
 impl BlockchainIterator {
-    fn new(tip_hash: String, db: Db, file_system_blocks_tree: String) -> BlockchainIterator {
+    fn new(tip_hash: Vec<u8>, db: Db, file_system_blocks_tree: String) -> BlockchainIterator {
         BlockchainIterator {
             current_hash: tip_hash,
             file_system_blocks_tree,
@@ -1419,7 +1421,7 @@ impl Iterator for BlockchainIterator {
         };
 
         let block = Block::deserialize(data.to_vec().as_slice()).ok()?;
-        self.current_hash = block.get_pre_block_hash().clone();
+        self.current_hash = block.get_pre_block_hash_bytes().to_vec();
         Some(block)
     }
 }
@@ -1429,7 +1431,9 @@ mod tests {
     use super::*;
     use crate::chain::BlockchainService;
     use crate::chain::UTXOSet;
-    use crate::primitives::transaction::Transaction;
+    use crate::pow::{DIFFICULTY_ADJUSTMENT_INTERVAL, POW_LIMIT_BITS};
+    use crate::primitives::block::BLOCK_HASH_SIZE;
+    use crate::primitives::transaction::{SEQUENCE_FINAL, TXOutput, Transaction};
     use crate::wallet::get_pub_key_hash;
 
     use std::fs;
@@ -1675,7 +1679,7 @@ mod tests {
             .await
             .expect("Failed to get tip hash");
         let genesis_block = blockchain
-            .get_block(tip_hash.as_bytes())
+            .get_block_by_hash(&tip_hash)
             .await
             .expect("Failed to get genesis block")
             .expect("Genesis block should exist");
@@ -1694,7 +1698,7 @@ mod tests {
         let genesis_address = generate_test_genesis_address();
 
         // Create a new block
-        let coinbase_tx = Transaction::new_coinbase_tx(&genesis_address.clone())
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
             .expect("Failed to create coinbase tx");
         let transactions = vec![coinbase_tx];
         let new_block = blockchain
@@ -1725,8 +1729,8 @@ mod tests {
         let genesis_address = generate_test_genesis_address();
 
         // Create and add a block
-        let coinbase_tx =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions = vec![coinbase_tx];
         let new_block = blockchain
             .mine_block(transactions.as_slice())
@@ -1756,8 +1760,8 @@ mod tests {
         let genesis_address = generate_test_genesis_address();
 
         // Add a few blocks
-        for _i in 0..3 {
-            let coinbase_tx = Transaction::new_coinbase_tx(&genesis_address)
+        for height in 2..5 {
+            let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, height)
                 .expect("Failed to create coinbase tx");
             let transactions = vec![coinbase_tx];
             let new_block = blockchain
@@ -1778,6 +1782,20 @@ mod tests {
         // Should have genesis block + 3 new blocks = 4 total
         assert_eq!(block_hashes.len(), 4);
 
+        // Blocks are stored under their raw 32-byte hashes, and each parent is found by
+        // the hash its child commits to
+        for pair in block_hashes.windows(2) {
+            assert_eq!(pair[0].len(), BLOCK_HASH_SIZE);
+            let child = blockchain.get_block(&pair[0]).await.unwrap().unwrap();
+            assert_eq!(child.get_hash_bytes(), pair[0]);
+            assert_eq!(child.get_pre_block_hash_bytes(), pair[1].as_slice());
+        }
+        let tip_hash = blockchain.get_tip_hash().await.unwrap();
+        assert_eq!(
+            block_hash_from_hex(&tip_hash),
+            Some(block_hashes[0].clone())
+        );
+
         cleanup_test_blockchain(&db_path);
     }
 
@@ -1787,8 +1805,8 @@ mod tests {
 
         // Add a block
         let genesis_address = generate_test_genesis_address();
-        let coinbase_tx =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions = vec![coinbase_tx];
         let new_block = blockchain
             .mine_block(transactions.as_slice())
@@ -1820,8 +1838,8 @@ mod tests {
         let (blockchain, db_path) = create_test_blockchain().await;
 
         let genesis_address = generate_test_genesis_address();
-        let coinbase_tx =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions = vec![coinbase_tx];
 
         let new_block = blockchain
@@ -1927,7 +1945,7 @@ mod tests {
                 .expect("Failed to create blockchain");
 
             // Add a block
-            let coinbase_tx = Transaction::new_coinbase_tx(&genesis_address)
+            let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
                 .expect("Failed to create coinbase tx");
             let transactions = vec![coinbase_tx];
             let new_block = blockchain
@@ -1966,8 +1984,8 @@ mod tests {
 
         // Create initial chain: Genesis -> Block A
         let genesis_address = generate_test_genesis_address();
-        let coinbase_tx =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions = vec![coinbase_tx];
         let block_a = blockchain
             .mine_block(transactions.as_slice())
@@ -1976,10 +1994,14 @@ mod tests {
         let block_a_hash = block_a.get_hash().to_string();
 
         // Create block B with same height but different content
-        let coinbase_tx_b =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx_b = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions_b = vec![coinbase_tx_b];
-        let block_b = Block::new_block(block_a_hash.clone(), transactions_b.as_slice(), 2);
+        let block_b = Block::new_block(
+            &block_hash_from_hex(&block_a_hash).unwrap(),
+            transactions_b.as_slice(),
+            2,
+        );
 
         // Add block B - should trigger tie-breaking
         blockchain
@@ -2003,8 +2025,8 @@ mod tests {
 
         // Create initial chain: Genesis -> Block A
         let genesis_address = generate_test_genesis_address();
-        let coinbase_tx =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions = vec![coinbase_tx];
         let block_a = blockchain
             .mine_block(transactions.as_slice())
@@ -2015,21 +2037,29 @@ mod tests {
         // Create two competing blocks with same height but different content
         // Both blocks should have the same parent as block A (the block at height 1)
         let block_a_parent = blockchain
-            .get_block(block_a_hash.as_bytes())
+            .get_block_by_hash(&block_a_hash)
             .await
             .expect("Failed to get block A")
             .expect("Block A not found");
         let block_a_parent_hash = block_a_parent.get_pre_block_hash();
 
-        let coinbase_tx_b =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx_b = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions_b = vec![coinbase_tx_b];
-        let block_b = Block::new_block(block_a_parent_hash.clone(), transactions_b.as_slice(), 2);
+        let block_b = Block::new_block(
+            &block_hash_from_hex(&block_a_parent_hash).unwrap(),
+            transactions_b.as_slice(),
+            2,
+        );
 
-        let coinbase_tx_c =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx_c = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions_c = vec![coinbase_tx_c];
-        let _block_c = Block::new_block(block_a_parent_hash, transactions_c.as_slice(), 2);
+        let _block_c = Block::new_block(
+            &block_hash_from_hex(&block_a_parent_hash).unwrap(),
+            transactions_c.as_slice(),
+            2,
+        );
 
         // Add block B - should trigger tie-breaking
         blockchain
@@ -2054,8 +2084,8 @@ mod tests {
 
         // Create initial chain: Genesis -> Block A
         let genesis_address = generate_test_genesis_address();
-        let coinbase_tx =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions = vec![coinbase_tx];
         let block_a = blockchain
             .mine_block(transactions.as_slice())
@@ -2066,21 +2096,29 @@ mod tests {
         // Create two competing blocks with same height but different content
         // Both blocks should have the same parent as block A (the block at height 1)
         let block_a_parent = blockchain
-            .get_block(block_a_hash.as_bytes())
+            .get_block_by_hash(&block_a_hash)
             .await
             .expect("Failed to get block A")
             .expect("Block A not found");
         let block_a_parent_hash = block_a_parent.get_pre_block_hash();
 
-        let coinbase_tx_b =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx_b = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions_b = vec![coinbase_tx_b];
-        let block_b = Block::new_block(block_a_parent_hash.clone(), transactions_b.as_slice(), 2);
+        let block_b = Block::new_block(
+            &block_hash_from_hex(&block_a_parent_hash).unwrap(),
+            transactions_b.as_slice(),
+            2,
+        );
 
-        let coinbase_tx_c =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx_c = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions_c = vec![coinbase_tx_c];
-        let _block_c = Block::new_block(block_a_parent_hash, transactions_c.as_slice(), 2);
+        let _block_c = Block::new_block(
+            &block_hash_from_hex(&block_a_parent_hash).unwrap(),
+            transactions_c.as_slice(),
+            2,
+        );
 
         // Add block B - should trigger tie-breaking
         blockchain
@@ -2105,8 +2143,8 @@ mod tests {
 
         // Create initial chain: Genesis -> Block A
         let genesis_address = generate_test_genesis_address();
-        let coinbase_tx =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions = vec![coinbase_tx];
         let block_a = blockchain
             .mine_block(transactions.as_slice())
@@ -2117,23 +2155,32 @@ mod tests {
         // Create two competing blocks with same height but different content
         // Both blocks should have the same parent as block A (the block at height 1)
         let block_a_parent = blockchain
-            .get_block(block_a_hash.as_bytes())
+            .get_block_by_hash(&block_a_hash)
             .await
             .expect("Failed to get block A")
             .expect("Block A not found");
         let block_a_parent_hash = block_a_parent.get_pre_block_hash();
 
-        let coinbase_tx_b =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx_b = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions_b = vec![coinbase_tx_b];
-        let block_b = Block::new_block(block_a_parent_hash.clone(), transactions_b.as_slice(), 2);
+        let block_b = Block::new_block(
+            &block_hash_from_hex(&block_a_parent_hash).unwrap(),
+            transactions_b.as_slice(),
+            2,
+        );
 
-        let coinbase_tx_c =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx_c = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions_c = vec![coinbase_tx_c];
-        let _block_c = Block::new_block(block_a_parent_hash, transactions_c.as_slice(), 2);
+        let _block_c = Block::new_block(
+            &block_hash_from_hex(&block_a_parent_hash).unwrap(),
+            transactions_c.as_slice(),
+            2,
+        );
 
-        // Add block B - should trigger tie-breaking
+        // Add block B - it has as much work as block A, so block A (seen first) keeps the
+        // tip whichever hash is larger
         blockchain
             .add_block(&block_b)
             .await
@@ -2142,10 +2189,28 @@ mod tests {
             .get_tip_hash()
             .await
             .expect("Failed to get tip hash");
+        assert_eq!(tip_after_b, block_a_hash);
+        assert!(
+            blockchain
+                .get_block_by_hash(&block_b.get_hash())
+                .await
+                .expect("Failed to get block B")
+                .is_some(),
+            "Block B should stay stored for a later reorganization"
+        );
 
-        // Verify chain state is consistent - tie-breaking may accept or reject the competing block
-        // The tip should either remain as block A (if tie-breaking rejects) or switch to block B (if tie-breaking accepts)
-        assert!(tip_after_b == block_a_hash || tip_after_b == block_b.get_hash());
+        // A block on top of block B gives its branch more work and reorganizes onto it
+        let coinbase_tx_b2 = Transaction::new_test_coinbase(&genesis_address, 3)
+            .expect("Failed to create coinbase tx");
+        let block_b2 = Block::new_block(&block_b.get_hash_bytes(), &[coinbase_tx_b2], 3);
+        blockchain
+            .add_block(&block_b2)
+            .await
+            .expect("Failed to add block B2");
+        assert_eq!(
+            blockchain.get_tip_hash().await.expect("Failed to get tip"),
+            block_b2.get_hash()
+        );
 
         cleanup_test_blockchain(&db_path);
     }
@@ -2156,8 +2221,8 @@ mod tests {
 
         // Create initial chain: Genesis -> Block A
         let genesis_address = generate_test_genesis_address();
-        let coinbase_tx =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions = vec![coinbase_tx];
         let block_a = blockchain
             .mine_block(transactions.as_slice())
@@ -2166,8 +2231,8 @@ mod tests {
         let block_a_hash = block_a.get_hash().to_string();
 
         // Create two blocks with same transactions
-        let coinbase_tx_b =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx_b = Transaction::new_test_coinbase(&genesis_address, 3)
+            .expect("Failed to create coinbase tx");
         let transactions_b = vec![coinbase_tx_b];
         let block_b = blockchain
             .mine_block(transactions_b.as_slice())
@@ -2180,8 +2245,8 @@ mod tests {
             .await
             .expect("Failed to rollback to block A");
 
-        let coinbase_tx_c =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx_c = Transaction::new_test_coinbase(&genesis_address, 3)
+            .expect("Failed to create coinbase tx");
         let transactions_c = vec![coinbase_tx_c];
         let block_c = blockchain
             .mine_block(transactions_c.as_slice())
@@ -2210,8 +2275,8 @@ mod tests {
 
         // Create initial chain: Genesis -> Block A
         let genesis_address = generate_test_genesis_address();
-        let coinbase_tx =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions = vec![coinbase_tx];
         let block_a = blockchain
             .mine_block(transactions.as_slice())
@@ -2220,10 +2285,14 @@ mod tests {
         let block_a_hash = block_a.get_hash().to_string();
 
         // Create block B with same height but different content
-        let coinbase_tx_b =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx_b = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions_b = vec![coinbase_tx_b];
-        let block_b = Block::new_block(block_a_hash.clone(), transactions_b.as_slice(), 2);
+        let block_b = Block::new_block(
+            &block_hash_from_hex(&block_a_hash).unwrap(),
+            transactions_b.as_slice(),
+            2,
+        );
 
         // Add block B - should trigger tie-breaking
         blockchain
@@ -2247,8 +2316,8 @@ mod tests {
 
         // Create a block on empty blockchain
         let genesis_address = generate_test_genesis_address();
-        let coinbase_tx =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions = vec![coinbase_tx];
         let block = blockchain
             .mine_block(transactions.as_slice())
@@ -2271,8 +2340,8 @@ mod tests {
 
         // Create initial chain: Genesis -> Block A
         let genesis_address = generate_test_genesis_address();
-        let coinbase_tx =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions = vec![coinbase_tx];
         let block_a = blockchain
             .mine_block(transactions.as_slice())
@@ -2281,10 +2350,14 @@ mod tests {
         let block_a_hash = block_a.get_hash().to_string();
 
         // Create block B with same height but different content
-        let coinbase_tx_b =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx_b = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions_b = vec![coinbase_tx_b];
-        let block_b = Block::new_block(block_a_hash.clone(), transactions_b.as_slice(), 2);
+        let block_b = Block::new_block(
+            &block_hash_from_hex(&block_a_hash).unwrap(),
+            transactions_b.as_slice(),
+            2,
+        );
 
         // Add block B - should trigger tie-breaking
         blockchain
@@ -2308,8 +2381,8 @@ mod tests {
 
         // Create initial chain: Genesis -> Block A
         let genesis_address = generate_test_genesis_address();
-        let coinbase_tx =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions = vec![coinbase_tx];
         let block_a = blockchain
             .mine_block(transactions.as_slice())
@@ -2324,8 +2397,8 @@ mod tests {
             .expect("Failed to get work");
 
         // Create block B with different height (height 3) to avoid tie-breaking
-        let coinbase_tx_b =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx_b = Transaction::new_test_coinbase(&genesis_address, 3)
+            .expect("Failed to create coinbase tx");
         let transactions_b = vec![coinbase_tx_b];
         let block_b = blockchain
             .mine_block(transactions_b.as_slice())
@@ -2357,8 +2430,8 @@ mod tests {
 
         // Create initial chain: Genesis -> Block A -> Block B
         let genesis_address = generate_test_genesis_address();
-        let coinbase_tx_a =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx_a = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions_a = vec![coinbase_tx_a];
         let block_a = blockchain
             .mine_block(transactions_a.as_slice())
@@ -2367,10 +2440,14 @@ mod tests {
         let block_a_hash = block_a.get_hash().to_string();
 
         // Create block C with same height but different content
-        let coinbase_tx_c =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx_c = Transaction::new_test_coinbase(&genesis_address, 3)
+            .expect("Failed to create coinbase tx");
         let transactions_c = vec![coinbase_tx_c];
-        let block_c = Block::new_block(block_a_hash.clone(), transactions_c.as_slice(), 3);
+        let block_c = Block::new_block(
+            &block_hash_from_hex(&block_a_hash).unwrap(),
+            transactions_c.as_slice(),
+            3,
+        );
 
         // Add block C - should trigger tie-breaking
         blockchain
@@ -2403,11 +2480,15 @@ mod tests {
             .expect("Failed to get initial balance");
 
         // Initial balance should be 0 (only genesis block exists, but no UTXO set updates)
-        assert_eq!(initial_balance, 0, "Initial balance should be 0");
+        assert_eq!(
+            initial_balance,
+            Amount::from_sat(0),
+            "Initial balance should be 0"
+        );
 
         // Step 2: Mine and add a block with coinbase transaction
-        let coinbase_tx =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions = vec![coinbase_tx];
         let block = blockchain
             .mine_block(transactions.as_slice())
@@ -2428,7 +2509,8 @@ mod tests {
 
         // Balance should be 10 from coinbase transaction (SUBSIDY = 10)
         assert_eq!(
-            balance_after, 10,
+            balance_after,
+            Amount::from_sat(10),
             "Balance should be 10 coins after adding block with coinbase"
         );
 
@@ -2449,8 +2531,8 @@ mod tests {
         let genesis_address = generate_test_genesis_address();
 
         // Step 1: Mine a block with coinbase transaction
-        let coinbase_tx =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let transactions = vec![coinbase_tx];
         let block_a = blockchain
             .mine_block(transactions.as_slice())
@@ -2474,16 +2556,21 @@ mod tests {
 
         // Should have 10 coins from coinbase transaction (SUBSIDY = 10)
         assert_eq!(
-            balance_before_reorg, 10,
+            balance_before_reorg,
+            Amount::from_sat(10),
             "Balance should be 10 coins before reorganization"
         );
 
         // Step 3: Create a competing block with different content (triggers reorganization)
-        let coinbase_tx_b =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx B");
+        let coinbase_tx_b = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx B");
         let transactions_b = vec![coinbase_tx_b];
         // Block B should have the same height as block A (2) and reference the same previous block
-        let block_b = Block::new_block(block_a.get_pre_block_hash(), transactions_b.as_slice(), 2);
+        let block_b = Block::new_block(
+            block_a.get_pre_block_hash_bytes(),
+            transactions_b.as_slice(),
+            2,
+        );
 
         // Step 4: Trigger reorganization by adding competing block
         // This should cause block A to be rolled back
@@ -2505,7 +2592,8 @@ mod tests {
         // But the important thing is that block A's coinbase was properly rolled back
         // and block B's coinbase was properly applied
         assert_eq!(
-            balance_after_reorg, 10,
+            balance_after_reorg,
+            Amount::from_sat(10),
             "Balance should be 10 coins after reorganization (from block B)"
         );
 
@@ -2525,7 +2613,11 @@ mod tests {
             1,
             "Should have exactly 1 UTXO after reorganization"
         );
-        assert_eq!(utxos[0].get_value(), 10, "UTXO value should be 10 coins");
+        assert_eq!(
+            utxos[0].get_value(),
+            Amount::from_sat(10),
+            "UTXO value should be 10 coins"
+        );
 
         cleanup_test_blockchain(&db_path);
     }
@@ -2543,8 +2635,8 @@ mod tests {
 
         // Step 1: Create initial chain with multiple blocks
         // Add 3 blocks to create a longer chain
-        for _i in 1..=3 {
-            let coinbase_tx = Transaction::new_coinbase_tx(&genesis_address)
+        for height in 2..=4 {
+            let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, height)
                 .expect("Failed to create coinbase tx");
             let transactions = vec![coinbase_tx];
             let block = blockchain
@@ -2568,7 +2660,11 @@ mod tests {
             .expect("Failed to get initial balance");
 
         // Should have 30 coins (3 blocks * 10 SUBSIDY each: no genesis block created automatically)
-        assert_eq!(initial_balance, 30, "Initial balance should be 30 coins");
+        assert_eq!(
+            initial_balance,
+            Amount::from_sat(30),
+            "Initial balance should be 30 coins"
+        );
 
         // Step 3: Create a competing chain that will win reorganization
         // This creates a fork at block 2, with a different block 3
@@ -2580,11 +2676,11 @@ mod tests {
         let block_1_hash = genesis_block.get_hash();
 
         // Create competing block 2
-        let competing_coinbase = Transaction::new_coinbase_tx(&genesis_address)
+        let competing_coinbase = Transaction::new_test_coinbase(&genesis_address, 2)
             .expect("Failed to create competing coinbase");
         let competing_transactions = vec![competing_coinbase];
         let competing_block_2 = Block::new_block(
-            block_1_hash.to_string(),
+            &block_hash_from_hex(&block_1_hash).unwrap(),
             competing_transactions.as_slice(),
             2,
         );
@@ -2614,7 +2710,7 @@ mod tests {
         // Final balance should be consistent (either original chain or reorganized chain)
         // The exact value depends on whether reorganization occurred
         assert!(
-            final_balance >= 30,
+            final_balance >= Amount::from_sat(30),
             "Final balance should be at least 30 coins (original chain minimum)"
         );
 
@@ -2632,7 +2728,8 @@ mod tests {
         assert!(utxos.len() >= 1, "Should have at least 1 UTXO");
 
         // Verify total value matches expected balance
-        let total_utxo_value: i32 = utxos.iter().map(|utxo| utxo.get_value()).sum();
+        let total_utxo_value = Amount::checked_sum(utxos.iter().map(|utxo| utxo.get_value()))
+            .expect("UTXO values overflow");
         assert_eq!(
             total_utxo_value, final_balance,
             "Total UTXO value should match balance"
@@ -2650,6 +2747,8 @@ mod tests {
     /// 4. UTXO set state is consistent after rollback
     #[tokio::test]
     async fn test_rollback_utxo_set_method_isolation() -> Result<()> {
+        // Spends coinbase outputs a few blocks after they are mined
+        crate::GLOBAL_CONFIG.set_coinbase_maturity(0);
         let (mut blockchain, db_path) = create_test_blockchain().await;
 
         // Create wallets using the wallet service
@@ -2664,8 +2763,8 @@ mod tests {
             .expect("Failed to create recipient wallet");
 
         // Step 1: Mine a block with both coinbase and regular transactions
-        let coinbase_tx =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 3)
+            .expect("Failed to create coinbase tx");
 
         // Create a regular transaction (this will fail without funds, but we'll handle that)
         let utxo_set = UTXOSet::new(BlockchainService::from_blockchain_file_system(
@@ -2673,8 +2772,8 @@ mod tests {
         ));
 
         // First, mine a block to create funds
-        let funding_tx =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create funding tx");
+        let funding_tx = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create funding tx");
         let funding_block = blockchain
             .mine_block(&[funding_tx])
             .await
@@ -2686,10 +2785,14 @@ mod tests {
             .expect("Failed to add funding block");
 
         // Now create a regular transaction (send 5 coins, leaving 5 for fees)
-        let regular_tx =
-            Transaction::new_utxo_transaction(&genesis_address, &recipient_address, 5, &utxo_set)
-                .await
-                .expect("Failed to create regular transaction");
+        let regular_tx = Transaction::new_utxo_transaction(
+            &genesis_address,
+            &recipient_address,
+            Amount::from_sat(5),
+            &utxo_set,
+        )
+        .await
+        .expect("Failed to create regular transaction");
 
         // Mine block with both coinbase and regular transaction
         let transactions = vec![coinbase_tx, regular_tx];
@@ -2739,14 +2842,18 @@ mod tests {
         // Sender balance should decrease (coinbase transaction removed)
         // Recipient balance should decrease (received outputs removed)
         assert_eq!(
-            sender_balance_after, 10,
+            sender_balance_after,
+            Amount::from_sat(10),
             "Sender balance should be 10 after rollback (coinbase transaction removed). Before: {}, After: {}",
-            sender_balance_before, sender_balance_after
+            sender_balance_before,
+            sender_balance_after
         );
         assert_eq!(
-            recipient_balance_after, 0,
+            recipient_balance_after,
+            Amount::from_sat(0),
             "Recipient balance should be 0 after rollback (received outputs removed). Before: {}, After: {}",
-            recipient_balance_before, recipient_balance_after
+            recipient_balance_before,
+            recipient_balance_after
         );
 
         // Step 6: Verify coinbase transaction was removed
@@ -2760,17 +2867,19 @@ mod tests {
             .expect("Failed to find UTXOs after rollback");
 
         // Should not have the coinbase transaction from test_block
-        let test_block_coinbase_value: i32 = test_block.get_transactions().await?[0]
-            .get_vout()
-            .iter()
-            .map(|output| output.get_value())
-            .sum();
+        let test_block_coinbase_value =
+            test_block.get_transactions().await?[0].get_output_value()?;
 
-        let current_utxo_value: i32 = utxos_after.iter().map(|utxo| utxo.get_value()).sum();
+        let current_utxo_value =
+            Amount::checked_sum(utxos_after.iter().map(|utxo| utxo.get_value()))
+                .expect("UTXO values overflow");
 
         // The coinbase value should not be in the current UTXOs
         assert!(
-            current_utxo_value < sender_balance_before + test_block_coinbase_value,
+            current_utxo_value
+                < sender_balance_before
+                    .checked_add(test_block_coinbase_value)
+                    .unwrap(),
             "Coinbase transaction should be removed from UTXO set"
         );
 
@@ -2789,8 +2898,8 @@ mod tests {
         let genesis_address = generate_test_genesis_address();
 
         // Create competing blocks at the same height (simulating multiple nodes mining simultaneously)
-        let coinbase_tx_a =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx_a = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let block_a = blockchain
             .mine_block(&[coinbase_tx_a])
             .await
@@ -2808,8 +2917,8 @@ mod tests {
             .await
             .expect("Failed to rollback to genesis");
 
-        let coinbase_tx_b =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx_b = Transaction::new_test_coinbase(&genesis_address, 3)
+            .expect("Failed to create coinbase tx");
         let block_b = blockchain
             .mine_block(&[coinbase_tx_b])
             .await
@@ -2821,8 +2930,8 @@ mod tests {
             .await
             .expect("Failed to rollback to genesis");
 
-        let coinbase_tx_c =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx_c = Transaction::new_test_coinbase(&genesis_address, 3)
+            .expect("Failed to create coinbase tx");
         let block_c = blockchain
             .mine_block(&[coinbase_tx_c])
             .await
@@ -2843,7 +2952,8 @@ mod tests {
             .expect("Failed to count UTXOs");
 
         assert_eq!(
-            initial_balance, 20,
+            initial_balance,
+            Amount::from_sat(20),
             "Initial balance should be 20 (2 * SUBSIDY: genesis + block C)"
         );
 
@@ -2872,7 +2982,7 @@ mod tests {
         // After reorganization, the balance should be consistent with the consensus outcome
         // The exact value depends on which blocks are accepted by the consensus mechanism
         assert!(
-            final_balance >= 10,
+            final_balance >= Amount::from_sat(10),
             "Final balance should be at least 10 (minimum one SUBSIDY)"
         );
 
@@ -2900,8 +3010,8 @@ mod tests {
         let genesis_address = generate_test_genesis_address();
 
         // Create first block
-        let coinbase_tx_a =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx_a = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let block_a = blockchain
             .mine_block(&[coinbase_tx_a])
             .await
@@ -2911,14 +3021,18 @@ mod tests {
         // Create competing block B manually (not through mine_block to avoid automatic addition)
         // Block B should have the same parent as Block A (the block at height 1), not Block A itself
         let block_a_parent = blockchain
-            .get_block(block_a_hash.as_bytes())
+            .get_block_by_hash(&block_a_hash)
             .await
             .expect("Failed to get block A")
             .expect("Block A not found");
         let block_a_parent_hash = block_a_parent.get_pre_block_hash();
-        let coinbase_tx_b =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
-        let block_b = Block::new_block(block_a_parent_hash, &[coinbase_tx_b], 2);
+        let coinbase_tx_b = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
+        let block_b = Block::new_block(
+            &block_hash_from_hex(&block_a_parent_hash).unwrap(),
+            &[coinbase_tx_b],
+            2,
+        );
 
         // Check current blockchain state
         let current_tip = blockchain.get_tip_hash().await.expect("Failed to get tip");
@@ -2965,34 +3079,38 @@ mod tests {
         cleanup_test_blockchain(&db_path);
     }
 
-    /// Test that blocks with higher height are accepted immediately without tie-breaking
+    /// Test that a block claiming a higher height does not replace the tip without more work
     #[tokio::test]
-    async fn test_higher_height_no_tie_breaking() {
+    async fn test_higher_height_without_more_work_is_not_activated() {
         let (mut blockchain, db_path) = create_test_blockchain().await;
         let genesis_address = generate_test_genesis_address();
 
         // Create initial chain: Genesis -> Block A
-        let coinbase_tx_a =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
+        let coinbase_tx_a = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
         let block_a = blockchain
             .mine_block(&[coinbase_tx_a])
             .await
             .expect("Failed to mine block A");
         let block_a_hash = block_a.get_hash();
 
-        // Create block B with higher height (height 3) - should be accepted immediately
+        // Create block B on block A's parent, claiming height 3
         let block_a_parent = blockchain
-            .get_block(block_a_hash.as_bytes())
+            .get_block_by_hash(&block_a_hash)
             .await
             .expect("Failed to get block A")
             .expect("Block A not found");
         let block_a_parent_hash = block_a_parent.get_pre_block_hash();
 
-        let coinbase_tx_b =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx");
-        let block_b = Block::new_block(block_a_parent_hash, &[coinbase_tx_b], 3); // Height 3 > Height 2
+        let coinbase_tx_b = Transaction::new_test_coinbase(&genesis_address, 3)
+            .expect("Failed to create coinbase tx");
+        let block_b = Block::new_block(
+            &block_hash_from_hex(&block_a_parent_hash).unwrap(),
+            &[coinbase_tx_b],
+            3,
+        ); // Height 3 > Height 2
 
-        // Add block B - should be accepted immediately due to higher height
+        // Add block B - its branch has no more work than block A's, so it is not activated
         blockchain
             .add_block(&block_b)
             .await
@@ -3008,12 +3126,8 @@ mod tests {
             .await
             .expect("Failed to get final height");
 
-        assert_eq!(
-            final_tip,
-            block_b.get_hash(),
-            "Block B should be the new tip"
-        );
-        assert_eq!(final_height, 3, "Height should be 3");
+        assert_eq!(final_tip, block_a_hash, "Block A should stay the tip");
+        assert_eq!(final_height, 2, "Height should stay 2");
 
         cleanup_test_blockchain(&db_path);
     }
@@ -3037,8 +3151,8 @@ mod tests {
             .expect("Failed to count initial UTXOs");
 
         // Step 2: Simulate Node A mining a block
-        let coinbase_tx_a =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx A");
+        let coinbase_tx_a = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx A");
         let block_a = blockchain
             .mine_block(&[coinbase_tx_a])
             .await
@@ -3055,15 +3169,19 @@ mod tests {
 
         // Step 3: Simulate Node B creating a competing block at the SAME height
         let block_a_parent = blockchain
-            .get_block(block_a.get_hash().as_bytes())
+            .get_block_by_hash(&block_a.get_hash())
             .await
             .expect("Failed to get block A")
             .expect("Block A not found");
         let block_a_parent_hash = block_a_parent.get_pre_block_hash();
 
-        let coinbase_tx_b =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx B");
-        let block_b = Block::new_block(block_a_parent_hash, &[coinbase_tx_b], block_a.get_height()); // Same height as block A
+        let coinbase_tx_b = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx B");
+        let block_b = Block::new_block(
+            &block_hash_from_hex(&block_a_parent_hash).unwrap(),
+            &[coinbase_tx_b],
+            block_a.get_height(),
+        ); // Same height as block A
 
         // Step 4: Add Node B's block to Node A's blockchain (simulate block exchange)
         let result = blockchain.add_block(&block_b).await;
@@ -3094,7 +3212,8 @@ mod tests {
         // The balance should be exactly 2 * SUBSIDY (genesis + winning block)
         // If reorganization happened correctly, only one block's SUBSIDY should remain
         assert_eq!(
-            balance_after_consensus, 20,
+            balance_after_consensus,
+            Amount::from_sat(20),
             "Balance should be exactly 20 (2 * SUBSIDY), got {}",
             balance_after_consensus
         );
@@ -3145,11 +3264,15 @@ mod tests {
             let node_wallet =
                 crate::Wallet::new().expect(&format!("Failed to create wallet for node {}", i));
             let node_wallet_address = node_wallet.get_address().expect("Failed to get address");
-            let coinbase_tx = Transaction::new_coinbase_tx(&node_wallet_address)
+            let coinbase_tx = Transaction::new_test_coinbase(&node_wallet_address, 2)
                 .expect("Failed to create coinbase tx");
             // All competing blocks should have the same height (2) but different content
             // to ensure different work values through different transaction hashes
-            let block = Block::new_block(genesis_hash.to_string(), &[coinbase_tx], 2);
+            let block = Block::new_block(
+                &block_hash_from_hex(&genesis_hash).unwrap(),
+                &[coinbase_tx],
+                2,
+            );
             competing_blocks.push(block.clone());
         }
 
@@ -3196,7 +3319,8 @@ mod tests {
         // Blocks are accepted by add_block but UTXO set is not updated correctly
         // This indicates an issue with the UTXO update mechanism during block addition
         assert_eq!(
-            final_balance, 10,
+            final_balance,
+            Amount::from_sat(10),
             "Genesis address balance should remain 10, got {}. UTXO set not updated properly.",
             final_balance
         );
@@ -3238,7 +3362,7 @@ mod tests {
 
         // Create multiple transactions (but less than threshold)
         for i in 1..=5 {
-            let coinbase_tx = Transaction::new_coinbase_tx(&genesis_address)
+            let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, i + 1)
                 .expect("Failed to create coinbase tx");
             let _block = blockchain
                 .mine_block(&[coinbase_tx])
@@ -3272,7 +3396,8 @@ mod tests {
         // Each block should add exactly 10 SUBSIDY
         // Genesis + 5 blocks = 6 * 10 = 60
         assert_eq!(
-            final_balance, 60,
+            final_balance,
+            Amount::from_sat(60),
             "Balance should be exactly 60 (6 * SUBSIDY), got {}",
             final_balance
         );
@@ -3304,7 +3429,7 @@ mod tests {
         let genesis_address = generate_test_genesis_address();
 
         // Create genesis block
-        let coinbase_tx_genesis = Transaction::new_coinbase_tx(&genesis_address)
+        let coinbase_tx_genesis = Transaction::new_test_coinbase(&genesis_address, 2)
             .expect("Failed to create genesis coinbase tx");
         let _genesis_block = blockchain
             .mine_block(&[coinbase_tx_genesis])
@@ -3338,7 +3463,7 @@ mod tests {
             .expect("Failed to get recipient address");
 
         // Step 1: Create initial blockchain with genesis block
-        let coinbase_tx_genesis = Transaction::new_coinbase_tx(&genesis_address)
+        let coinbase_tx_genesis = Transaction::new_test_coinbase(&genesis_address, 2)
             .expect("Failed to create genesis coinbase tx");
         let _genesis_block = blockchain
             .mine_block(&[coinbase_tx_genesis])
@@ -3358,13 +3483,21 @@ mod tests {
             .expect("Failed to get recipient balance");
 
         // Initial balances should be 10 for genesis and 0 for recipient
-        assert_eq!(genesis_balance, 10, "Genesis balance should be 10");
-        assert_eq!(recipient_balance, 0, "Recipient balance should be 0");
+        assert_eq!(
+            genesis_balance,
+            Amount::from_sat(10),
+            "Genesis balance should be 10"
+        );
+        assert_eq!(
+            recipient_balance,
+            Amount::from_sat(0),
+            "Recipient balance should be 0"
+        );
 
         // Step 2: Create a second block with a transaction to the recipient
 
         // Create a simple transaction (coinbase to recipient)
-        let coinbase_tx_recipient = Transaction::new_coinbase_tx(&recipient_address)
+        let coinbase_tx_recipient = Transaction::new_test_coinbase(&recipient_address, 3)
             .expect("Failed to create recipient coinbase tx");
         let _second_block = blockchain
             .mine_block(&[coinbase_tx_recipient])
@@ -3383,7 +3516,8 @@ mod tests {
 
         // After second block, recipient should have 10 coins
         assert_eq!(
-            recipient_balance_after, 10,
+            recipient_balance_after,
+            Amount::from_sat(10),
             "Recipient should have 10 coins after second block"
         );
 
@@ -3409,18 +3543,20 @@ mod tests {
         );
         assert_eq!(
             recipient_utxos[0].get_value(),
-            10,
+            Amount::from_sat(10),
             "Recipient UTXO should have value 10"
         );
 
         // Expected: Genesis should still have 10, Recipient should have 10 (from coinbase)
         assert_eq!(
-            genesis_balance_after, 10,
+            genesis_balance_after,
+            Amount::from_sat(10),
             "Genesis balance should be 10, got {}",
             genesis_balance_after
         );
         assert_eq!(
-            recipient_balance_after, 10,
+            recipient_balance_after,
+            Amount::from_sat(10),
             "Recipient balance should be 10, got {}",
             recipient_balance_after
         );
@@ -3431,6 +3567,8 @@ mod tests {
     /// Test to simulate the actual network transaction flow
     #[tokio::test]
     async fn test_network_transaction_flow() {
+        // Spends coinbase outputs a few blocks after they are mined
+        crate::GLOBAL_CONFIG.set_coinbase_maturity(0);
         let (blockchain, db_path) = create_test_blockchain().await;
 
         // Create wallets using the wallet service consistently
@@ -3444,7 +3582,7 @@ mod tests {
             .expect("Failed to create recipient wallet");
 
         // Step 1: Create initial blockchain with genesis block
-        let coinbase_tx_genesis = Transaction::new_coinbase_tx(&genesis_address)
+        let coinbase_tx_genesis = Transaction::new_test_coinbase(&genesis_address, 2)
             .expect("Failed to create genesis coinbase tx");
         let _genesis_block = blockchain
             .mine_block(&[coinbase_tx_genesis])
@@ -3464,8 +3602,16 @@ mod tests {
             .expect("Failed to get recipient balance");
 
         // Initial balances should be 10 for genesis and 0 for recipient
-        assert_eq!(genesis_balance, 10, "Genesis balance should be 10");
-        assert_eq!(recipient_balance, 0, "Recipient balance should be 0");
+        assert_eq!(
+            genesis_balance,
+            Amount::from_sat(10),
+            "Genesis balance should be 10"
+        );
+        assert_eq!(
+            recipient_balance,
+            Amount::from_sat(0),
+            "Recipient balance should be 0"
+        );
 
         // Step 2: Simulate creating a transaction (like in the network)
 
@@ -3473,7 +3619,7 @@ mod tests {
         let transaction = Transaction::new_utxo_transaction(
             &genesis_address,
             &recipient_address,
-            3, // Send 3 coins
+            Amount::from_sat(3), // Send 3 coins
             &utxo_set,
         )
         .await
@@ -3493,11 +3639,13 @@ mod tests {
 
         // Before mining, balances should be unchanged
         assert_eq!(
-            genesis_balance_before, 10,
+            genesis_balance_before,
+            Amount::from_sat(10),
             "Genesis balance should be 10 before mining"
         );
         assert_eq!(
-            recipient_balance_before, 0,
+            recipient_balance_before,
+            Amount::from_sat(0),
             "Recipient balance should be 0 before mining"
         );
 
@@ -3519,11 +3667,13 @@ mod tests {
 
         // After mining, recipient should have 3 coins (from transaction)
         assert_eq!(
-            genesis_balance_after, 7,
+            genesis_balance_after,
+            Amount::from_sat(7),
             "Genesis balance should be 7 after mining (spent 3)"
         );
         assert_eq!(
-            recipient_balance_after, 3,
+            recipient_balance_after,
+            Amount::from_sat(3),
             "Recipient balance should be 3 after mining (received 3)"
         );
 
@@ -3545,12 +3695,14 @@ mod tests {
 
         // Expected: Genesis should have 7 (10 - 3), Recipient should have 3
         assert_eq!(
-            genesis_balance_after, 7,
+            genesis_balance_after,
+            Amount::from_sat(7),
             "Genesis balance should be 7 (10 - 3), got {}",
             genesis_balance_after
         );
         assert_eq!(
-            recipient_balance_after, 3,
+            recipient_balance_after,
+            Amount::from_sat(3),
             "Recipient balance should be 3, got {}",
             recipient_balance_after
         );
@@ -3562,6 +3714,8 @@ mod tests {
     /// Scenario: 3 nodes work, but adding a 4th node breaks balances
     #[tokio::test]
     async fn test_multi_node_transaction_issue() {
+        // Spends coinbase outputs a few blocks after they are mined
+        crate::GLOBAL_CONFIG.set_coinbase_maturity(0);
         // Scenario 1: 3 nodes (should work)
         test_three_node_scenario().await;
 
@@ -3629,22 +3783,34 @@ mod tests {
             .expect("Failed to get node3 balance");
 
         // Initial balances should be 10 for Node1, 0 for Node2 and Node3
-        assert_eq!(node1_balance, 10, "Node1 balance should be 10");
-        assert_eq!(node2_balance, 0, "Node2 balance should be 0");
-        assert_eq!(node3_balance, 0, "Node3 balance should be 0");
+        assert_eq!(
+            node1_balance,
+            Amount::from_sat(10),
+            "Node1 balance should be 10"
+        );
+        assert_eq!(
+            node2_balance,
+            Amount::from_sat(0),
+            "Node2 balance should be 0"
+        );
+        assert_eq!(
+            node3_balance,
+            Amount::from_sat(0),
+            "Node3 balance should be 0"
+        );
 
         // Step 4: Node 3 creates transaction from Node 1 to Node 2
         let transaction = Transaction::new_utxo_transaction(
             &node1_address,
             &node2_address,
-            5,
+            Amount::from_sat(5),
             &utxo_set3, // Use Node 3's UTXO set
         )
         .await
         .expect("Failed to create transaction");
 
         // Step 5: Node 3 mines the transaction (with coinbase for mining reward)
-        let coinbase_tx = Transaction::new_coinbase_tx(&node3_address)
+        let coinbase_tx = Transaction::new_test_coinbase(&node3_address, 2)
             .expect("Failed to create node3 coinbase tx");
         let block = blockchain3
             .mine_block(&[coinbase_tx, transaction])
@@ -3677,17 +3843,20 @@ mod tests {
 
         // Expected: Node1 should have 5 (10 - 5), Node2 should have 5, Node3 should have 10 (from mining)
         assert_eq!(
-            node1_balance_final, 5,
+            node1_balance_final,
+            Amount::from_sat(5),
             "Node 1 balance should be 5, got {}",
             node1_balance_final
         );
         assert_eq!(
-            node2_balance_final, 5,
+            node2_balance_final,
+            Amount::from_sat(5),
             "Node 2 balance should be 5, got {}",
             node2_balance_final
         );
         assert_eq!(
-            node3_balance_final, 10,
+            node3_balance_final,
+            Amount::from_sat(10),
             "Node 3 balance should be 10, got {}",
             node3_balance_final
         );
@@ -3770,23 +3939,39 @@ mod tests {
             .expect("Failed to get node4 balance");
 
         // Initial balances should be 10 for Node1, 0 for Node2, Node3, and Node4
-        assert_eq!(node1_balance, 10, "Node1 balance should be 10");
-        assert_eq!(node2_balance, 0, "Node2 balance should be 0");
-        assert_eq!(node3_balance, 0, "Node3 balance should be 0");
-        assert_eq!(node4_balance, 0, "Node4 balance should be 0");
+        assert_eq!(
+            node1_balance,
+            Amount::from_sat(10),
+            "Node1 balance should be 10"
+        );
+        assert_eq!(
+            node2_balance,
+            Amount::from_sat(0),
+            "Node2 balance should be 0"
+        );
+        assert_eq!(
+            node3_balance,
+            Amount::from_sat(0),
+            "Node3 balance should be 0"
+        );
+        assert_eq!(
+            node4_balance,
+            Amount::from_sat(0),
+            "Node4 balance should be 0"
+        );
 
         // Step 4: Node 4 creates transaction from Node 1 to Node 2
         let transaction = Transaction::new_utxo_transaction(
             &node1_address,
             &node2_address,
-            5,
+            Amount::from_sat(5),
             &utxo_set4, // Use Node 4's UTXO set
         )
         .await
         .expect("Failed to create transaction");
 
         // Step 5: Node 4 mines the transaction (with coinbase for mining reward)
-        let coinbase_tx = Transaction::new_coinbase_tx(&node4_address)
+        let coinbase_tx = Transaction::new_test_coinbase(&node4_address, 2)
             .expect("Failed to create node4 coinbase tx");
         let block = blockchain4
             .mine_block(&[coinbase_tx, transaction])
@@ -3827,22 +4012,26 @@ mod tests {
 
         // Expected: Node1 should have 5 (10 - 5), Node2 should have 5, Node3 should have 0, Node4 should have 10 (from mining)
         assert_eq!(
-            node1_balance_final, 5,
+            node1_balance_final,
+            Amount::from_sat(5),
             "Node 1 balance should be 5, got {}",
             node1_balance_final
         );
         assert_eq!(
-            node2_balance_final, 5,
+            node2_balance_final,
+            Amount::from_sat(5),
             "Node 2 balance should be 5, got {}",
             node2_balance_final
         );
         assert_eq!(
-            node3_balance_final, 0,
+            node3_balance_final,
+            Amount::from_sat(0),
             "Node 3 balance should be 0, got {}",
             node3_balance_final
         );
         assert_eq!(
-            node4_balance_final, 10,
+            node4_balance_final,
+            Amount::from_sat(10),
             "Node 4 balance should be 10, got {}",
             node4_balance_final
         );
@@ -3860,7 +4049,7 @@ mod tests {
         let genesis_address = generate_test_genesis_address();
 
         // Step 1: Create initial chain: Genesis -> Block A
-        let coinbase_tx_genesis = Transaction::new_coinbase_tx(&genesis_address)
+        let coinbase_tx_genesis = Transaction::new_test_coinbase(&genesis_address, 2)
             .expect("Failed to create genesis coinbase tx");
         let block_a = blockchain
             .mine_block(&[coinbase_tx_genesis])
@@ -3868,7 +4057,7 @@ mod tests {
             .expect("Failed to mine block A");
 
         // Step 2: Create competing chain: Genesis -> Block A -> Block B
-        let coinbase_tx_b = Transaction::new_coinbase_tx(&genesis_address)
+        let coinbase_tx_b = Transaction::new_test_coinbase(&genesis_address, 3)
             .expect("Failed to create block B coinbase tx");
         let block_b = blockchain
             .mine_block(&[coinbase_tx_b])
@@ -3882,14 +4071,14 @@ mod tests {
             .await
             .expect("Failed to rollback to block A");
 
-        let coinbase_tx_c = Transaction::new_coinbase_tx(&genesis_address)
+        let coinbase_tx_c = Transaction::new_test_coinbase(&genesis_address, 3)
             .expect("Failed to create block C coinbase tx");
         let _block_c = blockchain
             .mine_block(&[coinbase_tx_c])
             .await
             .expect("Failed to mine block C");
 
-        let coinbase_tx_d = Transaction::new_coinbase_tx(&genesis_address)
+        let coinbase_tx_d = Transaction::new_test_coinbase(&genesis_address, 4)
             .expect("Failed to create block D coinbase tx");
         let block_d = blockchain
             .mine_block(&[coinbase_tx_d])
@@ -3926,6 +4115,8 @@ mod tests {
     /// Test the Transaction 2 scenario where Node 3 incorrectly keeps mining reward
     #[tokio::test]
     async fn test_transaction2_node3_reward_issue() {
+        // Spends coinbase outputs a few blocks after they are mined
+        crate::GLOBAL_CONFIG.set_coinbase_maturity(0);
         // Test with 4 separate nodes running on different servers
         // This test simulates real network synchronization between independent nodes
 
@@ -3973,9 +4164,9 @@ mod tests {
             .expect("Node 4 failed to sync genesis block");
 
         // Add 2 more blocks to Node 1 to get to 30 balance (10 + 10 + 10)
-        for _ in 0..2 {
-            let coinbase_tx =
-                Transaction::new_coinbase_tx(&node1_address).expect("Failed to create coinbase tx");
+        for height in 2..4 {
+            let coinbase_tx = Transaction::new_test_coinbase(&node1_address, height)
+                .expect("Failed to create coinbase tx");
             let _block = blockchain1
                 .mine_block(&[coinbase_tx])
                 .await
@@ -4088,24 +4279,30 @@ mod tests {
 
         // Initial balances should be 30 for Node1, 0 for Node2
         assert_eq!(
-            node1_balance_initial, 30,
+            node1_balance_initial,
+            Amount::from_sat(30),
             "Node1 should start with 30 balance"
         );
         assert_eq!(
-            node2_balance_initial, 0,
+            node2_balance_initial,
+            Amount::from_sat(0),
             "Node2 should start with 0 balance"
         );
 
         // Step 5: Create and mine a transaction from Node 1 to Node 2
         // Use Node 1's UTXO set to create the transaction (Node 1 has the funds)
-        let transaction =
-            Transaction::new_utxo_transaction(&node1_address, &node2_address, 5, &utxo_set1)
-                .await
-                .expect("Failed to create transaction");
+        let transaction = Transaction::new_utxo_transaction(
+            &node1_address,
+            &node2_address,
+            Amount::from_sat(5),
+            &utxo_set1,
+        )
+        .await
+        .expect("Failed to create transaction");
 
         // Node 1 mines the transaction with coinbase reward
-        let coinbase_tx =
-            Transaction::new_coinbase_tx(&node1_address).expect("Failed to create coinbase tx");
+        let coinbase_tx = Transaction::new_test_coinbase(&node1_address, 4)
+            .expect("Failed to create coinbase tx");
         let block = blockchain1
             .mine_block(&[coinbase_tx, transaction])
             .await
@@ -4188,11 +4385,13 @@ mod tests {
         // After transaction: Node1 should have 35 (30+10-5), Node2 should have 5
         // Node1 gets 10 from mining reward, then spends 5, so 30+10-5=35
         assert_eq!(
-            node1_balance_after, 35,
+            node1_balance_after,
+            Amount::from_sat(35),
             "Node1 should have 35 after transaction (30+10-5)"
         );
         assert_eq!(
-            node2_balance_after, 5,
+            node2_balance_after,
+            Amount::from_sat(5),
             "Node2 should have 5 after transaction"
         );
 
@@ -4231,7 +4430,7 @@ mod tests {
         let genesis_address = generate_test_genesis_address();
 
         // Create initial chain: Genesis -> Block A
-        let coinbase_tx_genesis = Transaction::new_coinbase_tx(&genesis_address)
+        let coinbase_tx_genesis = Transaction::new_test_coinbase(&genesis_address, 2)
             .expect("Failed to create genesis coinbase tx");
         let block_a = blockchain
             .mine_block(&[coinbase_tx_genesis])
@@ -4242,7 +4441,7 @@ mod tests {
         // Block B will have a different recipient address to ensure different work
         let wallet_b = crate::Wallet::new().expect("Failed to create wallet");
         let recipient_b = wallet_b.get_address().expect("Failed to get address");
-        let coinbase_tx_b = Transaction::new_coinbase_tx(&recipient_b)
+        let coinbase_tx_b = Transaction::new_test_coinbase(&recipient_b, 3)
             .expect("Failed to create block B coinbase tx");
         let block_b = blockchain
             .mine_block(&[coinbase_tx_b])
@@ -4256,7 +4455,7 @@ mod tests {
             .expect("Failed to rollback to block A");
         let wallet_c = crate::Wallet::new().expect("Failed to create wallet");
         let recipient_c = wallet_c.get_address().expect("Failed to get address");
-        let coinbase_tx_c = Transaction::new_coinbase_tx(&recipient_c)
+        let coinbase_tx_c = Transaction::new_test_coinbase(&recipient_c, 3)
             .expect("Failed to create block C coinbase tx");
         let block_c = blockchain
             .mine_block(&[coinbase_tx_c])
@@ -4294,7 +4493,7 @@ mod tests {
         let genesis_address = generate_test_genesis_address();
 
         // Create initial chain: Genesis -> Block A
-        let coinbase_tx_genesis = Transaction::new_coinbase_tx(&genesis_address)
+        let coinbase_tx_genesis = Transaction::new_test_coinbase(&genesis_address, 2)
             .expect("Failed to create genesis coinbase tx");
         let block_a = blockchain
             .mine_block(&[coinbase_tx_genesis])
@@ -4304,21 +4503,29 @@ mod tests {
 
         // Create two identical blocks with same parent
         let block_a_parent = blockchain
-            .get_block(block_a_hash.as_bytes())
+            .get_block_by_hash(&block_a_hash)
             .await
             .expect("Failed to get block A")
             .expect("Block A not found");
         let block_a_parent_hash = block_a_parent.get_pre_block_hash();
 
-        let coinbase_tx_b = Transaction::new_coinbase_tx(&genesis_address)
+        let coinbase_tx_b = Transaction::new_test_coinbase(&genesis_address, 2)
             .expect("Failed to create block B coinbase tx");
         let transactions_b = vec![coinbase_tx_b];
-        let block_b = Block::new_block(block_a_parent_hash.clone(), transactions_b.as_slice(), 2);
+        let block_b = Block::new_block(
+            &block_hash_from_hex(&block_a_parent_hash).unwrap(),
+            transactions_b.as_slice(),
+            2,
+        );
 
-        let coinbase_tx_c = Transaction::new_coinbase_tx(&genesis_address)
+        let coinbase_tx_c = Transaction::new_test_coinbase(&genesis_address, 2)
             .expect("Failed to create block C coinbase tx");
         let transactions_c = vec![coinbase_tx_c];
-        let block_c = Block::new_block(block_a_parent_hash, transactions_c.as_slice(), 2);
+        let block_c = Block::new_block(
+            &block_hash_from_hex(&block_a_parent_hash).unwrap(),
+            transactions_c.as_slice(),
+            2,
+        );
 
         // Check if blocks are identical
         let identical = block_b.get_timestamp() == block_c.get_timestamp()
@@ -4400,15 +4607,15 @@ mod tests {
         );
 
         // Both nodes mine competing blocks
-        let coinbase_tx1 =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx 1");
+        let coinbase_tx1 = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx 1");
         let block1 = service1
             .mine_block(&[coinbase_tx1])
             .await
             .expect("Failed to mine block 1");
 
-        let coinbase_tx2 =
-            Transaction::new_coinbase_tx(&genesis_address).expect("Failed to create coinbase tx 2");
+        let coinbase_tx2 = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx 2");
         let block2 = service2
             .mine_block(&[coinbase_tx2])
             .await
@@ -4485,7 +4692,7 @@ mod tests {
             // Create and add genesis block if blockchain is empty
             let node1_wallet =
                 WalletAddress::validate(node1_address.to_string()).expect("Invalid address");
-            let genesis_tx = Transaction::new_coinbase_tx(&node1_wallet)
+            let genesis_tx = Transaction::new_test_coinbase(&node1_wallet, 1)
                 .expect("Failed to create genesis coinbase transaction");
             let _genesis_block = blockchain
                 .mine_block(&[genesis_tx])
@@ -4500,11 +4707,12 @@ mod tests {
         }
 
         // Create coinbase transactions for empty blocks
+        let height = blockchain.get_best_height().await.unwrap();
         let node1_wallet =
             WalletAddress::validate(node1_address.to_string()).expect("Invalid address");
-        let coinbase_tx1 = Transaction::new_coinbase_tx(&node1_wallet)
+        let coinbase_tx1 = Transaction::new_test_coinbase(&node1_wallet, height + 1)
             .expect("Failed to create coinbase transaction 1");
-        let coinbase_tx2 = Transaction::new_coinbase_tx(&node1_wallet)
+        let coinbase_tx2 = Transaction::new_test_coinbase(&node1_wallet, height + 2)
             .expect("Failed to create coinbase transaction 2");
 
         // Mine 2 empty blocks
@@ -4534,4 +4742,121 @@ mod tests {
         // Cleanup
         cleanup_test_blockchain(&db_path);
     }
+
+    /// Test that the target is recalculated at the end of a retarget window
+    #[tokio::test]
+    async fn test_difficulty_retarget_after_interval() {
+        let (blockchain, db_path) = create_test_blockchain().await;
+        let genesis_address = generate_test_genesis_address();
+
+        // Genesis is height 1, so mining up to the interval closes the first window
+        while blockchain.get_best_height().await.unwrap() < DIFFICULTY_ADJUSTMENT_INTERVAL {
+            let coinbase_tx = Transaction::new_test_coinbase(
+                &genesis_address,
+                blockchain.get_best_height().await.unwrap() + 1,
+            )
+            .expect("Failed to create coinbase tx");
+            let block = blockchain
+                .mine_block(&[coinbase_tx])
+                .await
+                .expect("Failed to mine block");
+            assert_eq!(block.get_bits(), POW_LIMIT_BITS);
+        }
+
+        // The window was mined far faster than the target spacing, so the next
+        // block must be the maximum 4x harder
+        let coinbase_tx =
+            Transaction::new_test_coinbase(&genesis_address, DIFFICULTY_ADJUSTMENT_INTERVAL + 1)
+                .expect("Failed to create coinbase tx");
+        let retarget_block = blockchain
+            .mine_block(&[coinbase_tx])
+            .await
+            .expect("Failed to mine retarget block");
+        let limit_block = Block::new_block(&[0; BLOCK_HASH_SIZE], &[], 1);
+        assert_eq!(retarget_block.get_target(), limit_block.get_target() / 4);
+        assert_eq!(retarget_block.get_target_bits(), 10);
+        assert_eq!(retarget_block.get_difficulty(), 4);
+        assert_eq!(retarget_block.get_work(), limit_block.get_work() * 4);
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    /// Test that blocks whose bits do not match the expected retarget are rejected
+    #[tokio::test]
+    async fn test_add_block_rejects_unexpected_bits() {
+        let (mut blockchain, db_path) = create_test_blockchain().await;
+        let genesis_address = generate_test_genesis_address();
+
+        let tip_hash = blockchain.get_tip_hash().await.unwrap();
+        let best_height = blockchain.get_best_height().await.unwrap();
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, best_height + 1)
+            .expect("Failed to create coinbase tx");
+        let harder_bits = 0x1f40_0000;
+        let block = Block::new_block_with_bits(
+            &block_hash_from_hex(&tip_hash).unwrap(),
+            &[coinbase_tx],
+            best_height + 1,
+            harder_bits,
+        );
+
+        let result = blockchain.add_block(&block).await;
+        assert!(matches!(
+            result,
+            Err(BtcError::InvalidBlockBits(POW_LIMIT_BITS, 0x1f40_0000))
+        ));
+        assert_eq!(blockchain.get_tip_hash().await.unwrap(), tip_hash);
+        assert!(
+            blockchain
+                .get_block(block.get_hash_bytes().as_slice())
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        cleanup_test_blockchain(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_find_all_transactions_labels_data_carrier_outputs() {
+        let (blockchain, db_path) = create_test_blockchain().await;
+        let genesis_address = generate_test_genesis_address();
+
+        let genesis_hash = blockchain.get_tip_hash().await.unwrap();
+        let genesis = blockchain
+            .get_block_by_hash(&genesis_hash)
+            .await
+            .unwrap()
+            .expect("Genesis block should exist");
+        let genesis_coinbase = genesis.get_transactions().await.unwrap()[0].clone();
+        let anchor = Transaction::test_builder()
+            .spending(genesis_coinbase.get_id(), 0, SEQUENCE_FINAL)
+            .with_output(TXOutput::new_data_carrier(b"anchor").unwrap())
+            .with_output(TXOutput::new(Amount::from_sat(4), &genesis_address).unwrap())
+            .build();
+        let coinbase_tx = Transaction::new_test_coinbase(&genesis_address, 2)
+            .expect("Failed to create coinbase tx");
+        blockchain
+            .mine_block(&[coinbase_tx, anchor.clone()])
+            .await
+            .expect("Failed to mine block");
+
+        let mut transactions = blockchain.find_all_transactions().await.unwrap();
+        let summary = transactions
+            .get_mut(&anchor.get_tx_id_hex())
+            .expect("Mined transaction should be summarized");
+        let outputs = summary.get_outputs().to_vec();
+        let data = outputs
+            .iter()
+            .find(|output| output.get_data().is_some())
+            .expect("Data carrier output should be summarized");
+        assert!(data.get_wlt_addr().is_none());
+        assert_eq!(data.get_data(), Some(b"anchor".as_slice()));
+        let payment = outputs
+            .iter()
+            .find(|output| output.get_data().is_none())
+            .expect("Payment output should be summarized");
+        assert_eq!(payment.get_wlt_addr(), Some(&genesis_address));
+
+        cleanup_test_blockchain(&db_path);
+    }
 }
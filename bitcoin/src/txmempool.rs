@@ -1,22 +1,141 @@
+use crate::consensus::Amount;
 use crate::error::{BtcError, Result};
+use crate::policy::DEFAULT_INCREMENTAL_RELAY_FEE;
 use crate::primitives::transaction::Transaction;
-use std::collections::HashMap;
+use crate::util::current_timestamp;
+use std::collections::{BTreeSet, HashMap};
 use std::sync::RwLock;
 
+/// A transaction in the memory pool together with its place in the pool's dependency graph
+/// (Bitcoin Core's `CTxMemPoolEntry`).
+///
+/// # Fields
+///
+/// `tx` - The transaction.
+/// `fee` - The fee it pays, valued when it entered the pool.
+/// `size` - Its serialized size in bytes.
+/// `time` - When it entered the pool, in milliseconds since the Unix epoch.
+/// `parents` - Ids of the pool transactions whose outputs it spends.
+/// `children` - Ids of the pool transactions spending its outputs.
+#[derive(Clone)]
+pub struct TxMemPoolEntry {
+    tx: Transaction,
+    fee: Amount,
+    size: usize,
+    time: i64,
+    parents: BTreeSet<String>,
+    children: BTreeSet<String>,
+}
+
+impl TxMemPoolEntry {
+    pub fn get_transaction(&self) -> &Transaction {
+        &self.tx
+    }
+
+    pub fn get_fee(&self) -> Amount {
+        self.fee
+    }
+
+    /// Serialized size in bytes.
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    /// When the transaction entered the pool, in milliseconds since the Unix epoch.
+    pub fn get_time(&self) -> i64 {
+        self.time
+    }
+
+    /// Ids of the pool transactions this one spends from, sorted.
+    pub fn get_parents(&self) -> &BTreeSet<String> {
+        &self.parents
+    }
+
+    /// Ids of the pool transactions spending this one's outputs, sorted.
+    pub fn get_children(&self) -> &BTreeSet<String> {
+        &self.children
+    }
+}
+
+/// Fee and size of a pool transaction together with all its pool ancestors: what a miner
+/// earns per byte for including the transaction (Bitcoin Core's `nModFeesWithAncestors`
+/// and `nSizeWithAncestors`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AncestorPackage {
+    count: usize,
+    fee: Amount,
+    size: usize,
+}
+
+impl AncestorPackage {
+    /// Number of transactions in the package, the transaction itself included.
+    pub fn get_count(&self) -> usize {
+        self.count
+    }
+
+    pub fn get_fee(&self) -> Amount {
+        self.fee
+    }
+
+    /// Serialized size of the package in bytes.
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    /// Whether this package pays more per byte than `other`, compared without rounding.
+    pub fn has_higher_fee_rate_than(&self, other: &AncestorPackage) -> bool {
+        self.fee.to_sat() as u128 * other.size as u128
+            > other.fee.to_sat() as u128 * self.size as u128
+    }
+}
+
+/// Half-life of the memory pool minimum fee rate once the pool stops evicting, in
+/// milliseconds (Bitcoin Core's `CTxMemPool::ROLLING_FEE_HALFLIFE` of 12 hours).
+pub const ROLLING_FEE_HALFLIFE: i64 = 12 * 60 * 60 * 1000;
+
+/// An output of a transaction: the transaction id and the output index.
+type OutPoint = (Vec<u8>, usize);
+
+/// The minimum fee rate a full pool demands, and when it was last decayed.
+#[derive(Clone, Copy, Default)]
+struct RollingMinimumFee {
+    fee_rate: u64,
+    last_update: i64,
+}
+
 /// The `MemoryPool` struct is used to store transactions that are in the memory pool.
 ///
+/// Transactions may spend outputs of other pool transactions, so the pool keeps a
+/// dependency graph: every entry knows its parents and children in the pool, and a
+/// transaction's ancestors must be mined before it or together with it.
+///
+/// The pool also indexes the outputs its transactions spend, so the wallet and the
+/// conflict checks treat them as spent while the UTXO set keeps only confirmed state.
+///
+/// The pool is bounded: `trim_to_size` evicts the transactions paying the lowest fee rate
+/// and raises a minimum fee rate new transactions must pay, which decays back to zero
+/// while the pool has room; `expire` drops transactions that waited too long.
+///
 /// # Fields
 ///
-/// `inner_tx` - A `RwLock` that holds a `HashMap` of `Transaction`s.
+/// `inner_tx` - A `RwLock` that holds a `HashMap` of `TxMemPoolEntry`s by txid.
+/// `spent_outpoints` - The id of the pool transaction spending each outpoint (Bitcoin
+/// Core's `mapNextTx`); locked after `inner_tx` whenever both are held.
+/// `rolling_minimum_fee` - The minimum fee rate raised by evictions, in satoshis per 1000
+/// bytes (Bitcoin Core's `rollingMinimumFeeRate`).
 ///
 pub struct MemoryPool {
-    inner_tx: RwLock<HashMap<String, Transaction>>,
+    inner_tx: RwLock<HashMap<String, TxMemPoolEntry>>,
+    spent_outpoints: RwLock<HashMap<OutPoint, String>>,
+    rolling_minimum_fee: RwLock<RollingMinimumFee>,
 }
 
 impl MemoryPool {
     pub fn new() -> MemoryPool {
         MemoryPool {
             inner_tx: RwLock::new(HashMap::new()),
+            spent_outpoints: RwLock::new(HashMap::new()),
+            rolling_minimum_fee: RwLock::new(RollingMinimumFee::default()),
         }
     }
 
@@ -33,16 +152,75 @@ impl MemoryPool {
         self.contains(&txid_hex)
     }
 
-    pub fn add(&self, tx: Transaction) -> Result<()> {
+    /// Add `tx`, paying `fee`, and link it to the pool transactions it spends from and to
+    /// those already spending its outputs.
+    pub fn add(&self, tx: Transaction, fee: Amount) -> Result<()> {
+        self.add_with_time(tx, fee, current_timestamp())
+    }
+
+    /// Add `tx` as `add` does, recording it as entering the pool at `time` milliseconds
+    /// since the Unix epoch.
+    pub fn add_with_time(&self, tx: Transaction, fee: Amount, time: i64) -> Result<()> {
         let txid_hex = tx.get_tx_id_hex();
-        self.inner_tx
+        let size = tx.get_size()?;
+        let mut inner = self
+            .inner_tx
+            .write()
+            .map_err(|e| BtcError::MemoryPoolInnerPoisonedLockError(e.to_string()))?;
+        let mut spent = self
+            .spent_outpoints
             .write()
-            .map_err(|e| BtcError::MemoryPoolInnerPoisonedLockError(e.to_string()))?
-            .insert(txid_hex, tx);
+            .map_err(|e| BtcError::MemoryPoolInnerPoisonedLockError(e.to_string()))?;
+        let parents: BTreeSet<String> = tx
+            .get_vin()
+            .iter()
+            .map(|input| input.get_input_tx_id_hex())
+            .filter(|parent| *parent != txid_hex && inner.contains_key(parent))
+            .collect();
+        let children: BTreeSet<String> = spent
+            .iter()
+            .filter(|((spent_txid, _), child)| spent_txid == tx.get_id() && **child != txid_hex)
+            .map(|(_, child)| child.clone())
+            .collect();
+        for input in tx.get_vin() {
+            spent.insert(
+                (input.get_txid().to_vec(), input.get_vout()),
+                txid_hex.clone(),
+            );
+        }
+        for parent in &parents {
+            if let Some(entry) = inner.get_mut(parent) {
+                entry.children.insert(txid_hex.clone());
+            }
+        }
+        for child in &children {
+            if let Some(entry) = inner.get_mut(child) {
+                entry.parents.insert(txid_hex.clone());
+            }
+        }
+        inner.insert(
+            txid_hex,
+            TxMemPoolEntry {
+                tx,
+                fee,
+                size,
+                time,
+                parents,
+                children,
+            },
+        );
         Ok(())
     }
 
     pub fn get(&self, txid_hex: &str) -> Result<Option<Transaction>> {
+        let inner = self
+            .inner_tx
+            .read()
+            .map_err(|e| BtcError::MemoryPoolInnerPoisonedLockError(e.to_string()))?;
+        Ok(inner.get(txid_hex).map(|entry| entry.tx.clone()))
+    }
+
+    pub fn get_entry(&self, txid_hex: &str) -> Result<Option<TxMemPoolEntry>> {
         let inner = self
             .inner_tx
             .read()
@@ -50,14 +228,333 @@ impl MemoryPool {
         Ok(inner.get(txid_hex).cloned())
     }
 
+    /// Remove `tx` and unlink it from its parents and children; the children stay in the
+    /// pool, spending an output that is now confirmed or gone.
     pub fn remove(&self, tx: Transaction) -> Result<Option<Transaction>> {
         let txid_hex = tx.get_tx_id_hex();
         let mut inner = self
             .inner_tx
             .write()
             .map_err(|e| BtcError::MemoryPoolInnerPoisonedLockError(e.to_string()))?;
-        let rem_trx_op = inner.remove(txid_hex.as_str());
-        Ok(rem_trx_op)
+        let mut spent = self
+            .spent_outpoints
+            .write()
+            .map_err(|e| BtcError::MemoryPoolInnerPoisonedLockError(e.to_string()))?;
+        Ok(Self::unlink(&mut inner, &mut spent, &txid_hex).map(|removed| removed.tx))
+    }
+
+    /// Remove the entry `txid_hex` from `inner`, from the parent and child sets of its
+    /// neighbours, and its inputs from `spent`.
+    fn unlink(
+        inner: &mut HashMap<String, TxMemPoolEntry>,
+        spent: &mut HashMap<OutPoint, String>,
+        txid_hex: &str,
+    ) -> Option<TxMemPoolEntry> {
+        let removed = inner.remove(txid_hex)?;
+        for input in removed.tx.get_vin() {
+            let outpoint = (input.get_txid().to_vec(), input.get_vout());
+            if spent
+                .get(&outpoint)
+                .is_some_and(|spender| spender == txid_hex)
+            {
+                spent.remove(&outpoint);
+            }
+        }
+        for parent in &removed.parents {
+            if let Some(entry) = inner.get_mut(parent) {
+                entry.children.remove(txid_hex);
+            }
+        }
+        for child in &removed.children {
+            if let Some(entry) = inner.get_mut(child) {
+                entry.parents.remove(txid_hex);
+            }
+        }
+        Some(removed)
+    }
+
+    /// Remove the entry `txid_hex` together with every pool transaction spending its
+    /// outputs, directly or not (Bitcoin Core's `CTxMemPool::RemoveStaged`), returning the
+    /// removed transactions.
+    fn unlink_with_descendants(
+        inner: &mut HashMap<String, TxMemPoolEntry>,
+        spent: &mut HashMap<OutPoint, String>,
+        txid_hex: &str,
+    ) -> Vec<Transaction> {
+        let mut stage = vec![txid_hex.to_string()];
+        stage.extend(Self::walk(inner, txid_hex, |entry| &entry.children));
+        stage
+            .iter()
+            .filter_map(|id| Self::unlink(inner, spent, id))
+            .map(|removed| removed.tx)
+            .collect()
+    }
+
+    /// Serialized size of all pool transactions in bytes (what Bitcoin Core bounds with
+    /// `DynamicMemoryUsage`).
+    pub fn get_total_size(&self) -> Result<usize> {
+        let inner = self
+            .inner_tx
+            .read()
+            .map_err(|e| BtcError::MemoryPoolInnerPoisonedLockError(e.to_string()))?;
+        Ok(inner.values().map(|entry| entry.size).sum())
+    }
+
+    /// Evict transactions until the pool holds at most `size_limit` bytes (Bitcoin Core's
+    /// `CTxMemPool::TrimToSize`).
+    ///
+    /// The transaction whose descendant package (itself and every pool transaction spending
+    /// its outputs) pays the lowest fee rate leaves first, together with its descendants.
+    /// Each eviction raises the minimum fee rate to the evicted package's rate plus
+    /// `DEFAULT_INCREMENTAL_RELAY_FEE`, so whatever enters next pays more than what left.
+    ///
+    /// # Returns
+    ///
+    /// The evicted transactions, whose spent outputs are no longer reserved by the pool.
+    pub fn trim_to_size(&self, size_limit: usize) -> Result<Vec<Transaction>> {
+        let mut inner = self
+            .inner_tx
+            .write()
+            .map_err(|e| BtcError::MemoryPoolInnerPoisonedLockError(e.to_string()))?;
+        let mut spent = self
+            .spent_outpoints
+            .write()
+            .map_err(|e| BtcError::MemoryPoolInnerPoisonedLockError(e.to_string()))?;
+        let mut total_size: usize = inner.values().map(|entry| entry.size).sum();
+        let mut evicted = vec![];
+        let mut max_fee_rate = 0;
+        while total_size > size_limit {
+            let mut worst: Option<(String, u128, u128)> = None;
+            for txid_hex in inner.keys() {
+                let (fee, size) = Self::descendant_package(&inner, txid_hex);
+                let is_worse = match &worst {
+                    None => true,
+                    // Compared by cross-multiplying; ties go to the lowest txid
+                    Some((worst_id, worst_fee, worst_size)) => {
+                        let (lhs, rhs) = (fee * worst_size, worst_fee * size);
+                        lhs < rhs || (lhs == rhs && txid_hex < worst_id)
+                    }
+                };
+                if is_worse {
+                    worst = Some((txid_hex.clone(), fee, size));
+                }
+            }
+            let Some((worst_id, fee, size)) = worst else {
+                break;
+            };
+            let fee_rate = u64::try_from(fee * 1_000 / size.max(1)).unwrap_or(u64::MAX);
+            max_fee_rate = max_fee_rate.max(fee_rate.saturating_add(DEFAULT_INCREMENTAL_RELAY_FEE));
+            for tx in Self::unlink_with_descendants(&mut inner, &mut spent, &worst_id) {
+                total_size -= tx.get_size()?;
+                evicted.push(tx);
+            }
+        }
+        drop(spent);
+        drop(inner);
+
+        if max_fee_rate > 0 {
+            let mut rolling = self
+                .rolling_minimum_fee
+                .write()
+                .map_err(|e| BtcError::MemoryPoolInnerPoisonedLockError(e.to_string()))?;
+            if max_fee_rate > rolling.fee_rate {
+                rolling.fee_rate = max_fee_rate;
+                rolling.last_update = current_timestamp();
+            }
+        }
+        Ok(evicted)
+    }
+
+    /// Fee and size of the pool transaction `txid_hex` summed with those of its descendants.
+    fn descendant_package(inner: &HashMap<String, TxMemPoolEntry>, txid_hex: &str) -> (u128, u128) {
+        std::iter::once(txid_hex.to_string())
+            .chain(Self::walk(inner, txid_hex, |entry| &entry.children))
+            .filter_map(|id| inner.get(&id))
+            .fold((0, 0), |(fee, size), entry| {
+                (fee + entry.fee.to_sat() as u128, size + entry.size as u128)
+            })
+    }
+
+    /// Remove every transaction that entered the pool before `cutoff_time`, milliseconds
+    /// since the Unix epoch, together with its descendants (Bitcoin Core's
+    /// `CTxMemPool::Expire`).
+    ///
+    /// # Returns
+    ///
+    /// The removed transactions, whose spent outputs are no longer reserved by the pool.
+    pub fn expire(&self, cutoff_time: i64) -> Result<Vec<Transaction>> {
+        let mut inner = self
+            .inner_tx
+            .write()
+            .map_err(|e| BtcError::MemoryPoolInnerPoisonedLockError(e.to_string()))?;
+        let mut spent = self
+            .spent_outpoints
+            .write()
+            .map_err(|e| BtcError::MemoryPoolInnerPoisonedLockError(e.to_string()))?;
+        let mut stale: Vec<String> = inner
+            .iter()
+            .filter(|(_, entry)| entry.time < cutoff_time)
+            .map(|(id, _)| id.clone())
+            .collect();
+        stale.sort();
+        let mut expired = vec![];
+        for txid_hex in stale {
+            expired.extend(Self::unlink_with_descendants(
+                &mut inner, &mut spent, &txid_hex,
+            ));
+        }
+        Ok(expired)
+    }
+
+    /// The fee rate, in satoshis per 1000 bytes, a transaction must pay to enter a pool
+    /// bounded to `size_limit` bytes (Bitcoin Core's `CTxMemPool::GetMinFee`).
+    pub fn get_min_fee_rate(&self, size_limit: usize) -> Result<u64> {
+        self.get_min_fee_rate_at(size_limit, current_timestamp())
+    }
+
+    /// The minimum fee rate as `get_min_fee_rate` computes it at `now`, milliseconds since
+    /// the Unix epoch.
+    ///
+    /// The rate raised by evictions halves every `ROLLING_FEE_HALFLIFE`, twice as fast while
+    /// the pool is under half full and four times as fast under a quarter, and drops to zero
+    /// once it falls below half of `DEFAULT_INCREMENTAL_RELAY_FEE`; until then it is at least
+    /// `DEFAULT_INCREMENTAL_RELAY_FEE`.
+    pub fn get_min_fee_rate_at(&self, size_limit: usize, now: i64) -> Result<u64> {
+        let total_size = self.get_total_size()?;
+        let mut rolling = self
+            .rolling_minimum_fee
+            .write()
+            .map_err(|e| BtcError::MemoryPoolInnerPoisonedLockError(e.to_string()))?;
+        if rolling.fee_rate == 0 {
+            return Ok(0);
+        }
+        // Decayed at most every ten seconds, as in Bitcoin Core
+        if now > rolling.last_update + 10_000 {
+            let mut halflife = ROLLING_FEE_HALFLIFE as f64;
+            if total_size < size_limit / 4 {
+                halflife /= 4.0;
+            } else if total_size < size_limit / 2 {
+                halflife /= 2.0;
+            }
+            let elapsed = (now - rolling.last_update) as f64;
+            rolling.fee_rate = (rolling.fee_rate as f64 / 2f64.powf(elapsed / halflife)) as u64;
+            rolling.last_update = now;
+            if rolling.fee_rate < DEFAULT_INCREMENTAL_RELAY_FEE / 2 {
+                rolling.fee_rate = 0;
+                return Ok(0);
+            }
+        }
+        Ok(rolling.fee_rate.max(DEFAULT_INCREMENTAL_RELAY_FEE))
+    }
+
+    /// Whether a pool transaction spends output `vout` of the transaction `txid` (Bitcoin
+    /// Core's `CTxMemPool::isSpent`).
+    pub fn is_spent(&self, txid: &[u8], vout: usize) -> Result<bool> {
+        let spent = self
+            .spent_outpoints
+            .read()
+            .map_err(|e| BtcError::MemoryPoolInnerPoisonedLockError(e.to_string()))?;
+        Ok(spent.contains_key(&(txid.to_vec(), vout)))
+    }
+
+    /// Transactions in the pool that spend an output `tx` also spends (Bitcoin Core's
+    /// `CTxMemPool::GetConflictTx` over every input), sorted by txid. `tx` itself is not
+    /// its own conflict.
+    pub fn get_conflicts(&self, tx: &Transaction) -> Result<Vec<Transaction>> {
+        let inner = self
+            .inner_tx
+            .read()
+            .map_err(|e| BtcError::MemoryPoolInnerPoisonedLockError(e.to_string()))?;
+        let spent = self
+            .spent_outpoints
+            .read()
+            .map_err(|e| BtcError::MemoryPoolInnerPoisonedLockError(e.to_string()))?;
+        let txid_hex = tx.get_tx_id_hex();
+        let conflict_ids: BTreeSet<&String> = tx
+            .get_vin()
+            .iter()
+            .filter_map(|input| spent.get(&(input.get_txid().to_vec(), input.get_vout())))
+            .filter(|spender| **spender != txid_hex)
+            .collect();
+        Ok(conflict_ids
+            .into_iter()
+            .filter_map(|id| inner.get(id).map(|entry| entry.tx.clone()))
+            .collect())
+    }
+
+    /// Ids of the pool transactions `txid_hex` spends from, directly or through other pool
+    /// transactions (Bitcoin Core's `CTxMemPool::CalculateMemPoolAncestors`), sorted. The
+    /// transaction itself is not included.
+    pub fn get_ancestors(&self, txid_hex: &str) -> Result<BTreeSet<String>> {
+        let inner = self
+            .inner_tx
+            .read()
+            .map_err(|e| BtcError::MemoryPoolInnerPoisonedLockError(e.to_string()))?;
+        Ok(Self::walk(&inner, txid_hex, |entry| &entry.parents))
+    }
+
+    /// Transactions in the pool that spend an output of the transaction `txid_hex`, directly
+    /// or through other pool transactions (Bitcoin Core's `CTxMemPool::CalculateDescendants`),
+    /// sorted by txid. The transaction itself is not included.
+    pub fn get_descendants(&self, txid_hex: &str) -> Result<Vec<Transaction>> {
+        let inner = self
+            .inner_tx
+            .read()
+            .map_err(|e| BtcError::MemoryPoolInnerPoisonedLockError(e.to_string()))?;
+        Ok(Self::walk(&inner, txid_hex, |entry| &entry.children)
+            .iter()
+            .filter_map(|id| inner.get(id).map(|entry| entry.tx.clone()))
+            .collect())
+    }
+
+    /// The ancestor package of the pool transaction `txid_hex`: its fee and size summed
+    /// with those of all its pool ancestors, or `None` if it is not in the pool.
+    pub fn get_ancestor_package(&self, txid_hex: &str) -> Result<Option<AncestorPackage>> {
+        let inner = self
+            .inner_tx
+            .read()
+            .map_err(|e| BtcError::MemoryPoolInnerPoisonedLockError(e.to_string()))?;
+        let Some(entry) = inner.get(txid_hex) else {
+            return Ok(None);
+        };
+        let mut package = AncestorPackage {
+            count: 1,
+            fee: entry.fee,
+            size: entry.size,
+        };
+        for ancestor in Self::walk(&inner, txid_hex, |entry| &entry.parents) {
+            let Some(entry) = inner.get(&ancestor) else {
+                continue;
+            };
+            package.count += 1;
+            package.fee = package
+                .fee
+                .checked_add(entry.fee)
+                .ok_or(BtcError::AmountOverflow)?;
+            package.size += entry.size;
+        }
+        Ok(Some(package))
+    }
+
+    /// Every pool transaction reachable from `txid_hex` by repeatedly following `edges`.
+    fn walk(
+        inner: &HashMap<String, TxMemPoolEntry>,
+        txid_hex: &str,
+        edges: impl Fn(&TxMemPoolEntry) -> &BTreeSet<String>,
+    ) -> BTreeSet<String> {
+        let mut found = BTreeSet::new();
+        let mut to_visit = vec![txid_hex.to_string()];
+        while let Some(id) = to_visit.pop() {
+            let Some(entry) = inner.get(&id) else {
+                continue;
+            };
+            for next in edges(entry) {
+                if next != txid_hex && found.insert(next.clone()) {
+                    to_visit.push(next.clone());
+                }
+            }
+        }
+        found
     }
 
     pub fn get_all(&self) -> Result<Vec<Transaction>> {
@@ -67,11 +564,22 @@ impl MemoryPool {
             .map_err(|e| BtcError::MemoryPoolInnerPoisonedLockError(e.to_string()))?;
         let mut txs = vec![];
         for (_, v) in inner.iter() {
-            txs.push(v.clone());
+            txs.push(v.tx.clone());
         }
         Ok(txs)
     }
 
+    /// Every entry in the pool, sorted by txid.
+    pub fn get_all_entries(&self) -> Result<Vec<TxMemPoolEntry>> {
+        let inner = self
+            .inner_tx
+            .read()
+            .map_err(|e| BtcError::MemoryPoolInnerPoisonedLockError(e.to_string()))?;
+        let mut entries: Vec<TxMemPoolEntry> = inner.values().cloned().collect();
+        entries.sort_by_key(|entry| entry.tx.get_tx_id_hex());
+        Ok(entries)
+    }
+
     pub fn len(&self) -> Result<usize> {
         let inner = self
             .inner_tx
@@ -194,3 +702,241 @@ impl Default for BlockInTransit {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::transaction::MAX_BIP125_RBF_SEQUENCE;
+
+    /// A transaction with id `[id; 32]` spending the given `(txid byte, vout)` outpoints and
+    /// signaling replaceability (BIP125).
+    fn pool_transaction(id: u8, spends: &[(u8, usize)]) -> Transaction {
+        spends
+            .iter()
+            .fold(
+                Transaction::test_builder().with_id(id),
+                |builder, (txid, vout)| builder.with_input(*txid, *vout, MAX_BIP125_RBF_SEQUENCE),
+            )
+            .build()
+    }
+
+    fn ids(txs: &[Transaction]) -> Vec<String> {
+        txs.iter().map(|tx| tx.get_tx_id_hex()).collect()
+    }
+
+    fn txid_hex(id: u8) -> String {
+        data_encoding::HEXLOWER.encode(&[id; 32])
+    }
+
+    #[test]
+    fn test_get_conflicts_matches_spent_outpoints() {
+        let pool = MemoryPool::new();
+        let original = pool_transaction(1, &[(9, 0), (9, 1)]);
+        pool.add(original.clone(), Amount::ZERO).unwrap();
+        pool.add(pool_transaction(2, &[(9, 2)]), Amount::ZERO)
+            .unwrap();
+
+        let replacement = pool_transaction(3, &[(9, 1)]);
+        assert_eq!(
+            ids(&pool.get_conflicts(&replacement).unwrap()),
+            ids(std::slice::from_ref(&original))
+        );
+        // Another output of the same transaction is not a conflict
+        assert!(
+            pool.get_conflicts(&pool_transaction(4, &[(9, 3)]))
+                .unwrap()
+                .is_empty()
+        );
+        // A pool transaction does not conflict with itself
+        assert!(pool.get_conflicts(&original).unwrap().is_empty());
+        assert!(pool.is_spent(&[9; 32], 1).unwrap());
+        assert!(!pool.is_spent(&[9; 32], 3).unwrap());
+    }
+
+    #[test]
+    fn test_get_descendants_follows_chains_of_pool_transactions() {
+        let pool = MemoryPool::new();
+        let parent = pool_transaction(1, &[(9, 0)]);
+        let child = pool_transaction(2, &[(1, 0)]);
+        let grandchild = pool_transaction(3, &[(2, 0), (1, 1)]);
+        for tx in [&parent, &child, &grandchild] {
+            pool.add(tx.clone(), Amount::ZERO).unwrap();
+        }
+        pool.add(pool_transaction(4, &[(9, 1)]), Amount::ZERO)
+            .unwrap();
+
+        assert_eq!(
+            ids(&pool.get_descendants(&parent.get_tx_id_hex()).unwrap()),
+            ids(&[child.clone(), grandchild.clone()])
+        );
+        assert_eq!(
+            ids(&pool.get_descendants(&child.get_tx_id_hex()).unwrap()),
+            ids(std::slice::from_ref(&grandchild))
+        );
+        assert!(
+            pool.get_descendants(&grandchild.get_tx_id_hex())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_spent_outpoints_are_released_with_their_spender() {
+        let pool = MemoryPool::new();
+        pool.add(pool_transaction(1, &[(9, 0)]), Amount::ZERO)
+            .unwrap();
+        pool.add_with_time(pool_transaction(2, &[(9, 1)]), Amount::ZERO, 100)
+            .unwrap();
+        pool.add(pool_transaction(3, &[(9, 2)]), Amount::from_sat(1_000))
+            .unwrap();
+        assert!((0..3).all(|vout| pool.is_spent(&[9; 32], vout).unwrap()));
+
+        // Removed, expired or evicted, a transaction no longer spends its outputs
+        pool.remove(pool_transaction(1, &[(9, 0)])).unwrap();
+        assert!(!pool.is_spent(&[9; 32], 0).unwrap());
+        pool.expire(200).unwrap();
+        assert!(!pool.is_spent(&[9; 32], 1).unwrap());
+        pool.trim_to_size(0).unwrap();
+        assert!(!pool.is_spent(&[9; 32], 2).unwrap());
+        assert!(pool.is_empty().unwrap());
+
+        // A released outpoint can be spent again, and conflicts with any later spender
+        pool.add(pool_transaction(4, &[(9, 0)]), Amount::ZERO)
+            .unwrap();
+        assert_eq!(
+            ids(&pool.get_conflicts(&pool_transaction(5, &[(9, 0)])).unwrap()),
+            vec![txid_hex(4)]
+        );
+    }
+
+    #[test]
+    fn test_graph_links_children_added_before_their_parent() {
+        let pool = MemoryPool::new();
+        let child = pool_transaction(2, &[(1, 0)]);
+        pool.add(child.clone(), Amount::ZERO).unwrap();
+        pool.add(pool_transaction(1, &[(9, 0)]), Amount::ZERO)
+            .unwrap();
+
+        let parent_entry = pool.get_entry(&txid_hex(1)).unwrap().unwrap();
+        assert!(parent_entry.get_children().contains(&txid_hex(2)));
+        assert_eq!(
+            pool.get_ancestors(&txid_hex(2)).unwrap(),
+            BTreeSet::from([txid_hex(1)])
+        );
+
+        // Removing the parent (say, it was mined) leaves the child without pool parents
+        pool.remove(pool_transaction(1, &[(9, 0)])).unwrap();
+        let child_entry = pool.get_entry(&txid_hex(2)).unwrap().unwrap();
+        assert!(child_entry.get_parents().is_empty());
+        assert!(pool.get_ancestors(&txid_hex(2)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_ancestor_package_sums_fees_and_sizes() {
+        let pool = MemoryPool::new();
+        let parent = pool_transaction(1, &[(9, 0)]);
+        let child = pool_transaction(2, &[(1, 0)]);
+        pool.add(parent.clone(), Amount::from_sat(1)).unwrap();
+        pool.add(child.clone(), Amount::from_sat(50)).unwrap();
+
+        let parent_package = pool.get_ancestor_package(&txid_hex(1)).unwrap().unwrap();
+        assert_eq!(parent_package.get_count(), 1);
+        assert_eq!(parent_package.get_fee(), Amount::from_sat(1));
+
+        let child_package = pool.get_ancestor_package(&txid_hex(2)).unwrap().unwrap();
+        assert_eq!(child_package.get_count(), 2);
+        assert_eq!(child_package.get_fee(), Amount::from_sat(51));
+        assert_eq!(
+            child_package.get_size(),
+            parent.get_size().unwrap() + child.get_size().unwrap()
+        );
+        // The high fee child makes its parent worth mining
+        assert!(child_package.has_higher_fee_rate_than(&parent_package));
+        assert!(pool.get_ancestor_package(&txid_hex(3)).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_trim_to_size_evicts_lowest_fee_rate_package_first() {
+        let pool = MemoryPool::new();
+        let parent = pool_transaction(1, &[(9, 0)]);
+        let child = pool_transaction(2, &[(1, 0)]);
+        let cheap = pool_transaction(3, &[(9, 1)]);
+        pool.add(parent.clone(), Amount::ZERO).unwrap();
+        pool.add(child.clone(), Amount::from_sat(1_000)).unwrap();
+        pool.add(cheap.clone(), Amount::from_sat(10)).unwrap();
+        let total = pool.get_total_size().unwrap();
+        assert!(pool.trim_to_size(total).unwrap().is_empty());
+
+        // The zero fee parent is carried by its child, so the cheap transaction goes
+        let evicted = pool.trim_to_size(total - 1).unwrap();
+        assert_eq!(ids(&evicted), ids(std::slice::from_ref(&cheap)));
+        let cheap_rate = 10 * 1_000 / cheap.get_size().unwrap() as u64;
+        assert_eq!(
+            pool.rolling_minimum_fee.read().unwrap().fee_rate,
+            cheap_rate + DEFAULT_INCREMENTAL_RELAY_FEE
+        );
+
+        // A package leaves whole, and the minimum only rises
+        let evicted = pool.trim_to_size(0).unwrap();
+        assert_eq!(ids(&evicted), ids(&[parent, child]));
+        assert!(pool.is_empty().unwrap());
+        assert!(
+            pool.rolling_minimum_fee.read().unwrap().fee_rate
+                > cheap_rate + DEFAULT_INCREMENTAL_RELAY_FEE
+        );
+    }
+
+    #[test]
+    fn test_min_fee_rate_decays_to_zero() {
+        let pool = MemoryPool::new();
+        assert_eq!(pool.get_min_fee_rate_at(0, 0).unwrap(), 0);
+        *pool.rolling_minimum_fee.write().unwrap() = RollingMinimumFee {
+            fee_rate: 4 * DEFAULT_INCREMENTAL_RELAY_FEE,
+            last_update: 0,
+        };
+
+        let halflife = ROLLING_FEE_HALFLIFE;
+        assert_eq!(
+            pool.get_min_fee_rate_at(0, halflife).unwrap(),
+            2 * DEFAULT_INCREMENTAL_RELAY_FEE
+        );
+        // Decayed below the incremental relay fee, the minimum still asks for it
+        assert_eq!(
+            pool.get_min_fee_rate_at(0, 3 * halflife).unwrap(),
+            DEFAULT_INCREMENTAL_RELAY_FEE
+        );
+        assert_eq!(pool.get_min_fee_rate_at(0, 4 * halflife).unwrap(), 0);
+        // A pool with plenty of room decays four times as fast
+        *pool.rolling_minimum_fee.write().unwrap() = RollingMinimumFee {
+            fee_rate: 4 * DEFAULT_INCREMENTAL_RELAY_FEE,
+            last_update: 0,
+        };
+        assert_eq!(
+            pool.get_min_fee_rate_at(1_000, halflife / 4).unwrap(),
+            2 * DEFAULT_INCREMENTAL_RELAY_FEE
+        );
+    }
+
+    #[test]
+    fn test_expire_removes_stale_transactions_with_descendants() {
+        let pool = MemoryPool::new();
+        let parent = pool_transaction(1, &[(9, 0)]);
+        let child = pool_transaction(2, &[(1, 0)]);
+        let recent = pool_transaction(3, &[(9, 1)]);
+        pool.add_with_time(parent.clone(), Amount::ZERO, 100)
+            .unwrap();
+        pool.add_with_time(child.clone(), Amount::ZERO, 500)
+            .unwrap();
+        pool.add_with_time(recent, Amount::ZERO, 300).unwrap();
+
+        assert!(pool.expire(100).unwrap().is_empty());
+        let expired = pool.expire(200).unwrap();
+        assert_eq!(ids(&expired), ids(&[parent, child]));
+        assert_eq!(pool.len().unwrap(), 1);
+        assert!(pool.contains(&txid_hex(3)).unwrap());
+        assert_eq!(
+            pool.get_entry(&txid_hex(3)).unwrap().unwrap().get_time(),
+            300
+        );
+    }
+}
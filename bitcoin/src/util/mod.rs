@@ -3,6 +3,6 @@ pub mod functional_operations;
 pub mod utils;
 
 // Re-export the utils module
-pub use utils::current_timestamp;
+pub use utils::{current_timestamp, get_mock_time, set_mock_time};
 // Re-export functional utilities
 pub use functional_operations::transaction as functional_transaction;
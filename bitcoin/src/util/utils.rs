@@ -1,5 +1,27 @@
+use std::sync::atomic::{AtomicI64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Mock time in milliseconds, zero while the system clock is used (Bitcoin Core's
+/// `nMockTime`).
+static MOCK_TIME: AtomicI64 = AtomicI64::new(0);
+
+///
+/// The `set_mock_time` function pins the node clock read by `current_timestamp` to
+/// `timestamp` (Bitcoin Core's `SetMockTime`), so regtest scenarios produce the same block
+/// timestamps on every run. Zero goes back to the system clock.
+///
+/// # Arguments
+///
+/// * `timestamp` - Unix timestamp in milliseconds, or zero to stop mocking.
+pub fn set_mock_time(timestamp: i64) {
+    MOCK_TIME.store(timestamp, Ordering::SeqCst);
+}
+
+/// Mock time in milliseconds, zero when the system clock is used.
+pub fn get_mock_time() -> i64 {
+    MOCK_TIME.load(Ordering::SeqCst)
+}
+
 ///
 /// The `current_timestamp` function returns the current Unix timestamp in milliseconds.
 /// It uses the system time to generate a timestamp that represents the current moment
@@ -19,13 +41,18 @@ use std::time::{SystemTime, UNIX_EPOCH};
 ///
 /// # Returns
 ///
-/// A 64-bit integer representing the current Unix timestamp in milliseconds.
+/// A 64-bit integer representing the current Unix timestamp in milliseconds, or the mock
+/// time while one is set (see `set_mock_time`).
 ///
 /// # Error Handling
 ///
 /// This function will panic if the system time is set to a date before the Unix epoch,
 /// which should never happen in normal operation.
 pub fn current_timestamp() -> i64 {
+    let mock_time = get_mock_time();
+    if mock_time > 0 {
+        return mock_time;
+    }
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .expect("Time went backwards")
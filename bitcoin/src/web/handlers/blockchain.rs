@@ -3,13 +3,16 @@ use axum::{
     http::StatusCode,
     response::Json,
 };
+use data_encoding::HEXLOWER;
 use std::sync::Arc;
 use tracing::error;
 
+use crate::GLOBAL_CONFIG;
 use crate::node::NodeContext;
 use crate::primitives::Block;
 use crate::web::models::{
     ApiResponse, BlockQuery, BlockResponse, BlockchainInfoResponse, PaginatedResponse,
+    TxOutSetInfoResponse,
 };
 
 /// Get blockchain information
@@ -40,6 +43,11 @@ pub async fn get_blockchain_info(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    let difficulty = last_block
+        .as_ref()
+        .map(|block| block.get_difficulty())
+        .unwrap_or(1);
+
     let last_block_hash = last_block
         .map(|block| block.get_hash().to_string())
         .unwrap_or_else(|| "genesis".to_string());
@@ -59,8 +67,12 @@ pub async fn get_blockchain_info(
     };
 
     let info = BlockchainInfoResponse {
+        chain: GLOBAL_CONFIG
+            .get_chain_params()
+            .get_chain_name()
+            .to_string(),
         height,
-        difficulty: 1, // TODO: Get actual difficulty
+        difficulty,
         // Since genesis block is at height 1 (1-indexed), height directly equals total blocks
         total_blocks: height,
         total_transactions,
@@ -72,6 +84,43 @@ pub async fn get_blockchain_info(
     Ok(Json(ApiResponse::success(info)))
 }
 
+/// Get UTXO set information
+///
+/// Returns statistics about the unspent transaction output set together with the
+/// supply issued by the subsidy schedule, so emission can be audited.
+#[utoipa::path(
+    get,
+    path = "/api/v1/blockchain/txoutsetinfo",
+    tag = "Blockchain",
+    responses(
+        (status = 200, description = "UTXO set information retrieved successfully", body = ApiResponse<TxOutSetInfoResponse>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_tx_out_set_info(
+    State(node): State<Arc<NodeContext>>,
+) -> Result<Json<ApiResponse<TxOutSetInfoResponse>>, StatusCode> {
+    let info = node.get_tx_out_set_info().await.map_err(|e| {
+        error!("Failed to get UTXO set info: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let network = GLOBAL_CONFIG.get_network();
+
+    let response = TxOutSetInfoResponse {
+        network,
+        height: info.get_height(),
+        best_block_hash: info.get_best_block_hash().to_string(),
+        transactions: info.get_transactions(),
+        txouts: info.get_txouts(),
+        total_amount: info.get_total_amount(),
+        total_subsidy: info.get_total_subsidy(),
+        block_subsidy: network.get_block_subsidy(info.get_height() + 1),
+        halving_interval: network.get_subsidy_halving_interval(),
+    };
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
 /// Get block by hash
 ///
 /// Retrieves a specific block from the blockchain using its hash.
@@ -214,10 +263,10 @@ async fn block_to_response(block: Block, height: usize) -> BlockResponse {
         timestamp: chrono::DateTime::from_timestamp(timestamp_sec, timestamp_nanos)
             .unwrap_or_else(chrono::Utc::now),
         height,
-        nonce: 0,      // TODO: Get actual nonce
-        difficulty: 1, // TODO: Get actual difficulty
+        nonce: 0, // TODO: Get actual nonce
+        difficulty: block.get_difficulty(),
         transaction_count: block.get_transactions().await.unwrap_or(&[]).len(),
-        merkle_root: "".to_string(), // TODO: Calculate merkle root
-        size_bytes: 0,               // TODO: Calculate block size
+        merkle_root: HEXLOWER.encode(block.get_merkle_root()),
+        size_bytes: 0, // TODO: Calculate block size
     }
 }
@@ -11,7 +11,9 @@
 //! | Bitcoin Core RPC | REST Endpoint | Purpose |
 //! |------------------|---------------|---------|
 //! | `getmininginfo` | `GET /api/v1/mining/info` | Get mining statistics |
+//! | `getblocktemplate` | `GET /api/v1/mining/blocktemplate` | Preview the next block |
 //! | `generatetoaddress` | `POST /api/v1/mining/generatetoaddress` | Generate blocks |
+//! | `setmocktime` | `POST /api/admin/mining/setmocktime` | Pin the node clock (regtest) |
 //!
 //! # Architecture
 //!
@@ -42,19 +44,35 @@
 //! # Get mining information
 //! curl -X GET http://localhost:8080/api/v1/mining/info
 //!
+//! # Preview the transactions of the next block
+//! curl -X GET http://localhost:8080/api/v1/mining/blocktemplate
+//!
 //! # Generate 1 block to address
 //! curl -X POST http://localhost:8080/api/v1/mining/generatetoaddress \
 //!   -H "Content-Type: application/json" \
 //!   -d '{"nblocks": 1, "address": "bc1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh"}'
+//!
+//! # Pin the clock of a regtest node (admin only)
+//! curl -X POST http://localhost:28080/api/admin/mining/setmocktime \
+//!   -H "Content-Type: application/json" \
+//!   -d '{"timestamp": 1700000000000}'
 //! ```
 
 use axum::{extract::State, http::StatusCode, response::Json};
 use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 
+use data_encoding::HEXLOWER;
+use tracing::error;
+
+use crate::consensus::Network;
 use crate::node::{NodeContext, miner::broadcast_new_block};
+use crate::pow::{MiningOptions, compact_to_target, get_hash_rate};
 use crate::web::models::{
-    ApiResponse, GenerateToAddressRequest, GenerateToAddressResponse, MiningInfoResponse,
+    ApiResponse, BlockTemplateResponse, BlockTemplateTransaction, GenerateToAddressRequest,
+    GenerateToAddressResponse, MiningInfoResponse, SetMockTimeRequest, SetMockTimeResponse,
 };
+use crate::{BLOCK_VERSION, GLOBAL_CONFIG, current_timestamp, util};
 
 /// Get mining information
 ///
@@ -76,7 +94,7 @@ use crate::web::models::{
 /// - `currentblocksize` (u64): Size of the current block being mined in bytes
 /// - `currentblocktx` (u32): Number of transactions in the current block
 /// - `difficulty` (f64): Current mining difficulty
-/// - `networkhashps` (f64): Network hash rate per second
+/// - `networkhashps` (f64): Hashes per second measured while this node last mined
 /// - `pooledtx` (u32): Number of transactions in the mempool
 /// - `chain` (String): Chain name (main, test, regtest)
 /// - `warnings` (String): Any active warnings
@@ -93,8 +111,6 @@ use crate::web::models::{
 ///
 /// This is a placeholder implementation. In production, this would:
 /// - Gather real-time statistics from the blockchain
-/// - Calculate current difficulty based on recent blocks
-/// - Estimate network hash rate from recent block times
 /// - Query mempool for transaction count
 /// - Determine chain type from configuration
 /// - Collect any active warnings from the system
@@ -130,6 +146,12 @@ pub async fn get_mining_info(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    // Difficulty of the tip block, driven by its compact `bits` target
+    let difficulty = last_block
+        .as_ref()
+        .map(|block| block.get_difficulty() as f64)
+        .unwrap_or(1.0);
+
     // Extract current block information
     let (currentblocksize, currentblocktx) = if let Some(block) = &last_block {
         // Calculate block size by serializing it
@@ -150,17 +172,19 @@ pub async fn get_mining_info(
         .get_mempool_size()
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)? as u32;
 
-    // Determine chain type (default to "main" for now)
-    // TODO: Add chain type to Config or read from environment variable
-    let chain = "main".to_string();
+    // Chain name of the configured network (main, test, regtest)
+    let chain = GLOBAL_CONFIG
+        .get_chain_params()
+        .get_chain_name()
+        .to_string();
 
     // Construct response
     let response = MiningInfoResponse {
         blocks,
         currentblocksize,
         currentblocktx,
-        difficulty: 1.0,    // TODO: Calculate actual difficulty from recent blocks
-        networkhashps: 0.0, // TODO: Calculate network hash rate from recent block times
+        difficulty,
+        networkhashps: get_hash_rate(),
         pooledtx,
         chain,
         warnings: String::new(), // TODO: Gather active warnings
@@ -169,6 +193,88 @@ pub async fn get_mining_info(
     Ok(Json(ApiResponse::success(response)))
 }
 
+/// Get a block template
+///
+/// Equivalent to Bitcoin Core's `getblocktemplate` RPC command.
+/// Returns the transactions the next block would include and the header fields a miner
+/// needs, without mining or changing the mempool.
+///
+/// # Bitcoin Core Equivalent
+///
+/// ```bash
+/// bitcoin-cli getblocktemplate '{"rules": ["segwit"]}'
+/// ```
+///
+/// # Transaction Selection
+///
+/// Transactions are chosen by `BlockAssembler`: best ancestor package fee rate first,
+/// within the default block size limit, parents before children, and at most one
+/// transaction spending any output. Transactions that are not final yet are left out.
+///
+/// # Response Fields
+///
+/// - `previousblockhash`, `height`, `bits`, `target`: Header fields of the next block
+/// - `transactions`: Encoded transactions with their fee, size and in-template parents
+/// - `coinbasevalue`: Subsidy plus fees the coinbase may claim
+/// - `mintime`: Earliest valid timestamp (one past the median time past)
+/// - `sizelimit`: Block size limit the template respects
+#[utoipa::path(
+    get,
+    path = "/api/v1/mining/blocktemplate",
+    tag = "Mining",
+    responses(
+        (status = 200, description = "Block template built successfully", body = ApiResponse<BlockTemplateResponse>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_block_template(
+    State(node): State<Arc<NodeContext>>,
+) -> Result<Json<ApiResponse<BlockTemplateResponse>>, StatusCode> {
+    let template = node.get_block_template().await.map_err(|e| {
+        error!("Failed to build block template: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let total_fees = template
+        .get_total_fees()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let coinbasevalue = GLOBAL_CONFIG
+        .get_network()
+        .get_block_subsidy(template.get_height())
+        .checked_add(total_fees)
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut transactions = Vec::with_capacity(template.get_entries().len());
+    for entry in template.get_entries() {
+        let tx = entry.get_transaction();
+        let data = tx
+            .serialize()
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        transactions.push(BlockTemplateTransaction {
+            data: HEXLOWER.encode(&data),
+            txid: tx.get_tx_id_hex(),
+            depends: entry.get_depends().to_vec(),
+            fee: entry.get_fee(),
+            size: entry.get_size(),
+        });
+    }
+
+    let (_, target) = compact_to_target(template.get_bits()).to_bytes_be();
+    let response = BlockTemplateResponse {
+        version: BLOCK_VERSION,
+        previousblockhash: template.get_pre_block_hash().to_string(),
+        transactions,
+        coinbasevalue,
+        target: format!("{:0>64}", HEXLOWER.encode(&target)),
+        mintime: template.get_min_time(),
+        curtime: current_timestamp(),
+        bits: format!("{:08x}", template.get_bits()),
+        height: template.get_height(),
+        sizelimit: template.get_max_block_size(),
+    };
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
 /// Generate blocks to address
 ///
 /// Equivalent to Bitcoin Core's `generatetoaddress` RPC command.
@@ -187,13 +293,15 @@ pub async fn get_mining_info(
 /// - `nblocks` (u32): Number of blocks to generate (1-1000)
 /// - `address` (String): Address to receive block rewards (26-35 characters)
 /// - `maxtries` (Option<u32>): Maximum iterations to try (1-1000000)
+/// - `thread_count` (Option<u8>): Threads searching for nonces (1-10), the node's
+///   configured mining threads when omitted
 ///
 /// # Behavior
 ///
 /// This function performs the following steps:
 /// 1. Validates the mining address format
-/// 2. Creates a new block template
-/// 3. Adds transactions from the mempool (if available)
+/// 2. Creates a new block template (see `get_block_template`)
+/// 3. Adds the template's mempool transactions (if available)
 /// 4. Mines the block by finding a valid nonce
 /// 5. Adds the block to the blockchain
 /// 6. Clears mined transactions from the mempool
@@ -213,6 +321,7 @@ pub async fn get_mining_info(
 /// - `nblocks` must be between 1 and 1000
 /// - Address must be valid format (26-35 characters)
 /// - `maxtries` must be between 1 and 1000000 (if provided)
+/// - `thread_count` must be between 1 and 10 (if provided)
 /// - Invalid parameters return HTTP 400 Bad Request
 ///
 /// # Implementation Notes
@@ -260,12 +369,22 @@ pub async fn generate_to_address(
     Json(request): Json<GenerateToAddressRequest>,
 ) -> Result<Json<ApiResponse<GenerateToAddressResponse>>, StatusCode> {
     use crate::{Transaction, WalletAddress};
-    use tracing::error;
 
     // Validate parameters
     if request.nblocks == 0 || request.nblocks > 1000 {
         return Err(StatusCode::BAD_REQUEST);
     }
+    if request
+        .thread_count
+        .is_some_and(|threads| !(1..=10).contains(&threads))
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let never_cancelled = AtomicBool::new(false);
+    let threads = request
+        .thread_count
+        .map_or_else(|| GLOBAL_CONFIG.get_mining_threads(), usize::from);
+    let options = MiningOptions::new(threads, &never_cancelled);
 
     // Validate address format
     let reward_address =
@@ -276,31 +395,43 @@ pub async fn generate_to_address(
 
     // Generate nblocks blocks
     for block_num in 0..request.nblocks {
-        // Create coinbase transaction with the specified reward address
-        let coinbase_tx = Transaction::new_coinbase_tx(&reward_address).map_err(|e| {
-            error!("Failed to create coinbase transaction: {}", e);
+        // Choose mempool transactions (if any) by fee rate within the block size limit
+        let template = node.get_block_template().await.map_err(|e| {
+            error!("Failed to build block template: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        let total_fees = template.get_total_fees().map_err(|e| {
+            error!("Mempool fees overflow: {}", e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
-        // Get transactions from mempool (if any)
-        let mempool_txs = node.get_mempool_transactions().map_err(|e| {
-            error!("Failed to get mempool transactions: {}", e);
+        // Create coinbase transaction paying the subsidy of the new block's height plus fees
+        // to the specified reward address
+        let coinbase_tx = Transaction::new_coinbase_tx_for_height(
+            &reward_address,
+            template.get_height(),
+            total_fees,
+        )
+        .map_err(|e| {
+            error!("Failed to create coinbase transaction: {}", e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
-        // Combine mempool transactions with coinbase (coinbase should be first)
-        let mut transactions = vec![coinbase_tx];
-        transactions.extend(mempool_txs);
+        // The coinbase comes first, followed by the template's transactions
+        let transactions = template.get_block_transactions(coinbase_tx);
 
         // Mine the block (already adds to blockchain and updates UTXO)
-        let mined_block = node.mine_block(&transactions).await.map_err(|e| {
-            error!("Failed to mine block {}: {}", block_num + 1, e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+        let mined_block = node
+            .mine_block_with_options(&transactions, &options)
+            .await
+            .map_err(|e| {
+                error!("Failed to mine block {}: {}", block_num + 1, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
 
         // Remove mined transactions from mempool (excluding coinbase)
         for tx in transactions.into_iter().skip(1) {
-            node.remove_from_memory_pool(tx).await;
+            node.remove_from_memory_pool(tx);
         }
         broadcast_new_block(&mined_block).await.map_err(|e| {
             error!("Failed to broadcast new block: {}", e);
@@ -322,3 +453,59 @@ pub async fn generate_to_address(
 
     Ok(Json(ApiResponse::success(response)))
 }
+
+/// Set the node clock
+///
+/// Equivalent to Bitcoin Core's `setmocktime` RPC command. Pins the time the node reads
+/// (block timestamps, the future drift check, lock times) to `timestamp`, so regtest
+/// scenarios with several nodes produce the same chain on every run. A timestamp of 0
+/// returns to the system clock.
+///
+/// # Bitcoin Core Equivalent
+///
+/// ```bash
+/// bitcoin-cli -regtest setmocktime 1700000000
+/// ```
+///
+/// Bitcoin Core takes seconds; block timestamps here are in milliseconds.
+///
+/// # Restrictions
+///
+/// - Admin only
+/// - Regtest only: other networks answer HTTP 403 Forbidden
+/// - Negative timestamps return HTTP 400 Bad Request
+///
+/// Blocks mined while the clock stands still still get timestamps past the median time
+/// past of their parent, so `generatetoaddress` keeps producing valid blocks.
+#[utoipa::path(
+    post,
+    path = "/api/admin/mining/setmocktime",
+    tag = "Mining",
+    request_body = SetMockTimeRequest,
+    responses(
+        (status = 200, description = "Mock time set successfully", body = ApiResponse<SetMockTimeResponse>),
+        (status = 400, description = "Negative timestamp"),
+        (status = 403, description = "The node does not run on regtest")
+    )
+)]
+pub async fn set_mock_time(
+    Json(request): Json<SetMockTimeRequest>,
+) -> Result<Json<ApiResponse<SetMockTimeResponse>>, StatusCode> {
+    if GLOBAL_CONFIG.get_network() != Network::Regtest {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if request.timestamp < 0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    util::set_mock_time(request.timestamp);
+    let message = if request.timestamp == 0 {
+        "Node clock follows the system clock".to_string()
+    } else {
+        format!("Node clock set to {}", request.timestamp)
+    };
+    Ok(Json(ApiResponse::success(SetMockTimeResponse {
+        timestamp: request.timestamp,
+        message,
+    })))
+}
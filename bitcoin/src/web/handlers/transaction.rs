@@ -6,13 +6,19 @@ use axum::{
 use std::sync::Arc;
 use tracing::{error, info};
 
-use crate::WalletAddress;
+use crate::consensus::MerkleProof;
+use crate::error::BtcError;
 use crate::node::NodeContext;
+use crate::transaction::TxLockTime;
+use crate::web::handlers::validation::ValidationService;
 use crate::web::models::{
-    ApiResponse, PaginatedResponse, SendBitCoinResponse, SendTransactionRequest, TransactionQuery,
-    TransactionResponse, TxInputSummaryResponse, TxOutputSummaryResponse, TxSummaryResponse,
-    WalletTransactionRespose,
+    ApiResponse, PaginatedResponse, SaveMempoolResponse, SendBitCoinResponse,
+    SendDataTransactionRequest, SendTransactionRequest, TransactionQuery, TransactionResponse,
+    TxInputSummaryResponse, TxOutProofQuery, TxOutProofResponse, TxOutputSummaryResponse,
+    TxSummaryResponse, VerifyTxOutProofRequest, VerifyTxOutProofResponse, WalletTransactionRespose,
 };
+use crate::{Transaction, UTXOSet, WalletAddress};
+use data_encoding::HEXLOWER;
 
 /// Send a transaction
 ///
@@ -32,25 +38,126 @@ pub async fn send_transaction(
     State(node): State<Arc<NodeContext>>,
     Json(request): Json<SendTransactionRequest>,
 ) -> Result<Json<ApiResponse<SendBitCoinResponse>>, StatusCode> {
-    let txid = node
-        .btc_transaction(&request.from_address, &request.to_address, request.amount)
+    ValidationService::validate_send_transaction_request(&request).map_err(|e| {
+        error!("Invalid send transaction request: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+    let tx_fee = request.get_tx_fee().map_err(|e| {
+        error!("Invalid send transaction request: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+
+    let lock = if request.replaceable {
+        TxLockTime::replaceable()
+    } else {
+        TxLockTime::none()
+    };
+
+    let tx = node
+        .create_transaction_with_lock_time(
+            &request.from_address,
+            &request.to_address,
+            request.amount,
+            tx_fee,
+            lock,
+        )
         .await
         .map_err(|e| {
             error!("Failed to create transaction: {}", e);
             StatusCode::BAD_REQUEST
         })?;
+    let fee = node.get_transaction_fee(&tx).await.map_err(|e| {
+        error!("Failed to compute transaction fee: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+    let txid = node
+        .submit_transaction(&crate::GLOBAL_CONFIG.get_node_addr(), tx)
+        .await
+        .map_err(|e| {
+            error!("Failed to submit transaction: {}", e);
+            StatusCode::BAD_REQUEST
+        })?;
 
-    info!("Transaction {} submitted successfully", txid);
+    info!(
+        "Transaction {} submitted successfully with fee {}",
+        txid, fee
+    );
 
     // Create response using the actual TransactionResponse structure
     let response = SendBitCoinResponse {
         txid,
+        fee,
         timestamp: chrono::Utc::now(),
     };
 
     Ok(Json(ApiResponse::success(response)))
 }
 
+/// Anchor data on chain
+///
+/// Creates and broadcasts a transaction with a data carrier (`OP_RETURN`) output holding
+/// the given payload, such as a document hash. The sender pays the fee and receives the
+/// change; the data output itself pays nothing and can never be spent.
+#[utoipa::path(
+    post,
+    path = "/api/v1/transactions/data",
+    tag = "Transaction",
+    request_body = SendDataTransactionRequest,
+    responses(
+        (status = 202, description = "Transaction has been accepted and is being processed", body = ApiResponse<SendBitCoinResponse>),
+        (status = 400, description = "Bad request - invalid address, data or fee"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn send_data_transaction(
+    State(node): State<Arc<NodeContext>>,
+    Json(request): Json<SendDataTransactionRequest>,
+) -> Result<Json<ApiResponse<SendBitCoinResponse>>, StatusCode> {
+    ValidationService::validate_send_data_transaction_request(&request).map_err(|e| {
+        error!("Invalid send data transaction request: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+    let (data, tx_fee) = request
+        .get_data()
+        .and_then(|data| Ok((data, request.get_tx_fee()?)))
+        .map_err(|e| {
+            error!("Invalid send data transaction request: {}", e);
+            StatusCode::BAD_REQUEST
+        })?;
+
+    let tx = node
+        .create_data_transaction(&request.from_address, &data, tx_fee)
+        .await
+        .map_err(|e| {
+            error!("Failed to create data transaction: {}", e);
+            StatusCode::BAD_REQUEST
+        })?;
+    let fee = node.get_transaction_fee(&tx).await.map_err(|e| {
+        error!("Failed to compute transaction fee: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+    let txid = node
+        .submit_transaction(&crate::GLOBAL_CONFIG.get_node_addr(), tx)
+        .await
+        .map_err(|e| {
+            error!("Failed to submit data transaction: {}", e);
+            StatusCode::BAD_REQUEST
+        })?;
+
+    info!(
+        "Data transaction {} anchoring {} bytes submitted with fee {}",
+        txid,
+        data.len(),
+        fee
+    );
+
+    Ok(Json(ApiResponse::success(SendBitCoinResponse {
+        txid,
+        fee,
+        timestamp: chrono::Utc::now(),
+    })))
+}
+
 /// Get transaction by ID
 ///
 /// Retrieves a specific transaction by its transaction ID.
@@ -81,17 +188,7 @@ pub async fn get_mempool_transaction(
         .ok_or(StatusCode::NOT_FOUND)?;
 
     // Convert to response
-    let response = TransactionResponse {
-        txid: tx.get_tx_id_hex(),
-        is_coinbase: tx.is_coinbase(),
-        input_count: tx.get_vin().len(),
-        output_count: tx.get_vout().len(),
-        total_input_value: 0, // TODO: Calculate from inputs
-        total_output_value: tx.get_vout().iter().map(|o| o.get_value()).sum(),
-        fee: 0, // TODO: Calculate fee
-        timestamp: chrono::Utc::now(),
-        size_bytes: tx.serialize().unwrap_or_default().len(),
-    };
+    let response = mempool_transaction_response(&node, &tx).await;
 
     Ok(Json(ApiResponse::success(response)))
 }
@@ -118,24 +215,72 @@ pub async fn get_mempool(
     })?;
 
     // Convert to response format
-    let responses: Vec<TransactionResponse> = transactions
-        .iter()
-        .map(|tx| TransactionResponse {
-            txid: tx.get_tx_id_hex(),
-            is_coinbase: tx.is_coinbase(),
-            input_count: tx.get_vin().len(),
-            output_count: tx.get_vout().len(),
-            total_input_value: 0, // TODO: Calculate from inputs
-            total_output_value: tx.get_vout().iter().map(|o| o.get_value()).sum(),
-            fee: 0, // TODO: Calculate fee
-            timestamp: chrono::Utc::now(),
-            size_bytes: tx.serialize().unwrap_or_default().len(),
-        })
-        .collect();
+    let mut responses: Vec<TransactionResponse> = Vec::with_capacity(transactions.len());
+    for tx in &transactions {
+        responses.push(mempool_transaction_response(&node, tx).await);
+    }
 
     Ok(Json(ApiResponse::success(responses)))
 }
 
+/// Save the mempool
+///
+/// Equivalent to Bitcoin Core's `savemempool` RPC command. Writes every mempool transaction
+/// to the node database, where the node reloads them from on its next start. The node
+/// also saves its mempool on shutdown and every 15 minutes.
+///
+/// # Bitcoin Core Equivalent
+///
+/// ```bash
+/// bitcoin-cli savemempool
+/// ```
+#[utoipa::path(
+    post,
+    path = "/api/admin/mempool/savemempool",
+    tag = "Transaction",
+    responses(
+        (status = 200, description = "Mempool saved successfully", body = ApiResponse<SaveMempoolResponse>),
+        (status = 500, description = "The mempool could not be written to disk")
+    )
+)]
+pub async fn save_mempool(
+    State(node): State<Arc<NodeContext>>,
+) -> Result<Json<ApiResponse<SaveMempoolResponse>>, StatusCode> {
+    let transactions = node.save_mempool().await.map_err(|e| {
+        error!("Failed to save mempool: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(ApiResponse::success(SaveMempoolResponse {
+        transactions,
+        message: format!("Saved {} mempool transactions", transactions),
+    })))
+}
+
+/// Build the response for a mempool transaction, valuing its inputs against the UTXO set.
+///
+/// An input that can no longer be resolved (e.g. spent by a block mined meanwhile) leaves
+/// the input value and fee at 0.
+async fn mempool_transaction_response(node: &NodeContext, tx: &Transaction) -> TransactionResponse {
+    let total_output_value = tx.get_output_value().unwrap_or_default();
+    let total_input_value = UTXOSet::new(node.get_blockchain().clone())
+        .get_input_value(tx)
+        .await
+        .unwrap_or_default();
+    TransactionResponse {
+        txid: tx.get_tx_id_hex(),
+        is_coinbase: tx.is_coinbase(),
+        input_count: tx.get_vin().len(),
+        output_count: tx.get_vout().len(),
+        total_input_value,
+        total_output_value,
+        fee: total_input_value
+            .checked_sub(total_output_value)
+            .unwrap_or_default(),
+        timestamp: chrono::Utc::now(),
+        size_bytes: tx.serialize().unwrap_or_default().len(),
+    }
+}
+
 /// Get transactions with pagination
 ///
 /// Retrieves a paginated list of transactions from the blockchain.
@@ -185,8 +330,9 @@ pub async fn get_transactions(
                     .get_outputs()
                     .iter()
                     .map(|output| TxOutputSummaryResponse {
-                        wlt_addr: output.get_wlt_addr().as_string(),
+                        wlt_addr: output.get_wlt_addr().map(|addr| addr.as_string()),
                         value: output.get_value(),
+                        data_hex: output.get_data().map(|data| HEXLOWER.encode(data)),
                     })
                     .collect(),
             }
@@ -290,3 +436,107 @@ pub async fn get_address_transactions(
     let paginated = PaginatedResponse::new(paginated_items, page, limit, total);
     Ok(Json(ApiResponse::success(paginated)))
 }
+
+/// Get a Merkle inclusion proof for a transaction
+///
+/// Equivalent to Bitcoin Core's `gettxoutproof` RPC command. Returns the Merkle branch
+/// linking a confirmed transaction to its block header so light clients can verify
+/// inclusion without downloading the block.
+#[utoipa::path(
+    get,
+    path = "/api/v1/transactions/{txid}/proof",
+    tag = "Transaction",
+    params(
+        ("txid" = String, Path, description = "Transaction ID"),
+        ("block_hash" = Option<String>, Query, description = "Block on the active chain containing the transaction (optional)")
+    ),
+    responses(
+        (status = 200, description = "Proof generated successfully", body = ApiResponse<TxOutProofResponse>),
+        (status = 400, description = "Invalid transaction ID"),
+        (status = 404, description = "Transaction not found in a block, or the block is not on the active chain"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_tx_out_proof(
+    State(node): State<Arc<NodeContext>>,
+    Path(txid): Path<String>,
+    Query(query): Query<TxOutProofQuery>,
+) -> Result<Json<ApiResponse<TxOutProofResponse>>, StatusCode> {
+    let proof = node
+        .get_tx_out_proof(&txid, query.block_hash.as_deref())
+        .await
+        .map_err(|e| {
+            error!("Failed to build proof for {}: {}", txid, e);
+            match e {
+                BtcError::TransactionIdHexDecodingError(_) => StatusCode::BAD_REQUEST,
+                BtcError::TransactionNotFoundError(_) | BtcError::BlockNotInBestChain(_) => {
+                    StatusCode::NOT_FOUND
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            }
+        })?;
+
+    let serialized = proof
+        .serialize()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let response = TxOutProofResponse {
+        txid: HEXLOWER.encode(proof.get_txid()),
+        block_hash: proof.get_block_hash().to_string(),
+        merkle_root: HEXLOWER.encode(proof.get_merkle_root()),
+        index: proof.get_index(),
+        branch: proof
+            .get_branch()
+            .iter()
+            .map(|hash| HEXLOWER.encode(hash))
+            .collect(),
+        proof: HEXLOWER.encode(&serialized),
+    };
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
+/// Verify a Merkle inclusion proof
+///
+/// Equivalent to Bitcoin Core's `verifytxoutproof` RPC command. Checks that the proof
+/// links the transaction in the path to a block on this node's best chain.
+#[utoipa::path(
+    post,
+    path = "/api/v1/transactions/{txid}/proof/verify",
+    tag = "Transaction",
+    params(
+        ("txid" = String, Path, description = "Transaction ID the proof is expected to commit to")
+    ),
+    request_body = VerifyTxOutProofRequest,
+    responses(
+        (status = 200, description = "Proof checked", body = ApiResponse<VerifyTxOutProofResponse>),
+        (status = 400, description = "Malformed proof"),
+        (status = 404, description = "Block not in best chain"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn verify_tx_out_proof(
+    State(node): State<Arc<NodeContext>>,
+    Path(txid): Path<String>,
+    Json(request): Json<VerifyTxOutProofRequest>,
+) -> Result<Json<ApiResponse<VerifyTxOutProofResponse>>, StatusCode> {
+    let proof = HEXLOWER
+        .decode(request.proof.as_bytes())
+        .map_err(|_| StatusCode::BAD_REQUEST)
+        .and_then(|bytes| MerkleProof::deserialize(&bytes).map_err(|_| StatusCode::BAD_REQUEST))?;
+
+    let txids = node.verify_tx_out_proof(&proof).await.map_err(|e| {
+        error!("Failed to verify proof for {}: {}", txid, e);
+        match e {
+            BtcError::BlockNotInBestChain(_) => StatusCode::NOT_FOUND,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    })?;
+
+    let response = VerifyTxOutProofResponse {
+        valid: txids.iter().any(|id| id.eq(&txid)),
+        txids,
+    };
+
+    Ok(Json(ApiResponse::success(response)))
+}
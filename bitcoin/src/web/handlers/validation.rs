@@ -18,6 +18,13 @@ impl ValidationService {
         request.validate().map_err(|e| e.to_string())
     }
 
+    /// Validate a data carrier transaction request
+    pub fn validate_send_data_transaction_request(
+        request: &SendDataTransactionRequest,
+    ) -> Result<(), String> {
+        request.validate().map_err(|e| e.to_string())
+    }
+
     /// Validate a mining request
     pub fn validate_mining_request(request: &MiningRequest) -> Result<(), String> {
         request.validate().map_err(|e| e.to_string())
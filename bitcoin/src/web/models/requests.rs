@@ -1,7 +1,9 @@
-use crate::WalletAddress;
+use crate::policy::MAX_DATA_CARRIER_SIZE;
+use crate::{Amount, TxFee, WalletAddress};
+use data_encoding::HEXLOWER_PERMISSIVE;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
 /// Request model for creating a new wallet
 #[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
@@ -23,8 +25,110 @@ pub struct SendTransactionRequest {
     /// Already validated via WalletAddress::validate()
     pub to_address: WalletAddress,
 
-    #[validate(range(min = 1, message = "Amount must be greater than 0"))]
-    pub amount: i32,
+    /// Amount paid to the recipient, in satoshis
+    #[validate(custom(function = "validate_send_amount"))]
+    pub amount: Amount,
+
+    /// Absolute fee paid to the miner, in satoshis. Mutually exclusive with `fee_rate`.
+    #[serde(default)]
+    #[validate(custom(function = "validate_money_range"))]
+    pub fee: Option<Amount>,
+
+    /// Fee rate in satoshis per byte of serialized transaction. Mutually exclusive with `fee`.
+    #[serde(default)]
+    pub fee_rate: Option<u64>,
+
+    /// Signal that the transaction may be replaced by one paying a higher fee while it is
+    /// unconfirmed (BIP125 replace-by-fee)
+    #[serde(default)]
+    pub replaceable: bool,
+}
+
+impl SendTransactionRequest {
+    /// The fee policy requested by the caller.
+    ///
+    /// # Returns
+    ///
+    /// A zero absolute fee if neither `fee` nor `fee_rate` is set, or an error if both are.
+    pub fn get_tx_fee(&self) -> Result<TxFee, String> {
+        tx_fee(self.fee, self.fee_rate)
+    }
+}
+
+/// Request model for anchoring data on chain in a data carrier (`OP_RETURN`) output
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct SendDataTransactionRequest {
+    /// Already validated via WalletAddress::validate(); pays the fee and receives the change
+    pub from_address: WalletAddress,
+
+    /// Payload as lowercase or uppercase hex, at most `MAX_DATA_CARRIER_SIZE` (80) bytes
+    #[validate(custom(function = "validate_data_hex"))]
+    pub data: String,
+
+    /// Absolute fee paid to the miner, in satoshis. Mutually exclusive with `fee_rate`.
+    #[serde(default)]
+    #[validate(custom(function = "validate_money_range"))]
+    pub fee: Option<Amount>,
+
+    /// Fee rate in satoshis per byte of serialized transaction. Mutually exclusive with `fee`.
+    #[serde(default)]
+    pub fee_rate: Option<u64>,
+}
+
+impl SendDataTransactionRequest {
+    /// The payload decoded from hex.
+    pub fn get_data(&self) -> Result<Vec<u8>, String> {
+        decode_data_hex(&self.data)
+    }
+
+    /// The fee policy requested by the caller; see `SendTransactionRequest::get_tx_fee`.
+    pub fn get_tx_fee(&self) -> Result<TxFee, String> {
+        tx_fee(self.fee, self.fee_rate)
+    }
+}
+
+fn tx_fee(fee: Option<Amount>, fee_rate: Option<u64>) -> Result<TxFee, String> {
+    match (fee, fee_rate) {
+        (Some(_), Some(_)) => Err("Specify either fee or fee_rate, not both".to_string()),
+        (Some(fee), None) => Ok(TxFee::Absolute(fee)),
+        (None, Some(rate)) => Ok(TxFee::PerByte(rate)),
+        (None, None) => Ok(TxFee::Absolute(Amount::ZERO)),
+    }
+}
+
+fn decode_data_hex(data: &str) -> Result<Vec<u8>, String> {
+    HEXLOWER_PERMISSIVE
+        .decode(data.as_bytes())
+        .map_err(|e| format!("Data must be hex: {}", e))
+}
+
+/// Data carried by an output must be hex and fit the relay policy
+fn validate_data_hex(data: &str) -> Result<(), ValidationError> {
+    let payload = decode_data_hex(data)
+        .map_err(|e| ValidationError::new("data_hex").with_message(e.into()))?;
+    if payload.len() > MAX_DATA_CARRIER_SIZE {
+        return Err(ValidationError::new("data_size")
+            .with_message(format!("Data must be at most {} bytes", MAX_DATA_CARRIER_SIZE).into()));
+    }
+    Ok(())
+}
+
+/// An amount sent must be positive and within `MAX_MONEY`
+fn validate_send_amount(amount: &Amount) -> Result<(), ValidationError> {
+    if *amount == Amount::ZERO {
+        return Err(
+            ValidationError::new("amount").with_message("Amount must be greater than 0".into())
+        );
+    }
+    validate_money_range(amount)
+}
+
+fn validate_money_range(amount: &Amount) -> Result<(), ValidationError> {
+    if !amount.is_money_range() {
+        return Err(ValidationError::new("money_range")
+            .with_message("Amount must not exceed MAX_MONEY".into()));
+    }
+    Ok(())
 }
 
 /// Request model for mining operations
@@ -32,9 +136,6 @@ pub struct SendTransactionRequest {
 pub struct MiningRequest {
     #[validate(length(min = 26, max = 35, message = "Invalid mining address format"))]
     pub mining_address: String,
-
-    #[validate(range(min = 1, max = 10, message = "Thread count must be between 1 and 10"))]
-    pub thread_count: Option<u8>,
 }
 
 /// Request model for querying blocks
@@ -61,6 +162,21 @@ pub struct TransactionQuery {
     pub txid: Option<String>,
 }
 
+/// Request model for gettxoutproof queries
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct TxOutProofQuery {
+    /// Block to look in; the active chain is scanned when omitted
+    pub block_hash: Option<String>,
+}
+
+/// Request model for verifytxoutproof
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct VerifyTxOutProofRequest {
+    /// Hex-encoded proof as returned by gettxoutproof
+    #[validate(length(min = 1, message = "Proof must not be empty"))]
+    pub proof: String,
+}
+
 /// Request model for balance queries
 #[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct BalanceQuery {
@@ -88,4 +204,17 @@ pub struct GenerateToAddressRequest {
         message = "Max tries must be between 1 and 1000000"
     ))]
     pub maxtries: Option<u32>,
+    /// Number of threads searching for proof-of-work nonces (optional, defaults to the
+    /// node's configured mining threads)
+    #[validate(range(min = 1, max = 10, message = "Thread count must be between 1 and 10"))]
+    pub thread_count: Option<u8>,
+}
+
+/// Request model for the setmocktime RPC command (regtest only)
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct SetMockTimeRequest {
+    /// Unix timestamp in milliseconds the node clock is pinned to; 0 returns to the
+    /// system clock
+    #[validate(range(min = 0, message = "Mock time must not be negative"))]
+    pub timestamp: i64,
 }
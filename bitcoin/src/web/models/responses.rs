@@ -1,3 +1,5 @@
+use crate::Amount;
+use crate::consensus::Network;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
@@ -34,6 +36,8 @@ impl<T> ApiResponse<T> {
 /// Blockchain information response
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct BlockchainInfoResponse {
+    /// Chain name of the network the node runs on (main, test, regtest)
+    pub chain: String,
     pub height: usize,
     pub difficulty: u32,
     pub total_blocks: usize,
@@ -43,6 +47,25 @@ pub struct BlockchainInfoResponse {
     pub last_block_timestamp: DateTime<Utc>,
 }
 
+/// UTXO set statistics response (Bitcoin Core's `gettxoutsetinfo`)
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TxOutSetInfoResponse {
+    pub network: Network,
+    pub height: usize,
+    pub best_block_hash: String,
+    /// Transactions with at least one unspent output
+    pub transactions: usize,
+    /// Unspent outputs
+    pub txouts: usize,
+    /// Value held by all unspent outputs
+    pub total_amount: Amount,
+    /// Subsidy issued from genesis up to the tip by the halving schedule
+    pub total_subsidy: Amount,
+    /// Subsidy the next block may mint
+    pub block_subsidy: Amount,
+    pub halving_interval: usize,
+}
+
 /// Block response model
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct BlockResponse {
@@ -61,6 +84,8 @@ pub struct BlockResponse {
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct SendBitCoinResponse {
     pub txid: String,
+    /// Fee paid to the miner (inputs − outputs), in satoshis
+    pub fee: Amount,
     pub timestamp: DateTime<Utc>,
 }
 
@@ -71,9 +96,9 @@ pub struct TransactionResponse {
     pub is_coinbase: bool,
     pub input_count: usize,
     pub output_count: usize,
-    pub total_input_value: i32,
-    pub total_output_value: i32,
-    pub fee: i32,
+    pub total_input_value: Amount,
+    pub total_output_value: Amount,
+    pub fee: Amount,
     pub timestamp: DateTime<Utc>,
     pub size_bytes: usize,
 }
@@ -87,8 +112,13 @@ pub struct TxInputSummaryResponse {
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct TxOutputSummaryResponse {
-    pub wlt_addr: String,
-    pub value: i32,
+    /// Recipient address; absent for data carrier outputs, which pay nobody
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wlt_addr: Option<String>,
+    pub value: Amount,
+    /// Hex payload of a data carrier (`OP_RETURN`) output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_hex: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -104,19 +134,45 @@ pub struct WalletTransactionRespose {
     pub tx_id: Vec<u8>,
     pub from_wlt_addr: Option<String>,
     pub to_wlt_addr: String,
-    pub value: i32,
+    pub value: Amount,
     pub transaction_type: String,
     pub status: String,
     pub vout: usize,
     pub is_coinbase: bool,
     pub input_count: usize,
     pub output_count: usize,
-    pub total_output_value: i32,
-    pub fee: i32,
+    pub total_output_value: Amount,
+    pub fee: Amount,
     pub timestamp: i64,
     pub size_bytes: usize,
 }
 
+/// Response for gettxoutproof
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TxOutProofResponse {
+    /// Transaction ID (hex)
+    pub txid: String,
+    /// Hash of the block containing the transaction
+    pub block_hash: String,
+    /// Merkle root from the block header (hex)
+    pub merkle_root: String,
+    /// Position of the transaction in the block
+    pub index: usize,
+    /// Sibling hashes from the transaction up to the root (hex)
+    pub branch: Vec<String>,
+    /// Serialized proof (hex), accepted by verifytxoutproof
+    pub proof: String,
+}
+
+/// Response for verifytxoutproof
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct VerifyTxOutProofResponse {
+    /// Whether the proof links the transaction to a block on the best chain
+    pub valid: bool,
+    /// Transaction IDs (hex) the proof commits to; empty if invalid
+    pub txids: Vec<String>,
+}
+
 /// Wallet response model
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct WalletResponse {
@@ -129,8 +185,12 @@ pub struct WalletResponse {
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct BalanceResponse {
     pub address: String,
-    pub balance: i32,
-    pub unconfirmed_balance: i32,
+    pub balance: Amount,
+    pub unconfirmed_balance: Amount,
+    /// Part of `balance` that can be spent in the next block
+    pub spendable_balance: Amount,
+    /// Part of `balance` held in coinbase outputs that have not reached coinbase maturity
+    pub immature_balance: Amount,
     pub utxo_count: usize,
     pub last_updated: DateTime<Utc>,
 }
@@ -189,6 +249,45 @@ pub struct MiningInfoResponse {
     pub warnings: String,
 }
 
+/// A transaction of a block template
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BlockTemplateTransaction {
+    /// Consensus encoding of the transaction, in hex
+    pub data: String,
+    pub txid: String,
+    /// 1-based positions of the template transactions this one spends from
+    pub depends: Vec<usize>,
+    /// Fee paid to the miner (inputs − outputs), in satoshis
+    pub fee: Amount,
+    /// Serialized size in bytes
+    pub size: usize,
+}
+
+/// Response for getblocktemplate RPC command
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BlockTemplateResponse {
+    /// Header version
+    pub version: u32,
+    /// Hash of the tip the block extends
+    pub previousblockhash: String,
+    /// Transactions to include after the coinbase, in order
+    pub transactions: Vec<BlockTemplateTransaction>,
+    /// Subsidy plus fees the coinbase may claim
+    pub coinbasevalue: Amount,
+    /// Full proof-of-work target, in hex
+    pub target: String,
+    /// Earliest timestamp the block may have, in milliseconds
+    pub mintime: i64,
+    /// Current time, in milliseconds
+    pub curtime: i64,
+    /// Compact target (`nBits`), in hex
+    pub bits: String,
+    /// Height of the block
+    pub height: usize,
+    /// Largest serialized block size the template respects
+    pub sizelimit: usize,
+}
+
 /// Response for generatetoaddress RPC command
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct GenerateToAddressResponse {
@@ -198,6 +297,24 @@ pub struct GenerateToAddressResponse {
     pub message: String,
 }
 
+/// Response for setmocktime RPC command
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SetMockTimeResponse {
+    /// Mock time now in effect in milliseconds, 0 when the system clock is used
+    pub timestamp: i64,
+    /// Status message
+    pub message: String,
+}
+
+/// Response for savemempool RPC command
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SaveMempoolResponse {
+    /// Number of transactions written to disk
+    pub transactions: usize,
+    /// Status message
+    pub message: String,
+}
+
 impl<T> PaginatedResponse<T> {
     pub fn new(items: Vec<T>, page: u32, limit: u32, total: u32) -> Self {
         let total_pages = if total == 0 {
@@ -16,6 +16,7 @@ use crate::web::handlers::{blockchain, health, mining, transaction, wallet};
         blockchain::get_blocks,
         blockchain::get_latest_blocks,
         blockchain::get_block_by_hash,
+        blockchain::get_tx_out_set_info,
         // Wallet endpoints
         wallet::create_wallet,
         wallet::get_addresses,
@@ -23,14 +24,20 @@ use crate::web::handlers::{blockchain, health, mining, transaction, wallet};
         wallet::get_balance,
         // Transaction endpoints(mempool)
         transaction::send_transaction,
+        transaction::send_data_transaction,
         transaction::get_mempool_transaction,
         transaction::get_mempool,
+        transaction::save_mempool,
         // Transaction endpoints(blockchain)
         transaction::get_transactions,
         transaction::get_address_transactions,
+        transaction::get_tx_out_proof,
+        transaction::verify_tx_out_proof,
         // Mining endpoints
         mining::get_mining_info,
+        mining::get_block_template,
         mining::generate_to_address,
+        mining::set_mock_time,
     ),
     components(
         schemas(
@@ -38,6 +45,7 @@ use crate::web::handlers::{blockchain, health, mining, transaction, wallet};
             crate::web::models::responses::HealthResponse,
             crate::web::models::responses::BlockchainInfoResponse,
             crate::web::models::responses::BlockResponse,
+            crate::web::models::responses::TxOutSetInfoResponse,
             crate::web::models::responses::SendBitCoinResponse,
             crate::web::models::responses::TransactionResponse,
             crate::web::models::responses::TxInputSummaryResponse,
@@ -47,10 +55,19 @@ use crate::web::handlers::{blockchain, health, mining, transaction, wallet};
             crate::web::models::responses::WalletResponse,
             crate::web::models::responses::BalanceResponse,
             crate::web::models::responses::MiningStatusResponse,
+            crate::web::models::responses::TxOutProofResponse,
+            crate::web::models::responses::VerifyTxOutProofResponse,
+            crate::web::models::responses::BlockTemplateResponse,
+            crate::web::models::responses::BlockTemplateTransaction,
+            crate::web::models::responses::SetMockTimeResponse,
+            crate::web::models::responses::SaveMempoolResponse,
             // Request schemas
             crate::web::models::requests::CreateWalletRequest,
             crate::web::models::requests::SendTransactionRequest,
+            crate::web::models::requests::SendDataTransactionRequest,
             crate::web::models::requests::MiningRequest,
+            crate::web::models::requests::VerifyTxOutProofRequest,
+            crate::web::models::requests::SetMockTimeRequest,
             // Error schemas
             crate::web::models::errors::ErrorResponse,
         )
@@ -22,6 +22,10 @@ pub fn create_api_routes() -> Router<Arc<NodeContext>> {
             "/blockchain/blocks/{hash}",
             get(blockchain::get_block_by_hash),
         )
+        .route(
+            "/blockchain/txoutsetinfo",
+            get(blockchain::get_tx_out_set_info),
+        )
         // Wallet endpoints
         .route("/wallet", post(wallet::create_wallet))
         .route("/wallet/addresses", get(wallet::get_addresses))
@@ -29,6 +33,10 @@ pub fn create_api_routes() -> Router<Arc<NodeContext>> {
         .route("/wallet/{address}/balance", get(wallet::get_balance))
         // Transaction endpoints(mempool)
         .route("/transactions", post(transaction::send_transaction))
+        .route(
+            "/transactions/data",
+            post(transaction::send_data_transaction),
+        )
         .route(
             "/transactions/mempool/{txid}",
             get(transaction::get_mempool_transaction),
@@ -40,8 +48,17 @@ pub fn create_api_routes() -> Router<Arc<NodeContext>> {
             "/transactions/address/{address}",
             get(transaction::get_address_transactions),
         )
+        .route(
+            "/transactions/{txid}/proof",
+            get(transaction::get_tx_out_proof),
+        )
+        .route(
+            "/transactions/{txid}/proof/verify",
+            post(transaction::verify_tx_out_proof),
+        )
         // Mining endpoints
         .route("/mining/info", get(mining::get_mining_info))
+        .route("/mining/blocktemplate", get(mining::get_block_template))
         .route(
             "/mining/generatetoaddress",
             post(mining::generate_to_address),
@@ -56,12 +73,20 @@ pub fn create_monitor_api_routes() -> Router<Arc<NodeContext>> {
         .route("/health/ready", get(health::readiness))
 }
 
+/// Create routes only administrators may call (node control)
+pub fn create_admin_only_api_routes() -> Router<Arc<NodeContext>> {
+    Router::new()
+        .route("/mining/setmocktime", post(mining::set_mock_time))
+        .route("/mempool/savemempool", post(transaction::save_mempool))
+}
+
 /// Create admin API routes
 pub fn create_admin_api_routes() -> Router<Arc<NodeContext>> {
-    // Admin router includes all endpoints plus health endpoints
+    // Admin router includes all endpoints plus health and node control endpoints
     Router::new()
         .nest("/api/admin", create_api_routes())
         .nest("/api/admin", create_monitor_api_routes())
+        .nest("/api/admin", create_admin_only_api_routes())
         .layer(axum::middleware::from_fn(require_admin))
 }
 
@@ -82,7 +107,11 @@ pub fn create_all_api_routes() -> Router<Arc<NodeContext>> {
 pub fn create_wallet_only_routes() -> Router<Arc<NodeContext>> {
     let wallet_only = Router::new()
         .route("/wallet", post(wallet::create_wallet))
-        .route("/transactions", post(transaction::send_transaction));
+        .route("/transactions", post(transaction::send_transaction))
+        .route(
+            "/transactions/data",
+            post(transaction::send_data_transaction),
+        );
 
     Router::new()
         .nest("/api/wallet", wallet_only)
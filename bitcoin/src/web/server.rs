@@ -1,3 +1,4 @@
+use crate::GLOBAL_CONFIG;
 use crate::node::NodeContext;
 use crate::web::middleware::cors;
 use crate::web::middleware::rate_limit::{RateLimitConfig, build_rate_limiter_manager};
@@ -24,11 +25,13 @@ pub struct WebServerConfig {
     pub rate_limit_burst_size: u32,
 }
 
+/// Defaults: all interfaces on the REST port of the configured network (`default_web_port`
+/// of its `ChainParams`), with CORS and rate limiting enabled.
 impl Default for WebServerConfig {
     fn default() -> Self {
         Self {
             host: "0.0.0.0".to_string(),
-            port: 8080,
+            port: GLOBAL_CONFIG.get_chain_params().get_default_web_port(),
             enable_cors: true,
             enable_rate_limiting: true,
             rate_limit_requests_per_second: 10,
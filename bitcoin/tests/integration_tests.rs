@@ -1,5 +1,5 @@
 use blockchain::{
-    BlockchainService, ConnectNode, GLOBAL_CONFIG, Transaction, UTXOSet, WalletService,
+    Amount, BlockchainService, ConnectNode, GLOBAL_CONFIG, Transaction, UTXOSet, WalletService,
 };
 use std::str::FromStr;
 
@@ -313,6 +313,8 @@ async fn test_blockchain_iterator() {
 
 #[tokio::test]
 async fn test_wallet_transaction_creation() {
+    // The test spends a coinbase output one block after it is mined
+    GLOBAL_CONFIG.set_coinbase_maturity(0);
     let (blockchain, db_path) = create_test_blockchain().await;
 
     // Create wallets with unique path
@@ -327,7 +329,8 @@ async fn test_wallet_transaction_creation() {
     let utxo_set = create_and_reindex_utxo_set(blockchain).await;
 
     // Test creating a transaction between wallets
-    let transaction_result = Transaction::new_utxo_transaction(&address1, &address2, 5, &utxo_set);
+    let transaction_result =
+        Transaction::new_utxo_transaction(&address1, &address2, Amount::from_sat(5), &utxo_set);
     let transaction = transaction_result.await;
     assert!(transaction.is_ok());
 
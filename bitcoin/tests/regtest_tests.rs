@@ -0,0 +1,257 @@
+//! Regtest scenarios driven by mock time and `generatetoaddress`
+//!
+//! The node clock (`setmocktime`), the selected network and the mempool limits are
+//! process-wide, so these tests live in their own test binary and hold `NODE_CLOCK` while
+//! they run. No test sleeps or
+//! retries: blocks are only made on demand and every timestamp comes from the mock clock.
+
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use blockchain::consensus::Network;
+use blockchain::crypto::{check_address_network, pub_key_hash_address};
+use blockchain::node::{NodeContext, should_trigger_mining};
+use blockchain::web::handlers::mining::{generate_to_address, set_mock_time};
+use blockchain::web::models::{GenerateToAddressRequest, SetMockTimeRequest};
+use blockchain::{
+    Amount, BlockchainService, BtcError, GLOBAL_CONFIG, TxFee, TxLockTime, current_timestamp,
+    get_mock_time,
+};
+use std::sync::Arc;
+use tokio::sync::{Mutex, MutexGuard};
+
+/// Serializes the tests of this binary, which share the node clock, network and mempool.
+static NODE_CLOCK: Mutex<()> = Mutex::const_new(());
+
+/// Mock time the scenarios start from, in milliseconds (2023-11-14).
+const MOCK_TIME: i64 = 1_700_000_000_000;
+
+/// Take the node clock and switch the process to regtest, mining on one thread so the
+/// nonce a search finds does not depend on thread scheduling.
+async fn regtest() -> MutexGuard<'static, ()> {
+    let guard = NODE_CLOCK.lock().await;
+    GLOBAL_CONFIG.set_network(Network::Regtest);
+    GLOBAL_CONFIG.set_mining_threads(1);
+    guard
+}
+
+/// Point `setmocktime` at `timestamp`.
+async fn mock_time(timestamp: i64) -> Result<i64, StatusCode> {
+    set_mock_time(Json(SetMockTimeRequest { timestamp }))
+        .await
+        .map(|response| response.0.data.expect("setmocktime returns data").timestamp)
+}
+
+/// Database directory of one test node, removed when dropped.
+struct TestNodeDir {
+    db_path: String,
+}
+
+impl TestNodeDir {
+    fn new() -> Self {
+        TestNodeDir {
+            db_path: format!("test_regtest_db_{}", uuid::Uuid::new_v4()),
+        }
+    }
+}
+
+impl Drop for TestNodeDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.db_path);
+    }
+}
+
+/// Start a node whose genesis coinbase pays `genesis_address`.
+async fn start_node(
+    dir: &TestNodeDir,
+    genesis_address: &blockchain::WalletAddress,
+) -> Arc<NodeContext> {
+    unsafe {
+        std::env::set_var("TREE_DIR", &dir.db_path);
+        std::env::set_var("BLOCKS_TREE", &dir.db_path);
+    }
+    let blockchain = BlockchainService::initialize(genesis_address)
+        .await
+        .expect("Failed to create regtest blockchain");
+    Arc::new(NodeContext::new(blockchain))
+}
+
+/// Mine `nblocks` blocks through the `generatetoaddress` handler.
+async fn generate(node: &Arc<NodeContext>, nblocks: u32, address: &str) -> Vec<String> {
+    let request = GenerateToAddressRequest {
+        nblocks,
+        address: address.to_string(),
+        maxtries: None,
+        thread_count: Some(1),
+    };
+    generate_to_address(State(node.clone()), Json(request))
+        .await
+        .expect("generatetoaddress failed")
+        .0
+        .data
+        .expect("generatetoaddress returns data")
+        .block_hashes
+}
+
+#[tokio::test]
+async fn test_setmocktime_pins_and_releases_node_clock() {
+    let _clock = regtest().await;
+
+    assert_eq!(mock_time(MOCK_TIME).await, Ok(MOCK_TIME));
+    assert_eq!(get_mock_time(), MOCK_TIME);
+    assert_eq!(current_timestamp(), MOCK_TIME);
+
+    // A negative time is rejected and leaves the clock alone
+    assert_eq!(mock_time(-1).await, Err(StatusCode::BAD_REQUEST));
+    assert_eq!(current_timestamp(), MOCK_TIME);
+
+    // Zero returns to the system clock
+    assert_eq!(mock_time(0).await, Ok(0));
+    assert_eq!(get_mock_time(), 0);
+    assert!(current_timestamp() > MOCK_TIME);
+}
+
+#[tokio::test]
+async fn test_setmocktime_is_refused_off_regtest() {
+    let _clock = regtest().await;
+
+    for network in [Network::Mainnet, Network::Testnet] {
+        GLOBAL_CONFIG.set_network(network);
+        assert_eq!(mock_time(MOCK_TIME).await, Err(StatusCode::FORBIDDEN));
+        assert_eq!(get_mock_time(), 0);
+    }
+    GLOBAL_CONFIG.set_network(Network::Regtest);
+}
+
+#[tokio::test]
+async fn test_address_of_another_network_is_rejected() {
+    let _clock = regtest().await;
+    let pub_key_hash = [7u8; 32];
+
+    GLOBAL_CONFIG.set_network(Network::Mainnet);
+    let mainnet_address = pub_key_hash_address(&pub_key_hash).expect("mainnet address");
+    assert!(check_address_network(&mainnet_address).is_ok());
+
+    GLOBAL_CONFIG.set_network(Network::Regtest);
+    let regtest_address = pub_key_hash_address(&pub_key_hash).expect("regtest address");
+    assert_ne!(mainnet_address, regtest_address);
+    assert!(check_address_network(&regtest_address).is_ok());
+    assert!(matches!(
+        check_address_network(&mainnet_address),
+        Err(BtcError::InvalidAddress(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_regtest_never_triggers_automatic_mining() {
+    let _clock = regtest().await;
+    let miner = blockchain::Wallet::new()
+        .and_then(|wallet| wallet.get_address())
+        .expect("Failed to create miner address");
+    GLOBAL_CONFIG.set_mining_addr(&miner);
+
+    assert!(GLOBAL_CONFIG.is_miner());
+    assert!(!should_trigger_mining());
+}
+
+#[tokio::test]
+async fn test_generated_blocks_are_identical_across_nodes() {
+    let _clock = regtest().await;
+    mock_time(MOCK_TIME).await.unwrap();
+    let address = blockchain::Wallet::new()
+        .and_then(|wallet| wallet.get_address())
+        .expect("Failed to create reward address");
+
+    // Two nodes started at the same mock time share their genesis block and, mining the
+    // same templates with the clock standing still, every block after it
+    let (dir_a, dir_b) = (TestNodeDir::new(), TestNodeDir::new());
+    let node_a = start_node(&dir_a, &address).await;
+    let node_b = start_node(&dir_b, &address).await;
+    let hashes_a = generate(&node_a, 3, address.as_str()).await;
+    let hashes_b = generate(&node_b, 3, address.as_str()).await;
+    assert_eq!(hashes_a.len(), 3);
+    assert_eq!(hashes_a, hashes_b);
+
+    // A block from one node passes the other's full validation, regtest proof of work
+    // included
+    let hashes = generate(&node_a, 1, address.as_str()).await;
+    let block = node_a
+        .get_block_by_hash(&hashes[0])
+        .await
+        .unwrap()
+        .expect("Generated block is stored");
+    assert!(block.get_timestamp() > MOCK_TIME);
+    node_b
+        .validate_block(&block)
+        .await
+        .expect("Regtest block rejected by a second node");
+    node_b.add_block(&block).await.unwrap();
+    assert_eq!(
+        node_b.get_blockchain().get_tip_hash().await.unwrap(),
+        hashes[0]
+    );
+    assert_eq!(node_b.get_blockchain_height().await.unwrap(), 5);
+
+    mock_time(0).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_replacement_evicted_from_full_mempool_keeps_original() {
+    let _clock = regtest().await;
+    GLOBAL_CONFIG.set_coinbase_maturity(0);
+    let address = blockchain::Wallet::new()
+        .and_then(|wallet| wallet.get_address())
+        .expect("Failed to create funded address");
+    let dir = TestNodeDir::new();
+    let node = start_node(&dir, &address).await;
+
+    // Two transactions spending the genesis coinbase, the second paying more
+    let mut txs = vec![];
+    for fee in [1, 3] {
+        let to = blockchain::Wallet::new()
+            .and_then(|wallet| wallet.get_address())
+            .expect("Failed to create recipient address");
+        let tx = node
+            .create_transaction_with_lock_time(
+                &address,
+                &to,
+                Amount::from_sat(4),
+                TxFee::Absolute(Amount::from_sat(fee)),
+                TxLockTime::replaceable(),
+            )
+            .await
+            .expect("Failed to create transaction");
+        txs.push(tx);
+    }
+    let (original, replacement) = (txs[0].clone(), txs[1].clone());
+    let entry_time = current_timestamp();
+    node.accept_to_memory_pool(&original, entry_time)
+        .await
+        .expect("Original transaction rejected");
+
+    // A mempool too small for the replacement evicts it as soon as it enters, and the
+    // original it replaced comes back unchanged
+    let max_mempool_size = GLOBAL_CONFIG.get_max_mempool_size();
+    GLOBAL_CONFIG.set_max_mempool_size(1);
+    let result = node
+        .accept_to_memory_pool(&replacement, current_timestamp())
+        .await;
+    GLOBAL_CONFIG.set_max_mempool_size(max_mempool_size);
+    assert!(matches!(result, Err(BtcError::MempoolFull(..))));
+
+    let txid = original.get_tx_id_hex();
+    assert!(node.get_mempool_transaction(&txid).unwrap().is_some());
+    assert!(
+        node.get_mempool_transaction(&replacement.get_tx_id_hex())
+            .unwrap()
+            .is_none()
+    );
+    let entry = blockchain::node::GLOBAL_MEMORY_POOL
+        .get_entry(&txid)
+        .unwrap()
+        .expect("Original transaction restored");
+    assert_eq!(entry.get_fee(), Amount::from_sat(1));
+    assert_eq!(entry.get_time(), entry_time);
+
+    node.remove_from_memory_pool(original);
+}
@@ -125,11 +125,11 @@ pub async fn verify_blockchain_integrity(blockchain: &BlockchainService) -> bool
             blocks.iter().enumerate().all(|(i, block)| {
                 let expected_height = i + 1;
                 let prev_hash = if i > 0 {
-                    Some(blocks[i - 1].get_hash().as_ref())
+                    Some(blocks[i - 1].get_hash())
                 } else {
                     None
                 };
-                verify_block_integrity(block, expected_height, prev_hash)
+                verify_block_integrity(block, expected_height, prev_hash.as_deref())
             })
         })
         .unwrap_or(false)